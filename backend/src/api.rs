@@ -1,39 +1,72 @@
+use std::convert::Infallible;
+
 use axum::{
-    Json,
+    Extension, Json,
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
 };
+use chrono::{NaiveTime, SubsecRound, Utc};
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
 use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
 
-use crate::db::{Event, Job, JobStatus};
-use crate::producer::{ScheduleManager, TimerManager};
+use crate::consumer;
+use crate::db::{
+    Event, EventHandler, Job, JobStatus, ScheduleRecord, ScheduleState, TailRecord, TimerRecord,
+    WatcherRecord, WebhookRecord, WindowRecord, WorkflowRecord, WorkflowRun,
+};
+use crate::filter::Filter;
+use crate::middleware::AllowedIp;
+use crate::output::{OutputMessage, OutputStream};
+use crate::producer::{ScheduleManager, TailManager, TimerManager, WatcherManager};
 use crate::queue::EventSender;
+use crate::recurrence;
 use crate::store::JobStore;
-use shev_core::ShellType;
+use crate::tail;
+use crate::template;
+use crate::watcher;
+use crate::webhook;
+use crate::window;
+use crate::workflow;
 use shev_core::api::{
-    ConfigResponse, CreateHandlerRequest, CreateScheduleRequest, CreateTimerRequest,
-    HandlerResponse, HealthResponse, ReloadResponse, ScheduleResponse, StatusResponse,
-    TimerResponse, UpdateConfigRequest, UpdateHandlerRequest, UpdateScheduleRequest,
-    UpdateTimerRequest,
+    ConfigResponse, CreateHandlerRequest, CreateScheduleRequest, CreateTailRequest,
+    CreateTimerRequest, CreateWatcherRequest, CreateWebhookRequest, CreateWindowRequest,
+    CreateWorkflowRequest, FilterTestRequest, FilterTestResult, HandlerResponse, HealthResponse,
+    ReloadResponse, RunWorkflowRequest, SchedulePreviewResponse, ScheduleResponse, StatusResponse,
+    TailResponse, TimerResponse, UpdateConfigRequest, UpdateHandlerRequest, UpdateScheduleRequest,
+    UpdateTailRequest, UpdateTimerRequest, UpdateWatcherRequest, UpdateWebhookRequest,
+    UpdateWindowRequest, UpdateWorkflowRequest, WatcherResponse, WebhookResponse, WindowResponse,
+    WorkflowResponse,
 };
+use shev_core::{DEFAULT_IDEMPOTENCY_TTL_SECS, RetryPolicy, ShellType, ThrottlePolicy};
 
 #[derive(Clone)]
 pub struct ApiState {
     pub store: JobStore,
     pub timer_manager: TimerManager,
     pub schedule_manager: ScheduleManager,
+    pub watcher_manager: WatcherManager,
+    pub tail_manager: TailManager,
     pub sender: EventSender,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct EventRequest {
     pub event_type: String,
-    #[serde(default)]
+    /// A string, or JSON whose top-level fields become `EVENT_<KEY>` variables and
+    /// `{{ context.<field> }}` placeholders
+    #[serde(default, deserialize_with = "shev_core::api::deserialize_context")]
     pub context: String,
+    /// Triggering again with the same key (also accepted as an `Idempotency-Key` header) returns
+    /// the original event and its jobs instead of queuing a new event
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -41,6 +74,12 @@ pub struct EventResponse {
     #[serde(flatten)]
     pub event: Event,
     pub message: String,
+    /// Whether the idempotency key was used before, so no new event was queued
+    pub duplicate: bool,
+    /// Jobs of the original event created so far, for a duplicate. Jobs are created after the
+    /// event leaves the queue, so this is empty for a new event and for a duplicate that
+    /// arrives before the original event's jobs exist.
+    pub job_ids: Vec<Uuid>,
 }
 
 #[utoipa::path(
@@ -89,7 +128,7 @@ pub struct JobsQuery {
     get,
     path = "/jobs",
     params(
-        ("status" = Option<String>, Query, description = "Filter by job status (pending, running, completed, failed, cancelled)")
+        ("status" = Option<String>, Query, description = "Filter by job status (pending, running, completed, failed, cancelled, timed_out)")
     ),
     responses(
         (status = 200, description = "List of jobs", body = Vec<Job>)
@@ -166,36 +205,145 @@ pub async fn cancel_job(
     }
 }
 
-fn handler_to_response(h: crate::db::EventHandler) -> HandlerResponse {
+#[utoipa::path(
+    get,
+    path = "/jobs/{job_id}/stream",
+    params(
+        ("job_id" = Uuid, Path, description = "Job ID")
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events: `stdout`/`stderr` per output line, `lagged` with the number of lines a slow client missed, then `end` with the final status. A running job's stream starts with its recent lines in the order they were written; a finished job's stored output is replayed as all of stdout, then all of stderr, not interleaved.", content_type = "text/event-stream"),
+        (status = 404, description = "Job not found")
+    ),
+    tag = "Jobs"
+)]
+pub async fn stream_job(
+    State(state): State<ApiState>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, StatusCode> {
+    state
+        .store
+        .get_job(job_id)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let events = match state.store.subscribe_output(job_id).await {
+        Some((lines, receiver)) => {
+            let backlog = stream::iter(
+                lines
+                    .into_iter()
+                    .map(|(output, line)| output_event(output, &line)),
+            );
+            let live = stream::unfold(Some(receiver), |receiver| async move {
+                let mut receiver = receiver?;
+                match receiver.recv().await {
+                    Ok(OutputMessage::Line(output, line)) => {
+                        Some((output_event(output, &line), Some(receiver)))
+                    }
+                    Ok(OutputMessage::Finished(status)) => Some((end_event(&status), None)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        Some((lagged_event(skipped), Some(receiver)))
+                    }
+                    Err(RecvError::Closed) => None,
+                }
+            });
+            backlog.chain(live).map(Ok).boxed()
+        }
+        None => {
+            // Already finished: replay what was stored. Only the text of each stream is
+            // stored, so the order between stdout and stderr lines is lost.
+            let job = state
+                .store
+                .get_job(job_id)
+                .await
+                .ok_or(StatusCode::NOT_FOUND)?;
+            let mut events = Vec::new();
+            for (output, text) in [
+                (OutputStream::Stdout, &job.stdout),
+                (OutputStream::Stderr, &job.stderr),
+            ] {
+                if let Some(text) = text {
+                    events.extend(text.lines().map(|line| output_event(output, line)));
+                }
+            }
+            events.push(end_event(&job.status));
+            stream::iter(events).map(Ok).boxed()
+        }
+    };
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+fn output_event(output: OutputStream, line: &str) -> SseEvent {
+    SseEvent::default().event(output.as_str()).data(line)
+}
+
+/// Tells a client that fell behind how many lines it missed
+fn lagged_event(skipped: u64) -> SseEvent {
+    SseEvent::default()
+        .event("lagged")
+        .data(skipped.to_string())
+}
+
+fn end_event(status: &JobStatus) -> SseEvent {
+    SseEvent::default().event("end").data(status.as_str())
+}
+
+fn handler_to_response(h: EventHandler) -> HandlerResponse {
     HandlerResponse {
         id: h.id.to_string(),
         event_type: h.event_type,
+        name: h.name,
         shell: format!("{:?}", h.shell).to_lowercase(),
         command: h.command,
         timeout: h.timeout,
         env: h.env,
+        retry: h.retry,
+        max_concurrency: h.max_concurrency,
+        enabled: h.enabled,
+        on_success: h.on_success,
+        on_failure: h.on_failure,
+        on_timeout: h.on_timeout,
+        filter: h.filter,
+        debounce_secs: h.debounce_secs,
+        throttle: h.throttle,
     }
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct HandlersQuery {
+    pub event_type: Option<String>,
+}
+
 #[utoipa::path(
     get,
     path = "/handlers",
+    params(
+        ("event_type" = Option<String>, Query, description = "Only list handlers for this event type")
+    ),
     responses(
         (status = 200, description = "List of handlers", body = Vec<HandlerResponse>)
     ),
     tag = "Handlers"
 )]
-pub async fn get_handlers(State(state): State<ApiState>) -> Json<Vec<HandlerResponse>> {
-    let handlers = state.store.get_handlers().await;
+pub async fn get_handlers(
+    State(state): State<ApiState>,
+    Query(query): Query<HandlersQuery>,
+) -> Json<Vec<HandlerResponse>> {
+    let handlers = match &query.event_type {
+        Some(event_type) => state.store.get_handlers_for(event_type).await,
+        None => state.store.get_handlers().await,
+    };
     let responses: Vec<HandlerResponse> = handlers.into_iter().map(handler_to_response).collect();
     Json(responses)
 }
 
 #[utoipa::path(
     get,
-    path = "/handlers/{event_type}",
+    path = "/handlers/{event_type}/{name}",
     params(
-        ("event_type" = String, Path, description = "Event type")
+        ("event_type" = String, Path, description = "Event type"),
+        ("name" = String, Path, description = "Handler name")
     ),
     responses(
         (status = 200, description = "Handler details", body = HandlerResponse),
@@ -203,25 +351,30 @@ pub async fn get_handlers(State(state): State<ApiState>) -> Json<Vec<HandlerResp
     ),
     tag = "Handlers"
 )]
-pub async fn get_handler_by_type(
+pub async fn get_handler(
     State(state): State<ApiState>,
-    Path(event_type): Path<String>,
+    Path((event_type, name)): Path<(String, String)>,
 ) -> Result<Json<HandlerResponse>, StatusCode> {
     state
         .store
-        .get_handler(&event_type)
+        .get_handler(&event_type, &name)
         .await
         .map(|h| Json(handler_to_response(h)))
         .ok_or(StatusCode::NOT_FOUND)
 }
 
+/// Names that routes under `/handlers/{event_type}/` already use, so a handler with one of
+/// them could not be reached
+const RESERVED_HANDLER_NAMES: &[&str] = &["filter-test", "pause", "resume"];
+
 #[utoipa::path(
     post,
     path = "/handlers",
     request_body = CreateHandlerRequest,
     responses(
         (status = 200, description = "Handler created", body = HandlerResponse),
-        (status = 400, description = "Invalid shell type"),
+        (status = 400, description = "Invalid shell type, handler name, follow-up event type, filter, debounce or throttle"),
+        (status = 409, description = "A handler with this event type and name already exists"),
         (status = 500, description = "Internal error")
     ),
     tag = "Handlers"
@@ -237,15 +390,61 @@ pub async fn create_handler(
         )
     })?;
 
+    if request.name.is_empty() || request.name.contains('/') {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid handler name: '{}'", request.name),
+        ));
+    }
+    if RESERVED_HANDLER_NAMES.contains(&request.name.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Handler name '{}' is reserved", request.name),
+        ));
+    }
+
+    validate_retry(request.retry.as_ref())?;
+    validate_max_concurrency(request.max_concurrency)?;
+    validate_rate(request.debounce_secs, request.throttle.as_ref())?;
+
+    let mut handler = EventHandler::new(
+        request.event_type,
+        request.name,
+        shell,
+        request.command,
+        request.timeout,
+        request.env,
+    );
+    handler.retry = request.retry;
+    handler.max_concurrency = request.max_concurrency;
+    handler.on_success = request.on_success;
+    handler.on_failure = request.on_failure;
+    handler.on_timeout = request.on_timeout;
+    handler.filter = request.filter;
+    handler.debounce_secs = request.debounce_secs;
+    handler.throttle = request.throttle;
+    validate_chain(&state.store, &handler).await?;
+    validate_filter(&handler)?;
+    validate_command(&handler)?;
+
+    if state
+        .store
+        .get_handler(&handler.event_type, &handler.name)
+        .await
+        .is_some()
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "Handler '{}/{}' already exists",
+                handler.event_type, handler.name
+            ),
+        ));
+    }
+
     let handler = state
         .store
-        .create_handler(
-            &request.event_type,
-            &shell,
-            &request.command,
-            request.timeout,
-            &request.env,
-        )
+        .create_handler(handler)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -254,21 +453,22 @@ pub async fn create_handler(
 
 #[utoipa::path(
     put,
-    path = "/handlers/{event_type}",
+    path = "/handlers/{event_type}/{name}",
     params(
-        ("event_type" = String, Path, description = "Event type")
+        ("event_type" = String, Path, description = "Event type"),
+        ("name" = String, Path, description = "Handler name")
     ),
     request_body = UpdateHandlerRequest,
     responses(
         (status = 200, description = "Handler updated", body = HandlerResponse),
-        (status = 400, description = "Invalid shell type"),
+        (status = 400, description = "Invalid shell type, follow-up event type, filter, debounce or throttle"),
         (status = 404, description = "Handler not found")
     ),
     tag = "Handlers"
 )]
 pub async fn update_handler(
     State(state): State<ApiState>,
-    Path(event_type): Path<String>,
+    Path((event_type, name)): Path<(String, String)>,
     Json(request): Json<UpdateHandlerRequest>,
 ) -> Result<Json<HandlerResponse>, (StatusCode, String)> {
     let shell = match &request.shell {
@@ -281,27 +481,293 @@ pub async fn update_handler(
         None => None,
     };
 
+    if let Some(retry) = &request.retry {
+        validate_retry(retry.as_ref())?;
+    }
+    if let Some(max_concurrency) = request.max_concurrency {
+        validate_max_concurrency(max_concurrency)?;
+    }
+    validate_rate(
+        request.debounce_secs.flatten(),
+        request.throttle.as_ref().and_then(|t| t.as_ref()),
+    )?;
+
+    let mut handler = state
+        .store
+        .get_handler(&event_type, &name)
+        .await
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Handler '{}/{}' not found", event_type, name),
+            )
+        })?;
+
+    handler.id = Uuid::new_v4();
+    if let Some(shell) = shell {
+        handler.shell = shell;
+    }
+    if let Some(command) = request.command {
+        handler.command = command;
+    }
+    if let Some(timeout) = request.timeout {
+        handler.timeout = timeout;
+    }
+    if let Some(env) = request.env {
+        handler.env = env;
+    }
+    if let Some(retry) = request.retry {
+        handler.retry = retry;
+    }
+    if let Some(max_concurrency) = request.max_concurrency {
+        handler.max_concurrency = max_concurrency;
+    }
+    if let Some(on_success) = request.on_success {
+        handler.on_success = on_success;
+    }
+    if let Some(on_failure) = request.on_failure {
+        handler.on_failure = on_failure;
+    }
+    if let Some(on_timeout) = request.on_timeout {
+        handler.on_timeout = on_timeout;
+    }
+    if let Some(filter) = request.filter {
+        handler.filter = filter;
+    }
+    if let Some(debounce_secs) = request.debounce_secs {
+        handler.debounce_secs = debounce_secs;
+    }
+    if let Some(throttle) = request.throttle {
+        handler.throttle = throttle;
+    }
+    validate_chain(&state.store, &handler).await?;
+    validate_filter(&handler)?;
+    validate_command(&handler)?;
+
     let handler = state
         .store
-        .update_handler(
-            &event_type,
-            shell.as_ref(),
-            request.command.as_deref(),
-            request.timeout,
-            request.env.as_ref(),
-        )
+        .update_handler(handler)
         .await
         .map_err(|e| (StatusCode::NOT_FOUND, e))?;
 
     Ok(Json(handler_to_response(handler)))
 }
 
+fn validate_max_concurrency(max_concurrency: Option<u32>) -> Result<(), (StatusCode, String)> {
+    if max_concurrency == Some(0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "max_concurrency must be at least 1 (omit it for no limit)".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Follow-up event types must be named and must not trigger the handler's own event type again
+async fn validate_chain(
+    store: &JobStore,
+    handler: &EventHandler,
+) -> Result<(), (StatusCode, String)> {
+    let chained = [
+        &handler.on_success,
+        &handler.on_failure,
+        &handler.on_timeout,
+    ];
+    for event_type in chained.into_iter().flatten() {
+        if event_type.is_empty() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Follow-up event types must not be empty".to_string(),
+            ));
+        }
+        if *event_type == handler.event_type {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Handler for '{}' cannot trigger its own event type",
+                    event_type
+                ),
+            ));
+        }
+    }
+    if let Some(cycle) = consumer::follow_up_cycle(&store.get_handlers().await, handler) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Follow-up events would form a cycle: {}",
+                cycle.join(" -> ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_rate(
+    debounce_secs: Option<u32>,
+    throttle: Option<&ThrottlePolicy>,
+) -> Result<(), (StatusCode, String)> {
+    if debounce_secs == Some(0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "debounce_secs must be at least 1 (omit it to run every event)".to_string(),
+        ));
+    }
+    if let Some(throttle) = throttle
+        && (throttle.max_runs == 0 || throttle.window_secs == 0)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Throttle max_runs and window_secs must be at least 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_filter(handler: &EventHandler) -> Result<(), (StatusCode, String)> {
+    match &handler.filter {
+        Some(filter) => Filter::parse(filter)
+            .map(|_| ())
+            .map_err(|e| (StatusCode::BAD_REQUEST, e)),
+        None => Ok(()),
+    }
+}
+
+fn validate_command(handler: &EventHandler) -> Result<(), (StatusCode, String)> {
+    template::validate(&handler.command, &handler.shell).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
 #[utoipa::path(
-    delete,
-    path = "/handlers/{event_type}",
+    post,
+    path = "/handlers/{event_type}/filter-test",
     params(
         ("event_type" = String, Path, description = "Event type")
     ),
+    request_body = FilterTestRequest,
+    responses(
+        (status = 200, description = "Whether the filter, or each handler's filter, matches the context", body = Vec<FilterTestResult>),
+        (status = 400, description = "Invalid filter"),
+        (status = 404, description = "No handlers for the event type")
+    ),
+    tag = "Handlers"
+)]
+pub async fn test_filter(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+    Json(request): Json<FilterTestRequest>,
+) -> Result<Json<Vec<FilterTestResult>>, (StatusCode, String)> {
+    let event = Event::new(event_type.clone(), request.context);
+
+    if let Some(filter) = request.filter {
+        let matches = Filter::parse(&filter)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?
+            .matches(&event);
+        return Ok(Json(vec![FilterTestResult {
+            handler: None,
+            filter: Some(filter),
+            matches,
+        }]));
+    }
+
+    let handlers = state.store.get_handlers_for(&event_type).await;
+    if handlers.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No handlers for event type '{}'", event_type),
+        ));
+    }
+    let results = handlers
+        .into_iter()
+        .map(|h| {
+            let matches = state.store.filters().matches(&h, &event).unwrap_or(false);
+            FilterTestResult {
+                handler: Some(h.name),
+                filter: h.filter,
+                matches,
+            }
+        })
+        .collect();
+    Ok(Json(results))
+}
+
+fn validate_retry(retry: Option<&RetryPolicy>) -> Result<(), (StatusCode, String)> {
+    match retry {
+        Some(r) if r.max_attempts == 0 => Err((
+            StatusCode::BAD_REQUEST,
+            "Retry policy max_attempts must be at least 1".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/handlers/{event_type}/{name}/pause",
+    params(
+        ("event_type" = String, Path, description = "Event type"),
+        ("name" = String, Path, description = "Handler name")
+    ),
+    responses(
+        (status = 200, description = "Handler paused", body = HandlerResponse),
+        (status = 404, description = "Handler not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Handlers"
+)]
+pub async fn pause_handler(
+    State(state): State<ApiState>,
+    Path((event_type, name)): Path<(String, String)>,
+) -> Result<Json<HandlerResponse>, (StatusCode, String)> {
+    set_handler_enabled(&state, &event_type, &name, false).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/handlers/{event_type}/{name}/resume",
+    params(
+        ("event_type" = String, Path, description = "Event type"),
+        ("name" = String, Path, description = "Handler name")
+    ),
+    responses(
+        (status = 200, description = "Handler resumed", body = HandlerResponse),
+        (status = 404, description = "Handler not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Handlers"
+)]
+pub async fn resume_handler(
+    State(state): State<ApiState>,
+    Path((event_type, name)): Path<(String, String)>,
+) -> Result<Json<HandlerResponse>, (StatusCode, String)> {
+    set_handler_enabled(&state, &event_type, &name, true).await
+}
+
+async fn set_handler_enabled(
+    state: &ApiState,
+    event_type: &str,
+    name: &str,
+    enabled: bool,
+) -> Result<Json<HandlerResponse>, (StatusCode, String)> {
+    state
+        .store
+        .set_handler_enabled(event_type, name, enabled)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map(|h| Json(handler_to_response(h)))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Handler '{}/{}' not found", event_type, name),
+            )
+        })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/handlers/{event_type}/{name}",
+    params(
+        ("event_type" = String, Path, description = "Event type"),
+        ("name" = String, Path, description = "Handler name")
+    ),
     responses(
         (status = 200, description = "Handler deleted"),
         (status = 404, description = "Handler not found"),
@@ -311,11 +777,11 @@ pub async fn update_handler(
 )]
 pub async fn delete_handler(
     State(state): State<ApiState>,
-    Path(event_type): Path<String>,
+    Path((event_type, name)): Path<(String, String)>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     let deleted = state
         .store
-        .delete_handler(&event_type)
+        .delete_handler(&event_type, &name)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -324,17 +790,27 @@ pub async fn delete_handler(
     } else {
         Err((
             StatusCode::NOT_FOUND,
-            format!("Handler '{}' not found", event_type),
+            format!("Handler '{}/{}' not found", event_type, name),
         ))
     }
 }
 
-fn timer_to_response(t: crate::db::TimerRecord) -> TimerResponse {
+fn timer_to_response(t: TimerRecord) -> TimerResponse {
     TimerResponse {
         id: t.id.to_string(),
         event_type: t.event_type,
         context: t.context,
         interval_secs: t.interval_secs,
+        overlap: t.overlap,
+        last_fired_at: t.last_fired_at,
+        next_fire_at: t.next_fire_at,
+        enabled: t.enabled,
+        align: t.align,
+        jitter_secs: t.jitter_secs,
+        start_at: t.start_at,
+        end_at: t.end_at,
+        max_fires: t.max_fires,
+        fire_count: t.fire_count,
     }
 }
 
@@ -382,6 +858,7 @@ pub async fn get_timer_by_type(
     request_body = CreateTimerRequest,
     responses(
         (status = 200, description = "Timer created", body = TimerResponse),
+        (status = 400, description = "Invalid timing"),
         (status = 500, description = "Internal error")
     ),
     tag = "Timers"
@@ -390,9 +867,18 @@ pub async fn create_timer(
     State(state): State<ApiState>,
     Json(request): Json<CreateTimerRequest>,
 ) -> Result<Json<TimerResponse>, (StatusCode, String)> {
+    let mut timer = TimerRecord::new(request.event_type, request.context, request.interval_secs);
+    timer.overlap = request.overlap;
+    timer.align = request.align;
+    timer.jitter_secs = request.jitter_secs;
+    timer.start_at = request.start_at;
+    timer.end_at = request.end_at;
+    timer.max_fires = request.max_fires;
+    recurrence::validate_timer(&timer).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     let timer = state
         .store
-        .create_timer(&request.event_type, request.interval_secs, &request.context)
+        .create_timer(timer)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -413,6 +899,7 @@ pub async fn create_timer(
     request_body = UpdateTimerRequest,
     responses(
         (status = 200, description = "Timer updated", body = TimerResponse),
+        (status = 400, description = "Invalid timing"),
         (status = 404, description = "Timer not found")
     ),
     tag = "Timers"
@@ -422,13 +909,49 @@ pub async fn update_timer(
     Path(event_type): Path<String>,
     Json(request): Json<UpdateTimerRequest>,
 ) -> Result<Json<TimerResponse>, (StatusCode, String)> {
+    let mut timer = state.store.get_timer(&event_type).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Timer '{}' not found", event_type),
+        )
+    })?;
+
+    timer.id = Uuid::new_v4();
+    if let Some(interval_secs) = request.interval_secs {
+        timer.interval_secs = interval_secs;
+    }
+    if let Some(context) = request.context {
+        timer.context = context;
+    }
+    if let Some(overlap) = request.overlap {
+        timer.overlap = overlap;
+    }
+    if let Some(jitter_secs) = request.jitter_secs {
+        timer.jitter_secs = jitter_secs;
+    }
+    if let Some(end_at) = request.end_at {
+        timer.end_at = end_at;
+    }
+    if let Some(max_fires) = request.max_fires {
+        timer.max_fires = max_fires;
+    }
+    // A new cadence starts from the last event instead of the previously planned one
+    let retimed =
+        request.interval_secs.is_some() || request.align.is_some() || request.start_at.is_some();
+    if let Some(align) = request.align {
+        timer.align = align;
+    }
+    if let Some(start_at) = request.start_at {
+        timer.start_at = start_at;
+    }
+    if retimed {
+        timer.next_fire_at = None;
+    }
+    recurrence::validate_timer(&timer).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     let timer = state
         .store
-        .update_timer_record(
-            &event_type,
-            request.interval_secs,
-            request.context.as_deref(),
-        )
+        .update_timer_record(timer)
         .await
         .map_err(|e| (StatusCode::NOT_FOUND, e))?;
 
@@ -440,6 +963,65 @@ pub async fn update_timer(
     Ok(Json(timer_to_response(timer)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/timers/{event_type}/pause",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Timer paused", body = TimerResponse),
+        (status = 404, description = "Timer not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Timers"
+)]
+pub async fn pause_timer(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<TimerResponse>, (StatusCode, String)> {
+    set_timer_enabled(&state, &event_type, false).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/timers/{event_type}/resume",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Timer resumed", body = TimerResponse),
+        (status = 404, description = "Timer not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Timers"
+)]
+pub async fn resume_timer(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<TimerResponse>, (StatusCode, String)> {
+    set_timer_enabled(&state, &event_type, true).await
+}
+
+async fn set_timer_enabled(
+    state: &ApiState,
+    event_type: &str,
+    enabled: bool,
+) -> Result<Json<TimerResponse>, (StatusCode, String)> {
+    state
+        .store
+        .set_timer_enabled(event_type, enabled)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map(|t| Json(timer_to_response(t)))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Timer '{}' not found", event_type),
+            )
+        })
+}
+
 #[utoipa::path(
     delete,
     path = "/timers/{event_type}",
@@ -493,6 +1075,8 @@ pub async fn reload(State(state): State<ApiState>) -> Json<ReloadResponse> {
             .await;
     }
 
+    let windows_loaded = state.store.load_windows().await;
+
     let schedules = state.store.load_schedules().await;
     for schedule in &schedules {
         state
@@ -501,21 +1085,55 @@ pub async fn reload(State(state): State<ApiState>) -> Json<ReloadResponse> {
             .await;
     }
 
+    let watchers = state.store.load_watchers().await;
+    for watcher in &watchers {
+        state
+            .watcher_manager
+            .register_watcher(watcher.clone(), state.sender.clone())
+            .await;
+    }
+
+    let tails = state.store.load_tails().await;
+    for tail in &tails {
+        state
+            .tail_manager
+            .register_tail(tail.clone(), state.sender.clone())
+            .await;
+    }
+
+    let webhooks_loaded = state.store.load_webhooks().await;
+    let workflows_loaded = state.store.load_workflows().await;
+
     Json(ReloadResponse {
         success: true,
         handlers_loaded: handlers.len(),
         timers_loaded: timers.len(),
         schedules_loaded: schedules.len(),
+        windows_loaded,
+        watchers_loaded: watchers.len(),
+        webhooks_loaded,
+        tails_loaded: tails.len(),
+        workflows_loaded,
     })
 }
 
-fn schedule_to_response(s: crate::db::ScheduleRecord) -> ScheduleResponse {
+fn schedule_to_response(s: ScheduleRecord) -> ScheduleResponse {
     ScheduleResponse {
         id: s.id.to_string(),
         event_type: s.event_type,
         context: s.context,
         scheduled_time: s.scheduled_time,
         periodic: s.periodic,
+        overlap: s.overlap,
+        cron: s.cron,
+        timezone: s.timezone,
+        misfire: s.misfire,
+        state: s.state,
+        last_fired_at: s.last_fired_at,
+        next_fire_at: s.next_fire_at,
+        enabled: s.enabled,
+        rrule: s.rrule,
+        exdates: s.exdates,
     }
 }
 
@@ -558,96 +1176,1179 @@ pub async fn get_schedule_by_type(
         .ok_or(StatusCode::NOT_FOUND)
 }
 
+/// Upper bound on the number of fire times returned by a preview
+const MAX_PREVIEW_COUNT: usize = 1000;
+
+#[derive(Deserialize, ToSchema)]
+pub struct PreviewQuery {
+    pub count: Option<usize>,
+}
+
 #[utoipa::path(
-    post,
-    path = "/schedules",
-    request_body = CreateScheduleRequest,
+    get,
+    path = "/schedules/{event_type}/preview",
+    params(
+        ("event_type" = String, Path, description = "Event type"),
+        ("count" = Option<usize>, Query, description = "Number of fire times to list (default 10)")
+    ),
     responses(
-        (status = 200, description = "Schedule created", body = ScheduleResponse),
-        (status = 500, description = "Internal error")
+        (status = 200, description = "Upcoming fire times", body = SchedulePreviewResponse),
+        (status = 404, description = "Schedule not found"),
+        (status = 500, description = "Schedule cannot be evaluated")
     ),
     tag = "Schedules"
 )]
-pub async fn create_schedule(
+pub async fn preview_schedule(
     State(state): State<ApiState>,
-    Json(request): Json<CreateScheduleRequest>,
+    Path(event_type): Path<String>,
+    Query(query): Query<PreviewQuery>,
+) -> Result<Json<SchedulePreviewResponse>, (StatusCode, String)> {
+    let schedule = state.store.get_schedule(&event_type).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Schedule '{}' not found", event_type),
+        )
+    })?;
+
+    let count = query.count.unwrap_or(10).min(MAX_PREVIEW_COUNT);
+    let fire_times = recurrence::upcoming(&schedule, Utc::now(), count)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(SchedulePreviewResponse {
+        event_type,
+        fire_times,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/schedules",
+    request_body = CreateScheduleRequest,
+    responses(
+        (status = 200, description = "Schedule created", body = ScheduleResponse),
+        (status = 400, description = "Invalid cron expression, rrule or time zone"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Schedules"
+)]
+pub async fn create_schedule(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateScheduleRequest>,
+) -> Result<Json<ScheduleResponse>, (StatusCode, String)> {
+    let scheduled_time = match request.scheduled_time {
+        Some(time) => time,
+        None if request.cron.is_some() || request.rrule.is_some() => Utc::now().trunc_subsecs(0),
+        None => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "scheduled_time is required unless a cron expression or rrule is given".to_string(),
+            ));
+        }
+    };
+    let mut schedule = ScheduleRecord::new(
+        request.event_type,
+        request.context,
+        scheduled_time,
+        request.periodic,
+    );
+    schedule.overlap = request.overlap;
+    schedule.cron = request.cron;
+    schedule.timezone = request.timezone;
+    schedule.misfire = request.misfire;
+    schedule.rrule = request.rrule;
+    schedule.exdates = request.exdates;
+    recurrence::validate(&schedule).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let schedule = state
+        .store
+        .create_schedule(schedule)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    state
+        .schedule_manager
+        .register_schedule(schedule.clone(), state.sender.clone())
+        .await;
+
+    Ok(Json(schedule_to_response(schedule)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/schedules/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    request_body = UpdateScheduleRequest,
+    responses(
+        (status = 200, description = "Schedule updated", body = ScheduleResponse),
+        (status = 400, description = "Invalid cron expression, rrule or time zone"),
+        (status = 404, description = "Schedule not found")
+    ),
+    tag = "Schedules"
+)]
+pub async fn update_schedule(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+    Json(request): Json<UpdateScheduleRequest>,
 ) -> Result<Json<ScheduleResponse>, (StatusCode, String)> {
+    let mut schedule = state.store.get_schedule(&event_type).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Schedule '{}' not found", event_type),
+        )
+    })?;
+
+    schedule.id = Uuid::new_v4();
+    // Changing when the schedule fires re-arms it
+    if request.scheduled_time.is_some()
+        || request.periodic.is_some()
+        || request.cron.is_some()
+        || request.timezone.is_some()
+        || request.rrule.is_some()
+        || request.exdates.is_some()
+    {
+        schedule.state = ScheduleState::Active;
+        schedule.next_fire_at = None;
+    }
+    if let Some(scheduled_time) = request.scheduled_time {
+        schedule.scheduled_time = scheduled_time;
+    }
+    if let Some(context) = request.context {
+        schedule.context = context;
+    }
+    if let Some(periodic) = request.periodic {
+        schedule.periodic = periodic;
+    }
+    if let Some(overlap) = request.overlap {
+        schedule.overlap = overlap;
+    }
+    if let Some(cron) = request.cron {
+        schedule.cron = cron;
+    }
+    if let Some(timezone) = request.timezone {
+        schedule.timezone = timezone;
+    }
+    if let Some(misfire) = request.misfire {
+        schedule.misfire = misfire;
+    }
+    if let Some(rrule) = request.rrule {
+        schedule.rrule = rrule;
+    }
+    if let Some(exdates) = request.exdates {
+        schedule.exdates = exdates;
+    }
+    recurrence::validate(&schedule).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     let schedule = state
         .store
-        .create_schedule(
-            &request.event_type,
-            request.scheduled_time,
-            &request.context,
-            request.periodic,
-        )
+        .update_schedule_record(schedule)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    state
+        .schedule_manager
+        .register_schedule(schedule.clone(), state.sender.clone())
+        .await;
+
+    Ok(Json(schedule_to_response(schedule)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/schedules/{event_type}/pause",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Schedule paused", body = ScheduleResponse),
+        (status = 404, description = "Schedule not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Schedules"
+)]
+pub async fn pause_schedule(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<ScheduleResponse>, (StatusCode, String)> {
+    set_schedule_enabled(&state, &event_type, false).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/schedules/{event_type}/resume",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Schedule resumed", body = ScheduleResponse),
+        (status = 404, description = "Schedule not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Schedules"
+)]
+pub async fn resume_schedule(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<ScheduleResponse>, (StatusCode, String)> {
+    set_schedule_enabled(&state, &event_type, true).await
+}
+
+async fn set_schedule_enabled(
+    state: &ApiState,
+    event_type: &str,
+    enabled: bool,
+) -> Result<Json<ScheduleResponse>, (StatusCode, String)> {
+    state
+        .store
+        .set_schedule_enabled(event_type, enabled)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map(|s| Json(schedule_to_response(s)))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Schedule '{}' not found", event_type),
+            )
+        })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/schedules/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Schedule deleted"),
+        (status = 404, description = "Schedule not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Schedules"
+)]
+pub async fn delete_schedule(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let deleted = state
+        .store
+        .delete_schedule(&event_type)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if deleted {
+        Ok(Json(serde_json::json!({"deleted": true})))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("Schedule '{}' not found", event_type),
+        ))
+    }
+}
+
+fn window_to_response(w: WindowRecord) -> WindowResponse {
+    let active = window::is_active(&w, Utc::now());
+    WindowResponse {
+        id: w.id.to_string(),
+        name: w.name,
+        description: w.description,
+        event_type: w.event_type,
+        handler_name: w.handler_name,
+        action: w.action,
+        start_time: w.start_time.map(|t| t.format("%H:%M").to_string()),
+        end_time: w.end_time.map(|t| t.format("%H:%M").to_string()),
+        days: w
+            .days
+            .iter()
+            .map(|d| d.to_string().to_lowercase())
+            .collect(),
+        dates: w.dates,
+        timezone: w.timezone,
+        starts_at: w.starts_at,
+        ends_at: w.ends_at,
+        active,
+    }
+}
+
+fn parse_time_of_day(value: Option<String>) -> Result<Option<NaiveTime>, (StatusCode, String)> {
+    value
+        .as_deref()
+        .map(window::parse_time_of_day)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+#[utoipa::path(
+    get,
+    path = "/windows",
+    responses(
+        (status = 200, description = "List of blackout windows", body = Vec<WindowResponse>)
+    ),
+    tag = "Windows"
+)]
+pub async fn get_windows(State(state): State<ApiState>) -> Json<Vec<WindowResponse>> {
+    let windows = state.store.get_windows().await;
+    Json(windows.into_iter().map(window_to_response).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/windows/{name}",
+    params(
+        ("name" = String, Path, description = "Window name")
+    ),
+    responses(
+        (status = 200, description = "Window details", body = WindowResponse),
+        (status = 404, description = "Window not found")
+    ),
+    tag = "Windows"
+)]
+pub async fn get_window(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<WindowResponse>, StatusCode> {
+    state
+        .store
+        .get_window(&name)
+        .await
+        .map(|w| Json(window_to_response(w)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[utoipa::path(
+    post,
+    path = "/windows",
+    request_body = CreateWindowRequest,
+    responses(
+        (status = 200, description = "Window created", body = WindowResponse),
+        (status = 400, description = "Invalid window"),
+        (status = 409, description = "A window with this name already exists"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Windows"
+)]
+pub async fn create_window(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateWindowRequest>,
+) -> Result<Json<WindowResponse>, (StatusCode, String)> {
+    let mut window = WindowRecord::new(request.name);
+    window.description = request.description;
+    window.event_type = request.event_type;
+    window.handler_name = request.handler_name;
+    window.action = request.action;
+    window.start_time = parse_time_of_day(request.start_time)?;
+    window.end_time = parse_time_of_day(request.end_time)?;
+    window.days = window::parse_days(&request.days).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    window.dates = request.dates;
+    window.timezone = request.timezone;
+    window.starts_at = request.starts_at;
+    window.ends_at = request.ends_at;
+    window::validate(&window).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    if state.store.get_window(&window.name).await.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Window '{}' already exists", window.name),
+        ));
+    }
+
+    let window = state
+        .store
+        .create_window(window)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!("Window '{}' created", window.name);
+    Ok(Json(window_to_response(window)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/windows/{name}",
+    params(
+        ("name" = String, Path, description = "Window name")
+    ),
+    request_body = UpdateWindowRequest,
+    responses(
+        (status = 200, description = "Window updated", body = WindowResponse),
+        (status = 400, description = "Invalid window"),
+        (status = 404, description = "Window not found")
+    ),
+    tag = "Windows"
+)]
+pub async fn update_window(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Json(request): Json<UpdateWindowRequest>,
+) -> Result<Json<WindowResponse>, (StatusCode, String)> {
+    let mut window = state.store.get_window(&name).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Window '{}' not found", name),
+        )
+    })?;
+
+    if let Some(description) = request.description {
+        window.description = description;
+    }
+    if let Some(event_type) = request.event_type {
+        window.event_type = event_type;
+    }
+    if let Some(handler_name) = request.handler_name {
+        window.handler_name = handler_name;
+    }
+    if let Some(action) = request.action {
+        window.action = action;
+    }
+    if let Some(start_time) = request.start_time {
+        window.start_time = parse_time_of_day(start_time)?;
+    }
+    if let Some(end_time) = request.end_time {
+        window.end_time = parse_time_of_day(end_time)?;
+    }
+    if let Some(days) = request.days {
+        window.days = window::parse_days(&days).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if let Some(dates) = request.dates {
+        window.dates = dates;
+    }
+    if let Some(timezone) = request.timezone {
+        window.timezone = timezone;
+    }
+    if let Some(starts_at) = request.starts_at {
+        window.starts_at = starts_at;
+    }
+    if let Some(ends_at) = request.ends_at {
+        window.ends_at = ends_at;
+    }
+    window::validate(&window).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let window = state
+        .store
+        .update_window(window)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    Ok(Json(window_to_response(window)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/windows/{name}",
+    params(
+        ("name" = String, Path, description = "Window name")
+    ),
+    responses(
+        (status = 200, description = "Window deleted"),
+        (status = 404, description = "Window not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Windows"
+)]
+pub async fn delete_window(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let deleted = state
+        .store
+        .delete_window(&name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if deleted {
+        Ok(Json(serde_json::json!({"deleted": true})))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("Window '{}' not found", name),
+        ))
+    }
+}
+
+fn watcher_to_response(w: WatcherRecord) -> WatcherResponse {
+    WatcherResponse {
+        id: w.id.to_string(),
+        event_type: w.event_type,
+        paths: w.paths,
+        include: w.include,
+        exclude: w.exclude,
+        recursive: w.recursive,
+        changes: w.changes,
+        debounce_ms: w.debounce_ms,
+        overlap: w.overlap,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/watchers",
+    responses(
+        (status = 200, description = "List of watchers", body = Vec<WatcherResponse>)
+    ),
+    tag = "Watchers"
+)]
+pub async fn get_watchers(State(state): State<ApiState>) -> Json<Vec<WatcherResponse>> {
+    let watchers = state.store.get_watchers().await;
+    Json(watchers.into_iter().map(watcher_to_response).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/watchers/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Watcher details", body = WatcherResponse),
+        (status = 404, description = "Watcher not found")
+    ),
+    tag = "Watchers"
+)]
+pub async fn get_watcher(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<WatcherResponse>, StatusCode> {
+    state
+        .store
+        .get_watcher(&event_type)
+        .await
+        .map(|w| Json(watcher_to_response(w)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[utoipa::path(
+    post,
+    path = "/watchers",
+    request_body = CreateWatcherRequest,
+    responses(
+        (status = 200, description = "Watcher created", body = WatcherResponse),
+        (status = 400, description = "Invalid paths, globs or debounce"),
+        (status = 409, description = "A watcher for this event type already exists"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Watchers"
+)]
+pub async fn create_watcher(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateWatcherRequest>,
+) -> Result<Json<WatcherResponse>, (StatusCode, String)> {
+    let mut watcher = WatcherRecord::new(request.event_type, request.paths);
+    watcher.include = request.include;
+    watcher.exclude = request.exclude;
+    watcher.recursive = request.recursive;
+    watcher.changes = request.changes;
+    watcher.debounce_ms = request.debounce_ms;
+    watcher.overlap = request.overlap;
+    watcher::validate(&watcher).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    if state.store.get_watcher(&watcher.event_type).await.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Watcher for '{}' already exists", watcher.event_type),
+        ));
+    }
+
+    let watcher = state
+        .store
+        .create_watcher(watcher)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    state
+        .watcher_manager
+        .register_watcher(watcher.clone(), state.sender.clone())
+        .await;
+
+    Ok(Json(watcher_to_response(watcher)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/watchers/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    request_body = UpdateWatcherRequest,
+    responses(
+        (status = 200, description = "Watcher updated", body = WatcherResponse),
+        (status = 400, description = "Invalid paths, globs or debounce"),
+        (status = 404, description = "Watcher not found")
+    ),
+    tag = "Watchers"
+)]
+pub async fn update_watcher(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+    Json(request): Json<UpdateWatcherRequest>,
+) -> Result<Json<WatcherResponse>, (StatusCode, String)> {
+    let mut watcher = state.store.get_watcher(&event_type).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Watcher '{}' not found", event_type),
+        )
+    })?;
+
+    watcher.id = Uuid::new_v4();
+    if let Some(paths) = request.paths {
+        watcher.paths = paths;
+    }
+    if let Some(include) = request.include {
+        watcher.include = include;
+    }
+    if let Some(exclude) = request.exclude {
+        watcher.exclude = exclude;
+    }
+    if let Some(recursive) = request.recursive {
+        watcher.recursive = recursive;
+    }
+    if let Some(changes) = request.changes {
+        watcher.changes = changes;
+    }
+    if let Some(debounce_ms) = request.debounce_ms {
+        watcher.debounce_ms = debounce_ms;
+    }
+    if let Some(overlap) = request.overlap {
+        watcher.overlap = overlap;
+    }
+    watcher::validate(&watcher).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let watcher = state
+        .store
+        .update_watcher_record(watcher)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    state
+        .watcher_manager
+        .register_watcher(watcher.clone(), state.sender.clone())
+        .await;
+
+    Ok(Json(watcher_to_response(watcher)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/watchers/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Watcher deleted"),
+        (status = 404, description = "Watcher not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Watchers"
+)]
+pub async fn delete_watcher(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let deleted = state
+        .store
+        .delete_watcher(&event_type)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if deleted {
+        Ok(Json(serde_json::json!({"deleted": true})))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("Watcher '{}' not found", event_type),
+        ))
+    }
+}
+
+fn tail_to_response(t: TailRecord) -> TailResponse {
+    TailResponse {
+        id: t.id.to_string(),
+        event_type: t.event_type,
+        path: t.path,
+        patterns: t.patterns,
+        from_start: t.from_start,
+        overlap: t.overlap,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tails",
+    responses(
+        (status = 200, description = "List of tails", body = Vec<TailResponse>)
+    ),
+    tag = "Tails"
+)]
+pub async fn get_tails(State(state): State<ApiState>) -> Json<Vec<TailResponse>> {
+    let tails = state.store.get_tails().await;
+    Json(tails.into_iter().map(tail_to_response).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/tails/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Tail details", body = TailResponse),
+        (status = 404, description = "Tail not found")
+    ),
+    tag = "Tails"
+)]
+pub async fn get_tail(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<TailResponse>, StatusCode> {
+    state
+        .store
+        .get_tail(&event_type)
+        .await
+        .map(|t| Json(tail_to_response(t)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[utoipa::path(
+    post,
+    path = "/tails",
+    request_body = CreateTailRequest,
+    responses(
+        (status = 200, description = "Tail created", body = TailResponse),
+        (status = 400, description = "Invalid path or patterns"),
+        (status = 409, description = "A tail for this event type already exists"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Tails"
+)]
+pub async fn create_tail(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateTailRequest>,
+) -> Result<Json<TailResponse>, (StatusCode, String)> {
+    let mut tail = TailRecord::new(request.event_type, request.path, request.patterns);
+    tail.from_start = request.from_start;
+    tail.overlap = request.overlap;
+    tail::validate(&tail).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    if state.store.get_tail(&tail.event_type).await.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Tail for '{}' already exists", tail.event_type),
+        ));
+    }
+
+    let tail = state
+        .store
+        .create_tail(tail)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    state
+        .tail_manager
+        .register_tail(tail.clone(), state.sender.clone())
+        .await;
+
+    Ok(Json(tail_to_response(tail)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/tails/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    request_body = UpdateTailRequest,
+    responses(
+        (status = 200, description = "Tail updated", body = TailResponse),
+        (status = 400, description = "Invalid path or patterns"),
+        (status = 404, description = "Tail not found")
+    ),
+    tag = "Tails"
+)]
+pub async fn update_tail(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+    Json(request): Json<UpdateTailRequest>,
+) -> Result<Json<TailResponse>, (StatusCode, String)> {
+    let mut tail = state.store.get_tail(&event_type).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Tail '{}' not found", event_type),
+        )
+    })?;
+
+    tail.id = Uuid::new_v4();
+    if let Some(path) = request.path {
+        tail.path = path;
+    }
+    if let Some(patterns) = request.patterns {
+        tail.patterns = patterns;
+    }
+    if let Some(from_start) = request.from_start {
+        tail.from_start = from_start;
+    }
+    if let Some(overlap) = request.overlap {
+        tail.overlap = overlap;
+    }
+    tail::validate(&tail).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let tail = state
+        .store
+        .update_tail_record(tail)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    state
+        .tail_manager
+        .register_tail(tail.clone(), state.sender.clone())
+        .await;
+
+    Ok(Json(tail_to_response(tail)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tails/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Tail deleted"),
+        (status = 404, description = "Tail not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Tails"
+)]
+pub async fn delete_tail(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let deleted = state
+        .store
+        .delete_tail(&event_type)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if deleted {
+        Ok(Json(serde_json::json!({"deleted": true})))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("Tail '{}' not found", event_type),
+        ))
+    }
+}
+
+fn webhook_to_response(w: WebhookRecord) -> WebhookResponse {
+    WebhookResponse {
+        id: w.id.to_string(),
+        event_type: w.event_type,
+        signed: w.secret.is_some(),
+        signature_header: w.signature_header,
+        fields: w.fields,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    responses(
+        (status = 200, description = "List of webhooks", body = Vec<WebhookResponse>)
+    ),
+    tag = "Webhooks"
+)]
+pub async fn get_webhooks(State(state): State<ApiState>) -> Json<Vec<WebhookResponse>> {
+    let webhooks = state.store.get_webhooks().await;
+    Json(webhooks.into_iter().map(webhook_to_response).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Webhook details", body = WebhookResponse),
+        (status = 404, description = "Webhook not found")
+    ),
+    tag = "Webhooks"
+)]
+pub async fn get_webhook(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<WebhookResponse>, StatusCode> {
+    state
+        .store
+        .get_webhook(&event_type)
+        .await
+        .map(|w| Json(webhook_to_response(w)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook created", body = WebhookResponse),
+        (status = 400, description = "Invalid secret, header or fields"),
+        (status = 409, description = "A webhook for this event type already exists"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Webhooks"
+)]
+pub async fn create_webhook(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<Json<WebhookResponse>, (StatusCode, String)> {
+    let mut webhook = WebhookRecord::new(request.event_type);
+    webhook.secret = request.secret;
+    webhook.signature_header = request.signature_header;
+    webhook.fields = request.fields;
+    webhook::validate(&webhook).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    if state.store.get_webhook(&webhook.event_type).await.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Webhook for '{}' already exists", webhook.event_type),
+        ));
+    }
+
+    let webhook = state
+        .store
+        .create_webhook(webhook)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(webhook_to_response(webhook)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/webhooks/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook updated", body = WebhookResponse),
+        (status = 400, description = "Invalid secret, header or fields"),
+        (status = 404, description = "Webhook not found")
+    ),
+    tag = "Webhooks"
+)]
+pub async fn update_webhook(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+    Json(request): Json<UpdateWebhookRequest>,
+) -> Result<Json<WebhookResponse>, (StatusCode, String)> {
+    let mut webhook = state.store.get_webhook(&event_type).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Webhook '{}' not found", event_type),
+        )
+    })?;
+
+    webhook.id = Uuid::new_v4();
+    if let Some(secret) = request.secret {
+        webhook.secret = secret;
+    }
+    if let Some(signature_header) = request.signature_header {
+        webhook.signature_header = signature_header;
+    }
+    if let Some(fields) = request.fields {
+        webhook.fields = fields;
+    }
+    webhook::validate(&webhook).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let webhook = state
+        .store
+        .update_webhook(webhook)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    Ok(Json(webhook_to_response(webhook)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted"),
+        (status = 404, description = "Webhook not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Webhooks"
+)]
+pub async fn delete_webhook(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let deleted = state
+        .store
+        .delete_webhook(&event_type)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if deleted {
+        Ok(Json(serde_json::json!({"deleted": true})))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("Webhook '{}' not found", event_type),
+        ))
+    }
+}
+
+fn workflow_to_response(w: WorkflowRecord) -> WorkflowResponse {
+    WorkflowResponse {
+        id: w.id.to_string(),
+        name: w.name,
+        description: w.description,
+        steps: w.steps,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/workflows",
+    responses(
+        (status = 200, description = "List of workflows", body = Vec<WorkflowResponse>)
+    ),
+    tag = "Workflows"
+)]
+pub async fn get_workflows(State(state): State<ApiState>) -> Json<Vec<WorkflowResponse>> {
+    let workflows = state.store.get_workflows().await;
+    Json(workflows.into_iter().map(workflow_to_response).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/workflows/{name}",
+    params(
+        ("name" = String, Path, description = "Workflow name")
+    ),
+    responses(
+        (status = 200, description = "Workflow details", body = WorkflowResponse),
+        (status = 404, description = "Workflow not found")
+    ),
+    tag = "Workflows"
+)]
+pub async fn get_workflow(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<WorkflowResponse>, StatusCode> {
+    state
+        .store
+        .get_workflow(&name)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        .map(|w| Json(workflow_to_response(w)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
 
-    state
-        .schedule_manager
-        .register_schedule(schedule.clone(), state.sender.clone())
-        .await;
+#[utoipa::path(
+    post,
+    path = "/workflows",
+    request_body = CreateWorkflowRequest,
+    responses(
+        (status = 200, description = "Workflow created", body = WorkflowResponse),
+        (status = 400, description = "Invalid steps, e.g. unknown dependencies or a cycle"),
+        (status = 409, description = "A workflow with this name already exists"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Workflows"
+)]
+pub async fn create_workflow(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateWorkflowRequest>,
+) -> Result<Json<WorkflowResponse>, (StatusCode, String)> {
+    let mut workflow = WorkflowRecord::new(request.name, request.steps);
+    workflow.description = request.description;
+    workflow::validate(&workflow).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
-    Ok(Json(schedule_to_response(schedule)))
+    if state.store.get_workflow(&workflow.name).await.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Workflow '{}' already exists", workflow.name),
+        ));
+    }
+
+    let workflow = state
+        .store
+        .create_workflow(workflow)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!("Workflow '{}' created", workflow.name);
+    Ok(Json(workflow_to_response(workflow)))
 }
 
 #[utoipa::path(
     put,
-    path = "/schedules/{event_type}",
+    path = "/workflows/{name}",
     params(
-        ("event_type" = String, Path, description = "Event type")
+        ("name" = String, Path, description = "Workflow name")
     ),
-    request_body = UpdateScheduleRequest,
+    request_body = UpdateWorkflowRequest,
     responses(
-        (status = 200, description = "Schedule updated", body = ScheduleResponse),
-        (status = 404, description = "Schedule not found")
+        (status = 200, description = "Workflow updated", body = WorkflowResponse),
+        (status = 400, description = "Invalid steps, e.g. unknown dependencies or a cycle"),
+        (status = 404, description = "Workflow not found")
     ),
-    tag = "Schedules"
+    tag = "Workflows"
 )]
-pub async fn update_schedule(
+pub async fn update_workflow(
     State(state): State<ApiState>,
-    Path(event_type): Path<String>,
-    Json(request): Json<UpdateScheduleRequest>,
-) -> Result<Json<ScheduleResponse>, (StatusCode, String)> {
-    let schedule = state
-        .store
-        .update_schedule_record(
-            &event_type,
-            request.scheduled_time,
-            request.context.as_deref(),
-            request.periodic,
+    Path(name): Path<String>,
+    Json(request): Json<UpdateWorkflowRequest>,
+) -> Result<Json<WorkflowResponse>, (StatusCode, String)> {
+    let mut workflow = state.store.get_workflow(&name).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Workflow '{}' not found", name),
         )
+    })?;
+
+    workflow.id = Uuid::new_v4();
+    if let Some(description) = request.description {
+        workflow.description = description;
+    }
+    if let Some(steps) = request.steps {
+        workflow.steps = steps;
+    }
+    workflow::validate(&workflow).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let workflow = state
+        .store
+        .update_workflow(workflow)
         .await
         .map_err(|e| (StatusCode::NOT_FOUND, e))?;
 
-    state
-        .schedule_manager
-        .register_schedule(schedule.clone(), state.sender.clone())
-        .await;
-
-    Ok(Json(schedule_to_response(schedule)))
+    Ok(Json(workflow_to_response(workflow)))
 }
 
 #[utoipa::path(
     delete,
-    path = "/schedules/{event_type}",
+    path = "/workflows/{name}",
     params(
-        ("event_type" = String, Path, description = "Event type")
+        ("name" = String, Path, description = "Workflow name")
     ),
     responses(
-        (status = 200, description = "Schedule deleted"),
-        (status = 404, description = "Schedule not found"),
+        (status = 200, description = "Workflow deleted"),
+        (status = 404, description = "Workflow not found"),
         (status = 500, description = "Internal error")
     ),
-    tag = "Schedules"
+    tag = "Workflows"
 )]
-pub async fn delete_schedule(
+pub async fn delete_workflow(
     State(state): State<ApiState>,
-    Path(event_type): Path<String>,
+    Path(name): Path<String>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     let deleted = state
         .store
-        .delete_schedule(&event_type)
+        .delete_workflow(&name)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -656,11 +2357,114 @@ pub async fn delete_schedule(
     } else {
         Err((
             StatusCode::NOT_FOUND,
-            format!("Schedule '{}' not found", event_type),
+            format!("Workflow '{}' not found", name),
         ))
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/workflows/{name}/run",
+    params(
+        ("name" = String, Path, description = "Workflow name")
+    ),
+    request_body = RunWorkflowRequest,
+    responses(
+        (status = 200, description = "Run started", body = WorkflowRun),
+        (status = 400, description = "A step's handler does not exist"),
+        (status = 404, description = "Workflow not found"),
+        (status = 500, description = "Internal error")
+    ),
+    tag = "Workflows"
+)]
+pub async fn run_workflow(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    request: Option<Json<RunWorkflowRequest>>,
+) -> Result<Json<WorkflowRun>, (StatusCode, String)> {
+    let workflow = state.store.get_workflow(&name).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Workflow '{}' not found", name),
+        )
+    })?;
+
+    for step in &workflow.steps {
+        if state
+            .store
+            .get_handler(&step.event_type, &step.handler)
+            .await
+            .is_none()
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Step '{}' runs handler '{}/{}', which does not exist",
+                    step.name, step.event_type, step.handler
+                ),
+            ));
+        }
+    }
+
+    let Json(request) = request.unwrap_or_default();
+    let run = workflow::start_run(&state.store, &state.sender, workflow, request.context)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(run))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WorkflowRunsQuery {
+    pub workflow: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/workflow-runs",
+    params(
+        ("workflow" = Option<String>, Query, description = "Only list runs of this workflow")
+    ),
+    responses(
+        (status = 200, description = "Workflow runs, latest first", body = Vec<WorkflowRun>)
+    ),
+    tag = "Workflows"
+)]
+pub async fn get_workflow_runs(
+    State(state): State<ApiState>,
+    Query(query): Query<WorkflowRunsQuery>,
+) -> Json<Vec<WorkflowRun>> {
+    let runs = state
+        .store
+        .get_workflow_runs(query.workflow.as_deref())
+        .await;
+    Json(runs)
+}
+
+#[utoipa::path(
+    get,
+    path = "/workflow-runs/{run_id}",
+    params(
+        ("run_id" = Uuid, Path, description = "Run ID")
+    ),
+    responses(
+        (status = 200, description = "Run with the status of each step", body = WorkflowRun),
+        (status = 404, description = "Run not found")
+    ),
+    tag = "Workflows"
+)]
+pub async fn get_workflow_run(
+    State(state): State<ApiState>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<WorkflowRun>, StatusCode> {
+    state
+        .store
+        .get_workflow_run(run_id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 #[utoipa::path(
     get,
     path = "/health",
@@ -696,8 +2500,23 @@ pub async fn get_config(State(state): State<ApiState>) -> Json<ConfigResponse> {
         .get_config("queue_size")
         .await
         .unwrap_or_else(|| "100".to_string());
+    let max_concurrent_jobs = state
+        .store
+        .get_config("max_concurrent_jobs")
+        .await
+        .unwrap_or_else(|| "0".to_string());
+    let idempotency_ttl_secs = state
+        .store
+        .get_config("idempotency_ttl_secs")
+        .await
+        .unwrap_or_else(|| DEFAULT_IDEMPOTENCY_TTL_SECS.to_string());
 
-    Json(ConfigResponse { port, queue_size })
+    Json(ConfigResponse {
+        port,
+        queue_size,
+        max_concurrent_jobs,
+        idempotency_ttl_secs,
+    })
 }
 
 #[utoipa::path(
@@ -749,6 +2568,42 @@ pub async fn update_config(
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
     }
 
+    if let Some(max_concurrent_jobs) = &request.max_concurrent_jobs {
+        let max: usize = max_concurrent_jobs.parse().map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid max_concurrent_jobs: {}", max_concurrent_jobs),
+            )
+        })?;
+        state
+            .store
+            .set_config("max_concurrent_jobs", max_concurrent_jobs)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        // Applies immediately, unlike port and queue_size
+        state.store.set_max_concurrent_jobs(max);
+    }
+
+    if let Some(ttl) = &request.idempotency_ttl_secs {
+        let secs: u32 = ttl.parse().map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid idempotency_ttl_secs: {}", ttl),
+            )
+        })?;
+        if secs == 0 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "idempotency_ttl_secs must be at least 1".to_string(),
+            ));
+        }
+        state
+            .store
+            .set_config("idempotency_ttl_secs", ttl)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    }
+
     let port = state
         .store
         .get_config("port")
@@ -759,8 +2614,23 @@ pub async fn update_config(
         .get_config("queue_size")
         .await
         .unwrap_or_else(|| "100".to_string());
+    let max_concurrent_jobs = state
+        .store
+        .get_config("max_concurrent_jobs")
+        .await
+        .unwrap_or_else(|| "0".to_string());
+    let idempotency_ttl_secs = state
+        .store
+        .get_config("idempotency_ttl_secs")
+        .await
+        .unwrap_or_else(|| DEFAULT_IDEMPOTENCY_TTL_SECS.to_string());
 
-    Ok(Json(ConfigResponse { port, queue_size }))
+    Ok(Json(ConfigResponse {
+        port,
+        queue_size,
+        max_concurrent_jobs,
+        idempotency_ttl_secs,
+    }))
 }
 
 #[utoipa::path(
@@ -768,17 +2638,148 @@ pub async fn update_config(
     path = "/events",
     request_body = EventRequest,
     responses(
-        (status = 200, description = "Event queued", body = EventResponse),
+        (status = 200, description = "Event queued, or the original event for a duplicate idempotency key", body = EventResponse),
+        (status = 400, description = "Invalid idempotency key"),
+        (status = 422, description = "Idempotency key already used for a different event type or context"),
         (status = 500, description = "Failed to queue event")
     ),
     tag = "Events"
 )]
 pub async fn trigger_event(
     State(state): State<ApiState>,
+    headers: HeaderMap,
     Json(request): Json<EventRequest>,
-) -> Result<Json<EventResponse>, StatusCode> {
+) -> Result<Json<EventResponse>, (StatusCode, String)> {
+    let header_key = match headers.get("idempotency-key") {
+        Some(value) => Some(value.to_str().map(str::to_string).map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                "Invalid Idempotency-Key header".to_string(),
+            )
+        })?),
+        None => None,
+    };
+    let key = match (request.idempotency_key, header_key) {
+        (Some(body), Some(header)) if body != header => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "idempotency_key and the Idempotency-Key header differ".to_string(),
+            ));
+        }
+        (body, header) => body.or(header),
+    };
+    if key.as_deref().is_some_and(|k| k.trim().is_empty()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The idempotency key must not be empty".to_string(),
+        ));
+    }
+
     let event = Event::new(request.event_type, request.context);
+
+    if let Some(key) = &key {
+        let original = state
+            .store
+            .claim_idempotency_key(key, &event)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        if let Some(original) = original {
+            if original.event_type != event.event_type || original.context != event.context {
+                return Err((
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!(
+                        "Idempotency key '{}' was already used for a different event (type '{}')",
+                        key, original.event_type
+                    ),
+                ));
+            }
+            info!(
+                "HTTP event with idempotency key '{}' already produced: {:?}",
+                key, original.id
+            );
+            let job_ids = state.store.get_job_ids_for_event(original.id).await;
+            return Ok(Json(EventResponse {
+                event: original,
+                message: "Duplicate idempotency key, event not queued again".to_string(),
+                duplicate: true,
+                job_ids,
+            }));
+        }
+    }
+
     info!("HTTP producing event: {:?}", event.id);
+    if state.sender.send(event.clone()).await.is_err() {
+        if let Some(key) = &key {
+            let _ = state.store.release_idempotency_key(key).await;
+        }
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to queue event".to_string(),
+        ));
+    }
+
+    Ok(Json(EventResponse {
+        event,
+        message: "Event queued".to_string(),
+        duplicate: false,
+        job_ids: Vec::new(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/hooks/{event_type}",
+    params(
+        ("event_type" = String, Path, description = "Event type of a configured webhook")
+    ),
+    request_body(content = String, description = "Any payload; it becomes the event context", content_type = "*/*"),
+    responses(
+        (status = 200, description = "Event queued", body = EventResponse),
+        (status = 401, description = "Missing or invalid signature"),
+        (status = 403, description = "Unsigned webhook called from an address that is not allowed"),
+        (status = 404, description = "No webhook for this event type"),
+        (status = 500, description = "Failed to queue event")
+    ),
+    tag = "Webhooks"
+)]
+pub async fn receive_webhook(
+    State(state): State<ApiState>,
+    Path(event_type): Path<String>,
+    Extension(AllowedIp(allowed_ip)): Extension<AllowedIp>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<EventResponse>, StatusCode> {
+    let webhook = state
+        .store
+        .get_webhook(&event_type)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    match &webhook.secret {
+        Some(secret) => {
+            let signature = headers
+                .get(webhook.signature_header.as_str())
+                .and_then(|v| v.to_str().ok());
+            if !signature.is_some_and(|s| webhook::verify_signature(secret, s, &body)) {
+                warn!("Rejected webhook '{}': invalid signature", event_type);
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+        }
+        None if !allowed_ip => return Err(StatusCode::FORBIDDEN),
+        None => {}
+    }
+
+    let mut event = Event::new(event_type, String::from_utf8_lossy(&body).into_owned());
+    if !webhook.fields.is_empty() {
+        match serde_json::from_slice(&body) {
+            Ok(payload) => event.env = webhook::extract_fields(&webhook.fields, &payload),
+            Err(e) => warn!(
+                "Webhook '{}' payload is not JSON, no fields extracted: {}",
+                event.event_type, e
+            ),
+        }
+    }
+    info!("Webhook producing event: {:?}", event.id);
 
     state
         .sender
@@ -789,6 +2790,8 @@ pub async fn trigger_event(
     Ok(Json(EventResponse {
         event,
         message: "Event queued".to_string(),
+        duplicate: false,
+        job_ids: Vec::new(),
     }))
 }
 
@@ -796,42 +2799,194 @@ pub fn create_api_router(
     store: JobStore,
     timer_manager: TimerManager,
     schedule_manager: ScheduleManager,
+    watcher_manager: WatcherManager,
+    tail_manager: TailManager,
     sender: EventSender,
 ) -> OpenApiRouter {
     let state = ApiState {
         store,
         timer_manager,
         schedule_manager,
+        watcher_manager,
+        tail_manager,
         sender,
     };
 
     OpenApiRouter::new()
         .routes(routes!(get_status))
         .routes(routes!(healthcheck))
-        .routes(routes!(get_jobs, get_job, cancel_job))
-        .routes(routes!(
-            get_handlers,
-            create_handler,
-            get_handler_by_type,
-            update_handler,
-            delete_handler
-        ))
-        .routes(routes!(
-            get_timers,
-            create_timer,
-            get_timer_by_type,
-            update_timer,
-            delete_timer
-        ))
+        .routes(routes!(get_jobs))
+        .routes(routes!(get_job))
+        .routes(routes!(cancel_job))
+        .routes(routes!(stream_job))
+        .routes(routes!(get_handlers, create_handler))
+        .routes(routes!(get_handler, update_handler, delete_handler))
+        .routes(routes!(pause_handler))
+        .routes(routes!(resume_handler))
+        .routes(routes!(test_filter))
+        .routes(routes!(get_timers, create_timer))
+        .routes(routes!(get_timer_by_type, update_timer, delete_timer))
+        .routes(routes!(pause_timer))
+        .routes(routes!(resume_timer))
+        .routes(routes!(get_schedules, create_schedule))
         .routes(routes!(
-            get_schedules,
-            create_schedule,
             get_schedule_by_type,
             update_schedule,
             delete_schedule
         ))
+        .routes(routes!(preview_schedule))
+        .routes(routes!(pause_schedule))
+        .routes(routes!(resume_schedule))
+        .routes(routes!(get_windows, create_window))
+        .routes(routes!(get_window, update_window, delete_window))
+        .routes(routes!(get_watchers, create_watcher))
+        .routes(routes!(get_watcher, update_watcher, delete_watcher))
+        .routes(routes!(get_tails, create_tail))
+        .routes(routes!(get_tail, update_tail, delete_tail))
+        .routes(routes!(get_webhooks, create_webhook))
+        .routes(routes!(get_webhook, update_webhook, delete_webhook))
+        .routes(routes!(get_workflows, create_workflow))
+        .routes(routes!(get_workflow, update_workflow, delete_workflow))
+        .routes(routes!(run_workflow))
+        .routes(routes!(get_workflow_runs))
+        .routes(routes!(get_workflow_run))
         .routes(routes!(get_config, update_config))
         .routes(routes!(trigger_event))
+        .routes(routes!(receive_webhook))
         .routes(routes!(reload))
         .with_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::db::Database;
+    use crate::queue::{EventReceiver, create_event_queue};
+
+    async fn state() -> (ApiState, EventReceiver) {
+        let db = Database::open(":memory:").unwrap();
+        db.init_schema().await.unwrap();
+        let store = JobStore::new(db);
+        let (sender, receiver) = create_event_queue(10);
+        let state = ApiState {
+            timer_manager: TimerManager::new(store.clone()),
+            schedule_manager: ScheduleManager::new(store.clone()),
+            watcher_manager: WatcherManager::new(store.clone()),
+            tail_manager: TailManager::new(store.clone()),
+            store,
+            sender,
+        };
+        (state, receiver)
+    }
+
+    async fn trigger(
+        state: &ApiState,
+        event_type: &str,
+        context: &str,
+        key: &str,
+    ) -> Result<Json<EventResponse>, (StatusCode, String)> {
+        let request = EventRequest {
+            event_type: event_type.to_string(),
+            context: context.to_string(),
+            idempotency_key: Some(key.to_string()),
+        };
+        trigger_event(State(state.clone()), HeaderMap::new(), Json(request)).await
+    }
+
+    fn handler(event_type: &str) -> EventHandler {
+        EventHandler::new(
+            event_type.to_string(),
+            "default".to_string(),
+            ShellType::Sh,
+            "true".to_string(),
+            None,
+            HashMap::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn slow_stream_clients_are_told_how_many_lines_they_missed() {
+        use axum::response::IntoResponse;
+
+        let (state, _receiver) = state().await;
+        let event = Event::new("build".to_string(), String::new());
+        let job = state.store.create_job(event, &handler("build"), 0).await;
+        let stream = stream_job(State(state.clone()), Path(job.id))
+            .await
+            .unwrap();
+
+        // More lines than the live channel holds arrive before the client reads any
+        for i in 0..1100 {
+            let line = format!("line {}", i);
+            state
+                .store
+                .append_output(job.id, OutputStream::Stdout, line)
+                .await;
+        }
+        state.store.mark_failed(job.id, "test".to_string()).await;
+
+        let body = axum::body::to_bytes(stream.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let lagged = body.find("event: lagged\ndata: 77\n").unwrap();
+        assert!(lagged < body.find("data: line 77\n").unwrap());
+        assert!(!body.contains("data: line 76\n"));
+        assert!(body.ends_with("event: end\ndata: failed\n\n"));
+    }
+
+    #[tokio::test]
+    async fn duplicate_keys_return_the_jobs_created_so_far() {
+        let (state, mut receiver) = state().await;
+
+        let Json(first) = trigger(&state, "deploy", "v1", "release-1").await.unwrap();
+        assert!(!first.duplicate);
+        assert!(first.job_ids.is_empty());
+        assert_eq!(receiver.try_recv().unwrap().id, first.event.id);
+
+        // The consumer has not created the jobs yet
+        let Json(early) = trigger(&state, "deploy", "v1", "release-1").await.unwrap();
+        assert!(early.duplicate);
+        assert_eq!(early.event.id, first.event.id);
+        assert!(early.job_ids.is_empty());
+
+        let handler = EventHandler::new(
+            "deploy".to_string(),
+            "default".to_string(),
+            ShellType::Sh,
+            "true".to_string(),
+            None,
+            HashMap::new(),
+        );
+        let job = state
+            .store
+            .create_job(first.event.clone(), &handler, 0)
+            .await;
+        let Json(late) = trigger(&state, "deploy", "v1", "release-1").await.unwrap();
+        assert!(late.duplicate);
+        assert_eq!(late.job_ids, vec![job.id]);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn keys_reused_for_another_event_are_rejected() {
+        let (state, mut receiver) = state().await;
+        let Json(first) = trigger(&state, "deploy", "v1", "release-1").await.unwrap();
+
+        let (status, _) = trigger(&state, "deploy", "v2", "release-1")
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, _) = trigger(&state, "rollback", "v1", "release-1")
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert_eq!(receiver.try_recv().unwrap().id, first.event.id);
+        assert!(receiver.try_recv().is_err());
+    }
+}
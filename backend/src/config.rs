@@ -30,11 +30,11 @@ pub fn get_db_path() -> String {
         return path;
     }
 
-    if let Ok(exe_path) = std::env::current_exe() {
-        if let Some(exe_dir) = exe_path.parent() {
-            let db_path: PathBuf = exe_dir.join(DEFAULT_DB_NAME);
-            return db_path.to_string_lossy().to_string();
-        }
+    if let Ok(exe_path) = std::env::current_exe()
+        && let Some(exe_dir) = exe_path.parent()
+    {
+        let db_path: PathBuf = exe_dir.join(DEFAULT_DB_NAME);
+        return db_path.to_string_lossy().to_string();
     }
 
     DEFAULT_DB_NAME.to_string()
@@ -1,11 +1,21 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::oneshot;
+use tokio::time::sleep;
 use tracing::{error, info, warn};
+use uuid::Uuid;
 
-use crate::db::JobStatus;
+use crate::db::{Event, EventHandler, Job, JobStatus};
 use crate::executor::execute_command;
-use crate::queue::EventReceiver;
+use crate::gate::Throttled;
+use crate::output::{OutputReceiver, create_output_channel};
+use crate::queue::{EventReceiver, EventSender};
 use crate::store::JobStore;
+use crate::window;
 
-pub async fn start_consumer(mut receiver: EventReceiver, store: JobStore) {
+pub async fn start_consumer(mut receiver: EventReceiver, sender: EventSender, store: JobStore) {
     info!("Event consumer started");
 
     while let Some(event) = receiver.recv().await {
@@ -14,52 +24,739 @@ pub async fn start_consumer(mut receiver: EventReceiver, store: JobStore) {
             event.id, event.event_type
         );
 
-        let handler = match store.get_handler(&event.event_type).await {
-            Some(h) => h,
-            None => {
-                warn!("No handler for event type: {}", event.event_type);
-                continue;
+        let handlers: Vec<_> = store
+            .get_handlers_for(&event.event_type)
+            .await
+            .into_iter()
+            .filter(|h| h.enabled)
+            .collect();
+        if handlers.is_empty() {
+            warn!("No enabled handler for event type: {}", event.event_type);
+            store.in_flight().finish(event.id);
+            continue;
+        }
+
+        let mut admissions = Vec::new();
+        for handler in handlers {
+            admissions.push(offer(&store, &sender, event.clone(), handler).await);
+        }
+        // The producer's overlap policy waits for every handler to be done with the event
+        let store = store.clone();
+        tokio::spawn(async move {
+            for admission in admissions {
+                let _ = admission.await;
             }
-        };
+            store.in_flight().finish(event.id);
+        });
+    }
 
-        let job = store.create_job(event.clone(), &handler).await;
-        let job_id = job.id;
+    info!("Event consumer stopped");
+}
 
-        info!("Created job: {:?} (handler: {:?})", job_id, handler.id);
+/// What became of an event offered to a handler
+pub enum Admission {
+    /// The handler's filter did not match the event, or is invalid
+    Skipped(Job),
+    /// A blackout window dropped the event
+    Dropped,
+    /// The event ran as a job: its last attempt, and how it ended unless it was cancelled
+    /// before it started
+    Ran(Job, Option<JobOutcome>),
+    /// The task running the job failed
+    Failed(String),
+}
 
-        let store = store.clone();
-        tokio::spawn(async move {
-            if let Some(j) = store.get_job(job_id).await {
-                if j.status == JobStatus::Cancelled {
-                    info!("Job {:?} was cancelled before execution", job_id);
+/// An event on its way to one of its handlers' jobs, with whoever waits to hear what became
+/// of it
+pub struct Offer {
+    pub event: Event,
+    done: oneshot::Sender<Admission>,
+}
+
+impl Offer {
+    /// The offer, and the receiver that hears what became of it
+    pub fn new(event: Event) -> (Self, oneshot::Receiver<Admission>) {
+        let (done, admission) = oneshot::channel();
+        (Self { event, done }, admission)
+    }
+
+    fn finish(self, admission: Admission) {
+        let _ = self.done.send(admission);
+    }
+}
+
+/// Offer the event to one handler: check the handler's filter, then pass the event through its
+/// debounce, throttle and blackout windows on to a job. The receiver hears what became of the
+/// event; it fails if debounce or throttle suppressed the event in favour of another one.
+pub async fn offer(
+    store: &JobStore,
+    sender: &EventSender,
+    event: Event,
+    handler: EventHandler,
+) -> oneshot::Receiver<Admission> {
+    let (offer, admission) = Offer::new(event);
+
+    let skipped = match store.filters().matches(&handler, &offer.event) {
+        Ok(true) => None,
+        Ok(false) => Some("Filter did not match the event".to_string()),
+        Err(e) => Some(format!("Invalid filter: {}", e)),
+    };
+    if let Some(reason) = skipped {
+        let job = store
+            .record_skipped_job(offer.event.clone(), &handler, reason)
+            .await;
+        info!(
+            "Skipped job: {:?} (handler: {}/{}, {})",
+            job.id,
+            handler.event_type,
+            handler.name,
+            job.error.as_deref().unwrap_or_default()
+        );
+        offer.finish(Admission::Skipped(job));
+        return admission;
+    }
+
+    admit(store, sender, offer, handler).await;
+    admission
+}
+
+/// Pass the event through the handler's debounce and throttle on its way to a job
+async fn admit(store: &JobStore, sender: &EventSender, offer: Offer, handler: EventHandler) {
+    let Some(debounce_secs) = handler.debounce_secs else {
+        throttle(store, sender, offer, handler).await;
+        return;
+    };
+
+    let generation = store.gate().debounce(&handler, offer);
+    let store = store.clone();
+    let sender = sender.clone();
+    tokio::spawn(async move {
+        sleep(Duration::from_secs(debounce_secs.into())).await;
+        if let Some(offer) = store.gate().settle(&handler, generation) {
+            throttle(&store, &sender, offer, handler).await;
+        }
+    });
+}
+
+async fn throttle(store: &JobStore, sender: &EventSender, offer: Offer, handler: EventHandler) {
+    let event_id = offer.event.id;
+    let mut wait = match store.gate().throttle(&handler, offer) {
+        Throttled::Run(offer, suppressed) => {
+            dispatch(store, sender, offer, handler, suppressed).await;
+            return;
+        }
+        Throttled::Suppressed => {
+            info!(
+                "Throttled event: {:?} (handler: {}/{})",
+                event_id, handler.event_type, handler.name
+            );
+            return;
+        }
+        Throttled::Held(wait) => wait,
+    };
+
+    // Wait for room in the window without holding up other events
+    let store = store.clone();
+    let sender = sender.clone();
+    tokio::spawn(async move {
+        loop {
+            sleep(wait).await;
+            match store.gate().release(&handler) {
+                Throttled::Run(offer, suppressed) => {
+                    dispatch(&store, &sender, offer, handler, suppressed).await;
                     return;
                 }
+                Throttled::Suppressed => return,
+                Throttled::Held(more) => wait = more,
             }
+        }
+    });
+}
 
-            store.mark_running(job_id).await;
-
-            match execute_command(&handler, &event.context).await {
-                Ok(result) => {
-                    if result.success {
-                        info!("Job {:?} completed successfully", job_id);
-                        store.mark_completed(job_id, result.stdout).await;
-                    } else {
-                        let error_msg = if result.stderr.is_empty() {
-                            format!("Exit code: {:?}", result.exit_code)
-                        } else {
-                            result.stderr
-                        };
-                        error!("Job {:?} failed", job_id);
-                        store.mark_failed(job_id, error_msg).await;
-                    }
-                }
-                Err(e) => {
-                    error!("Job {:?} execution error: {}", job_id, e);
-                    store.mark_failed(job_id, e).await;
-                }
+/// Create the job, once no blackout window blocks the handler
+async fn dispatch(
+    store: &JobStore,
+    sender: &EventSender,
+    offer: Offer,
+    handler: EventHandler,
+    suppressed: u32,
+) {
+    let event_type = offer.event.event_type.clone();
+    if store
+        .blocking_window(&event_type, Some(&handler.name), Utc::now())
+        .await
+        .is_none()
+    {
+        spawn_job(store, sender, offer, handler, suppressed).await;
+        return;
+    }
+
+    // Wait out the window without holding up other events
+    let store = store.clone();
+    let sender = sender.clone();
+    tokio::spawn(async move {
+        if window::wait_for_windows(&store, &event_type, Some(&handler.name), "Handler").await {
+            spawn_job(&store, &sender, offer, handler, suppressed).await;
+        } else {
+            offer.finish(Admission::Dropped);
+        }
+    });
+}
+
+/// How often output of a running job is written to the database
+const OUTPUT_PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of trailing output lines passed on to follow-up events
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// How many follow-up events a chain may trigger one after another. The API refuses cycles, but
+/// handlers loaded from an edited database are not checked, so this stops any that slip through.
+const MAX_FOLLOW_UP_DEPTH: u64 = 16;
+
+/// How a job ended
+pub struct JobOutcome {
+    pub status: JobStatus,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Create a job for one handler of the event and run it in the background
+async fn spawn_job(
+    store: &JobStore,
+    sender: &EventSender,
+    offer: Offer,
+    handler: EventHandler,
+    suppressed: u32,
+) {
+    let job = store
+        .create_job(offer.event.clone(), &handler, suppressed)
+        .await;
+
+    info!(
+        "Created job: {:?} (handler: {}/{} {:?})",
+        job.id, handler.event_type, handler.name, handler.id
+    );
+
+    let store = store.clone();
+    let sender = sender.clone();
+    let event = offer.event.clone();
+    let attempts =
+        tokio::spawn(async move { run_attempts(&store, &sender, &event, &handler, job).await });
+    tokio::spawn(async move {
+        match attempts.await {
+            Ok((job, outcome)) => offer.finish(Admission::Ran(job, outcome)),
+            Err(e) => {
+                error!("Job task failed: {}", e);
+                offer.finish(Admission::Failed(format!("Job task failed: {}", e)));
             }
+        }
+    });
+}
+
+/// Run a created job, retrying it per the handler's retry policy, and trigger the handler's
+/// follow-up event once it is done. Returns the last attempt and how it ended, or None if it
+/// was cancelled before it started.
+async fn run_attempts(
+    store: &JobStore,
+    sender: &EventSender,
+    event: &Event,
+    handler: &EventHandler,
+    mut job: Job,
+) -> (Job, Option<JobOutcome>) {
+    loop {
+        let Some(outcome) = run_job(store, event, handler, job.id).await else {
+            return (job, None);
+        };
+
+        let retry = handler.retry.as_ref().filter(|policy| {
+            matches!(outcome.status, JobStatus::Failed | JobStatus::TimedOut)
+                && policy.should_retry(job.attempt, outcome.exit_code)
         });
+        let Some(policy) = retry else {
+            trigger_follow_up(sender, event, handler, &job, &outcome).await;
+            return (job, Some(outcome));
+        };
+
+        let delay = policy.delay(job.attempt);
+        job = store.create_retry_job(&job).await;
+        info!(
+            "Retrying as job {:?} (attempt {}/{}) in {}s",
+            job.id,
+            job.attempt,
+            policy.max_attempts,
+            delay.as_secs()
+        );
+        sleep(delay).await;
     }
+}
 
-    info!("Event consumer stopped");
+/// Execute one job to completion. Returns how it ended, or None if it was cancelled before it
+/// started.
+async fn run_job(
+    store: &JobStore,
+    event: &Event,
+    handler: &EventHandler,
+    job_id: Uuid,
+) -> Option<JobOutcome> {
+    let mut cancel = store.register_process(job_id).await;
+
+    // Stay pending until a slot frees up; a cancel while waiting leaves the permit unset and
+    // mark_running then sees the cancelled status
+    let _permit = tokio::select! {
+        permit = store.acquire_slot(handler) => Some(permit),
+        _ = &mut cancel => None,
+    };
+
+    if !store.mark_running(job_id).await {
+        info!("Job {:?} was cancelled before execution", job_id);
+        store.unregister_process(job_id).await;
+        return None;
+    }
+
+    let (output_tx, output_rx) = create_output_channel();
+    let forwarder = tokio::spawn(forward_output(store.clone(), job_id, output_rx));
+
+    let result = execute_command(handler, event, cancel, output_tx).await;
+    store.unregister_process(job_id).await;
+    // Every line reaches the store before the job is finalized
+    let _ = forwarder.await;
+
+    match result {
+        Ok(result) => {
+            let (status, error_msg) = if result.cancelled {
+                info!("Job {:?} was cancelled, process group terminated", job_id);
+                (JobStatus::Cancelled, None)
+            } else if result.timed_out {
+                warn!("Job {:?} timed out, process group terminated", job_id);
+                let error_msg = format!(
+                    "Command timed out after {} seconds",
+                    handler.timeout.unwrap_or_default()
+                );
+                (JobStatus::TimedOut, Some(error_msg))
+            } else if result.success {
+                info!("Job {:?} completed successfully", job_id);
+                (JobStatus::Completed, None)
+            } else {
+                error!("Job {:?} failed", job_id);
+                let error_msg = match result.exit_code {
+                    Some(code) => format!("Exit code: {}", code),
+                    None => "Terminated by signal".to_string(),
+                };
+                (JobStatus::Failed, Some(error_msg))
+            };
+            let status = store.finish_job(job_id, status, &result, error_msg).await;
+            Some(JobOutcome {
+                status,
+                exit_code: result.exit_code,
+                stdout: result.stdout,
+                stderr: result.stderr,
+            })
+        }
+        Err(e) => {
+            error!("Job {:?} execution error: {}", job_id, e);
+            store.mark_failed(job_id, e).await;
+            Some(JobOutcome {
+                status: JobStatus::Failed,
+                exit_code: None,
+                stdout: String::new(),
+                stderr: String::new(),
+            })
+        }
+    }
+}
+
+fn output_tail(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n")
+}
+
+/// Enqueue the handler's on_success, on_failure or on_timeout event for a finished job. Its
+/// context describes the job and carries the original context along.
+async fn trigger_follow_up(
+    sender: &EventSender,
+    event: &Event,
+    handler: &EventHandler,
+    job: &Job,
+    outcome: &JobOutcome,
+) {
+    let follow_up = match outcome.status {
+        JobStatus::Completed => handler.on_success.as_ref(),
+        JobStatus::Failed => handler.on_failure.as_ref(),
+        JobStatus::TimedOut => handler.on_timeout.as_ref().or(handler.on_failure.as_ref()),
+        _ => None,
+    };
+    let Some(event_type) = follow_up else {
+        return;
+    };
+
+    // How many follow-ups led to this event
+    let depth = serde_json::from_str::<serde_json::Value>(&event.context)
+        .ok()
+        .and_then(|context| context.get("depth")?.as_u64())
+        .unwrap_or(0)
+        + 1;
+    if depth > MAX_FOLLOW_UP_DEPTH {
+        warn!(
+            "Job {:?} {}, not triggering '{}': the chain is {} follow-ups deep",
+            job.id,
+            outcome.status.as_str(),
+            event_type,
+            MAX_FOLLOW_UP_DEPTH
+        );
+        return;
+    }
+
+    let context = serde_json::json!({
+        "event_type": event.event_type,
+        "context": event.context,
+        "handler": handler.name,
+        "job_id": job.id,
+        "attempt": job.attempt,
+        "status": outcome.status.as_str(),
+        "exit_code": outcome.exit_code,
+        "stdout_tail": output_tail(&outcome.stdout),
+        "stderr_tail": output_tail(&outcome.stderr),
+        "depth": depth,
+    });
+    let mut next = Event::new(event_type.clone(), context.to_string());
+    next.env = event.env.clone();
+    info!(
+        "Job {:?} {}, triggering '{}' (event: {:?})",
+        job.id,
+        outcome.status.as_str(),
+        event_type,
+        next.id
+    );
+
+    if sender.send(next).await.is_err() {
+        warn!(
+            "Event queue closed, dropping follow-up event '{}'",
+            event_type
+        );
+    }
+}
+
+/// The follow-up event types of a handler, in the order they are looked up
+fn follow_ups(handler: &EventHandler) -> impl Iterator<Item = &String> {
+    [
+        &handler.on_success,
+        &handler.on_failure,
+        &handler.on_timeout,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// The event types of a cycle that saving the handler would close in the follow-up chains of
+/// the other handlers, starting and ending at the handler's own event type
+pub fn follow_up_cycle(handlers: &[EventHandler], handler: &EventHandler) -> Option<Vec<String>> {
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    let others = handlers
+        .iter()
+        .filter(|h| (&h.event_type, &h.name) != (&handler.event_type, &handler.name));
+    for h in others.chain([handler]) {
+        edges
+            .entry(&h.event_type)
+            .or_default()
+            .extend(follow_ups(h).map(String::as_str));
+    }
+
+    // Depth-first search from the handler's event type, keeping the path to the current type
+    let start = handler.event_type.as_str();
+    let mut path = vec![start];
+    let mut pending = vec![edges.get(start).cloned().unwrap_or_default()];
+    let mut visited = HashSet::from([start]);
+    while let Some(next) = pending.last_mut() {
+        let Some(event_type) = next.pop() else {
+            pending.pop();
+            path.pop();
+            continue;
+        };
+        if event_type == start {
+            path.push(start);
+            return Some(path.into_iter().map(String::from).collect());
+        }
+        if visited.insert(event_type) {
+            path.push(event_type);
+            pending.push(edges.get(event_type).cloned().unwrap_or_default());
+        }
+    }
+    None
+}
+
+/// Feed lines from the executor into the job's live output until the process closes its pipes
+async fn forward_output(store: JobStore, job_id: Uuid, mut output: OutputReceiver) {
+    let mut persist = tokio::time::interval(OUTPUT_PERSIST_INTERVAL);
+    loop {
+        tokio::select! {
+            line = output.recv() => match line {
+                Some((stream, line)) => store.append_output(job_id, stream, line).await,
+                None => break,
+            },
+            _ = persist.tick() => store.persist_output(job_id).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::db::Database;
+    use crate::queue::create_event_queue;
+    use shev_core::{Backoff, RetryPolicy, ShellType};
+
+    fn handler(
+        event_type: &str,
+        on_success: Option<&str>,
+        on_failure: Option<&str>,
+    ) -> EventHandler {
+        let mut handler = EventHandler::new(
+            event_type.to_string(),
+            "default".to_string(),
+            ShellType::Sh,
+            "true".to_string(),
+            None,
+            HashMap::new(),
+        );
+        handler.on_success = on_success.map(String::from);
+        handler.on_failure = on_failure.map(String::from);
+        handler
+    }
+
+    #[test]
+    fn follow_up_cycles_through_other_handlers_are_found() {
+        let stored = [handler("a", None, Some("b"))];
+        assert_eq!(
+            follow_up_cycle(&stored, &handler("b", None, Some("a"))),
+            Some(vec!["b".to_string(), "a".to_string(), "b".to_string()])
+        );
+
+        let stored = [handler("a", Some("b"), None), handler("b", Some("c"), None)];
+        assert_eq!(
+            follow_up_cycle(&stored, &handler("c", None, Some("a"))),
+            Some(vec![
+                "c".to_string(),
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn follow_up_chains_without_cycles_are_allowed() {
+        // Two paths to the same event type are not a cycle
+        let stored = [
+            handler("a", Some("b"), Some("c")),
+            handler("b", Some("d"), None),
+            handler("c", Some("d"), None),
+        ];
+        assert_eq!(follow_up_cycle(&stored, &handler("d", None, None)), None);
+        assert_eq!(
+            follow_up_cycle(&stored, &handler("d", Some("e"), None)),
+            None
+        );
+
+        // The stored version of the handler being saved is replaced, not added to
+        let stored = [handler("a", Some("b"), None), handler("b", Some("a"), None)];
+        assert_eq!(follow_up_cycle(&stored, &handler("b", None, None)), None);
+    }
+
+    #[tokio::test]
+    async fn follow_up_chains_stop_at_the_depth_limit() {
+        let (sender, mut receiver) = create_event_queue(1);
+        let handler = handler("a", Some("b"), None);
+        let outcome = JobOutcome {
+            status: JobStatus::Completed,
+            exit_code: Some(0),
+            stdout: String::new(),
+            stderr: String::new(),
+        };
+
+        let event = Event::new("a".to_string(), r#"{"depth": 3}"#.to_string());
+        let job = Job::new(event.clone(), &handler);
+        trigger_follow_up(&sender, &event, &handler, &job, &outcome).await;
+        let next = receiver.try_recv().unwrap();
+        let context: serde_json::Value = serde_json::from_str(&next.context).unwrap();
+        assert_eq!(context["depth"], 4);
+
+        let event = Event::new(
+            "a".to_string(),
+            format!(r#"{{"depth": {}}}"#, MAX_FOLLOW_UP_DEPTH),
+        );
+        trigger_follow_up(&sender, &event, &handler, &job, &outcome).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    fn outcome(status: JobStatus, exit_code: Option<i32>, stdout: &str) -> JobOutcome {
+        JobOutcome {
+            status,
+            exit_code,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn follow_up_context_describes_the_finished_job() {
+        let (sender, mut receiver) = create_event_queue(1);
+        let handler = handler("build", Some("deploy"), None);
+        let mut event = Event::new("build".to_string(), r#"{"ref":"main"}"#.to_string());
+        event.env.insert("TOKEN".to_string(), "secret".to_string());
+        let job = Job::new(event.clone(), &handler);
+        let stdout: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+
+        let done = outcome(JobStatus::Completed, Some(0), &stdout);
+        trigger_follow_up(&sender, &event, &handler, &job, &done).await;
+        let next = receiver.try_recv().unwrap();
+        assert_eq!(next.event_type, "deploy");
+        assert_eq!(next.env.get("TOKEN").map(String::as_str), Some("secret"));
+
+        let context: serde_json::Value = serde_json::from_str(&next.context).unwrap();
+        assert_eq!(context["event_type"], "build");
+        assert_eq!(context["context"], r#"{"ref":"main"}"#);
+        assert_eq!(context["handler"], "default");
+        assert_eq!(context["job_id"], job.id.to_string());
+        assert_eq!(context["status"], "completed");
+        assert_eq!(context["exit_code"], 0);
+        assert_eq!(context["depth"], 1);
+        // Only the last lines of the output are passed on
+        let tail = context["stdout_tail"].as_str().unwrap();
+        assert_eq!(tail.lines().count(), OUTPUT_TAIL_LINES);
+        assert!(tail.starts_with("line 11\n") && tail.ends_with("line 30"));
+    }
+
+    #[tokio::test]
+    async fn follow_ups_depend_on_how_the_job_ended() {
+        let (sender, mut receiver) = create_event_queue(1);
+        let event = Event::new("build".to_string(), "{}".to_string());
+        let mut handler = handler("build", Some("deploy"), Some("alert"));
+        let job = Job::new(event.clone(), &handler);
+        let follow_up = async |handler: &EventHandler, status| {
+            let ended = outcome(status, None, "");
+            trigger_follow_up(&sender, &event, handler, &job, &ended).await;
+        };
+
+        follow_up(&handler, JobStatus::Failed).await;
+        assert_eq!(receiver.try_recv().unwrap().event_type, "alert");
+        // Timeouts count as failures unless they have their own follow-up
+        follow_up(&handler, JobStatus::TimedOut).await;
+        assert_eq!(receiver.try_recv().unwrap().event_type, "alert");
+        handler.on_timeout = Some("page".to_string());
+        follow_up(&handler, JobStatus::TimedOut).await;
+        assert_eq!(receiver.try_recv().unwrap().event_type, "page");
+
+        follow_up(&handler, JobStatus::Cancelled).await;
+        follow_up(&handler, JobStatus::Skipped).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn producer_events_are_in_flight_until_their_jobs_finish() {
+        let db = Database::open(":memory:").unwrap();
+        db.init_schema().await.unwrap();
+        let store = JobStore::new(db);
+        let mut handler = handler("test", None, None);
+        handler.debounce_secs = Some(1);
+        store.create_handler(handler).await.unwrap();
+        let (sender, receiver) = create_event_queue(10);
+        tokio::spawn(start_consumer(receiver, sender.clone(), store.clone()));
+
+        let event = Event::new("test".to_string(), String::new());
+        store.in_flight().start("Timer", "test", event.id);
+        sender.send(event.clone()).await.unwrap();
+
+        // Held by the debounce, so there is no job yet
+        sleep(Duration::from_millis(300)).await;
+        assert!(store.get_job_ids_for_event(event.id).await.is_empty());
+        assert!(store.in_flight().is_busy("Timer", "test"));
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            store.in_flight().wait_until_idle("Timer", "test"),
+        )
+        .await
+        .unwrap();
+        let job_ids = store.get_job_ids_for_event(event.id).await;
+        let job = store.get_job(job_ids[0]).await.unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failed_jobs_are_retried_on_the_listed_exit_codes() {
+        let db = Database::open(":memory:").unwrap();
+        db.init_schema().await.unwrap();
+        let store = JobStore::new(db);
+        let (sender, _receiver) = create_event_queue(1);
+
+        // Fails with 75 twice, then with 3, which is not retried
+        let mut handler = handler("test", None, None);
+        handler.command =
+            r#"n=$(cat "$MARK" 2>/dev/null || echo 0); n=$((n + 1)); echo $n > "$MARK"
+            [ $n -le 2 ] && exit 75; exit 3"#
+                .to_string();
+        let mark = std::env::temp_dir().join(format!("shev-retry-{}", Uuid::new_v4()));
+        handler
+            .env
+            .insert("MARK".to_string(), mark.display().to_string());
+        handler.retry = Some(RetryPolicy {
+            max_attempts: 5,
+            backoff: Backoff::Fixed,
+            delay_secs: 0,
+            retry_on: vec![75],
+        });
+        let event = Event::new("test".to_string(), String::new());
+        let job = store.create_job(event.clone(), &handler, 0).await;
+
+        let (last, outcome) = run_attempts(&store, &sender, &event, &handler, job).await;
+        let _ = std::fs::remove_file(&mark);
+
+        assert_eq!(outcome.unwrap().exit_code, Some(3));
+        assert_eq!(last.attempt, 3);
+        let mut jobs = Vec::new();
+        for id in store.get_job_ids_for_event(event.id).await {
+            jobs.push(store.get_job(id).await.unwrap());
+        }
+        jobs.sort_by_key(|j| j.attempt);
+        let codes: Vec<_> = jobs.iter().map(|j| (j.attempt, j.exit_code)).collect();
+        assert_eq!(codes, [(1, Some(75)), (2, Some(75)), (3, Some(3))]);
+        assert_eq!(jobs[1].parent_job_id, Some(jobs[0].id));
+        assert_eq!(jobs[2].parent_job_id, Some(jobs[1].id));
+        assert!(jobs.iter().all(|j| j.status == JobStatus::Failed));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn background_process_holding_the_pipes_times_out() {
+        let db = Database::open(":memory:").unwrap();
+        db.init_schema().await.unwrap();
+        let store = JobStore::new(db);
+        let (sender, _receiver) = create_event_queue(1);
+
+        let handler = EventHandler::new(
+            "test".to_string(),
+            "default".to_string(),
+            ShellType::Sh,
+            "sleep 100 & echo x".to_string(),
+            Some(1),
+            HashMap::new(),
+        );
+        let event = Event::new("test".to_string(), String::new());
+        let job = store.create_job(event.clone(), &handler, 0).await;
+
+        let started = Instant::now();
+        let (job, outcome) = run_attempts(&store, &sender, &event, &handler, job).await;
+
+        let outcome = outcome.unwrap();
+        assert_eq!(outcome.status, JobStatus::TimedOut);
+        assert_eq!(outcome.stdout, "x\n");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let job = store.get_job(job.id).await.unwrap();
+        assert_eq!(job.status, JobStatus::TimedOut);
+    }
 }
@@ -1,11 +1,15 @@
 use std::path::Path;
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
-pub use shev_core::{Database as SyncDatabase, ScheduleRecord, TimerRecord};
-pub use shev_core::{Event, EventHandler, Job, JobStatus};
+pub use shev_core::{
+    Database as SyncDatabase, ScheduleRecord, ScheduleState, TailRecord, TimerRecord,
+    WatcherRecord, WebhookRecord, WindowRecord, WorkflowRecord,
+};
+pub use shev_core::{Event, EventHandler, Job, JobStatus, WorkflowRun};
 
 /// Async wrapper around the sync shev_core::Database
 pub struct Database {
@@ -35,6 +39,11 @@ impl Database {
         db.get_queue_size()
     }
 
+    pub async fn get_max_concurrent_jobs(&self) -> usize {
+        let db = self.inner.lock().await;
+        db.get_max_concurrent_jobs()
+    }
+
     pub async fn get_all_handlers(&self) -> Vec<EventHandler> {
         let db = self.inner.lock().await;
         db.get_all_handlers().unwrap_or_default()
@@ -50,9 +59,29 @@ impl Database {
         db.insert_job(job)
     }
 
-    pub async fn update_job(&self, job: &Job) -> Result<(), String> {
+    pub async fn update_active_job(&self, job: &Job) -> Result<bool, String> {
         let db = self.inner.lock().await;
-        db.update_job(job)
+        db.update_active_job(job)
+    }
+
+    pub async fn append_job_output(
+        &self,
+        job_id: Uuid,
+        stdout: &str,
+        stderr: &str,
+    ) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.append_job_output(job_id, stdout, stderr)
+    }
+
+    pub async fn update_job_output(&self, job: &Job) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.update_job_output(job)
+    }
+
+    pub async fn cancel_job(&self, job_id: Uuid) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.cancel_job(job_id)
     }
 
     pub async fn get_job(&self, job_id: Uuid) -> Option<Job> {
@@ -70,6 +99,11 @@ impl Database {
         db.get_all_jobs(Some(&status), 1000).unwrap_or_default()
     }
 
+    pub async fn get_active_job_ids(&self, event_type: &str) -> Vec<Uuid> {
+        let db = self.inner.lock().await;
+        db.get_active_job_ids(event_type).unwrap_or_default()
+    }
+
     pub async fn get_timer_id(&self, event_type: &str) -> Option<Uuid> {
         let db = self.inner.lock().await;
         db.get_timer_id(event_type).ok().flatten()
@@ -80,6 +114,11 @@ impl Database {
         db.cancel_stale_jobs().unwrap_or(0)
     }
 
+    pub async fn fail_stale_workflow_runs(&self) -> usize {
+        let db = self.inner.lock().await;
+        db.fail_stale_workflow_runs().unwrap_or(0)
+    }
+
     pub async fn get_all_schedules(&self) -> Vec<ScheduleRecord> {
         let db = self.inner.lock().await;
         db.get_all_schedules().unwrap_or_default()
@@ -90,53 +129,55 @@ impl Database {
         db.get_schedule_id(event_type).ok().flatten()
     }
 
-    pub async fn insert_handler(
-        &self,
-        event_type: &str,
-        shell: &shev_core::ShellType,
-        command: &str,
-        timeout: Option<u32>,
-        env: &std::collections::HashMap<String, String>,
-    ) -> Result<EventHandler, String> {
+    pub async fn insert_handler(&self, handler: &EventHandler) -> Result<(), String> {
         let db = self.inner.lock().await;
-        db.insert_handler(event_type, shell, command, timeout, env)
+        db.insert_handler(handler)
     }
 
-    pub async fn update_handler(
+    pub async fn update_handler(&self, handler: &EventHandler) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.update_handler(handler)
+    }
+
+    pub async fn set_handler_enabled(
         &self,
         event_type: &str,
-        shell: Option<&shev_core::ShellType>,
-        command: Option<&str>,
-        timeout: Option<Option<u32>>,
-        env: Option<&std::collections::HashMap<String, String>>,
-    ) -> Result<EventHandler, String> {
+        name: &str,
+        enabled: bool,
+    ) -> Result<bool, String> {
         let db = self.inner.lock().await;
-        db.update_handler(event_type, shell, command, timeout, env)
+        db.set_handler_enabled(event_type, name, enabled)
     }
 
-    pub async fn delete_handler(&self, event_type: &str) -> Result<bool, String> {
+    pub async fn delete_handler(&self, event_type: &str, name: &str) -> Result<bool, String> {
         let db = self.inner.lock().await;
-        db.delete_handler(event_type)
+        db.delete_handler(event_type, name)
     }
 
-    pub async fn insert_timer(
-        &self,
-        event_type: &str,
-        interval_secs: u32,
-        context: &str,
-    ) -> Result<TimerRecord, String> {
+    pub async fn insert_timer(&self, timer: &TimerRecord) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_timer(timer)
+    }
+
+    pub async fn update_timer(&self, timer: &TimerRecord) -> Result<bool, String> {
         let db = self.inner.lock().await;
-        db.insert_timer(event_type, interval_secs, context)
+        db.update_timer(timer)
     }
 
-    pub async fn update_timer(
+    pub async fn set_timer_fire_times(
         &self,
-        event_type: &str,
-        interval_secs: Option<u32>,
-        context: Option<&str>,
-    ) -> Result<TimerRecord, String> {
+        id: &Uuid,
+        last_fired_at: Option<DateTime<Utc>>,
+        next_fire_at: Option<DateTime<Utc>>,
+        fire_count: u32,
+    ) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.set_timer_fire_times(id, last_fired_at, next_fire_at, fire_count)
+    }
+
+    pub async fn set_timer_enabled(&self, event_type: &str, enabled: bool) -> Result<bool, String> {
         let db = self.inner.lock().await;
-        db.update_timer(event_type, interval_secs, context)
+        db.set_timer_enabled(event_type, enabled)
     }
 
     pub async fn delete_timer(&self, event_type: &str) -> Result<bool, String> {
@@ -144,26 +185,38 @@ impl Database {
         db.delete_timer(event_type)
     }
 
-    pub async fn insert_schedule(
+    pub async fn insert_schedule(&self, schedule: &ScheduleRecord) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_schedule(schedule)
+    }
+
+    pub async fn update_schedule(&self, schedule: &ScheduleRecord) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.update_schedule(schedule)
+    }
+
+    pub async fn set_schedule_fire_times(
         &self,
-        event_type: &str,
-        scheduled_time: chrono::DateTime<chrono::Utc>,
-        context: &str,
-        periodic: bool,
-    ) -> Result<ScheduleRecord, String> {
+        id: &Uuid,
+        last_fired_at: Option<DateTime<Utc>>,
+        next_fire_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String> {
         let db = self.inner.lock().await;
-        db.insert_schedule(event_type, scheduled_time, context, periodic)
+        db.set_schedule_fire_times(id, last_fired_at, next_fire_at)
     }
 
-    pub async fn update_schedule(
+    pub async fn set_schedule_state(&self, id: &Uuid, state: ScheduleState) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.set_schedule_state(id, state)
+    }
+
+    pub async fn set_schedule_enabled(
         &self,
         event_type: &str,
-        scheduled_time: Option<chrono::DateTime<chrono::Utc>>,
-        context: Option<&str>,
-        periodic: Option<bool>,
-    ) -> Result<ScheduleRecord, String> {
+        enabled: bool,
+    ) -> Result<bool, String> {
         let db = self.inner.lock().await;
-        db.update_schedule(event_type, scheduled_time, context, periodic)
+        db.set_schedule_enabled(event_type, enabled)
     }
 
     pub async fn delete_schedule(&self, event_type: &str) -> Result<bool, String> {
@@ -171,6 +224,155 @@ impl Database {
         db.delete_schedule(event_type)
     }
 
+    pub async fn get_all_windows(&self) -> Vec<WindowRecord> {
+        let db = self.inner.lock().await;
+        db.get_all_windows().unwrap_or_default()
+    }
+
+    pub async fn insert_window(&self, window: &WindowRecord) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_window(window)
+    }
+
+    pub async fn update_window(&self, window: &WindowRecord) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.update_window(window)
+    }
+
+    pub async fn delete_window(&self, name: &str) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.delete_window(name)
+    }
+
+    pub async fn get_all_tails(&self) -> Vec<TailRecord> {
+        let db = self.inner.lock().await;
+        db.get_all_tails().unwrap_or_default()
+    }
+
+    pub async fn get_tail_id(&self, event_type: &str) -> Option<Uuid> {
+        let db = self.inner.lock().await;
+        db.get_tail_id(event_type).ok().flatten()
+    }
+
+    pub async fn insert_tail(&self, tail: &TailRecord) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_tail(tail)
+    }
+
+    pub async fn update_tail(&self, tail: &TailRecord) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.update_tail(tail)
+    }
+
+    pub async fn delete_tail(&self, event_type: &str) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.delete_tail(event_type)
+    }
+
+    pub async fn get_all_webhooks(&self) -> Vec<WebhookRecord> {
+        let db = self.inner.lock().await;
+        db.get_all_webhooks().unwrap_or_default()
+    }
+
+    pub async fn insert_webhook(&self, webhook: &WebhookRecord) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_webhook(webhook)
+    }
+
+    pub async fn update_webhook(&self, webhook: &WebhookRecord) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.update_webhook(webhook)
+    }
+
+    pub async fn delete_webhook(&self, event_type: &str) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.delete_webhook(event_type)
+    }
+
+    pub async fn get_all_watchers(&self) -> Vec<WatcherRecord> {
+        let db = self.inner.lock().await;
+        db.get_all_watchers().unwrap_or_default()
+    }
+
+    pub async fn get_watcher_id(&self, event_type: &str) -> Option<Uuid> {
+        let db = self.inner.lock().await;
+        db.get_watcher_id(event_type).ok().flatten()
+    }
+
+    pub async fn insert_watcher(&self, watcher: &WatcherRecord) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_watcher(watcher)
+    }
+
+    pub async fn update_watcher(&self, watcher: &WatcherRecord) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.update_watcher(watcher)
+    }
+
+    pub async fn delete_watcher(&self, event_type: &str) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.delete_watcher(event_type)
+    }
+
+    pub async fn get_all_workflows(&self) -> Vec<WorkflowRecord> {
+        let db = self.inner.lock().await;
+        db.get_all_workflows().unwrap_or_default()
+    }
+
+    pub async fn insert_workflow(&self, workflow: &WorkflowRecord) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_workflow(workflow)
+    }
+
+    pub async fn update_workflow(&self, workflow: &WorkflowRecord) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.update_workflow(workflow)
+    }
+
+    pub async fn delete_workflow(&self, name: &str) -> Result<bool, String> {
+        let db = self.inner.lock().await;
+        db.delete_workflow(name)
+    }
+
+    pub async fn insert_workflow_run(&self, run: &WorkflowRun) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.insert_workflow_run(run)
+    }
+
+    pub async fn update_workflow_run(&self, run: &WorkflowRun) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.update_workflow_run(run)
+    }
+
+    pub async fn get_workflow_run(&self, run_id: Uuid) -> Option<WorkflowRun> {
+        let db = self.inner.lock().await;
+        db.get_workflow_run(run_id).ok().flatten()
+    }
+
+    pub async fn get_workflow_runs(&self, workflow: Option<&str>) -> Vec<WorkflowRun> {
+        let db = self.inner.lock().await;
+        db.get_workflow_runs(workflow, 1000).unwrap_or_default()
+    }
+
+    pub async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        event: &Event,
+    ) -> Result<Option<Event>, String> {
+        let db = self.inner.lock().await;
+        db.claim_idempotency_key(key, event)
+    }
+
+    pub async fn release_idempotency_key(&self, key: &str) -> Result<(), String> {
+        let db = self.inner.lock().await;
+        db.release_idempotency_key(key)
+    }
+
+    pub async fn get_job_ids_for_event(&self, event_id: Uuid) -> Vec<Uuid> {
+        let db = self.inner.lock().await;
+        db.get_job_ids_for_event(event_id).unwrap_or_default()
+    }
+
     pub async fn get_config(&self, key: &str) -> Option<String> {
         let db = self.inner.lock().await;
         db.get_config(key)
@@ -1,10 +1,18 @@
 use std::process::Stdio;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use tokio::process::Command;
-use tokio::time::timeout;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, timeout};
 
-use crate::db::EventHandler;
+use crate::db::{Event, EventHandler};
+use crate::output::{OutputSender, OutputStream};
+use crate::template;
+
+/// Time a cancelled or timed out process group gets to exit after SIGTERM before it is killed
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct ExecutionResult {
@@ -12,49 +20,205 @@ pub struct ExecutionResult {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: Option<i32>,
+    pub duration: Duration,
+    pub cancelled: bool,
+    pub timed_out: bool,
 }
 
 pub async fn execute_command(
     handler: &EventHandler,
-    event_context: &str,
+    event: &Event,
+    mut cancel: oneshot::Receiver<()>,
+    output: OutputSender,
 ) -> Result<ExecutionResult, String> {
-    let (shell_cmd, args) = handler.shell.command_args(&handler.command);
+    let command = template::render(&handler.command, event, &handler.shell)
+        .map_err(|e| format!("Failed to render command: {}", e))?;
+    let (shell_cmd, args) = handler.shell.command_args(&command);
 
     let mut cmd = Command::new(shell_cmd);
     cmd.args(&args)
-        .env("EVENT_CONTEXT", event_context)
+        .env("EVENT_CONTEXT", &event.context)
+        .envs(template::context_env(&event.context))
         .envs(&handler.env)
+        .envs(&event.env)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
+    // Run the shell in its own process group so everything it forks can be signalled together
+    #[cfg(unix)]
+    {
+        cmd.process_group(0);
+    }
+
     #[cfg(target_os = "windows")]
     {
         cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
     }
 
-    let future = async {
-        let child = cmd
-            .spawn()
-            .map_err(|e| format!("Failed to spawn process: {}", e))?;
-        child
-            .wait_with_output()
-            .await
-            .map_err(|e| format!("Failed to wait for process: {}", e))
-    };
+    let started = Instant::now();
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn process: {}", e))?;
+    let pid = child.id();
+
+    let stdout = read_pipe(child.stdout.take(), OutputStream::Stdout, output.clone());
+    let stderr = read_pipe(child.stderr.take(), OutputStream::Stderr, output);
+    let readers = [stdout.abort_handle(), stderr.abort_handle()];
+    let pipes = async { tokio::join!(collect_pipe(stdout), collect_pipe(stderr)) };
+    tokio::pin!(pipes);
 
-    let output = if let Some(timeout_secs) = handler.timeout {
-        match timeout(Duration::from_secs(timeout_secs.into()), future).await {
-            Ok(result) => result?,
-            Err(_) => return Err(format!("Command timed out after {} seconds", timeout_secs)),
+    let deadline = handler
+        .timeout
+        .map(|timeout_secs| tokio::time::Instant::now() + Duration::from_secs(timeout_secs.into()));
+    let expired = async {
+        match deadline {
+            Some(deadline) => sleep_until(deadline).await,
+            None => std::future::pending().await,
         }
-    } else {
-        future.await?
     };
+    tokio::pin!(expired);
+
+    // The job ends once the shell exited and the pipes closed. Anything the shell left in the
+    // background can hold the pipes open, so the deadline and a cancel apply to both.
+    let mut status = None;
+    let mut pipes_output = None;
+    let mut cancel_open = true;
+    let (mut cancelled, mut timed_out) = (false, false);
+    while status.is_none() || pipes_output.is_none() {
+        tokio::select! {
+            result = child.wait(), if status.is_none() => {
+                status = Some(result.map_err(|e| format!("Failed to wait for process: {}", e))?);
+            }
+            output = &mut pipes, if pipes_output.is_none() => pipes_output = Some(output),
+            _ = &mut expired => {
+                timed_out = true;
+                break;
+            }
+            result = &mut cancel, if cancel_open => match result {
+                Ok(()) => {
+                    cancelled = true;
+                    break;
+                }
+                Err(_) => cancel_open = false,
+            },
+        }
+    }
+
+    if cancelled || timed_out {
+        if let Some(pid) = pid {
+            terminate(pid, &mut child);
+            // Give the whole group the grace period to exit and close its pipes
+            let exited = timeout(KILL_GRACE_PERIOD, async {
+                if status.is_none() {
+                    status = child.wait().await.ok();
+                }
+                if pipes_output.is_none() {
+                    pipes_output = Some((&mut pipes).await);
+                }
+            })
+            .await
+            .is_ok();
+            // Also kills anything the shell left behind in the group
+            kill(pid);
+            if !exited {
+                tracing::warn!("Process group {} did not exit after SIGTERM, killed", pid);
+            }
+        }
+        if status.is_none() {
+            status = Some(
+                child
+                    .wait()
+                    .await
+                    .map_err(|e| format!("Failed to wait for process: {}", e))?,
+            );
+        }
+        if pipes_output.is_none() {
+            // Only a process that left the group can still hold the pipes open
+            pipes_output = timeout(KILL_GRACE_PERIOD, &mut pipes).await.ok();
+            if pipes_output.is_none() {
+                readers.iter().for_each(|reader| reader.abort());
+            }
+        }
+    }
+    let duration = started.elapsed();
+    let status = status.expect("the process was waited for");
+    let (stdout, stderr) = pipes_output.unwrap_or_default();
 
     Ok(ExecutionResult {
-        success: output.status.success(),
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        exit_code: output.status.code(),
+        success: status.success() && !cancelled && !timed_out,
+        stdout,
+        stderr,
+        exit_code: status.code(),
+        duration,
+        cancelled,
+        timed_out,
     })
 }
+
+/// Read a pipe to the end, forwarding each line as it arrives
+fn read_pipe<R>(pipe: Option<R>, stream: OutputStream, output: OutputSender) -> JoinHandle<Vec<u8>>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut buf = Vec::new();
+        if let Some(pipe) = pipe {
+            let mut reader = BufReader::new(pipe);
+            let mut line = Vec::new();
+            while let Ok(n) = reader.read_until(b'\n', &mut line).await {
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&line);
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end_matches(['\n', '\r']).to_string();
+                let _ = output.send((stream, text));
+                line.clear();
+            }
+        }
+        buf
+    })
+}
+
+async fn collect_pipe(handle: JoinHandle<Vec<u8>>) -> String {
+    let buf = handle.await.unwrap_or_default();
+    String::from_utf8_lossy(&buf).to_string()
+}
+
+/// Ask the child's process group to exit
+#[cfg(unix)]
+fn terminate(pid: u32, _child: &mut Child) {
+    signal_group(pid, libc::SIGTERM);
+}
+
+/// Ask the child to exit. Without process groups only the shell itself is stopped; anything it
+/// started keeps running.
+#[cfg(not(unix))]
+fn terminate(_pid: u32, child: &mut Child) {
+    let _ = child.start_kill();
+}
+
+/// Kill whatever is left of the child's process group
+#[cfg(unix)]
+fn kill(pid: u32) {
+    signal_group(pid, libc::SIGKILL);
+}
+
+/// `terminate` already killed the shell, and there is no group to clean up after it
+#[cfg(not(unix))]
+fn kill(_pid: u32) {}
+
+/// Signal the process group led by the child, if any of it is left. Once the shell has been
+/// reaped its pid could go to an unrelated process group, but not while a member of its own
+/// group is alive, so the group is checked for first.
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) {
+    let group = -(pid as libc::pid_t);
+    // SAFETY: kill(2) with a negative pid only signals the process group; signal 0 only checks
+    // that the group exists. No memory is touched.
+    unsafe {
+        if libc::kill(group, 0) == 0 {
+            libc::kill(group, signal);
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::db::{Event, EventHandler};
+
+/// A parsed handler filter, matched against an event's context
+///
+/// Operands are `context` (the parsed JSON context, or the raw string if it is not JSON),
+/// `context.<field>` with dots reaching nested fields and array items, `event_type`, and
+/// string (`"main"`, `'main'`), number, `true`, `false` and `null` literals. They are compared
+/// with `==`, `!=`, `<`, `<=`, `>`, `>=`, matched against a regex with `=~` and `!~`, and
+/// combined with `&&`, `||`, `!` and parentheses. A lone operand is true unless it is missing,
+/// `null`, `false`, `0`, or an empty string, array or object.
+pub struct Filter(Expr);
+
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, Comparison, Operand),
+    Matches(Operand, Regex),
+    Truthy(Operand),
+}
+
+enum Operand {
+    Literal(Value),
+    Context(Vec<String>),
+    EventType,
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 14] = [
+    "&&", "||", "==", "!=", "<=", ">=", "=~", "!~", "<", ">", "!", "(", ")", ".",
+];
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            pos: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err("The filter is empty".to_string());
+        }
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Self(expr)),
+            Some(token) => Err(format!("Unexpected {} in filter", describe(token))),
+        }
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        let context = serde_json::from_str(&event.context)
+            .unwrap_or_else(|_| Value::String(event.context.clone()));
+        self.0.eval(event, &context)
+    }
+}
+
+/// A handler's filter text and what it parsed to
+type Parsed = (String, Result<Arc<Filter>, String>);
+
+/// Handler filters parsed when the handlers are saved, so events are matched without parsing
+/// the filter again
+#[derive(Clone, Default)]
+pub struct FilterCache {
+    /// By event type and handler name
+    filters: Arc<RwLock<HashMap<(String, String), Parsed>>>,
+}
+
+impl FilterCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the handler's filter, replacing the one cached for the handler
+    pub fn set(&self, handler: &EventHandler) {
+        match handler.filter.as_deref() {
+            Some(source) => {
+                self.get(handler, source).ok();
+            }
+            None => self.remove(&handler.event_type, &handler.name),
+        }
+    }
+
+    pub fn remove(&self, event_type: &str, name: &str) {
+        let mut filters = self.filters.write().unwrap();
+        filters.remove(&(event_type.to_string(), name.to_string()));
+    }
+
+    /// Whether the handler's filter, if any, lets the event through; an invalid filter lets
+    /// nothing through
+    pub fn matches(&self, handler: &EventHandler, event: &Event) -> Result<bool, String> {
+        match handler.filter.as_deref() {
+            Some(source) => self.get(handler, source).map(|f| f.matches(event)),
+            None => Ok(true),
+        }
+    }
+
+    /// The parsed filter, parsing it again if the handler's filter is not the cached one
+    fn get(&self, handler: &EventHandler, source: &str) -> Result<Arc<Filter>, String> {
+        let key = (handler.event_type.clone(), handler.name.clone());
+        if let Some((cached, filter)) = self.filters.read().unwrap().get(&key)
+            && cached == source
+        {
+            return filter.clone();
+        }
+        let filter = Filter::parse(source).map(Arc::new);
+        let mut filters = self.filters.write().unwrap();
+        filters.insert(key, (source.to_string(), filter.clone()));
+        filter
+    }
+}
+
+impl Expr {
+    fn eval(&self, event: &Event, context: &Value) -> bool {
+        match self {
+            Expr::Or(a, b) => a.eval(event, context) || b.eval(event, context),
+            Expr::And(a, b) => a.eval(event, context) && b.eval(event, context),
+            Expr::Not(a) => !a.eval(event, context),
+            Expr::Compare(a, op, b) => {
+                // A missing field compares like null
+                let a = a.value(event, context).unwrap_or(Value::Null);
+                let b = b.value(event, context).unwrap_or(Value::Null);
+                compare(&a, *op, &b)
+            }
+            Expr::Matches(a, regex) => match a.value(event, context) {
+                Some(Value::String(s)) => regex.is_match(&s),
+                Some(v @ (Value::Number(_) | Value::Bool(_))) => regex.is_match(&v.to_string()),
+                _ => false,
+            },
+            Expr::Truthy(a) => match a.value(event, context) {
+                None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+                Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+                Some(Value::String(s)) => !s.is_empty(),
+                Some(Value::Array(items)) => !items.is_empty(),
+                Some(Value::Object(fields)) => !fields.is_empty(),
+                Some(Value::Bool(true)) => true,
+            },
+        }
+    }
+}
+
+impl Operand {
+    fn value(&self, event: &Event, context: &Value) -> Option<Value> {
+        match self {
+            Operand::Literal(value) => Some(value.clone()),
+            Operand::EventType => Some(Value::String(event.event_type.clone())),
+            Operand::Context(path) => {
+                let mut value = context;
+                for segment in path {
+                    value = match value {
+                        Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                        Value::Object(fields) => fields.get(segment)?,
+                        _ => return None,
+                    };
+                }
+                Some(value.clone())
+            }
+        }
+    }
+}
+
+/// Numbers compare by value and strings lexically; other values are only equal or not
+fn compare(a: &Value, op: Comparison, b: &Value) -> bool {
+    let ordering = match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64().partial_cmp(&y.as_f64()),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    };
+    match (op, ordering) {
+        (Comparison::Eq, Some(o)) => o.is_eq(),
+        (Comparison::Ne, Some(o)) => o.is_ne(),
+        (Comparison::Eq, None) => a == b,
+        (Comparison::Ne, None) => a != b,
+        (Comparison::Lt, Some(o)) => o.is_lt(),
+        (Comparison::Le, Some(o)) => o.is_le(),
+        (Comparison::Gt, Some(o)) => o.is_gt(),
+        (Comparison::Ge, Some(o)) => o.is_ge(),
+        _ => false,
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Str(s) => format!("string \"{}\"", s),
+        Token::Num(n) => format!("number {}", n),
+        Token::Op(op) => format!("'{}'", op),
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        // Array indexes after a dot are field names, not numbers
+        let number = (c.is_ascii_digit()
+            || (c == '-' && matches!(tokens.last(), None | Some(Token::Op(_)))))
+            && tokens.last() != Some(&Token::Op("."));
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, escaped)) => s.push(escaped),
+                        None => return Err("Unterminated string in filter".to_string()),
+                    },
+                    Some((_, q)) if q == c => break,
+                    Some((_, other)) => s.push(other),
+                    None => return Err("Unterminated string in filter".to_string()),
+                }
+            }
+            tokens.push(Token::Str(s));
+        } else if number {
+            chars.next();
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, d)) = chars.peek() {
+                if !(d.is_ascii_digit() || d == '.') {
+                    break;
+                }
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            let text = &expression[start..end];
+            let number = text
+                .parse()
+                .map_err(|_| format!("Invalid number '{}' in filter", text))?;
+            tokens.push(Token::Num(number));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, d)) = chars.peek() {
+                if !(d.is_alphanumeric() || d == '_' || d == '-') {
+                    break;
+                }
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Ident(expression[start..end].to_string()));
+        } else {
+            let rest = &expression[start..];
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("Unexpected '{}' in filter", c))?;
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push(Token::Op(op));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.or()?;
+            if !self.eat(")") {
+                return Err("Missing ')' in filter".to_string());
+            }
+            return Ok(expr);
+        }
+
+        let left = self.operand()?;
+        let comparison = match self.peek() {
+            Some(Token::Op("==")) => Comparison::Eq,
+            Some(Token::Op("!=")) => Comparison::Ne,
+            Some(Token::Op("<")) => Comparison::Lt,
+            Some(Token::Op("<=")) => Comparison::Le,
+            Some(Token::Op(">")) => Comparison::Gt,
+            Some(Token::Op(">=")) => Comparison::Ge,
+            Some(Token::Op(op @ ("=~" | "!~"))) => {
+                let negated = *op == "!~";
+                self.pos += 1;
+                let pattern = match self.next() {
+                    Some(Token::Str(pattern)) => pattern,
+                    _ => return Err("Expected a regex string after '=~' or '!~'".to_string()),
+                };
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+                let expr = Expr::Matches(left, regex);
+                return Ok(if negated {
+                    Expr::Not(Box::new(expr))
+                } else {
+                    expr
+                });
+            }
+            _ => return Ok(Expr::Truthy(left)),
+        };
+        self.pos += 1;
+        Ok(Expr::Compare(left, comparison, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(Operand::Literal(Value::String(s))),
+            Some(Token::Num(n)) => Ok(Operand::Literal(Value::from(n))),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Operand::Literal(Value::Bool(true))),
+                "false" => Ok(Operand::Literal(Value::Bool(false))),
+                "null" => Ok(Operand::Literal(Value::Null)),
+                "event_type" => Ok(Operand::EventType),
+                "context" => {
+                    let mut path = Vec::new();
+                    while self.eat(".") {
+                        match self.next() {
+                            Some(Token::Ident(segment)) => path.push(segment),
+                            _ => return Err("Expected a field name after '.'".to_string()),
+                        }
+                    }
+                    Ok(Operand::Context(path))
+                }
+                _ => Err(format!(
+                    "Unknown name '{}' in filter (use context.{} for a context field)",
+                    name, name
+                )),
+            },
+            Some(token) => Err(format!("Unexpected {} in filter", describe(&token))),
+            None => Err("The filter ends too early".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use shev_core::ShellType;
+
+    use super::*;
+
+    const CONTEXT: &str = r#"{
+        "branch": "main",
+        "count": 3,
+        "zero": 0,
+        "ready": true,
+        "none": null,
+        "quote": "say \"hi\"",
+        "path": "C:\\temp",
+        "tags": ["a", "b"],
+        "repo": {"name": "shev", "stars": 10}
+    }"#;
+
+    fn matches(filter: &str) -> bool {
+        let event = Event::new("push".to_string(), CONTEXT.to_string());
+        Filter::parse(filter).unwrap().matches(&event)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(matches("true || false && false"));
+        assert!(!matches("(true || false) && false"));
+        assert!(matches("false && false || true"));
+        assert!(matches("!false && true"));
+        assert!(!matches("!(false || true)"));
+        assert!(matches("!!context.ready"));
+        assert!(matches(
+            "context.branch == 'main' && context.count > 2 || context.nope"
+        ));
+    }
+
+    #[test]
+    fn strings_take_backslash_escapes() {
+        assert!(matches(r#"context.quote == "say \"hi\"""#));
+        assert!(matches(r#"context.quote == 'say "hi"'"#));
+        assert!(matches(r"context.path == 'C:\\temp'"));
+        assert!(matches(r#"'it\'s' == "it's""#));
+        assert!(matches(r"context.branch =~ '^ma\\w+$'"));
+        assert!(Filter::parse("context.branch == 'main").is_err());
+        assert!(Filter::parse(r"context.branch == 'main\").is_err());
+    }
+
+    #[test]
+    fn missing_fields_are_null() {
+        assert!(matches("context.nope == null"));
+        assert!(matches("context.none == null"));
+        assert!(matches("context.nope != 'main'"));
+        assert!(!matches("context.nope"));
+        assert!(!matches("context.nope > 0"));
+        assert!(!matches("context.nope < 0"));
+        assert!(!matches("context.nope =~ '.*'"));
+        assert!(matches("context.nope !~ '.*'"));
+        assert!(!matches("context.branch.deeper"));
+        assert!(!matches("context.tags.5"));
+        assert!(matches("context.tags.1 == 'b'"));
+        assert!(matches(
+            "context.repo.name == 'shev' && context.repo.stars >= 10"
+        ));
+    }
+
+    #[test]
+    fn mismatched_types_are_unequal_and_unordered() {
+        assert!(!matches("context.count == '3'"));
+        assert!(matches("context.count != '3'"));
+        assert!(!matches("context.count < 'z'"));
+        assert!(!matches("context.branch > 1"));
+        assert!(!matches("context.ready == 1"));
+        assert!(!matches("context.zero == false"));
+        assert!(!matches("context.zero"));
+        assert!(matches("context.count =~ '^3$'"));
+        assert!(matches("context.ready =~ 'true'"));
+        assert!(!matches("context.tags =~ 'a'"));
+        assert!(matches("context.tags"));
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        for filter in [
+            "",
+            "branch == 'main'",
+            "context.branch ==",
+            "(context.ready",
+            "context.branch =~ 'main' 'x'",
+            "context.branch =~ '('",
+            "context.branch =~ main",
+            "context. == 1",
+            "context.ready $ 1",
+        ] {
+            assert!(Filter::parse(filter).is_err(), "{}", filter);
+        }
+    }
+
+    #[test]
+    fn cache_follows_the_handler_filter() {
+        let cache = FilterCache::new();
+        let event = Event::new("push".to_string(), CONTEXT.to_string());
+        let mut handler = EventHandler::new(
+            "push".to_string(),
+            "default".to_string(),
+            ShellType::Sh,
+            "true".to_string(),
+            None,
+            HashMap::new(),
+        );
+        assert_eq!(cache.matches(&handler, &event), Ok(true));
+
+        handler.filter = Some("context.branch == 'main'".to_string());
+        cache.set(&handler);
+        assert_eq!(cache.matches(&handler, &event), Ok(true));
+
+        // A handler saved with a different filter is not matched against the old one
+        handler.filter = Some("context.branch == 'dev'".to_string());
+        assert_eq!(cache.matches(&handler, &event), Ok(false));
+
+        handler.filter = Some("context.branch ==".to_string());
+        cache.set(&handler);
+        assert!(cache.matches(&handler, &event).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::consumer::Offer;
+use crate::db::EventHandler;
+use shev_core::ThrottleMode;
+
+/// Event type and handler name
+type HandlerKey = (String, String);
+
+#[derive(Default)]
+struct HandlerGate {
+    /// Bumped by every debounced event; a quiet period only ends if no later event bumped it
+    generation: u64,
+    /// The last event of the current quiet period
+    debounced: Option<Offer>,
+    /// Start times of the jobs in the current throttle window, oldest first
+    runs: VecDeque<Instant>,
+    /// The last event waiting for room in the throttle window
+    coalesced: Option<Offer>,
+    /// Events suppressed since the handler's last job
+    suppressed: u32,
+}
+
+impl HandlerGate {
+    /// Forget the runs that left the window; true if another one fits
+    fn has_room(&mut self, max_runs: u32, window: Duration, now: Instant) -> bool {
+        while self
+            .runs
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= window)
+        {
+            self.runs.pop_front();
+        }
+        self.runs.len() < max_runs as usize
+    }
+
+    /// Time until the oldest run leaves the window
+    fn wait(&self, window: Duration, now: Instant) -> Duration {
+        self.runs
+            .front()
+            .map(|started| window.saturating_sub(now.duration_since(*started)))
+            .unwrap_or_default()
+    }
+
+    fn run(&mut self, offer: Offer, now: Instant) -> Throttled {
+        self.runs.push_back(now);
+        Throttled::Run(offer, std::mem::take(&mut self.suppressed))
+    }
+}
+
+/// What to do with an event that passed through a handler's throttle
+pub enum Throttled {
+    /// Start a job for the event; the number is how many events were suppressed in its favour
+    Run(Offer, u32),
+    /// The event was dropped, or replaced an event that is already waiting
+    Suppressed,
+    /// The event waits for room in the window; call `release` after this long
+    Held(Duration),
+}
+
+/// Debounces and throttles events on their way to a handler's jobs
+#[derive(Clone, Default)]
+pub struct EventGate {
+    handlers: Arc<Mutex<HashMap<HandlerKey, HandlerGate>>>,
+}
+
+impl EventGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with<T>(&self, handler: &EventHandler, f: impl FnOnce(&mut HandlerGate) -> T) -> T {
+        let mut handlers = self.handlers.lock().unwrap();
+        let gate = handlers
+            .entry((handler.event_type.clone(), handler.name.clone()))
+            .or_default();
+        f(gate)
+    }
+
+    /// Hold the event for the handler's quiet period, replacing the event held so far. Returns
+    /// the generation to `settle` it with.
+    pub fn debounce(&self, handler: &EventHandler, offer: Offer) -> u64 {
+        self.with(handler, |gate| {
+            if gate.debounced.replace(offer).is_some() {
+                gate.suppressed += 1;
+            }
+            gate.generation += 1;
+            gate.generation
+        })
+    }
+
+    /// The held event, unless another event arrived after the one given `generation`
+    pub fn settle(&self, handler: &EventHandler, generation: u64) -> Option<Offer> {
+        self.with(handler, |gate| {
+            if gate.generation == generation {
+                gate.debounced.take()
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Let the event through if the handler's throttle window has room
+    pub fn throttle(&self, handler: &EventHandler, offer: Offer) -> Throttled {
+        self.with(handler, |gate| {
+            let now = Instant::now();
+            let Some(policy) = &handler.throttle else {
+                return gate.run(offer, now);
+            };
+            let window = Duration::from_secs(policy.window_secs.into());
+            if gate.coalesced.is_none() && gate.has_room(policy.max_runs, window, now) {
+                return gate.run(offer, now);
+            }
+
+            match policy.mode {
+                ThrottleMode::Drop => {
+                    gate.suppressed += 1;
+                    Throttled::Suppressed
+                }
+                ThrottleMode::Coalesce => {
+                    if gate.coalesced.replace(offer).is_some() {
+                        gate.suppressed += 1;
+                        Throttled::Suppressed
+                    } else {
+                        Throttled::Held(gate.wait(window, now))
+                    }
+                }
+            }
+        })
+    }
+
+    /// Let the waiting coalesced event through once the window has room
+    pub fn release(&self, handler: &EventHandler) -> Throttled {
+        self.with(handler, |gate| {
+            let now = Instant::now();
+            if let Some(policy) = &handler.throttle {
+                let window = Duration::from_secs(policy.window_secs.into());
+                if !gate.has_room(policy.max_runs, window, now) {
+                    return Throttled::Held(gate.wait(window, now));
+                }
+            }
+            match gate.coalesced.take() {
+                Some(offer) => gate.run(offer, now),
+                None => Throttled::Suppressed,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::db::Event;
+    use shev_core::{ShellType, ThrottlePolicy};
+
+    fn handler(throttle: Option<ThrottlePolicy>) -> EventHandler {
+        let mut handler = EventHandler::new(
+            "push".to_string(),
+            "default".to_string(),
+            ShellType::Sh,
+            "true".to_string(),
+            None,
+            HashMap::new(),
+        );
+        handler.throttle = throttle;
+        handler
+    }
+
+    fn throttled(max_runs: u32, mode: ThrottleMode) -> EventHandler {
+        handler(Some(ThrottlePolicy {
+            max_runs,
+            window_secs: 60,
+            mode,
+        }))
+    }
+
+    fn offer() -> Offer {
+        Offer::new(Event::new("push".to_string(), "{}".to_string())).0
+    }
+
+    /// The event that runs and the number suppressed in its favour
+    fn ran(throttled: Throttled) -> Option<(Event, u32)> {
+        match throttled {
+            Throttled::Run(offer, suppressed) => Some((offer.event, suppressed)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn debouncing_keeps_the_last_event_of_a_quiet_period() {
+        let (gate, handler) = (EventGate::new(), handler(None));
+        let (first, mut first_admission) = Offer::new(Event::new("push".to_string(), "1".into()));
+        let (second, _) = Offer::new(Event::new("push".to_string(), "2".into()));
+
+        let early = gate.debounce(&handler, first);
+        let late = gate.debounce(&handler, second);
+        // The first event's quiet period was cut short by the second
+        assert!(gate.settle(&handler, early).is_none());
+        assert!(first_admission.try_recv().is_err());
+
+        let settled = gate.settle(&handler, late).unwrap();
+        assert_eq!(settled.event.context, "2");
+        let (event, suppressed) = ran(gate.throttle(&handler, settled)).unwrap();
+        assert_eq!((event.context.as_str(), suppressed), ("2", 1));
+    }
+
+    #[test]
+    fn runs_leave_the_window_after_it_passes() {
+        let mut gate = HandlerGate::default();
+        let window = Duration::from_secs(10);
+        let start = Instant::now();
+        gate.runs.extend([start, start + Duration::from_secs(4)]);
+
+        let later = start + Duration::from_secs(6);
+        assert!(!gate.has_room(2, window, later));
+        assert_eq!(gate.wait(window, later), Duration::from_secs(4));
+        assert!(gate.has_room(2, window, start + window));
+        assert_eq!(gate.runs.len(), 1);
+    }
+
+    #[test]
+    fn drop_mode_suppresses_events_over_the_limit() {
+        let (gate, handler) = (EventGate::new(), throttled(2, ThrottleMode::Drop));
+        assert!(ran(gate.throttle(&handler, offer())).is_some());
+        assert!(ran(gate.throttle(&handler, offer())).is_some());
+        for _ in 0..3 {
+            assert!(matches!(
+                gate.throttle(&handler, offer()),
+                Throttled::Suppressed
+            ));
+        }
+        let handlers = gate.handlers.lock().unwrap();
+        assert_eq!(handlers.values().next().unwrap().suppressed, 3);
+    }
+
+    #[test]
+    fn coalesce_mode_runs_the_last_event_once_there_is_room() {
+        let (gate, handler) = (EventGate::new(), throttled(1, ThrottleMode::Coalesce));
+        assert!(ran(gate.throttle(&handler, offer())).is_some());
+
+        let (waiting, mut waiting_admission) =
+            Offer::new(Event::new("push".to_string(), "1".into()));
+        let (last, _) = Offer::new(Event::new("push".to_string(), "2".into()));
+        let Throttled::Held(wait) = gate.throttle(&handler, waiting) else {
+            panic!("the event should wait for room");
+        };
+        assert!(wait <= Duration::from_secs(60) && wait > Duration::from_secs(59));
+        // A later event takes the waiting one's place
+        assert!(matches!(
+            gate.throttle(&handler, last),
+            Throttled::Suppressed
+        ));
+        assert!(waiting_admission.try_recv().is_err());
+        assert!(matches!(gate.release(&handler), Throttled::Held(_)));
+
+        // Once the window has passed
+        gate.with(&handler, |gate| gate.runs.clear());
+        let (event, suppressed) = ran(gate.release(&handler)).unwrap();
+        assert_eq!((event.context.as_str(), suppressed), ("2", 1));
+        // Nothing is left waiting
+        gate.with(&handler, |gate| gate.runs.clear());
+        assert!(matches!(gate.release(&handler), Throttled::Suppressed));
+    }
+}
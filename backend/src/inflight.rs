@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// The kind of producer ("Timer", "Schedule", "Watcher" or "Tail") and its event type
+type ProducerKey = (&'static str, String);
+
+/// Events sent by timers, schedules, watchers and tails that the consumer has not finished
+/// with, so overlap policies also see events that are still queued, debounced or deferred by a
+/// blackout window and have no job yet
+#[derive(Clone, Default)]
+pub struct InFlight {
+    events: Arc<Mutex<HashMap<Uuid, ProducerKey>>>,
+    finished: Arc<Notify>,
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold the event against its producer until `finish` is called for it
+    pub fn start(&self, source: &'static str, event_type: &str, event_id: Uuid) {
+        let mut events = self.events.lock().unwrap();
+        events.insert(event_id, (source, event_type.to_string()));
+    }
+
+    /// Release an event that every handler has finished with, or that was never delivered.
+    /// Events that no producer holds are ignored.
+    pub fn finish(&self, event_id: Uuid) {
+        let removed = self.events.lock().unwrap().remove(&event_id);
+        if removed.is_some() {
+            self.finished.notify_waiters();
+        }
+    }
+
+    /// Whether an event of the producer is still on its way through the consumer
+    pub fn is_busy(&self, source: &str, event_type: &str) -> bool {
+        let events = self.events.lock().unwrap();
+        events
+            .values()
+            .any(|(s, t)| *s == source && t == event_type)
+    }
+
+    /// Wait until the consumer has finished with every event of the producer
+    pub async fn wait_until_idle(&self, source: &str, event_type: &str) {
+        loop {
+            let finished = self.finished.notified();
+            if !self.is_busy(source, event_type) {
+                return;
+            }
+            finished.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[test]
+    fn events_are_held_per_producer() {
+        let in_flight = InFlight::new();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        in_flight.start("Timer", "backup", first);
+        in_flight.start("Timer", "backup", second);
+
+        assert!(in_flight.is_busy("Timer", "backup"));
+        assert!(!in_flight.is_busy("Schedule", "backup"));
+        assert!(!in_flight.is_busy("Timer", "deploy"));
+
+        in_flight.finish(first);
+        assert!(in_flight.is_busy("Timer", "backup"));
+        in_flight.finish(second);
+        assert!(!in_flight.is_busy("Timer", "backup"));
+
+        // Events sent by anything else are not held
+        in_flight.finish(Uuid::new_v4());
+    }
+
+    #[tokio::test]
+    async fn waiting_ends_when_the_last_event_finishes() {
+        let in_flight = InFlight::new();
+        let event = Uuid::new_v4();
+        in_flight.start("Tail", "errors", event);
+
+        let waiting = in_flight.clone();
+        let waiter = tokio::spawn(async move { waiting.wait_until_idle("Tail", "errors").await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        in_flight.finish(event);
+        timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
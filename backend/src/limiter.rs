@@ -0,0 +1,265 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+/// A counting limit whose maximum can change while jobs wait on it. Waiters are served in
+/// arrival order. A maximum of 0 means unlimited.
+struct Limit {
+    state: Mutex<LimitState>,
+}
+
+struct LimitState {
+    max: usize,
+    running: usize,
+    waiters: VecDeque<oneshot::Sender<()>>,
+}
+
+impl LimitState {
+    fn has_room(&self) -> bool {
+        self.max == 0 || self.running < self.max
+    }
+
+    /// Hand free slots to waiters, skipping those that gave up
+    fn wake(&mut self) {
+        while self.has_room() {
+            let Some(waiter) = self.waiters.pop_front() else {
+                break;
+            };
+            if waiter.send(()).is_ok() {
+                self.running += 1;
+            }
+        }
+    }
+}
+
+impl Limit {
+    fn new(max: usize) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(LimitState {
+                max,
+                running: 0,
+                waiters: VecDeque::new(),
+            }),
+        })
+    }
+
+    fn set_max(&self, max: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.max = max;
+        state.wake();
+    }
+
+    async fn acquire(self: &Arc<Self>) -> Slot {
+        let waiter = {
+            let mut state = self.state.lock().unwrap();
+            if state.waiters.is_empty() && state.has_room() {
+                state.running += 1;
+                None
+            } else {
+                let (tx, rx) = oneshot::channel();
+                state.waiters.push_back(tx);
+                Some(rx)
+            }
+        };
+
+        let mut slot = Slot {
+            limit: self.clone(),
+            acquired: waiter.is_none(),
+            waiter,
+        };
+        if let Some(waiter) = slot.waiter.as_mut() {
+            let _ = waiter.await;
+            slot.acquired = true;
+            slot.waiter = None;
+        }
+        slot
+    }
+}
+
+/// A slot of a limit, released on drop. Dropping it while still waiting gives up the place in
+/// the queue, or releases the slot if it was handed over in the meantime.
+struct Slot {
+    limit: Arc<Limit>,
+    acquired: bool,
+    waiter: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        // Slots are handed over under this lock, so the check below cannot race with one
+        let mut state = self.limit.state.lock().unwrap();
+        let held = self.acquired
+            || self
+                .waiter
+                .as_mut()
+                .is_some_and(|waiter| waiter.try_recv().is_ok());
+        self.waiter = None;
+        if held {
+            state.running = state.running.saturating_sub(1);
+            state.wake();
+        }
+    }
+}
+
+/// Slots held by a running job, released when it is dropped
+pub struct JobPermit {
+    _handler: Slot,
+    _global: Slot,
+}
+
+/// Event type and handler name
+type HandlerKey = (String, String);
+
+/// Bounds how many jobs run at once, globally and per handler
+#[derive(Clone)]
+pub struct ConcurrencyLimiter {
+    global: Arc<Limit>,
+    handlers: Arc<Mutex<HashMap<HandlerKey, Arc<Limit>>>>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new() -> Self {
+        Self {
+            global: Limit::new(0),
+            handlers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn set_global_limit(&self, max: usize) {
+        self.global.set_max(max);
+    }
+
+    pub fn set_handler_limit(&self, event_type: &str, name: &str, max: usize) {
+        self.handler_limit(event_type, name).set_max(max);
+    }
+
+    fn handler_limit(&self, event_type: &str, name: &str) -> Arc<Limit> {
+        let mut handlers = self.handlers.lock().unwrap();
+        handlers
+            .entry((event_type.to_string(), name.to_string()))
+            .or_insert_with(|| Limit::new(0))
+            .clone()
+    }
+
+    /// Wait for a slot of the handler and then a global one, in arrival order
+    pub async fn acquire(&self, event_type: &str, name: &str) -> JobPermit {
+        let handler = self.handler_limit(event_type, name).acquire().await;
+        let global = self.global.acquire().await;
+        JobPermit {
+            _handler: handler,
+            _global: global,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::FutureExt;
+    use tokio::sync::mpsc;
+    use tokio::time::sleep;
+
+    use super::*;
+
+    /// A permit if one is free right away
+    fn try_acquire(limiter: &ConcurrencyLimiter, name: &str) -> Option<JobPermit> {
+        limiter.acquire("build", name).now_or_never()
+    }
+
+    #[test]
+    fn no_limit_by_default() {
+        let limiter = ConcurrencyLimiter::new();
+        let permits: Vec<_> = (0..100).map(|_| try_acquire(&limiter, "a")).collect();
+        assert!(permits.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn handler_limits_count_running_jobs() {
+        let limiter = ConcurrencyLimiter::new();
+        limiter.set_handler_limit("build", "a", 2);
+
+        let first = try_acquire(&limiter, "a").unwrap();
+        let _second = try_acquire(&limiter, "a").unwrap();
+        assert!(try_acquire(&limiter, "a").is_none());
+        // Other handlers have their own limit
+        assert!(try_acquire(&limiter, "b").is_some());
+
+        drop(first);
+        assert!(try_acquire(&limiter, "a").is_some());
+    }
+
+    #[test]
+    fn the_global_limit_spans_handlers() {
+        let limiter = ConcurrencyLimiter::new();
+        limiter.set_global_limit(1);
+
+        let first = try_acquire(&limiter, "a").unwrap();
+        assert!(try_acquire(&limiter, "b").is_none());
+        drop(first);
+        assert!(try_acquire(&limiter, "b").is_some());
+    }
+
+    #[test]
+    fn giving_up_while_waiting_frees_the_place() {
+        let limiter = ConcurrencyLimiter::new();
+        limiter.set_handler_limit("build", "a", 1);
+        let running = try_acquire(&limiter, "a").unwrap();
+
+        // Each of these queues up and then leaves
+        for _ in 0..3 {
+            assert!(try_acquire(&limiter, "a").is_none());
+        }
+        drop(running);
+        let _next = try_acquire(&limiter, "a").unwrap();
+        assert!(try_acquire(&limiter, "a").is_none());
+    }
+
+    #[tokio::test]
+    async fn waiters_are_served_in_arrival_order() {
+        let limiter = ConcurrencyLimiter::new();
+        limiter.set_handler_limit("build", "a", 1);
+        let running = try_acquire(&limiter, "a").unwrap();
+
+        let (order, mut started) = mpsc::unbounded_channel();
+        for i in 0..3 {
+            let (limiter, order) = (limiter.clone(), order.clone());
+            tokio::spawn(async move {
+                let _permit = limiter.acquire("build", "a").await;
+                order.send(i).unwrap();
+                sleep(Duration::from_millis(10)).await;
+            });
+            // Let it queue up before the next one
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert!(started.try_recv().is_err());
+
+        drop(running);
+        for i in 0..3 {
+            assert_eq!(started.recv().await, Some(i));
+        }
+    }
+
+    #[tokio::test]
+    async fn raising_a_limit_wakes_waiters() {
+        let limiter = ConcurrencyLimiter::new();
+        limiter.set_handler_limit("build", "a", 1);
+        let _running = try_acquire(&limiter, "a").unwrap();
+
+        let waiting = limiter.clone();
+        let waiter = tokio::spawn(async move {
+            let _permit = waiting.acquire("build", "a").await;
+        });
+        sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        limiter.set_handler_limit("build", "a", 2);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        // The woken job released its slot again, and the limit is 2
+        assert!(try_acquire(&limiter, "a").is_some());
+    }
+}
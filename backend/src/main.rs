@@ -3,10 +3,23 @@ mod config;
 mod consumer;
 mod db;
 mod executor;
+mod filter;
+mod gate;
+mod inflight;
+mod limiter;
 mod middleware;
+mod output;
 mod producer;
 mod queue;
+mod recurrence;
+mod rrule;
 mod store;
+mod tail;
+mod template;
+mod watcher;
+mod webhook;
+mod window;
+mod workflow;
 
 use std::net::SocketAddr;
 
@@ -25,7 +38,7 @@ use clap::Parser;
 use crate::config::{Args, get_db_path};
 use crate::consumer::start_consumer;
 use crate::db::Database;
-use crate::producer::{ScheduleManager, TimerManager};
+use crate::producer::{ScheduleManager, TailManager, TimerManager, WatcherManager};
 use crate::queue::create_event_queue;
 use crate::store::JobStore;
 
@@ -43,12 +56,29 @@ use crate::store::JobStore;
         shev_core::JobStatus,
         shev_core::ShellType,
         shev_core::EventHandler,
+        shev_core::RetryPolicy,
+        shev_core::Backoff,
+        shev_core::ThrottlePolicy,
+        shev_core::ThrottleMode,
+        shev_core::OverlapPolicy,
+        shev_core::MisfirePolicy,
+        shev_core::ScheduleState,
+        shev_core::WindowAction,
+        shev_core::FileChange,
+        shev_core::WorkflowStep,
+        shev_core::StepCondition,
+        shev_core::StepStatus,
+        shev_core::StepRun,
+        shev_core::RunStatus,
+        shev_core::WorkflowRun,
         // API types
         shev_core::api::StatusResponse,
         shev_core::api::HealthResponse,
         shev_core::api::Warning,
         shev_core::api::WarningKind,
         shev_core::api::HandlerResponse,
+        shev_core::api::FilterTestRequest,
+        shev_core::api::FilterTestResult,
         shev_core::api::CreateHandlerRequest,
         shev_core::api::UpdateHandlerRequest,
         shev_core::api::TimerResponse,
@@ -57,6 +87,23 @@ use crate::store::JobStore;
         shev_core::api::ScheduleResponse,
         shev_core::api::CreateScheduleRequest,
         shev_core::api::UpdateScheduleRequest,
+        shev_core::api::SchedulePreviewResponse,
+        shev_core::api::WindowResponse,
+        shev_core::api::CreateWindowRequest,
+        shev_core::api::UpdateWindowRequest,
+        shev_core::api::WatcherResponse,
+        shev_core::api::CreateWatcherRequest,
+        shev_core::api::UpdateWatcherRequest,
+        shev_core::api::TailResponse,
+        shev_core::api::CreateTailRequest,
+        shev_core::api::UpdateTailRequest,
+        shev_core::api::WebhookResponse,
+        shev_core::api::CreateWebhookRequest,
+        shev_core::api::UpdateWebhookRequest,
+        shev_core::api::WorkflowResponse,
+        shev_core::api::CreateWorkflowRequest,
+        shev_core::api::UpdateWorkflowRequest,
+        shev_core::api::RunWorkflowRequest,
         shev_core::api::ConfigResponse,
         shev_core::api::UpdateConfigRequest,
         shev_core::api::ReloadResponse,
@@ -70,6 +117,11 @@ use crate::store::JobStore;
         (name = "Handlers", description = "Event handler management"),
         (name = "Timers", description = "Timer-based event producers"),
         (name = "Schedules", description = "Scheduled event producers"),
+        (name = "Windows", description = "Blackout windows that suppress events"),
+        (name = "Watchers", description = "File system event producers"),
+        (name = "Tails", description = "Log file event producers"),
+        (name = "Webhooks", description = "Inbound webhooks from external systems"),
+        (name = "Workflows", description = "DAGs of handler jobs"),
         (name = "Config", description = "System configuration"),
         (name = "Events", description = "Event triggering")
     )
@@ -94,21 +146,40 @@ async fn main() {
     if cancelled > 0 {
         info!("Cancelled {} stale job(s) from previous run", cancelled);
     }
+    let failed_runs = db.fail_stale_workflow_runs().await;
+    if failed_runs > 0 {
+        info!(
+            "Failed {} stale workflow run(s) from previous run",
+            failed_runs
+        );
+    }
 
     let port = db.get_port().await;
     let queue_size = db.get_queue_size().await;
+    let max_concurrent_jobs = db.get_max_concurrent_jobs().await;
 
     let store = JobStore::new(db);
+    store.set_max_concurrent_jobs(max_concurrent_jobs);
     store.load_handlers().await;
     let timers = store.load_timers().await;
     let schedules = store.load_schedules().await;
+    let window_count = store.load_windows().await;
+    let watchers = store.load_watchers().await;
+    let tails = store.load_tails().await;
+    let webhook_count = store.load_webhooks().await;
+    let workflow_count = store.load_workflows().await;
 
     let handler_count = store.get_handlers().await.len();
     info!(
-        "Loaded {} handler(s), {} timer(s), and {} schedule(s) from database",
+        "Loaded {} handler(s), {} timer(s), {} schedule(s), {} watcher(s), {} tail(s), {} webhook(s), {} workflow(s), and {} window(s) from database",
         handler_count,
         timers.len(),
-        schedules.len()
+        schedules.len(),
+        watchers.len(),
+        tails.len(),
+        webhook_count,
+        workflow_count,
+        window_count
     );
 
     let (sender, receiver) = create_event_queue(queue_size);
@@ -125,9 +196,22 @@ async fn main() {
             .await;
     }
 
+    let watcher_manager = WatcherManager::new(store.clone());
+    for watcher in watchers {
+        watcher_manager
+            .register_watcher(watcher, sender.clone())
+            .await;
+    }
+
+    let tail_manager = TailManager::new(store.clone());
+    for tail in tails {
+        tail_manager.register_tail(tail, sender.clone()).await;
+    }
+
     let consumer_store = store.clone();
+    let consumer_sender = sender.clone();
     tokio::spawn(async move {
-        start_consumer(receiver, consumer_store).await;
+        start_consumer(receiver, consumer_sender, consumer_store).await;
     });
 
     let ip_filter = IpFilter::new(args.allowed_ips.clone(), args.allowed_write_ips.clone());
@@ -137,8 +221,15 @@ async fn main() {
         .allow_headers(Any);
 
     // Merge OpenAPI routers and extract combined spec
-    let (router, openapi) =
-        create_api_router(store, timer_manager, schedule_manager, sender).split_for_parts();
+    let (router, openapi) = create_api_router(
+        store,
+        timer_manager,
+        schedule_manager,
+        watcher_manager,
+        tail_manager,
+        sender,
+    )
+    .split_for_parts();
 
     // Combine auto-collected paths with base ApiDoc (schemas, tags, info)
     let openapi = ApiDoc::openapi().nest("/", openapi);
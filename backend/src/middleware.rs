@@ -44,14 +44,25 @@ impl IpFilter {
     }
 }
 
+/// Whether the client passed the IP filter. Webhook requests from other addresses are let
+/// through so the hook can authenticate them by signature instead.
+#[derive(Debug, Clone, Copy)]
+pub struct AllowedIp(pub bool);
+
+fn is_webhook(request: &Request) -> bool {
+    request.method() == Method::POST && request.uri().path().starts_with("/hooks/")
+}
+
 pub async fn ip_filter_middleware(
     State(filter): State<IpFilter>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
     let method = request.method().clone();
-    if filter.is_allowed(addr.ip(), &method) {
+    let allowed = filter.is_allowed(addr.ip(), &method);
+    if allowed || is_webhook(&request) {
+        request.extensions_mut().insert(AllowedIp(allowed));
         Ok(next.run(request).await)
     } else {
         tracing::warn!("Blocked {} request from {}", method, addr.ip());
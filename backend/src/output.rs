@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+use tokio::sync::{broadcast, mpsc};
+
+use crate::db::JobStatus;
+
+/// Number of messages a slow stream subscriber may fall behind before it starts losing lines
+const LIVE_OUTPUT_CAPACITY: usize = 1024;
+
+/// Number of recent lines replayed to a stream subscriber that joins a running job
+const LIVE_OUTPUT_BACKLOG: usize = 1000;
+
+pub type OutputSender = mpsc::UnboundedSender<(OutputStream, String)>;
+pub type OutputReceiver = mpsc::UnboundedReceiver<(OutputStream, String)>;
+
+pub fn create_output_channel() -> (OutputSender, OutputReceiver) {
+    mpsc::unbounded_channel()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum OutputMessage {
+    Line(OutputStream, String),
+    Finished(JobStatus),
+}
+
+/// Output of a job that has not finished yet: the recent lines for new stream subscribers, and
+/// what was added since it was last written to the database
+pub struct LiveOutput {
+    lines: VecDeque<(OutputStream, String)>,
+    sender: broadcast::Sender<OutputMessage>,
+    stdout: String,
+    stderr: String,
+}
+
+impl LiveOutput {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(LIVE_OUTPUT_CAPACITY);
+        Self {
+            lines: VecDeque::new(),
+            sender,
+            stdout: String::new(),
+            stderr: String::new(),
+        }
+    }
+
+    pub fn push(&mut self, stream: OutputStream, line: String) {
+        let _ = self.sender.send(OutputMessage::Line(stream, line.clone()));
+        let unsaved = match stream {
+            OutputStream::Stdout => &mut self.stdout,
+            OutputStream::Stderr => &mut self.stderr,
+        };
+        unsaved.push_str(&line);
+        unsaved.push('\n');
+        if self.lines.len() == LIVE_OUTPUT_BACKLOG {
+            self.lines.pop_front();
+        }
+        self.lines.push_back((stream, line));
+    }
+
+    pub fn finish(self, status: JobStatus) {
+        let _ = self.sender.send(OutputMessage::Finished(status));
+    }
+
+    /// Recent lines plus a receiver for everything after them
+    pub fn subscribe(
+        &self,
+    ) -> (
+        Vec<(OutputStream, String)>,
+        broadcast::Receiver<OutputMessage>,
+    ) {
+        (
+            self.lines.iter().cloned().collect(),
+            self.sender.subscribe(),
+        )
+    }
+
+    /// Returns the (stdout, stderr) added since the last call, if any
+    pub fn take_changes(&mut self) -> Option<(String, String)> {
+        if self.stdout.is_empty() && self.stderr.is_empty() {
+            return None;
+        }
+        Some((
+            std::mem::take(&mut self.stdout),
+            std::mem::take(&mut self.stderr),
+        ))
+    }
+}
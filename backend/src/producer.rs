@@ -1,38 +1,63 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use chrono::Utc;
-use tokio::time::sleep;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::time::{Instant, sleep, sleep_until};
 use tracing::{info, warn};
+use uuid::Uuid;
 
-use crate::db::{Event, ScheduleRecord, TimerRecord};
+use crate::db::{Event, ScheduleRecord, ScheduleState, TailRecord, TimerRecord, WatcherRecord};
 use crate::queue::EventSender;
+use crate::recurrence;
 use crate::store::JobStore;
+use crate::tail::{LineMatcher, LogFollower};
+use crate::watcher::{self, FileFilter};
+use crate::window;
+use shev_core::{FileChange, OverlapPolicy};
+
+/// How often a watcher checks whether it was updated or removed, and retries paths it could
+/// not watch
+const WATCHER_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often a tail looks for new lines
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a tail checks whether it was updated or removed
+const TAIL_CHECK_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Clone)]
 pub struct TimerManager {
     store: JobStore,
+    running: RunningLoops,
 }
 
 impl TimerManager {
     pub fn new(store: JobStore) -> Self {
-        Self { store }
+        Self {
+            store,
+            running: RunningLoops::default(),
+        }
     }
 
     pub async fn register_timer(&self, config: TimerRecord, sender: EventSender) {
         let event_type = config.event_type.clone();
         let timer_id = config.id;
 
+        if !self.running.start(timer_id) {
+            info!(
+                "Timer '{}' (id: {}) already running, skipping",
+                event_type, timer_id
+            );
+            return;
+        }
+
         {
             let existing_timer = self.store.get_timer(&event_type).await;
-            if let Some(existing) = existing_timer {
-                if existing.id == timer_id {
-                    info!(
-                        "Timer '{}' (id: {}) already running, skipping",
-                        event_type, timer_id
-                    );
-                    return;
-                }
-
+            if let Some(existing) = existing_timer.filter(|e| e.id != timer_id) {
                 info!(
                     "Timer '{}' updated (old: {}, new: {}), old will stop on next cycle",
                     event_type, existing.id, timer_id
@@ -45,8 +70,10 @@ impl TimerManager {
         self.store.register_timer(config.clone()).await;
 
         let store = self.store.clone();
+        let running = self.running.clone();
         tokio::spawn(async move {
             run_timer(config, sender, store).await;
+            running.stop(timer_id);
         });
     }
 }
@@ -54,28 +81,32 @@ impl TimerManager {
 #[derive(Clone)]
 pub struct ScheduleManager {
     store: JobStore,
+    running: RunningLoops,
 }
 
 impl ScheduleManager {
     pub fn new(store: JobStore) -> Self {
-        Self { store }
+        Self {
+            store,
+            running: RunningLoops::default(),
+        }
     }
 
     pub async fn register_schedule(&self, config: ScheduleRecord, sender: EventSender) {
         let event_type = config.event_type.clone();
         let schedule_id = config.id;
 
+        if !self.running.start(schedule_id) {
+            info!(
+                "Schedule '{}' (id: {}) already running, skipping",
+                event_type, schedule_id
+            );
+            return;
+        }
+
         {
             let existing_schedule = self.store.get_schedule(&event_type).await;
-            if let Some(existing) = existing_schedule {
-                if existing.id == schedule_id {
-                    info!(
-                        "Schedule '{}' (id: {}) already running, skipping",
-                        event_type, schedule_id
-                    );
-                    return;
-                }
-
+            if let Some(existing) = existing_schedule.filter(|e| e.id != schedule_id) {
                 info!(
                     "Schedule '{}' updated (old: {}, new: {}), old will stop on next cycle",
                     event_type, existing.id, schedule_id
@@ -88,21 +119,230 @@ impl ScheduleManager {
         self.store.register_schedule(config.clone()).await;
 
         let store = self.store.clone();
+        let running = self.running.clone();
         tokio::spawn(async move {
             run_schedule(config, sender, store).await;
+            running.stop(schedule_id);
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct WatcherManager {
+    store: JobStore,
+    running: RunningLoops,
+}
+
+impl WatcherManager {
+    pub fn new(store: JobStore) -> Self {
+        Self {
+            store,
+            running: RunningLoops::default(),
+        }
+    }
+
+    pub async fn register_watcher(&self, config: WatcherRecord, sender: EventSender) {
+        let event_type = config.event_type.clone();
+        let watcher_id = config.id;
+
+        if !self.running.start(watcher_id) {
+            info!(
+                "Watcher '{}' (id: {}) already running, skipping",
+                event_type, watcher_id
+            );
+            return;
+        }
+
+        {
+            let existing_watcher = self.store.get_watcher(&event_type).await;
+            if let Some(existing) = existing_watcher.filter(|e| e.id != watcher_id) {
+                info!(
+                    "Watcher '{}' updated (old: {}, new: {}), old will stop shortly",
+                    event_type, existing.id, watcher_id
+                );
+            }
+        }
+
+        info!("Starting watcher '{}' (id: {})", event_type, watcher_id);
+
+        self.store.register_watcher(config.clone()).await;
+
+        let store = self.store.clone();
+        let running = self.running.clone();
+        tokio::spawn(async move {
+            run_watcher(config, sender, store).await;
+            running.stop(watcher_id);
         });
     }
 }
 
+#[derive(Clone)]
+pub struct TailManager {
+    store: JobStore,
+    running: RunningLoops,
+}
+
+impl TailManager {
+    pub fn new(store: JobStore) -> Self {
+        Self {
+            store,
+            running: RunningLoops::default(),
+        }
+    }
+
+    pub async fn register_tail(&self, config: TailRecord, sender: EventSender) {
+        let event_type = config.event_type.clone();
+        let tail_id = config.id;
+
+        if !self.running.start(tail_id) {
+            info!(
+                "Tail '{}' (id: {}) already running, skipping",
+                event_type, tail_id
+            );
+            return;
+        }
+
+        {
+            let existing_tail = self.store.get_tail(&event_type).await;
+            if let Some(existing) = existing_tail.filter(|e| e.id != tail_id) {
+                info!(
+                    "Tail '{}' updated (old: {}, new: {}), old will stop shortly",
+                    event_type, existing.id, tail_id
+                );
+            }
+        }
+
+        info!("Starting tail '{}' (id: {})", event_type, tail_id);
+
+        self.store.register_tail(config.clone()).await;
+
+        let store = self.store.clone();
+        let running = self.running.clone();
+        tokio::spawn(async move {
+            run_tail(config, sender, store).await;
+            running.stop(tail_id);
+        });
+    }
+}
+
+/// Ids of the timer, schedule, watcher or tail loops that are currently running
+#[derive(Clone, Default)]
+struct RunningLoops(Arc<Mutex<HashSet<Uuid>>>);
+
+impl RunningLoops {
+    /// Returns false if a loop with this id is already running
+    fn start(&self, id: Uuid) -> bool {
+        self.0.lock().unwrap().insert(id)
+    }
+
+    fn stop(&self, id: Uuid) {
+        self.0.lock().unwrap().remove(&id);
+    }
+}
+
+/// Enforce the overlap policy before a timer, schedule, watcher or tail emits an event.
+/// Returns false if the event should be dropped. Skip and Queue look at the producer's earlier
+/// events that the consumer has not finished with, whether or not they have a job yet.
+async fn apply_overlap(
+    store: &JobStore,
+    event_type: &str,
+    overlap: OverlapPolicy,
+    source: &'static str,
+) -> bool {
+    match overlap {
+        OverlapPolicy::Allow => true,
+        OverlapPolicy::Skip => {
+            if store.in_flight().is_busy(source, event_type) {
+                info!(
+                    "{} '{}': previous event still being handled, skipping event",
+                    source, event_type
+                );
+                return false;
+            }
+            true
+        }
+        OverlapPolicy::Queue => {
+            if store.in_flight().is_busy(source, event_type) {
+                info!(
+                    "{} '{}': previous event still being handled, waiting for it to finish",
+                    source, event_type
+                );
+                store.in_flight().wait_until_idle(source, event_type).await;
+            }
+            true
+        }
+        OverlapPolicy::Replace => {
+            let cancelled = store.cancel_active_jobs(event_type).await;
+            if cancelled > 0 {
+                info!(
+                    "{} '{}': cancelled {} active job(s) before firing",
+                    source, event_type, cancelled
+                );
+            }
+            true
+        }
+    }
+}
+
+/// Send a producer's event, holding it against the producer until the consumer is done with it
+async fn send_event(
+    store: &JobStore,
+    sender: &EventSender,
+    source: &'static str,
+    event: Event,
+) -> bool {
+    let event_id = event.id;
+    store.in_flight().start(source, &event.event_type, event_id);
+    if sender.send(event).await.is_err() {
+        store.in_flight().finish(event_id);
+        return false;
+    }
+    true
+}
+
 async fn run_timer(config: TimerRecord, sender: EventSender, store: JobStore) {
     let timer_id = config.id;
+    let interval = ChronoDuration::seconds(config.interval_secs.into());
     info!(
-        "Timer started for '{}' (id: {}) with interval {}s",
-        config.event_type, timer_id, config.interval_secs
+        "Timer started for '{}' (id: {}) with interval {}s{}",
+        config.event_type,
+        timer_id,
+        config.interval_secs,
+        if config.align { ", aligned" } else { "" }
     );
 
-    loop {
-        sleep(Duration::from_secs(config.interval_secs.into())).await;
+    let mut last_fired_at = config.last_fired_at;
+    let mut fire_count = config.fire_count;
+    let mut next_time = recurrence::first_timer_fire_time(&config, Utc::now());
+
+    while let Some(fire_time) = next_time {
+        if recurrence::timer_exhausted(&config, fire_count) {
+            break;
+        }
+        if let Err(e) = store
+            .set_timer_fire_times(
+                &config.event_type,
+                timer_id,
+                last_fired_at,
+                Some(fire_time),
+                fire_count,
+            )
+            .await
+        {
+            warn!("Timer '{}': {}", config.event_type, e);
+        }
+
+        let jitter = match config.jitter_secs {
+            0 => ChronoDuration::zero(),
+            max => ChronoDuration::seconds(rand::random_range(0..=i64::from(max))),
+        };
+        sleep(
+            (fire_time + jitter - Utc::now())
+                .to_std()
+                .unwrap_or_default(),
+        )
+        .await;
+        next_time = recurrence::timer_fire_time(&config, (fire_time + interval).max(Utc::now()));
 
         let current_id = store.get_timer_id(&config.event_type).await;
         if current_id != Some(timer_id) {
@@ -110,7 +350,12 @@ async fn run_timer(config: TimerRecord, sender: EventSender, store: JobStore) {
                 "Timer '{}' (id: {}) is outdated or removed, stopping",
                 config.event_type, timer_id
             );
-            break;
+            return;
+        }
+
+        if !store.is_timer_enabled(&config.event_type).await {
+            info!("Timer '{}' is paused, skipping event", config.event_type);
+            continue;
         }
 
         if !store.has_handler(&config.event_type).await {
@@ -121,54 +366,89 @@ async fn run_timer(config: TimerRecord, sender: EventSender, store: JobStore) {
             continue;
         }
 
+        if !window::wait_for_windows(&store, &config.event_type, None, "Timer").await {
+            continue;
+        }
+        // Ticks that fell due while the event was deferred are not made up for
+        next_time = recurrence::timer_fire_time(&config, (fire_time + interval).max(Utc::now()));
+
+        if !apply_overlap(&store, &config.event_type, config.overlap, "Timer").await {
+            continue;
+        }
+        if store.get_timer_id(&config.event_type).await != Some(timer_id) {
+            info!(
+                "Timer '{}' (id: {}) changed while waiting, stopping",
+                config.event_type, timer_id
+            );
+            return;
+        }
+
         let event = Event::new(config.event_type.clone(), config.context.clone());
         info!("Timer producing event: {:?}", event.id);
 
-        if sender.send(event).await.is_err() {
+        if !send_event(&store, &sender, "Timer", event).await {
             warn!("Timer channel closed for '{}'", config.event_type);
-            break;
+            return;
         }
+        last_fired_at = Some(Utc::now());
+        fire_count += 1;
+    }
+
+    info!(
+        "Timer '{}' (id: {}) finished after {} events",
+        config.event_type, timer_id, fire_count
+    );
+    if let Err(e) = store
+        .set_timer_fire_times(
+            &config.event_type,
+            timer_id,
+            last_fired_at,
+            None,
+            fire_count,
+        )
+        .await
+    {
+        warn!("Timer '{}': {}", config.event_type, e);
     }
 }
 
 async fn run_schedule(config: ScheduleRecord, sender: EventSender, store: JobStore) {
-    use chrono::Duration as ChronoDuration;
-
     let schedule_id = config.id;
-    let mode = if config.periodic {
-        "periodic"
-    } else {
-        "one-shot"
-    };
     info!(
-        "Schedule started for '{}' (id: {}) at {} ({})",
-        config.event_type, schedule_id, config.scheduled_time, mode
+        "Schedule started for '{}' (id: {}), fires {}",
+        config.event_type,
+        schedule_id,
+        recurrence::describe(&config)
     );
 
-    let mut next_time = config.scheduled_time;
+    let mut last_fired_at = config.last_fired_at;
+    let mut fired = false;
+    let mut next_time = match recurrence::first_fire_time(&config, Utc::now()) {
+        Ok(next) => next,
+        Err(e) => {
+            warn!("Schedule '{}': {}, stopping", config.event_type, e);
+            None
+        }
+    };
 
     loop {
-        let now = Utc::now();
-
-        if config.periodic {
-            while next_time <= now {
-                next_time = next_time + ChronoDuration::days(1);
-            }
+        if let Err(e) = store
+            .set_schedule_fire_times(&config.event_type, schedule_id, last_fired_at, next_time)
+            .await
+        {
+            warn!("Schedule '{}': {}", config.event_type, e);
         }
-
-        let wait_duration = if next_time > now {
-            let duration = next_time - now;
-            Duration::from_secs(duration.num_seconds().max(0) as u64)
-        } else {
-            Duration::from_secs(0)
+        let Some(fire_time) = next_time else {
+            break;
         };
 
-        if wait_duration.as_secs() > 0 {
+        let wait_duration = (fire_time - Utc::now()).to_std().unwrap_or_default();
+        if !wait_duration.is_zero() {
             info!(
                 "Schedule '{}' waiting {}s until {}",
                 config.event_type,
                 wait_duration.as_secs(),
-                next_time
+                fire_time
             );
             sleep(wait_duration).await;
         }
@@ -179,7 +459,13 @@ async fn run_schedule(config: ScheduleRecord, sender: EventSender, store: JobSto
                 "Schedule '{}' (id: {}) is outdated or removed, stopping",
                 config.event_type, schedule_id
             );
-            break;
+            return;
+        }
+
+        if !store.is_schedule_enabled(&config.event_type).await {
+            info!("Schedule '{}' is paused, skipping event", config.event_type);
+            next_time = following_fire_time(&config, fire_time);
+            continue;
         }
 
         if !store.has_handler(&config.event_type).await {
@@ -187,35 +473,437 @@ async fn run_schedule(config: ScheduleRecord, sender: EventSender, store: JobSto
                 "Schedule '{}': No handler found, skipping event",
                 config.event_type
             );
-            if config.periodic {
-                next_time = next_time + ChronoDuration::days(1);
-                continue;
-            } else {
-                break;
-            }
+            next_time = following_fire_time(&config, fire_time);
+            continue;
+        }
+
+        if !window::wait_for_windows(&store, &config.event_type, None, "Schedule").await {
+            next_time = following_fire_time(&config, fire_time);
+            continue;
+        }
+
+        if !apply_overlap(&store, &config.event_type, config.overlap, "Schedule").await {
+            next_time = following_fire_time(&config, fire_time);
+            continue;
+        }
+        if store.get_schedule_id(&config.event_type).await != Some(schedule_id) {
+            info!(
+                "Schedule '{}' (id: {}) changed while waiting, stopping",
+                config.event_type, schedule_id
+            );
+            return;
         }
 
         let event = Event::new(config.event_type.clone(), config.context.clone());
         info!("Schedule producing event: {:?}", event.id);
 
-        if sender.send(event).await.is_err() {
+        if !send_event(&store, &sender, "Schedule", event).await {
             warn!("Schedule channel closed for '{}'", config.event_type);
-            break;
+            return;
         }
+        last_fired_at = Some(Utc::now());
+        fired = true;
+        next_time = following_fire_time(&config, fire_time);
 
-        if config.periodic {
-            info!(
+        match next_time {
+            Some(next) => info!(
                 "Schedule '{}' fired, next run at {}",
-                config.event_type,
-                next_time + ChronoDuration::days(1)
-            );
-            next_time = next_time + ChronoDuration::days(1);
+                config.event_type, next
+            ),
+            None => info!("Schedule '{}' fired, no runs left", config.event_type),
+        }
+    }
+
+    // A one-shot schedule is done once its only run has passed
+    if recurrence::is_one_shot(&config) && config.state == ScheduleState::Active {
+        let state = if fired {
+            ScheduleState::Completed
         } else {
-            info!(
-                "Schedule '{}' fired (one-shot), stopping",
-                config.event_type
+            ScheduleState::Expired
+        };
+        if let Err(e) = store
+            .set_schedule_state(&config.event_type, schedule_id, state)
+            .await
+        {
+            warn!("Schedule '{}': {}", config.event_type, e);
+        }
+    }
+
+    info!(
+        "Schedule '{}' (id: {}) finished",
+        config.event_type, schedule_id
+    );
+}
+
+/// The schedule's next run after firing (or skipping) the one at `fire_time`
+fn following_fire_time(config: &ScheduleRecord, fire_time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    recurrence::following_fire_time(config, fire_time, Utc::now()).unwrap_or_else(|e| {
+        warn!("Schedule '{}': {}, stopping", config.event_type, e);
+        None
+    })
+}
+
+/// Start watching the given paths, keeping the ones that cannot be watched yet (e.g. because
+/// they do not exist) for a later attempt
+fn watch_paths(
+    fs_watcher: &mut impl Watcher,
+    unwatched: &mut Vec<PathBuf>,
+    mode: RecursiveMode,
+    event_type: &str,
+    retry: bool,
+) {
+    unwatched.retain(|path| match fs_watcher.watch(path, mode) {
+        Ok(()) => {
+            info!("Watcher '{}' watching {}", event_type, path.display());
+            false
+        }
+        Err(e) => {
+            if !retry {
+                warn!(
+                    "Watcher '{}': cannot watch {} yet: {}",
+                    event_type,
+                    path.display(),
+                    e
+                );
+            }
+            true
+        }
+    });
+}
+
+async fn run_watcher(config: WatcherRecord, sender: EventSender, store: JobStore) {
+    let watcher_id = config.id;
+    let filter = match FileFilter::new(&config) {
+        Ok(filter) => filter,
+        Err(e) => {
+            warn!("Watcher '{}': {}, stopping", config.event_type, e);
+            return;
+        }
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut fs_watcher = match notify::recommended_watcher(move |result| {
+        let _ = tx.send(result);
+    }) {
+        Ok(fs_watcher) => fs_watcher,
+        Err(e) => {
+            warn!("Watcher '{}': failed to start: {}", config.event_type, e);
+            return;
+        }
+    };
+    let mode = if config.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    let roots: Vec<PathBuf> = config.paths.iter().map(PathBuf::from).collect();
+    let mut unwatched = roots.clone();
+    watch_paths(
+        &mut fs_watcher,
+        &mut unwatched,
+        mode,
+        &config.event_type,
+        false,
+    );
+    info!(
+        "Watcher started for '{}' (id: {}) on {} path(s){}",
+        config.event_type,
+        watcher_id,
+        roots.len(),
+        if config.recursive { ", recursive" } else { "" }
+    );
+
+    let debounce = Duration::from_millis(config.debounce_ms.into());
+    // Changes waiting for their file to be left alone for the debounce period
+    let mut pending: HashMap<PathBuf, (FileChange, Instant)> = HashMap::new();
+    let mut next_check = Instant::now() + WATCHER_CHECK_INTERVAL;
+
+    loop {
+        let wake = pending
+            .values()
+            .map(|(_, due)| *due)
+            .fold(next_check, Instant::min);
+        tokio::select! {
+            received = rx.recv() => {
+                let Some(result) = received else {
+                    return;
+                };
+                let event = match result {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!("Watcher '{}': {}", config.event_type, e);
+                        continue;
+                    }
+                };
+                for (path, change) in watcher::classify(&event.kind, &event.paths) {
+                    // A watched path that disappears is watched again once it is back
+                    if change == FileChange::Deleted
+                        && roots.contains(&path)
+                        && !unwatched.contains(&path)
+                    {
+                        let _ = fs_watcher.unwatch(&path);
+                        unwatched.push(path.clone());
+                    }
+                    if !filter.matches(&path) {
+                        continue;
+                    }
+                    let merged = match pending.remove(&path) {
+                        Some((previous, _)) => watcher::merge(previous, change),
+                        None => Some(change),
+                    };
+                    if let Some(change) = merged {
+                        pending.insert(path, (change, Instant::now() + debounce));
+                    }
+                }
+            }
+            _ = sleep_until(wake) => {}
+        }
+
+        let now = Instant::now();
+        if now >= next_check {
+            next_check = now + WATCHER_CHECK_INTERVAL;
+            if store.get_watcher_id(&config.event_type).await != Some(watcher_id) {
+                info!(
+                    "Watcher '{}' (id: {}) is outdated or removed, stopping",
+                    config.event_type, watcher_id
+                );
+                return;
+            }
+            watch_paths(
+                &mut fs_watcher,
+                &mut unwatched,
+                mode,
+                &config.event_type,
+                true,
             );
-            break;
         }
+
+        let due: Vec<(PathBuf, FileChange)> = pending
+            .iter()
+            .filter(|(_, (_, at))| *at <= now)
+            .map(|(path, (change, _))| (path.clone(), *change))
+            .collect();
+        for (path, change) in due {
+            pending.remove(&path);
+            if !config.changes.contains(&change) {
+                continue;
+            }
+            if !emit_file_change(&config, &path, change, &sender, &store).await {
+                return;
+            }
+        }
+    }
+}
+
+/// Produce the event for a file change. Returns false if the watcher should stop.
+async fn emit_file_change(
+    config: &WatcherRecord,
+    path: &Path,
+    change: FileChange,
+    sender: &EventSender,
+    store: &JobStore,
+) -> bool {
+    if !store.has_handler(&config.event_type).await {
+        warn!(
+            "Watcher '{}': No handler found, skipping event",
+            config.event_type
+        );
+        return true;
+    }
+
+    if !window::wait_for_windows(store, &config.event_type, None, "Watcher").await {
+        return true;
+    }
+    if !apply_overlap(store, &config.event_type, config.overlap, "Watcher").await {
+        return true;
+    }
+    if store.get_watcher_id(&config.event_type).await != Some(config.id) {
+        info!(
+            "Watcher '{}' (id: {}) changed while waiting, stopping",
+            config.event_type, config.id
+        );
+        return false;
+    }
+
+    let event = Event::new(config.event_type.clone(), watcher::context(path, change));
+    info!(
+        "Watcher producing event: {:?} ({} {})",
+        event.id,
+        change.as_str(),
+        path.display()
+    );
+
+    if !send_event(store, sender, "Watcher", event).await {
+        warn!("Watcher channel closed for '{}'", config.event_type);
+        return false;
+    }
+    true
+}
+
+async fn run_tail(config: TailRecord, sender: EventSender, store: JobStore) {
+    let tail_id = config.id;
+    let matcher = match LineMatcher::new(&config.patterns) {
+        Ok(matcher) => matcher,
+        Err(e) => {
+            warn!("Tail '{}': {}, stopping", config.event_type, e);
+            return;
+        }
+    };
+    let mut follower = LogFollower::new(&config.path, config.from_start);
+    info!(
+        "Tail started for '{}' (id: {}) on {} with {} pattern(s)",
+        config.event_type,
+        tail_id,
+        config.path,
+        config.patterns.len()
+    );
+
+    let mut last_error: Option<String> = None;
+    let mut next_check = Instant::now() + TAIL_CHECK_INTERVAL;
+
+    loop {
+        let now = Instant::now();
+        if now >= next_check {
+            next_check = now + TAIL_CHECK_INTERVAL;
+            if store.get_tail_id(&config.event_type).await != Some(tail_id) {
+                info!(
+                    "Tail '{}' (id: {}) is outdated or removed, stopping",
+                    config.event_type, tail_id
+                );
+                return;
+            }
+        }
+
+        let lines = match follower.poll().await {
+            Ok(lines) => {
+                last_error = None;
+                lines
+            }
+            Err(e) => {
+                // Only report a problem once until it changes or goes away
+                if last_error.as_ref() != Some(&e) {
+                    warn!("Tail '{}': {}", config.event_type, e);
+                    last_error = Some(e);
+                }
+                Vec::new()
+            }
+        };
+
+        for line in lines {
+            let Some(context) = matcher.context(&config.path, &line) else {
+                continue;
+            };
+            if !emit_tail_match(&config, context, &sender, &store).await {
+                return;
+            }
+        }
+
+        sleep(TAIL_POLL_INTERVAL).await;
+    }
+}
+
+/// Produce the event for a matching line. Returns false if the tail should stop.
+async fn emit_tail_match(
+    config: &TailRecord,
+    context: String,
+    sender: &EventSender,
+    store: &JobStore,
+) -> bool {
+    if !store.has_handler(&config.event_type).await {
+        warn!(
+            "Tail '{}': No handler found, skipping event",
+            config.event_type
+        );
+        return true;
+    }
+
+    if !window::wait_for_windows(store, &config.event_type, None, "Tail").await {
+        return true;
+    }
+    if !apply_overlap(store, &config.event_type, config.overlap, "Tail").await {
+        return true;
+    }
+    if store.get_tail_id(&config.event_type).await != Some(config.id) {
+        info!(
+            "Tail '{}' (id: {}) changed while waiting, stopping",
+            config.event_type, config.id
+        );
+        return false;
+    }
+
+    let event = Event::new(config.event_type.clone(), context);
+    info!("Tail producing event: {:?}", event.id);
+
+    if !send_event(store, sender, "Tail", event).await {
+        warn!("Tail channel closed for '{}'", config.event_type);
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::db::Database;
+    use crate::queue::create_event_queue;
+
+    async fn store() -> JobStore {
+        let db = Database::open(":memory:").unwrap();
+        db.init_schema().await.unwrap();
+        JobStore::new(db)
+    }
+
+    fn event() -> Event {
+        Event::new("backup".to_string(), "{}".to_string())
+    }
+
+    #[tokio::test]
+    async fn skip_drops_events_while_an_earlier_one_is_handled() {
+        let store = store().await;
+        let (sender, mut receiver) = create_event_queue(10);
+        assert!(apply_overlap(&store, "backup", OverlapPolicy::Skip, "Timer").await);
+        assert!(send_event(&store, &sender, "Timer", event()).await);
+
+        // Still queued, with no job yet
+        assert!(!apply_overlap(&store, "backup", OverlapPolicy::Skip, "Timer").await);
+        // Other producers of the same event type are not held up
+        assert!(apply_overlap(&store, "backup", OverlapPolicy::Skip, "Schedule").await);
+        assert!(apply_overlap(&store, "backup", OverlapPolicy::Allow, "Timer").await);
+
+        let sent = receiver.recv().await.unwrap();
+        store.in_flight().finish(sent.id);
+        assert!(apply_overlap(&store, "backup", OverlapPolicy::Skip, "Timer").await);
+    }
+
+    #[tokio::test]
+    async fn queue_waits_for_the_earlier_event() {
+        let store = store().await;
+        let (sender, mut receiver) = create_event_queue(10);
+        assert!(send_event(&store, &sender, "Watcher", event()).await);
+
+        let waiting = store.clone();
+        let waiter = tokio::spawn(async move {
+            apply_overlap(&waiting, "backup", OverlapPolicy::Queue, "Watcher").await
+        });
+        sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        let sent = receiver.recv().await.unwrap();
+        store.in_flight().finish(sent.id);
+        let fired = timeout(Duration::from_secs(1), waiter).await.unwrap();
+        assert!(fired.unwrap());
+    }
+
+    #[tokio::test]
+    async fn undelivered_events_are_not_held() {
+        let store = store().await;
+        let (sender, receiver) = create_event_queue(10);
+        drop(receiver);
+
+        assert!(!send_event(&store, &sender, "Tail", event()).await);
+        assert!(!store.in_flight().is_busy("Tail", "backup"));
+        assert!(apply_overlap(&store, "backup", OverlapPolicy::Skip, "Tail").await);
     }
 }
@@ -0,0 +1,519 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration as ChronoDuration, SubsecRound, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+
+use crate::db::{ScheduleRecord, TimerRecord};
+use crate::rrule::parse_rrule;
+use shev_core::{MisfirePolicy, ScheduleState};
+
+/// Parse a cron expression: five fields (minute hour day-of-month month day-of-week) or an
+/// alias such as `@hourly`
+pub fn parse_cron(expr: &str) -> Result<Cron, String> {
+    Cron::new(expr)
+        .parse()
+        .map_err(|e| format!("Invalid cron expression '{}': {}", expr, e))
+}
+
+/// Parse an IANA time zone name such as `Europe/Berlin`
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    Tz::from_str(name).map_err(|_| format!("Unknown time zone '{}'", name))
+}
+
+/// Check that a schedule's timing fields can be evaluated
+pub fn validate(schedule: &ScheduleRecord) -> Result<(), String> {
+    if schedule.cron.is_some() && schedule.rrule.is_some() {
+        return Err("A schedule cannot have both a cron expression and an rrule".to_string());
+    }
+    if schedule.periodic && (schedule.cron.is_some() || schedule.rrule.is_some()) {
+        return Err("A cron or rrule schedule cannot also be periodic".to_string());
+    }
+    if let Some(ref expr) = schedule.cron {
+        parse_cron(expr)?;
+    }
+    if let Some(ref rule) = schedule.rrule {
+        parse_rrule(rule)?;
+    }
+    if schedule.cron.is_none() && schedule.rrule.is_none() && schedule.timezone.is_some() {
+        return Err(
+            "A time zone can only be set together with a cron expression or rrule".to_string(),
+        );
+    }
+    if is_one_shot(schedule) && !schedule.exdates.is_empty() {
+        return Err("Excluded dates only apply to recurring schedules".to_string());
+    }
+    if let Some(ref timezone) = schedule.timezone {
+        parse_timezone(timezone)?;
+    }
+    Ok(())
+}
+
+/// The first time strictly after `after` at which the schedule fires, or None if it will not
+/// fire again. Excluded dates are skipped.
+pub fn next_fire_time(
+    schedule: &ScheduleRecord,
+    after: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, String> {
+    let mut next = next_occurrence(schedule, after)?;
+    while let Some(time) = next {
+        if !schedule
+            .exdates
+            .iter()
+            .any(|ex| ex.timestamp() == time.timestamp())
+        {
+            break;
+        }
+        next = next_occurrence(schedule, time)?;
+    }
+    Ok(next)
+}
+
+fn schedule_timezone(schedule: &ScheduleRecord) -> Result<Tz, String> {
+    match schedule.timezone {
+        Some(ref timezone) => parse_timezone(timezone),
+        None => Ok(Tz::UTC),
+    }
+}
+
+/// The next occurrence after `after`, including excluded dates
+fn next_occurrence(
+    schedule: &ScheduleRecord,
+    after: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, String> {
+    if let Some(ref rule) = schedule.rrule {
+        let rule = parse_rrule(rule)?;
+        let tz = schedule_timezone(schedule)?;
+        return Ok(rule.next_after(schedule.scheduled_time, &tz, after));
+    }
+
+    if let Some(ref expr) = schedule.cron {
+        let cron = parse_cron(expr)?;
+        let tz = schedule_timezone(schedule)?;
+
+        // Cron schedules start at their scheduled time, which may be an occurrence itself
+        let (start, inclusive) = if after < schedule.scheduled_time {
+            (schedule.scheduled_time, true)
+        } else {
+            (after, false)
+        };
+        let next = cron
+            .find_next_occurrence(&start.trunc_subsecs(0).with_timezone(&tz), inclusive)
+            .map_err(|e| format!("Failed to find next run of '{}': {}", expr, e))?;
+        return Ok(Some(next.with_timezone(&Utc)));
+    }
+
+    if schedule.periodic {
+        let mut next = schedule.scheduled_time;
+        while next <= after {
+            next += ChronoDuration::days(1);
+        }
+        return Ok(Some(next));
+    }
+
+    Ok((schedule.scheduled_time > after).then_some(schedule.scheduled_time))
+}
+
+/// How late a run may start before it counts as missed
+pub const MISFIRE_GRACE_SECS: i64 = 60;
+
+pub fn is_one_shot(schedule: &ScheduleRecord) -> bool {
+    schedule.cron.is_none() && schedule.rrule.is_none() && !schedule.periodic
+}
+
+/// The first time the schedule fires when it is (re)started at `now`. A run that was due more
+/// than the grace period ago is handled according to the schedule's misfire policy.
+pub fn first_fire_time(
+    schedule: &ScheduleRecord,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, String> {
+    if schedule.state != ScheduleState::Active {
+        return Ok(None);
+    }
+
+    // The run the schedule was waiting for when it last stopped
+    let due = if is_one_shot(schedule) {
+        Some(schedule.scheduled_time)
+    } else {
+        schedule.next_fire_at.filter(|t| *t <= now)
+    };
+    let missed = match due {
+        Some(time) if now - time > ChronoDuration::seconds(MISFIRE_GRACE_SECS) => time,
+        Some(time) => return Ok(Some(time)),
+        None => return next_fire_time(schedule, now),
+    };
+
+    match schedule.misfire {
+        MisfirePolicy::FireAll => Ok(Some(missed)),
+        MisfirePolicy::FireOnce => Ok(Some(now)),
+        MisfirePolicy::Skip if is_one_shot(schedule) => Ok(None),
+        MisfirePolicy::Skip => next_fire_time(schedule, now),
+    }
+}
+
+/// The next time the schedule fires after firing at `fire_time`. Runs that fell due in the
+/// meantime are caught up one by one under `fire_all` and dropped otherwise.
+pub fn following_fire_time(
+    schedule: &ScheduleRecord,
+    fire_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, String> {
+    let after = match schedule.misfire {
+        MisfirePolicy::FireAll => fire_time,
+        MisfirePolicy::FireOnce | MisfirePolicy::Skip => fire_time.max(now),
+    };
+    next_fire_time(schedule, after)
+}
+
+/// The next `count` times the schedule fires when started at `now`
+pub fn upcoming(
+    schedule: &ScheduleRecord,
+    now: DateTime<Utc>,
+    count: usize,
+) -> Result<Vec<DateTime<Utc>>, String> {
+    let mut times = Vec::with_capacity(count);
+    let mut next = first_fire_time(schedule, now)?;
+    while let Some(time) = next {
+        if times.len() >= count {
+            break;
+        }
+        times.push(time);
+        next = following_fire_time(schedule, time, now)?;
+    }
+    Ok(times)
+}
+
+/// Human-readable description of when a schedule fires, for logs
+pub fn describe(schedule: &ScheduleRecord) -> String {
+    let timezone = schedule.timezone.as_deref().unwrap_or("UTC");
+    match (&schedule.cron, &schedule.rrule) {
+        (Some(expr), _) => format!("cron '{}' ({})", expr, timezone),
+        (None, Some(rule)) => format!(
+            "rrule '{}' from {} ({})",
+            rule, schedule.scheduled_time, timezone
+        ),
+        (None, None) if schedule.periodic => format!("daily from {}", schedule.scheduled_time),
+        (None, None) => format!("once at {}", schedule.scheduled_time),
+    }
+}
+
+/// Check that a timer's timing fields make sense
+pub fn validate_timer(timer: &TimerRecord) -> Result<(), String> {
+    if timer.interval_secs == 0 {
+        return Err("The interval must be at least one second".to_string());
+    }
+    if timer.jitter_secs >= timer.interval_secs {
+        return Err("The jitter must be shorter than the interval".to_string());
+    }
+    if timer
+        .start_at
+        .zip(timer.end_at)
+        .is_some_and(|(start, end)| end <= start)
+    {
+        return Err("The end time must be after the start time".to_string());
+    }
+    if timer.max_fires == Some(0) {
+        return Err("The maximum fire count must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+/// Whether the timer has produced all the events it is allowed to
+pub fn timer_exhausted(timer: &TimerRecord, fire_count: u32) -> bool {
+    timer.max_fires.is_some_and(|max| fire_count >= max)
+}
+
+/// The first time at or after `earliest` that the timer fires, ignoring jitter, or None once it
+/// has passed its end time
+pub fn timer_fire_time(timer: &TimerRecord, earliest: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut time = match timer.start_at {
+        Some(start) => earliest.max(start),
+        None => earliest,
+    };
+    if timer.align {
+        // Round up to the next multiple of the interval
+        let interval = i64::from(timer.interval_secs.max(1));
+        let secs = time.timestamp() + i64::from(time.timestamp_subsec_nanos() > 0);
+        time = DateTime::from_timestamp((secs + interval - 1).div_euclid(interval) * interval, 0)?;
+    }
+    match timer.end_at {
+        Some(end) if time > end => None,
+        _ => Some(time),
+    }
+}
+
+/// The first time the timer fires when it is (re)started at `now`. A timer carries on where it
+/// left off instead of restarting its interval.
+pub fn first_timer_fire_time(timer: &TimerRecord, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if timer_exhausted(timer, timer.fire_count) {
+        return None;
+    }
+    let interval = ChronoDuration::seconds(timer.interval_secs.into());
+    let earliest = match (timer.next_fire_at, timer.last_fired_at) {
+        (Some(next), _) => next.max(now),
+        (None, Some(last)) => (last + interval).max(now),
+        (None, None) if timer.align || timer.start_at.is_some_and(|start| start > now) => now,
+        (None, None) => now + interval,
+    };
+    timer_fire_time(timer, earliest)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn cron(expr: &str, timezone: Option<&str>) -> ScheduleRecord {
+        let mut schedule = ScheduleRecord::new(
+            "report".to_string(),
+            "{}".to_string(),
+            at(2026, 1, 1, 0, 0),
+            false,
+        );
+        schedule.cron = Some(expr.to_string());
+        schedule.timezone = timezone.map(String::from);
+        schedule
+    }
+
+    #[test]
+    fn cron_runs_follow_the_local_time_across_dst() {
+        let schedule = cron("0 9 * * *", Some("Europe/Berlin"));
+        // CET is UTC+1, CEST is UTC+2 (from 29 March 2026)
+        assert_eq!(
+            next_fire_time(&schedule, at(2026, 3, 27, 12, 0)),
+            Ok(Some(at(2026, 3, 28, 8, 0)))
+        );
+        assert_eq!(
+            next_fire_time(&schedule, at(2026, 3, 28, 12, 0)),
+            Ok(Some(at(2026, 3, 29, 7, 0)))
+        );
+    }
+
+    #[test]
+    fn cron_runs_default_to_utc() {
+        let schedule = cron("30 6 * * 1", None);
+        // 5 January 2026 is a Monday
+        assert_eq!(
+            next_fire_time(&schedule, at(2026, 1, 1, 0, 0)),
+            Ok(Some(at(2026, 1, 5, 6, 30)))
+        );
+    }
+
+    #[test]
+    fn cron_runs_start_at_the_scheduled_time() {
+        let mut schedule = cron("0 * * * *", None);
+        schedule.scheduled_time = at(2026, 6, 1, 10, 0);
+        // The scheduled time is a run itself
+        assert_eq!(
+            next_fire_time(&schedule, at(2026, 1, 1, 0, 0)),
+            Ok(Some(at(2026, 6, 1, 10, 0)))
+        );
+        assert_eq!(
+            next_fire_time(&schedule, at(2026, 6, 1, 10, 0)),
+            Ok(Some(at(2026, 6, 1, 11, 0)))
+        );
+    }
+
+    #[test]
+    fn cron_schedules_are_validated() {
+        assert!(validate(&cron("@daily", Some("America/New_York"))).is_ok());
+        assert!(validate(&cron("61 * * * *", None)).is_err());
+        assert!(validate(&cron("0 9 * * *", Some("Mars/Olympus"))).is_err());
+
+        let mut both = cron("0 9 * * *", None);
+        both.rrule = Some("FREQ=DAILY".to_string());
+        assert!(validate(&both).is_err());
+
+        let mut once = cron("0 9 * * *", Some("Europe/Berlin"));
+        once.cron = None;
+        assert!(validate(&once).is_err());
+    }
+
+    fn once(scheduled_time: DateTime<Utc>, misfire: MisfirePolicy) -> ScheduleRecord {
+        let mut schedule = ScheduleRecord::new(
+            "report".to_string(),
+            "{}".to_string(),
+            scheduled_time,
+            false,
+        );
+        schedule.misfire = misfire;
+        schedule
+    }
+
+    #[test]
+    fn late_one_shot_runs_within_the_grace_period_still_fire() {
+        let schedule = once(at(2026, 6, 1, 10, 0), MisfirePolicy::Skip);
+        let now = at(2026, 6, 1, 10, 0) + ChronoDuration::seconds(MISFIRE_GRACE_SECS);
+        assert_eq!(
+            first_fire_time(&schedule, now),
+            Ok(Some(at(2026, 6, 1, 10, 0)))
+        );
+    }
+
+    #[test]
+    fn missed_one_shot_runs_follow_the_misfire_policy() {
+        let now = at(2026, 6, 1, 12, 0);
+        let missed = at(2026, 6, 1, 10, 0);
+        assert_eq!(
+            first_fire_time(&once(missed, MisfirePolicy::FireOnce), now),
+            Ok(Some(now))
+        );
+        assert_eq!(
+            first_fire_time(&once(missed, MisfirePolicy::FireAll), now),
+            Ok(Some(missed))
+        );
+        // Skipped, so the schedule expires
+        assert_eq!(
+            first_fire_time(&once(missed, MisfirePolicy::Skip), now),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn finished_schedules_do_not_fire() {
+        let now = at(2026, 6, 1, 9, 0);
+        for state in [ScheduleState::Completed, ScheduleState::Expired] {
+            let mut schedule = once(at(2026, 6, 1, 10, 0), MisfirePolicy::FireAll);
+            schedule.state = state;
+            assert_eq!(first_fire_time(&schedule, now), Ok(None));
+        }
+    }
+
+    #[test]
+    fn missed_recurring_runs_follow_the_misfire_policy() {
+        let now = at(2026, 6, 1, 12, 30);
+        let hourly = |misfire| {
+            let mut schedule = cron("0 * * * *", None);
+            schedule.misfire = misfire;
+            // Waiting for the 10:00 run when the backend went down
+            schedule.next_fire_at = Some(at(2026, 6, 1, 10, 0));
+            schedule
+        };
+
+        // Every missed run is caught up before the schedule is back on time
+        assert_eq!(
+            upcoming(&hourly(MisfirePolicy::FireAll), now, 4),
+            Ok(vec![
+                at(2026, 6, 1, 10, 0),
+                at(2026, 6, 1, 11, 0),
+                at(2026, 6, 1, 12, 0),
+                at(2026, 6, 1, 13, 0),
+            ])
+        );
+        assert_eq!(
+            upcoming(&hourly(MisfirePolicy::FireOnce), now, 2),
+            Ok(vec![now, at(2026, 6, 1, 13, 0)])
+        );
+        assert_eq!(
+            upcoming(&hourly(MisfirePolicy::Skip), now, 2),
+            Ok(vec![at(2026, 6, 1, 13, 0), at(2026, 6, 1, 14, 0)])
+        );
+    }
+
+    fn timer(interval_secs: u32) -> TimerRecord {
+        TimerRecord::new("poll".to_string(), "{}".to_string(), interval_secs)
+    }
+
+    #[test]
+    fn aligned_timers_fire_on_multiples_of_the_interval() {
+        let mut five_minutes = timer(300);
+        five_minutes.align = true;
+        let on_grid = at(2026, 6, 1, 10, 5);
+
+        assert_eq!(
+            timer_fire_time(&five_minutes, at(2026, 6, 1, 10, 2)),
+            Some(on_grid)
+        );
+        assert_eq!(timer_fire_time(&five_minutes, on_grid), Some(on_grid));
+        assert_eq!(
+            timer_fire_time(&five_minutes, on_grid + ChronoDuration::milliseconds(1)),
+            Some(at(2026, 6, 1, 10, 10))
+        );
+        // The next tick is taken from the planned time, so jitter does not shift the grid
+        assert_eq!(
+            timer_fire_time(&five_minutes, on_grid + ChronoDuration::seconds(300)),
+            Some(at(2026, 6, 1, 10, 10))
+        );
+    }
+
+    #[test]
+    fn timers_fire_between_their_start_and_end() {
+        let mut bounded = timer(60);
+        bounded.start_at = Some(at(2026, 6, 1, 10, 0));
+        bounded.end_at = Some(at(2026, 6, 1, 11, 0));
+
+        assert_eq!(
+            timer_fire_time(&bounded, at(2026, 6, 1, 9, 0)),
+            Some(at(2026, 6, 1, 10, 0))
+        );
+        assert_eq!(
+            timer_fire_time(&bounded, at(2026, 6, 1, 11, 0)),
+            Some(at(2026, 6, 1, 11, 0))
+        );
+        assert_eq!(timer_fire_time(&bounded, at(2026, 6, 1, 11, 1)), None);
+
+        // A timer that has not started yet fires right at its start
+        assert_eq!(
+            first_timer_fire_time(&bounded, at(2026, 6, 1, 9, 0)),
+            Some(at(2026, 6, 1, 10, 0))
+        );
+    }
+
+    #[test]
+    fn timers_stop_after_their_maximum_fire_count() {
+        let mut limited = timer(60);
+        limited.max_fires = Some(3);
+        assert!(!timer_exhausted(&limited, 2));
+        assert!(timer_exhausted(&limited, 3));
+
+        limited.fire_count = 3;
+        assert_eq!(first_timer_fire_time(&limited, at(2026, 6, 1, 10, 0)), None);
+    }
+
+    #[test]
+    fn restarted_timers_carry_on_where_they_left_off() {
+        let now = at(2026, 6, 1, 10, 0);
+        let fresh = timer(600);
+        assert_eq!(
+            first_timer_fire_time(&fresh, now),
+            Some(at(2026, 6, 1, 10, 10))
+        );
+
+        let mut fired = timer(600);
+        fired.last_fired_at = Some(at(2026, 6, 1, 9, 55));
+        assert_eq!(
+            first_timer_fire_time(&fired, now),
+            Some(at(2026, 6, 1, 10, 5))
+        );
+
+        // A tick missed while the backend was down fires at once
+        fired.next_fire_at = Some(at(2026, 6, 1, 9, 58));
+        assert_eq!(first_timer_fire_time(&fired, now), Some(now));
+    }
+
+    #[test]
+    fn timer_settings_are_validated() {
+        assert!(validate_timer(&timer(60)).is_ok());
+        assert!(validate_timer(&timer(0)).is_err());
+
+        let mut jittery = timer(60);
+        jittery.jitter_secs = 59;
+        assert!(validate_timer(&jittery).is_ok());
+        jittery.jitter_secs = 60;
+        assert!(validate_timer(&jittery).is_err());
+
+        let mut backwards = timer(60);
+        backwards.start_at = Some(at(2026, 6, 1, 10, 0));
+        backwards.end_at = Some(at(2026, 6, 1, 10, 0));
+        assert!(validate_timer(&backwards).is_err());
+
+        let mut never = timer(60);
+        never.max_fires = Some(0);
+        assert!(validate_timer(&never).is_err());
+    }
+}
@@ -0,0 +1,800 @@
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// Upper bound on the number of periods examined for the next occurrence, so that a rule that
+/// can never match (e.g. February 30th) does not loop forever. Periods ruled out by BYMONTH,
+/// BYMONTHDAY, BYDAY or BYHOUR are skipped in bulk and barely count towards it.
+const MAX_PERIODS: i64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Yearly,
+    Monthly,
+    Weekly,
+    Daily,
+    Hourly,
+    Minutely,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Until {
+    Utc(DateTime<Utc>),
+    /// Floating or date-only UNTIL, in the schedule's time zone
+    Local(NaiveDateTime),
+}
+
+/// A BYDAY entry: a day of the week with an optional ordinal, e.g. `MO` or `-1FR` (last Friday)
+#[derive(Debug, Clone, Copy)]
+struct ByDay {
+    ordinal: Option<i32>,
+    weekday: Weekday,
+}
+
+/// An RFC 5545 recurrence rule. BYYEARDAY, BYWEEKNO and FREQ=SECONDLY are not supported.
+#[derive(Debug, Clone)]
+pub struct RecurrenceRule {
+    freq: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<Until>,
+    week_start: Weekday,
+    by_month: Vec<u32>,
+    by_month_day: Vec<i32>,
+    by_day: Vec<ByDay>,
+    by_hour: Vec<u32>,
+    by_minute: Vec<u32>,
+    by_second: Vec<u32>,
+    by_set_pos: Vec<i32>,
+}
+
+/// Parse a recurrence rule such as `FREQ=WEEKLY;BYDAY=MO,WE;BYHOUR=9`. A leading `RRULE:` is
+/// accepted.
+pub fn parse_rrule(value: &str) -> Result<RecurrenceRule, String> {
+    let value = value.trim();
+    let rule = value.strip_prefix("RRULE:").unwrap_or(value);
+    let invalid = |reason: String| format!("Invalid rrule '{}': {}", value, reason);
+
+    let mut freq = None;
+    let mut parsed = RecurrenceRule {
+        freq: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        week_start: Weekday::Mon,
+        by_month: Vec::new(),
+        by_month_day: Vec::new(),
+        by_day: Vec::new(),
+        by_hour: Vec::new(),
+        by_minute: Vec::new(),
+        by_second: Vec::new(),
+        by_set_pos: Vec::new(),
+    };
+
+    for part in rule.split(';').filter(|p| !p.is_empty()) {
+        let (key, val) = part
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("expected KEY=VALUE, got '{}'", part)))?;
+        let key = key.trim().to_uppercase();
+        let val = val.trim();
+        match key.as_str() {
+            "FREQ" => {
+                freq = Some(match val.to_uppercase().as_str() {
+                    "YEARLY" => Frequency::Yearly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "WEEKLY" => Frequency::Weekly,
+                    "DAILY" => Frequency::Daily,
+                    "HOURLY" => Frequency::Hourly,
+                    "MINUTELY" => Frequency::Minutely,
+                    "SECONDLY" => return Err(invalid("FREQ=SECONDLY is not supported".into())),
+                    _ => return Err(invalid(format!("unknown frequency '{}'", val))),
+                })
+            }
+            "INTERVAL" => {
+                parsed.interval = parse_number(val, 1, u32::MAX as i64).map_err(invalid)? as u32
+            }
+            "COUNT" => {
+                parsed.count = Some(parse_number(val, 1, u32::MAX as i64).map_err(invalid)? as u32)
+            }
+            "UNTIL" => parsed.until = Some(parse_until(val).map_err(invalid)?),
+            "WKST" => parsed.week_start = parse_weekday(val).map_err(invalid)?,
+            "BYMONTH" => parsed.by_month = parse_list(val, 1, 12).map_err(invalid)?,
+            "BYMONTHDAY" => parsed.by_month_day = parse_signed_list(val, 31).map_err(invalid)?,
+            "BYDAY" => {
+                parsed.by_day = val
+                    .split(',')
+                    .map(parse_by_day)
+                    .collect::<Result<_, _>>()
+                    .map_err(invalid)?
+            }
+            "BYHOUR" => parsed.by_hour = parse_list(val, 0, 23).map_err(invalid)?,
+            "BYMINUTE" => parsed.by_minute = parse_list(val, 0, 59).map_err(invalid)?,
+            "BYSECOND" => parsed.by_second = parse_list(val, 0, 59).map_err(invalid)?,
+            "BYSETPOS" => parsed.by_set_pos = parse_signed_list(val, 366).map_err(invalid)?,
+            "BYYEARDAY" | "BYWEEKNO" => {
+                return Err(invalid(format!("{} is not supported", key)));
+            }
+            _ => return Err(invalid(format!("unknown part '{}'", key))),
+        }
+    }
+
+    parsed.freq = freq.ok_or_else(|| invalid("FREQ is required".into()))?;
+    if parsed.count.is_some() && parsed.until.is_some() {
+        return Err(invalid("COUNT and UNTIL cannot both be set".into()));
+    }
+    if !matches!(parsed.freq, Frequency::Monthly | Frequency::Yearly)
+        && parsed.by_day.iter().any(|d| d.ordinal.is_some())
+    {
+        return Err(invalid(
+            "BYDAY ordinals like 1MO only work with FREQ=MONTHLY or FREQ=YEARLY".into(),
+        ));
+    }
+    Ok(parsed)
+}
+
+fn parse_number(value: &str, min: i64, max: i64) -> Result<i64, String> {
+    value
+        .parse::<i64>()
+        .ok()
+        .filter(|n| (min..=max).contains(n))
+        .ok_or_else(|| format!("'{}' is not a number between {} and {}", value, min, max))
+}
+
+fn parse_list(value: &str, min: i64, max: i64) -> Result<Vec<u32>, String> {
+    value
+        .split(',')
+        .map(|v| parse_number(v.trim(), min, max).map(|n| n as u32))
+        .collect()
+}
+
+/// A list of non-zero numbers up to `max` that may count from the end (e.g. -1 for the last)
+fn parse_signed_list(value: &str, max: i64) -> Result<Vec<i32>, String> {
+    value
+        .split(',')
+        .map(|v| {
+            parse_number(v.trim(), -max, max)
+                .ok()
+                .filter(|n| *n != 0)
+                .map(|n| n as i32)
+                .ok_or_else(|| format!("'{}' must be between -{} and {} but not 0", v, max, max))
+        })
+        .collect()
+}
+
+fn parse_weekday(value: &str) -> Result<Weekday, String> {
+    match value.to_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(format!("unknown day '{}'", value)),
+    }
+}
+
+fn parse_by_day(value: &str) -> Result<ByDay, String> {
+    let value = value.trim();
+    if value.len() < 2 || !value.is_ascii() {
+        return Err(format!("unknown day '{}'", value));
+    }
+    let (ordinal, day) = value.split_at(value.len() - 2);
+    let ordinal = match ordinal {
+        "" => None,
+        _ => Some(parse_signed_list(ordinal, 53)?[0]),
+    };
+    Ok(ByDay {
+        ordinal,
+        weekday: parse_weekday(day)?,
+    })
+}
+
+/// Parse a date-time in iCalendar notation: `20250115T090000Z` (UTC), `20250115T090000`
+/// (floating) or `20250115` (date)
+fn parse_ical_time(value: &str) -> Result<(NaiveDateTime, bool), String> {
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(rest) => (rest, true),
+        None => (value, false),
+    };
+    let time = if value.len() == 8 {
+        NaiveDate::parse_from_str(value, "%Y%m%d").map(|d| d.and_time(NaiveTime::MIN))
+    } else {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+    };
+    time.map(|t| (t, utc))
+        .map_err(|_| format!("invalid date-time '{}'", value))
+}
+
+fn parse_until(value: &str) -> Result<Until, String> {
+    let (time, utc) = parse_ical_time(value)?;
+    if utc {
+        return Ok(Until::Utc(time.and_utc()));
+    }
+    // A date-only UNTIL includes the whole day
+    if value.len() == 8 {
+        return Ok(Until::Local(
+            time + ChronoDuration::days(1) - ChronoDuration::seconds(1),
+        ));
+    }
+    Ok(Until::Local(time))
+}
+
+/// Convert a local time to UTC. Times skipped by a DST change move forward by an hour.
+fn to_utc(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(local + ChronoDuration::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    };
+    next.and_then(|d| d.pred_opt()).map_or(28, |d| d.day())
+}
+
+/// Resolve a possibly negative day number (-1 is the last) within a range of `len` days
+fn resolve_day(day: i32, len: u32) -> Option<u32> {
+    let day = if day < 0 { len as i32 + day + 1 } else { day };
+    (1..=len as i32).contains(&day).then_some(day as u32)
+}
+
+impl RecurrenceRule {
+    /// The first occurrence strictly after `after` of the rule started at `start` and evaluated
+    /// in `tz`, or None if there are no more
+    pub fn next_after(
+        &self,
+        start: DateTime<Utc>,
+        tz: &Tz,
+        after: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let dtstart = start.with_timezone(tz).naive_local();
+        let until = self.until.and_then(|until| match until {
+            Until::Utc(time) => Some(time),
+            Until::Local(local) => to_utc(tz, local),
+        });
+
+        // COUNT needs every occurrence from the start; otherwise jump close to `after`
+        let first = match self.count {
+            Some(_) => 0,
+            None => self.periods_between(dtstart, after.with_timezone(tz).naive_local()),
+        };
+        let mut produced = 0;
+        let mut period = first;
+        for _ in 0..MAX_PERIODS {
+            for local in self.period_occurrences(dtstart, period)? {
+                if local < dtstart {
+                    continue;
+                }
+                let Some(time) = to_utc(tz, local) else {
+                    continue;
+                };
+                if until.is_some_and(|until| time > until) {
+                    return None;
+                }
+                produced += 1;
+                if self.count.is_some_and(|count| produced > count) {
+                    return None;
+                }
+                if time > after {
+                    return Some(time);
+                }
+            }
+            period = self.next_period(dtstart, period)?;
+        }
+        None
+    }
+
+    /// The next period that can produce occurrences. Daily and finer rules jump over the months,
+    /// days and hours their filters rule out instead of stepping through them one by one.
+    fn next_period(&self, dtstart: NaiveDateTime, period: i64) -> Option<i64> {
+        let next = period.checked_add(1)?;
+        let (base, unit_secs) = match self.freq {
+            Frequency::Daily => (dtstart.date().and_time(NaiveTime::MIN), 86_400),
+            Frequency::Hourly => (dtstart.date().and_hms_opt(dtstart.hour(), 0, 0)?, 3_600),
+            Frequency::Minutely => (
+                dtstart
+                    .date()
+                    .and_hms_opt(dtstart.hour(), dtstart.minute(), 0)?,
+                60,
+            ),
+            _ => return Some(next),
+        };
+        let step_secs = unit_secs * i64::from(self.interval);
+        let start =
+            base.checked_add_signed(ChronoDuration::try_seconds(next.checked_mul(step_secs)?)?)?;
+
+        let date = start.date();
+        let earliest = if !matches_any(&self.by_month, date.month()) {
+            let (year, month) = match date.month() {
+                12 => (date.year() + 1, 1),
+                month => (date.year(), month + 1),
+            };
+            NaiveDate::from_ymd_opt(year, month, 1)?.and_time(NaiveTime::MIN)
+        } else if !self.date_matches(date) {
+            date.succ_opt()?.and_time(NaiveTime::MIN)
+        } else if self.freq == Frequency::Minutely && !matches_any(&self.by_hour, start.hour()) {
+            start.date().and_hms_opt(start.hour(), 0, 0)? + ChronoDuration::hours(1)
+        } else {
+            return Some(next);
+        };
+
+        // The first period starting at or after the earliest time that can match
+        let secs = (earliest - base).num_seconds();
+        Some((secs + step_secs - 1).div_euclid(step_secs).max(next))
+    }
+
+    /// Whole periods that can be skipped from the start without passing `target`
+    fn periods_between(&self, dtstart: NaiveDateTime, target: NaiveDateTime) -> i64 {
+        if target <= dtstart {
+            return 0;
+        }
+        let diff = target - dtstart;
+        let units = match self.freq {
+            Frequency::Yearly => i64::from(target.year() - dtstart.year()),
+            Frequency::Monthly => {
+                i64::from(target.year() - dtstart.year()) * 12 + i64::from(target.month())
+                    - i64::from(dtstart.month())
+            }
+            Frequency::Weekly => diff.num_weeks(),
+            Frequency::Daily => diff.num_days(),
+            Frequency::Hourly => diff.num_hours(),
+            Frequency::Minutely => diff.num_minutes(),
+        };
+        // Keep one period of slack for DST shifts and partial periods
+        (units / i64::from(self.interval) - 1).max(0)
+    }
+
+    /// All local times the rule produces in the given period, sorted. None once the period lies
+    /// beyond the representable calendar.
+    fn period_occurrences(
+        &self,
+        dtstart: NaiveDateTime,
+        period: i64,
+    ) -> Option<Vec<NaiveDateTime>> {
+        let step = period.checked_mul(i64::from(self.interval))?;
+        let mut times = match self.freq {
+            Frequency::Yearly => {
+                let year = dtstart.year().checked_add(i32::try_from(step).ok()?)?;
+                self.with_times(dtstart, self.dates_in_year(year, dtstart.date()))
+            }
+            Frequency::Monthly => {
+                let index = i64::from(dtstart.year()) * 12 + i64::from(dtstart.month0()) + step;
+                let year = i32::try_from(index.div_euclid(12)).ok()?;
+                let month = index.rem_euclid(12) as u32 + 1;
+                let dates = if self.by_month.is_empty() || self.by_month.contains(&month) {
+                    self.dates_in_month(year, month, dtstart.day())
+                } else {
+                    Vec::new()
+                };
+                self.with_times(dtstart, dates)
+            }
+            Frequency::Weekly => {
+                let offset = (7 + dtstart.weekday().num_days_from_monday()
+                    - self.week_start.num_days_from_monday())
+                    % 7;
+                let week = dtstart
+                    .date()
+                    .checked_sub_signed(ChronoDuration::days(i64::from(offset)))?
+                    .checked_add_signed(ChronoDuration::try_weeks(step)?)?;
+                let dates = (0..7)
+                    .filter_map(|i| week.checked_add_signed(ChronoDuration::days(i)))
+                    .filter(|d| match self.by_day.is_empty() {
+                        true => d.weekday() == dtstart.weekday(),
+                        false => self.by_day.iter().any(|b| b.weekday == d.weekday()),
+                    })
+                    .filter(|d| self.by_month.is_empty() || self.by_month.contains(&d.month()))
+                    .collect();
+                self.with_times(dtstart, dates)
+            }
+            Frequency::Daily => {
+                let date = dtstart
+                    .date()
+                    .checked_add_signed(ChronoDuration::try_days(step)?)?;
+                let dates = self
+                    .date_matches(date)
+                    .then_some(date)
+                    .into_iter()
+                    .collect();
+                self.with_times(dtstart, dates)
+            }
+            Frequency::Hourly => {
+                let hour = dtstart.date().and_hms_opt(dtstart.hour(), 0, 0)?;
+                let time = hour.checked_add_signed(ChronoDuration::try_hours(step)?)?;
+                if !self.date_matches(time.date()) || !matches_any(&self.by_hour, time.hour()) {
+                    Vec::new()
+                } else {
+                    let minutes = or_default(&self.by_minute, dtstart.minute());
+                    let seconds = or_default(&self.by_second, dtstart.second());
+                    minutes
+                        .iter()
+                        .flat_map(|m| seconds.iter().map(move |s| (*m, *s)))
+                        .filter_map(|(m, s)| time.with_minute(m)?.with_second(s))
+                        .collect()
+                }
+            }
+            Frequency::Minutely => {
+                let minute = dtstart
+                    .date()
+                    .and_hms_opt(dtstart.hour(), dtstart.minute(), 0)?;
+                let time = minute.checked_add_signed(ChronoDuration::try_minutes(step)?)?;
+                if !self.date_matches(time.date())
+                    || !matches_any(&self.by_hour, time.hour())
+                    || !matches_any(&self.by_minute, time.minute())
+                {
+                    Vec::new()
+                } else {
+                    or_default(&self.by_second, dtstart.second())
+                        .iter()
+                        .filter_map(|s| time.with_second(*s))
+                        .collect()
+                }
+            }
+        };
+        times.sort();
+        times.dedup();
+        Some(self.select_positions(times))
+    }
+
+    /// Days of a year for FREQ=YEARLY
+    fn dates_in_year(&self, year: i32, dtstart: NaiveDate) -> Vec<NaiveDate> {
+        if !self.by_day.is_empty() && self.by_month.is_empty() && self.by_month_day.is_empty() {
+            // BYDAY on its own counts ordinals within the whole year
+            let Some(first) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+                return Vec::new();
+            };
+            let len = first.leap_year() as u32 + 365;
+            return first
+                .iter_days()
+                .take(len as usize)
+                .filter(|d| self.by_day_matches(*d, d.ordinal(), len))
+                .collect();
+        }
+
+        let months = if !self.by_month.is_empty() {
+            self.by_month.clone()
+        } else if !self.by_month_day.is_empty() {
+            (1..=12).collect()
+        } else {
+            vec![dtstart.month()]
+        };
+        months
+            .into_iter()
+            .flat_map(|month| self.dates_in_month(year, month, dtstart.day()))
+            .collect()
+    }
+
+    /// Days of a month matching BYMONTHDAY and BYDAY, or `default_day` if neither is set
+    fn dates_in_month(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let len = days_in_month(year, month);
+        let days: Vec<u32> = if !self.by_month_day.is_empty() {
+            self.by_month_day
+                .iter()
+                .filter_map(|d| resolve_day(*d, len))
+                .collect()
+        } else if self.by_day.is_empty() {
+            (default_day <= len)
+                .then_some(default_day)
+                .into_iter()
+                .collect()
+        } else {
+            (1..=len).collect()
+        };
+        days.into_iter()
+            .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+            .filter(|d| self.by_day.is_empty() || self.by_day_matches(*d, d.day(), len))
+            .collect()
+    }
+
+    /// Whether a date matches BYDAY, with ordinals counted within a range of `len` days in which
+    /// the date is day number `index`
+    fn by_day_matches(&self, date: NaiveDate, index: u32, len: u32) -> bool {
+        self.by_day.iter().any(|b| {
+            b.weekday == date.weekday()
+                && match b.ordinal {
+                    None => true,
+                    Some(n) if n > 0 => (index - 1) / 7 + 1 == n as u32,
+                    Some(n) => (len - index) / 7 + 1 == n.unsigned_abs(),
+                }
+        })
+    }
+
+    /// Whether a single date passes the BYMONTH, BYMONTHDAY and BYDAY filters
+    fn date_matches(&self, date: NaiveDate) -> bool {
+        let len = days_in_month(date.year(), date.month());
+        (self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            && (self.by_month_day.is_empty()
+                || self
+                    .by_month_day
+                    .iter()
+                    .any(|d| resolve_day(*d, len) == Some(date.day())))
+            && (self.by_day.is_empty() || self.by_day.iter().any(|b| b.weekday == date.weekday()))
+    }
+
+    /// Combine dates with the times of day from BYHOUR, BYMINUTE and BYSECOND
+    fn with_times(&self, dtstart: NaiveDateTime, dates: Vec<NaiveDate>) -> Vec<NaiveDateTime> {
+        let hours = or_default(&self.by_hour, dtstart.hour());
+        let minutes = or_default(&self.by_minute, dtstart.minute());
+        let seconds = or_default(&self.by_second, dtstart.second());
+        let mut times = Vec::new();
+        for date in dates {
+            for h in &hours {
+                for m in &minutes {
+                    for s in &seconds {
+                        if let Some(time) = date.and_hms_opt(*h, *m, *s) {
+                            times.push(time);
+                        }
+                    }
+                }
+            }
+        }
+        times
+    }
+
+    /// Apply BYSETPOS to the sorted occurrences of one period
+    fn select_positions(&self, times: Vec<NaiveDateTime>) -> Vec<NaiveDateTime> {
+        if self.by_set_pos.is_empty() {
+            return times;
+        }
+        let len = times.len() as u32;
+        let mut selected: Vec<NaiveDateTime> = self
+            .by_set_pos
+            .iter()
+            .filter_map(|p| resolve_day(*p, len))
+            .map(|p| times[p as usize - 1])
+            .collect();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+}
+
+fn or_default(values: &[u32], default: u32) -> Vec<u32> {
+    if values.is_empty() {
+        vec![default]
+    } else {
+        values.to_vec()
+    }
+}
+
+fn matches_any(values: &[u32], value: u32) -> bool {
+    values.is_empty() || values.contains(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::SecondsFormat;
+
+    use super::*;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().to_utc()
+    }
+
+    /// Up to `n` occurrences of the rule from `start` on, in UTC
+    fn occurrences(rule: &str, start: &str, tz: Tz, n: usize) -> Vec<String> {
+        let rule = parse_rrule(rule).unwrap();
+        let start = utc(start);
+        let mut after = start - ChronoDuration::seconds(1);
+        let mut times = Vec::new();
+        while times.len() < n {
+            let Some(next) = rule.next_after(start, &tz, after) else {
+                break;
+            };
+            times.push(next.to_rfc3339_opts(SecondsFormat::Secs, true));
+            after = next;
+        }
+        times
+    }
+
+    #[test]
+    fn by_day_ordinals() {
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY;BYDAY=-1FR;BYHOUR=17;BYMINUTE=0",
+                "2026-01-01T00:00:00Z",
+                Tz::UTC,
+                4
+            ),
+            [
+                "2026-01-30T17:00:00Z",
+                "2026-02-27T17:00:00Z",
+                "2026-03-27T17:00:00Z",
+                "2026-04-24T17:00:00Z"
+            ]
+        );
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY;BYDAY=2TU,-1SU",
+                "2026-01-01T09:00:00Z",
+                Tz::UTC,
+                4
+            ),
+            [
+                "2026-01-13T09:00:00Z",
+                "2026-01-25T09:00:00Z",
+                "2026-02-10T09:00:00Z",
+                "2026-02-22T09:00:00Z"
+            ]
+        );
+        // Without BYMONTH the ordinal counts within the year
+        assert_eq!(
+            occurrences("FREQ=YEARLY;BYDAY=20MO", "2026-01-01T09:00:00Z", Tz::UTC, 3),
+            [
+                "2026-05-18T09:00:00Z",
+                "2027-05-17T09:00:00Z",
+                "2028-05-15T09:00:00Z"
+            ]
+        );
+    }
+
+    #[test]
+    fn by_set_pos() {
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "2026-01-01T18:00:00Z",
+                Tz::UTC,
+                4
+            ),
+            [
+                "2026-01-30T18:00:00Z",
+                "2026-02-27T18:00:00Z",
+                "2026-03-31T18:00:00Z",
+                "2026-04-30T18:00:00Z"
+            ]
+        );
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=1,-1",
+                "2026-01-01T18:00:00Z",
+                Tz::UTC,
+                4
+            ),
+            [
+                "2026-01-01T18:00:00Z",
+                "2026-01-30T18:00:00Z",
+                "2026-02-02T18:00:00Z",
+                "2026-02-27T18:00:00Z"
+            ]
+        );
+    }
+
+    #[test]
+    fn count_is_counted_from_the_start() {
+        assert_eq!(
+            occurrences("FREQ=DAILY;COUNT=3", "2026-01-01T09:00:00Z", Tz::UTC, 5),
+            [
+                "2026-01-01T09:00:00Z",
+                "2026-01-02T09:00:00Z",
+                "2026-01-03T09:00:00Z"
+            ]
+        );
+        let rule = parse_rrule("FREQ=DAILY;COUNT=3").unwrap();
+        let start = utc("2026-01-01T09:00:00Z");
+        assert_eq!(
+            rule.next_after(start, &Tz::UTC, utc("2026-01-02T10:00:00Z")),
+            Some(utc("2026-01-03T09:00:00Z"))
+        );
+        assert_eq!(
+            rule.next_after(start, &Tz::UTC, utc("2026-01-03T09:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn until_is_inclusive() {
+        let expected = [
+            "2026-01-05T09:00:00Z",
+            "2026-01-12T09:00:00Z",
+            "2026-01-19T09:00:00Z",
+            "2026-01-26T09:00:00Z",
+        ];
+        for rule in [
+            "FREQ=WEEKLY;BYDAY=MO;UNTIL=20260126T090000Z",
+            "FREQ=WEEKLY;BYDAY=MO;UNTIL=20260126",
+        ] {
+            assert_eq!(
+                occurrences(rule, "2026-01-01T09:00:00Z", Tz::UTC, 6),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn dst_transitions() {
+        let tz = chrono_tz::America::New_York;
+        // 02:30 does not exist on 8 March and moves to 03:30 EDT
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;BYHOUR=2;BYMINUTE=30",
+                "2026-03-06T07:30:00Z",
+                tz,
+                4
+            ),
+            [
+                "2026-03-06T07:30:00Z",
+                "2026-03-07T07:30:00Z",
+                "2026-03-08T07:30:00Z",
+                "2026-03-09T06:30:00Z"
+            ]
+        );
+        // The local time of day is kept across the change back to EST
+        assert_eq!(
+            occurrences("FREQ=DAILY", "2026-10-31T13:00:00Z", tz, 3),
+            [
+                "2026-10-31T13:00:00Z",
+                "2026-11-01T14:00:00Z",
+                "2026-11-02T14:00:00Z"
+            ]
+        );
+        // 01:30 happens twice on 1 November but fires once, at the first
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;BYHOUR=1;BYMINUTE=30",
+                "2026-10-31T05:30:00Z",
+                tz,
+                3
+            ),
+            [
+                "2026-10-31T05:30:00Z",
+                "2026-11-01T05:30:00Z",
+                "2026-11-02T06:30:00Z"
+            ]
+        );
+    }
+
+    #[test]
+    fn sparse_rules() {
+        assert_eq!(
+            occurrences(
+                "FREQ=MINUTELY;BYMONTH=12",
+                "2026-01-15T10:30:00Z",
+                Tz::UTC,
+                2
+            ),
+            ["2026-12-01T00:00:00Z", "2026-12-01T00:01:00Z"]
+        );
+        assert_eq!(
+            occurrences(
+                "FREQ=MINUTELY;INTERVAL=7;BYMONTH=12;BYHOUR=6",
+                "2026-01-15T10:30:00Z",
+                Tz::UTC,
+                3
+            ),
+            [
+                "2026-12-01T06:00:00Z",
+                "2026-12-01T06:07:00Z",
+                "2026-12-01T06:14:00Z"
+            ]
+        );
+        assert_eq!(
+            occurrences(
+                "FREQ=HOURLY;BYMONTH=2;BYMONTHDAY=29",
+                "2026-03-01T00:15:00Z",
+                Tz::UTC,
+                2
+            ),
+            ["2028-02-29T00:15:00Z", "2028-02-29T01:15:00Z"]
+        );
+        // A rule that can never match ends instead of looping
+        assert!(
+            occurrences(
+                "FREQ=MINUTELY;BYMONTH=2;BYMONTHDAY=30",
+                "2026-01-01T00:00:00Z",
+                Tz::UTC,
+                1
+            )
+            .is_empty()
+        );
+    }
+}
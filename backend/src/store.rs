@@ -1,20 +1,43 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use chrono::Utc;
-use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use tokio::sync::{Mutex, RwLock, oneshot};
 use uuid::Uuid;
 
-use crate::db::{Database, Event, EventHandler, Job, JobStatus, ScheduleRecord, TimerRecord};
+use crate::db::{
+    Database, Event, EventHandler, Job, JobStatus, ScheduleRecord, ScheduleState, TailRecord,
+    TimerRecord, WatcherRecord, WebhookRecord, WindowRecord, WorkflowRecord, WorkflowRun,
+};
+use crate::executor::ExecutionResult;
+use crate::filter::FilterCache;
+use crate::gate::EventGate;
+use crate::inflight::InFlight;
+use crate::limiter::{ConcurrencyLimiter, JobPermit};
+use crate::output::{LiveOutput, OutputMessage, OutputStream};
+use crate::window;
+use shev_core::WindowAction;
 pub use shev_core::api::{Warning, WarningKind};
+use tokio::sync::broadcast;
 
 #[derive(Clone)]
 pub struct JobStore {
     db: Database,
-    handlers: Arc<RwLock<HashMap<String, EventHandler>>>,
+    handlers: Arc<RwLock<HashMap<String, Vec<EventHandler>>>>,
     timers: Arc<RwLock<HashMap<String, TimerRecord>>>,
     schedules: Arc<RwLock<HashMap<String, ScheduleRecord>>>,
+    windows: Arc<RwLock<HashMap<String, WindowRecord>>>,
+    watchers: Arc<RwLock<HashMap<String, WatcherRecord>>>,
+    webhooks: Arc<RwLock<HashMap<String, WebhookRecord>>>,
+    tails: Arc<RwLock<HashMap<String, TailRecord>>>,
+    workflows: Arc<RwLock<HashMap<String, WorkflowRecord>>>,
     warnings: Arc<RwLock<Vec<Warning>>>,
+    processes: Arc<Mutex<HashMap<Uuid, oneshot::Sender<()>>>>,
+    live_output: Arc<Mutex<HashMap<Uuid, LiveOutput>>>,
+    limiter: ConcurrencyLimiter,
+    gate: EventGate,
+    filters: FilterCache,
+    in_flight: InFlight,
 }
 
 impl JobStore {
@@ -24,7 +47,18 @@ impl JobStore {
             handlers: Arc::new(RwLock::new(HashMap::new())),
             timers: Arc::new(RwLock::new(HashMap::new())),
             schedules: Arc::new(RwLock::new(HashMap::new())),
+            windows: Arc::new(RwLock::new(HashMap::new())),
+            watchers: Arc::new(RwLock::new(HashMap::new())),
+            webhooks: Arc::new(RwLock::new(HashMap::new())),
+            tails: Arc::new(RwLock::new(HashMap::new())),
+            workflows: Arc::new(RwLock::new(HashMap::new())),
             warnings: Arc::new(RwLock::new(Vec::new())),
+            processes: Arc::new(Mutex::new(HashMap::new())),
+            live_output: Arc::new(Mutex::new(HashMap::new())),
+            limiter: ConcurrencyLimiter::new(),
+            gate: EventGate::new(),
+            filters: FilterCache::new(),
+            in_flight: InFlight::new(),
         }
     }
 
@@ -52,9 +86,12 @@ impl JobStore {
             .collect()
     }
 
+    /// Whether any handler of the event type is enabled
     pub async fn has_handler(&self, event_type: &str) -> bool {
         let handlers = self.handlers.read().await;
-        handlers.contains_key(event_type)
+        handlers
+            .get(event_type)
+            .is_some_and(|list| list.iter().any(|h| h.enabled))
     }
 
     pub async fn load_handlers(&self) {
@@ -63,7 +100,12 @@ impl JobStore {
 
         handlers.clear();
         for handler in db_handlers {
-            handlers.insert(handler.event_type.clone(), handler);
+            self.set_handler_limit(&handler);
+            self.filters.set(&handler);
+            handlers
+                .entry(handler.event_type.clone())
+                .or_default()
+                .push(handler);
         }
     }
 
@@ -79,14 +121,25 @@ impl JobStore {
         db_timers
     }
 
-    pub async fn get_handler(&self, event_type: &str) -> Option<EventHandler> {
+    pub async fn get_handler(&self, event_type: &str, name: &str) -> Option<EventHandler> {
         let handlers = self.handlers.read().await;
-        handlers.get(event_type).cloned()
+        handlers
+            .get(event_type)
+            .and_then(|list| list.iter().find(|h| h.name == name))
+            .cloned()
+    }
+
+    /// All handlers attached to an event type, in name order
+    pub async fn get_handlers_for(&self, event_type: &str) -> Vec<EventHandler> {
+        let handlers = self.handlers.read().await;
+        handlers.get(event_type).cloned().unwrap_or_default()
     }
 
     pub async fn get_handlers(&self) -> Vec<EventHandler> {
         let handlers = self.handlers.read().await;
-        handlers.values().cloned().collect()
+        let mut all: Vec<EventHandler> = handlers.values().flatten().cloned().collect();
+        all.sort_by(|a, b| (&a.event_type, &a.name).cmp(&(&b.event_type, &b.name)));
+        all
     }
 
     pub async fn register_timer(&self, timer: TimerRecord) {
@@ -104,27 +157,135 @@ impl JobStore {
         timers.values().cloned().collect()
     }
 
-    pub async fn create_job(&self, event: Event, handler: &EventHandler) -> Job {
-        let job = Job::new(event, handler.id);
+    /// Create a pending job; `suppressed` counts the events debounced or throttled in its favour
+    pub async fn create_job(&self, event: Event, handler: &EventHandler, suppressed: u32) -> Job {
+        let job = Job {
+            suppressed,
+            ..Job::new(event, handler)
+        };
+        self.insert_job(job).await
+    }
+
+    /// Record that a handler did not run for an event
+    pub async fn record_skipped_job(
+        &self,
+        event: Event,
+        handler: &EventHandler,
+        reason: String,
+    ) -> Job {
+        let job = Job::skipped(event, handler, reason);
         let _ = self.db.insert_job(&job).await;
         job
     }
 
-    pub async fn mark_running(&self, job_id: Uuid) {
+    /// Create the next attempt of a failed job
+    pub async fn create_retry_job(&self, previous: &Job) -> Job {
+        self.insert_job(previous.retry()).await
+    }
+
+    async fn insert_job(&self, job: Job) -> Job {
+        let _ = self.db.insert_job(&job).await;
+        let mut live_output = self.live_output.lock().await;
+        live_output.insert(job.id, LiveOutput::new());
+        job
+    }
+
+    /// Register a running job so it can be terminated by `cancel_job`
+    pub async fn register_process(&self, job_id: Uuid) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        let mut processes = self.processes.lock().await;
+        processes.insert(job_id, tx);
+        rx
+    }
+
+    pub async fn unregister_process(&self, job_id: Uuid) {
+        let mut processes = self.processes.lock().await;
+        processes.remove(&job_id);
+    }
+
+    /// Returns false if the job is no longer pending (e.g. cancelled before it started)
+    pub async fn mark_running(&self, job_id: Uuid) -> bool {
         if let Some(mut job) = self.db.get_job(job_id).await {
             job.status = JobStatus::Running;
             job.started_at = Some(Utc::now());
-            let _ = self.db.update_job(&job).await;
+            if self.db.update_active_job(&job).await.unwrap_or(false) {
+                return true;
+            }
+        }
+        self.close_output(job_id).await;
+        false
+    }
+
+    pub async fn append_output(&self, job_id: Uuid, stream: OutputStream, line: String) {
+        let mut live_output = self.live_output.lock().await;
+        if let Some(output) = live_output.get_mut(&job_id) {
+            output.push(stream, line);
+        }
+    }
+
+    /// Write the output collected so far to the database, so it survives a crash
+    pub async fn persist_output(&self, job_id: Uuid) {
+        let changes = {
+            let mut live_output = self.live_output.lock().await;
+            live_output.get_mut(&job_id).and_then(|o| o.take_changes())
+        };
+        if let Some((stdout, stderr)) = changes {
+            let _ = self.db.append_job_output(job_id, &stdout, &stderr).await;
+        }
+    }
+
+    /// Recent output lines and a receiver for the rest, or None if the job is not active
+    pub async fn subscribe_output(
+        &self,
+        job_id: Uuid,
+    ) -> Option<(
+        Vec<(OutputStream, String)>,
+        broadcast::Receiver<OutputMessage>,
+    )> {
+        let live_output = self.live_output.lock().await;
+        live_output.get(&job_id).map(|o| o.subscribe())
+    }
+
+    /// Notify stream subscribers of the job's final status and drop its live output
+    async fn close_output(&self, job_id: Uuid) {
+        let output = {
+            let mut live_output = self.live_output.lock().await;
+            live_output.remove(&job_id)
+        };
+        if let Some(output) = output {
+            let status = match self.db.get_job(job_id).await {
+                Some(job) => job.status,
+                None => JobStatus::Cancelled,
+            };
+            output.finish(status);
         }
     }
 
-    pub async fn mark_completed(&self, job_id: Uuid, output: String) {
+    /// Record the outcome of an executed process and return the job's final status. If the job
+    /// was cancelled meanwhile its status is kept, but the process output is still stored.
+    pub async fn finish_job(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        result: &ExecutionResult,
+        error: Option<String>,
+    ) -> JobStatus {
+        let mut final_status = status.clone();
         if let Some(mut job) = self.db.get_job(job_id).await {
-            job.status = JobStatus::Completed;
-            job.output = Some(output);
+            job.status = status;
+            job.stdout = Some(result.stdout.clone());
+            job.stderr = Some(result.stderr.clone());
+            job.exit_code = result.exit_code;
+            job.duration_ms = Some(result.duration.as_millis() as i64);
+            job.error = error;
             job.finished_at = Some(Utc::now());
-            let _ = self.db.update_job(&job).await;
+            if !self.db.update_active_job(&job).await.unwrap_or(false) {
+                let _ = self.db.update_job_output(&job).await;
+                final_status = JobStatus::Cancelled;
+            }
         }
+        self.close_output(job_id).await;
+        final_status
     }
 
     pub async fn mark_failed(&self, job_id: Uuid, error: String) {
@@ -132,20 +293,33 @@ impl JobStore {
             job.status = JobStatus::Failed;
             job.error = Some(error);
             job.finished_at = Some(Utc::now());
-            let _ = self.db.update_job(&job).await;
+            let _ = self.db.update_active_job(&job).await;
         }
+        self.close_output(job_id).await;
     }
 
-    pub async fn cancel_job(&self, job_id: Uuid) -> bool {
-        if let Some(mut job) = self.db.get_job(job_id).await {
-            if job.status == JobStatus::Pending || job.status == JobStatus::Running {
-                job.status = JobStatus::Cancelled;
-                job.finished_at = Some(Utc::now());
-                let _ = self.db.update_job(&job).await;
-                return true;
+    /// Cancel every pending/running job of an event type, returning how many were cancelled
+    pub async fn cancel_active_jobs(&self, event_type: &str) -> usize {
+        let mut cancelled = 0;
+        for job_id in self.db.get_active_job_ids(event_type).await {
+            if self.cancel_job(job_id).await {
+                cancelled += 1;
             }
         }
-        false
+        cancelled
+    }
+
+    /// Mark a pending/running job as cancelled and signal its process (if any) to terminate
+    pub async fn cancel_job(&self, job_id: Uuid) -> bool {
+        if !self.db.cancel_job(job_id).await.unwrap_or(false) {
+            return false;
+        }
+
+        let mut processes = self.processes.lock().await;
+        if let Some(tx) = processes.remove(&job_id) {
+            let _ = tx.send(());
+        }
+        true
     }
 
     pub async fn get_job(&self, job_id: Uuid) -> Option<Job> {
@@ -164,6 +338,27 @@ impl JobStore {
         self.db.get_timer_id(event_type).await
     }
 
+    /// Record when a timer last fired and will fire next and how often it has fired
+    pub async fn set_timer_fire_times(
+        &self,
+        event_type: &str,
+        id: Uuid,
+        last_fired_at: Option<DateTime<Utc>>,
+        next_fire_at: Option<DateTime<Utc>>,
+        fire_count: u32,
+    ) -> Result<(), String> {
+        self.db
+            .set_timer_fire_times(&id, last_fired_at, next_fire_at, fire_count)
+            .await?;
+        let mut timers = self.timers.write().await;
+        if let Some(timer) = timers.get_mut(event_type).filter(|t| t.id == id) {
+            timer.last_fired_at = last_fired_at;
+            timer.next_fire_at = next_fire_at;
+            timer.fire_count = fire_count;
+        }
+        Ok(())
+    }
+
     pub async fn load_schedules(&self) -> Vec<ScheduleRecord> {
         let db_schedules = self.db.get_all_schedules().await;
         let mut schedules = self.schedules.write().await;
@@ -195,45 +390,147 @@ impl JobStore {
         self.db.get_schedule_id(event_type).await
     }
 
-    pub async fn create_handler(
+    /// Record when a schedule last fired and will fire next
+    pub async fn set_schedule_fire_times(
         &self,
         event_type: &str,
-        shell: &shev_core::ShellType,
-        command: &str,
-        timeout: Option<u32>,
-        env: &std::collections::HashMap<String, String>,
-    ) -> Result<EventHandler, String> {
-        let handler = self
-            .db
-            .insert_handler(event_type, shell, command, timeout, env)
+        id: Uuid,
+        last_fired_at: Option<DateTime<Utc>>,
+        next_fire_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String> {
+        self.db
+            .set_schedule_fire_times(&id, last_fired_at, next_fire_at)
             .await?;
+        let mut schedules = self.schedules.write().await;
+        if let Some(schedule) = schedules.get_mut(event_type).filter(|s| s.id == id) {
+            schedule.last_fired_at = last_fired_at;
+            schedule.next_fire_at = next_fire_at;
+        }
+        Ok(())
+    }
+
+    pub async fn set_schedule_state(
+        &self,
+        event_type: &str,
+        id: Uuid,
+        state: ScheduleState,
+    ) -> Result<(), String> {
+        self.db.set_schedule_state(&id, state).await?;
+        let mut schedules = self.schedules.write().await;
+        if let Some(schedule) = schedules.get_mut(event_type).filter(|s| s.id == id) {
+            schedule.state = state;
+        }
+        Ok(())
+    }
+
+    pub async fn create_handler(&self, handler: EventHandler) -> Result<EventHandler, String> {
+        self.db.insert_handler(&handler).await?;
+        self.set_handler_limit(&handler);
+        self.filters.set(&handler);
+        let mut handlers = self.handlers.write().await;
+        Self::put_handler(&mut handlers, handler.clone());
+        Ok(handler)
+    }
+
+    /// Replace the stored handler with the same event type and name
+    pub async fn update_handler(&self, handler: EventHandler) -> Result<EventHandler, String> {
+        if !self.db.update_handler(&handler).await? {
+            return Err(format!(
+                "Handler '{}/{}' not found",
+                handler.event_type, handler.name
+            ));
+        }
+        self.set_handler_limit(&handler);
+        self.filters.set(&handler);
         let mut handlers = self.handlers.write().await;
-        handlers.insert(event_type.to_string(), handler.clone());
+        Self::put_handler(&mut handlers, handler.clone());
         Ok(handler)
     }
 
-    pub async fn update_handler(
+    fn set_handler_limit(&self, handler: &EventHandler) {
+        self.limiter.set_handler_limit(
+            &handler.event_type,
+            &handler.name,
+            handler.max_concurrency.unwrap_or(0) as usize,
+        );
+    }
+
+    pub fn set_max_concurrent_jobs(&self, max: usize) {
+        self.limiter.set_global_limit(max);
+    }
+
+    /// Wait until the handler and the global concurrency limits let another job run
+    pub async fn acquire_slot(&self, handler: &EventHandler) -> JobPermit {
+        self.limiter
+            .acquire(&handler.event_type, &handler.name)
+            .await
+    }
+
+    /// Debounce and throttle state of every handler
+    pub fn gate(&self) -> &EventGate {
+        &self.gate
+    }
+
+    /// Parsed filters of every handler
+    pub fn filters(&self) -> &FilterCache {
+        &self.filters
+    }
+
+    /// Events of timers, schedules, watchers and tails that are not handled yet
+    pub fn in_flight(&self) -> &InFlight {
+        &self.in_flight
+    }
+
+    /// Insert or replace a handler in the in-memory map, keeping each list sorted by name
+    fn put_handler(handlers: &mut HashMap<String, Vec<EventHandler>>, handler: EventHandler) {
+        let list = handlers.entry(handler.event_type.clone()).or_default();
+        list.retain(|h| h.name != handler.name);
+        list.push(handler);
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Pause or resume a handler. Returns None if it does not exist.
+    pub async fn set_handler_enabled(
         &self,
         event_type: &str,
-        shell: Option<&shev_core::ShellType>,
-        command: Option<&str>,
-        timeout: Option<Option<u32>>,
-        env: Option<&std::collections::HashMap<String, String>>,
-    ) -> Result<EventHandler, String> {
-        let handler = self
+        name: &str,
+        enabled: bool,
+    ) -> Result<Option<EventHandler>, String> {
+        if !self
             .db
-            .update_handler(event_type, shell, command, timeout, env)
-            .await?;
+            .set_handler_enabled(event_type, name, enabled)
+            .await?
+        {
+            return Ok(None);
+        }
         let mut handlers = self.handlers.write().await;
-        handlers.insert(event_type.to_string(), handler.clone());
+        let handler = handlers
+            .get_mut(event_type)
+            .and_then(|list| list.iter_mut().find(|h| h.name == name))
+            .map(|h| {
+                h.enabled = enabled;
+                h.clone()
+            });
         Ok(handler)
     }
 
-    pub async fn delete_handler(&self, event_type: &str) -> Result<bool, String> {
-        let deleted = self.db.delete_handler(event_type).await?;
+    pub async fn delete_handler(&self, event_type: &str, name: &str) -> Result<bool, String> {
+        let deleted = self.db.delete_handler(event_type, name).await?;
         if deleted {
+            self.filters.remove(event_type, name);
             let mut handlers = self.handlers.write().await;
+            let remaining = match handlers.get_mut(event_type) {
+                Some(list) => {
+                    list.retain(|h| h.name != name);
+                    list.len()
+                }
+                None => 0,
+            };
+            if remaining > 0 {
+                return Ok(true);
+            }
             handlers.remove(event_type);
+            drop(handlers);
 
             // Check for orphaned timers/schedules and add warnings
             let timers = self.timers.read().await;
@@ -255,26 +552,38 @@ impl JobStore {
         Ok(deleted)
     }
 
-    pub async fn create_timer(
-        &self,
-        event_type: &str,
-        interval_secs: u32,
-        context: &str,
-    ) -> Result<TimerRecord, String> {
-        self.db
-            .insert_timer(event_type, interval_secs, context)
-            .await
+    pub async fn create_timer(&self, timer: TimerRecord) -> Result<TimerRecord, String> {
+        self.db.insert_timer(&timer).await?;
+        Ok(timer)
     }
 
-    pub async fn update_timer_record(
+    /// Replace the stored timer with the same event type
+    pub async fn update_timer_record(&self, timer: TimerRecord) -> Result<TimerRecord, String> {
+        if !self.db.update_timer(&timer).await? {
+            return Err(format!("Timer '{}' not found", timer.event_type));
+        }
+        Ok(timer)
+    }
+
+    /// Pause or resume a timer without restarting it. Returns None if it does not exist.
+    pub async fn set_timer_enabled(
         &self,
         event_type: &str,
-        interval_secs: Option<u32>,
-        context: Option<&str>,
-    ) -> Result<TimerRecord, String> {
-        self.db
-            .update_timer(event_type, interval_secs, context)
-            .await
+        enabled: bool,
+    ) -> Result<Option<TimerRecord>, String> {
+        if !self.db.set_timer_enabled(event_type, enabled).await? {
+            return Ok(None);
+        }
+        let mut timers = self.timers.write().await;
+        Ok(timers.get_mut(event_type).map(|t| {
+            t.enabled = enabled;
+            t.clone()
+        }))
+    }
+
+    pub async fn is_timer_enabled(&self, event_type: &str) -> bool {
+        let timers = self.timers.read().await;
+        timers.get(event_type).is_some_and(|t| t.enabled)
     }
 
     pub async fn delete_timer(&self, event_type: &str) -> Result<bool, String> {
@@ -288,26 +597,42 @@ impl JobStore {
 
     pub async fn create_schedule(
         &self,
-        event_type: &str,
-        scheduled_time: chrono::DateTime<chrono::Utc>,
-        context: &str,
-        periodic: bool,
+        schedule: ScheduleRecord,
     ) -> Result<ScheduleRecord, String> {
-        self.db
-            .insert_schedule(event_type, scheduled_time, context, periodic)
-            .await
+        self.db.insert_schedule(&schedule).await?;
+        Ok(schedule)
     }
 
+    /// Replace the stored schedule with the same event type
     pub async fn update_schedule_record(
         &self,
-        event_type: &str,
-        scheduled_time: Option<chrono::DateTime<chrono::Utc>>,
-        context: Option<&str>,
-        periodic: Option<bool>,
+        schedule: ScheduleRecord,
     ) -> Result<ScheduleRecord, String> {
-        self.db
-            .update_schedule(event_type, scheduled_time, context, periodic)
-            .await
+        if !self.db.update_schedule(&schedule).await? {
+            return Err(format!("Schedule '{}' not found", schedule.event_type));
+        }
+        Ok(schedule)
+    }
+
+    /// Pause or resume a schedule without restarting it. Returns None if it does not exist.
+    pub async fn set_schedule_enabled(
+        &self,
+        event_type: &str,
+        enabled: bool,
+    ) -> Result<Option<ScheduleRecord>, String> {
+        if !self.db.set_schedule_enabled(event_type, enabled).await? {
+            return Ok(None);
+        }
+        let mut schedules = self.schedules.write().await;
+        Ok(schedules.get_mut(event_type).map(|s| {
+            s.enabled = enabled;
+            s.clone()
+        }))
+    }
+
+    pub async fn is_schedule_enabled(&self, event_type: &str) -> bool {
+        let schedules = self.schedules.read().await;
+        schedules.get(event_type).is_some_and(|s| s.enabled)
     }
 
     pub async fn delete_schedule(&self, event_type: &str) -> Result<bool, String> {
@@ -319,6 +644,325 @@ impl JobStore {
         Ok(deleted)
     }
 
+    pub async fn load_windows(&self) -> usize {
+        let db_windows = self.db.get_all_windows().await;
+        let mut windows = self.windows.write().await;
+
+        windows.clear();
+        for window in db_windows {
+            windows.insert(window.name.clone(), window);
+        }
+        windows.len()
+    }
+
+    pub async fn get_windows(&self) -> Vec<WindowRecord> {
+        let windows = self.windows.read().await;
+        let mut all: Vec<WindowRecord> = windows.values().cloned().collect();
+        all.sort_by(|a, b| a.name.cmp(&b.name));
+        all
+    }
+
+    pub async fn get_window(&self, name: &str) -> Option<WindowRecord> {
+        let windows = self.windows.read().await;
+        windows.get(name).cloned()
+    }
+
+    pub async fn create_window(&self, window: WindowRecord) -> Result<WindowRecord, String> {
+        self.db.insert_window(&window).await?;
+        let mut windows = self.windows.write().await;
+        windows.insert(window.name.clone(), window.clone());
+        Ok(window)
+    }
+
+    /// Replace the stored window with the same name
+    pub async fn update_window(&self, window: WindowRecord) -> Result<WindowRecord, String> {
+        if !self.db.update_window(&window).await? {
+            return Err(format!("Window '{}' not found", window.name));
+        }
+        let mut windows = self.windows.write().await;
+        windows.insert(window.name.clone(), window.clone());
+        Ok(window)
+    }
+
+    pub async fn delete_window(&self, name: &str) -> Result<bool, String> {
+        let deleted = self.db.delete_window(name).await?;
+        if deleted {
+            let mut windows = self.windows.write().await;
+            windows.remove(name);
+        }
+        Ok(deleted)
+    }
+
+    pub async fn load_watchers(&self) -> Vec<WatcherRecord> {
+        let db_watchers = self.db.get_all_watchers().await;
+        let mut watchers = self.watchers.write().await;
+
+        watchers.clear();
+        for watcher in &db_watchers {
+            watchers.insert(watcher.event_type.clone(), watcher.clone());
+        }
+
+        db_watchers
+    }
+
+    pub async fn register_watcher(&self, watcher: WatcherRecord) {
+        let mut watchers = self.watchers.write().await;
+        watchers.insert(watcher.event_type.clone(), watcher);
+    }
+
+    pub async fn get_watcher(&self, event_type: &str) -> Option<WatcherRecord> {
+        let watchers = self.watchers.read().await;
+        watchers.get(event_type).cloned()
+    }
+
+    pub async fn get_watchers(&self) -> Vec<WatcherRecord> {
+        let watchers = self.watchers.read().await;
+        let mut all: Vec<WatcherRecord> = watchers.values().cloned().collect();
+        all.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+        all
+    }
+
+    pub async fn get_watcher_id(&self, event_type: &str) -> Option<Uuid> {
+        self.db.get_watcher_id(event_type).await
+    }
+
+    pub async fn create_watcher(&self, watcher: WatcherRecord) -> Result<WatcherRecord, String> {
+        self.db.insert_watcher(&watcher).await?;
+        Ok(watcher)
+    }
+
+    /// Replace the stored watcher with the same event type
+    pub async fn update_watcher_record(
+        &self,
+        watcher: WatcherRecord,
+    ) -> Result<WatcherRecord, String> {
+        if !self.db.update_watcher(&watcher).await? {
+            return Err(format!("Watcher '{}' not found", watcher.event_type));
+        }
+        Ok(watcher)
+    }
+
+    pub async fn delete_watcher(&self, event_type: &str) -> Result<bool, String> {
+        let deleted = self.db.delete_watcher(event_type).await?;
+        if deleted {
+            let mut watchers = self.watchers.write().await;
+            watchers.remove(event_type);
+        }
+        Ok(deleted)
+    }
+
+    pub async fn load_tails(&self) -> Vec<TailRecord> {
+        let db_tails = self.db.get_all_tails().await;
+        let mut tails = self.tails.write().await;
+
+        tails.clear();
+        for tail in &db_tails {
+            tails.insert(tail.event_type.clone(), tail.clone());
+        }
+
+        db_tails
+    }
+
+    pub async fn register_tail(&self, tail: TailRecord) {
+        let mut tails = self.tails.write().await;
+        tails.insert(tail.event_type.clone(), tail);
+    }
+
+    pub async fn get_tail(&self, event_type: &str) -> Option<TailRecord> {
+        let tails = self.tails.read().await;
+        tails.get(event_type).cloned()
+    }
+
+    pub async fn get_tails(&self) -> Vec<TailRecord> {
+        let tails = self.tails.read().await;
+        let mut all: Vec<TailRecord> = tails.values().cloned().collect();
+        all.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+        all
+    }
+
+    pub async fn get_tail_id(&self, event_type: &str) -> Option<Uuid> {
+        self.db.get_tail_id(event_type).await
+    }
+
+    pub async fn create_tail(&self, tail: TailRecord) -> Result<TailRecord, String> {
+        self.db.insert_tail(&tail).await?;
+        Ok(tail)
+    }
+
+    /// Replace the stored tail with the same event type
+    pub async fn update_tail_record(&self, tail: TailRecord) -> Result<TailRecord, String> {
+        if !self.db.update_tail(&tail).await? {
+            return Err(format!("Tail '{}' not found", tail.event_type));
+        }
+        Ok(tail)
+    }
+
+    pub async fn delete_tail(&self, event_type: &str) -> Result<bool, String> {
+        let deleted = self.db.delete_tail(event_type).await?;
+        if deleted {
+            let mut tails = self.tails.write().await;
+            tails.remove(event_type);
+        }
+        Ok(deleted)
+    }
+
+    pub async fn load_webhooks(&self) -> usize {
+        let db_webhooks = self.db.get_all_webhooks().await;
+        let mut webhooks = self.webhooks.write().await;
+
+        webhooks.clear();
+        for webhook in db_webhooks {
+            webhooks.insert(webhook.event_type.clone(), webhook);
+        }
+        webhooks.len()
+    }
+
+    pub async fn get_webhooks(&self) -> Vec<WebhookRecord> {
+        let webhooks = self.webhooks.read().await;
+        let mut all: Vec<WebhookRecord> = webhooks.values().cloned().collect();
+        all.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+        all
+    }
+
+    pub async fn get_webhook(&self, event_type: &str) -> Option<WebhookRecord> {
+        let webhooks = self.webhooks.read().await;
+        webhooks.get(event_type).cloned()
+    }
+
+    pub async fn create_webhook(&self, webhook: WebhookRecord) -> Result<WebhookRecord, String> {
+        self.db.insert_webhook(&webhook).await?;
+        let mut webhooks = self.webhooks.write().await;
+        webhooks.insert(webhook.event_type.clone(), webhook.clone());
+        Ok(webhook)
+    }
+
+    /// Replace the stored webhook with the same event type
+    pub async fn update_webhook(&self, webhook: WebhookRecord) -> Result<WebhookRecord, String> {
+        if !self.db.update_webhook(&webhook).await? {
+            return Err(format!("Webhook '{}' not found", webhook.event_type));
+        }
+        let mut webhooks = self.webhooks.write().await;
+        webhooks.insert(webhook.event_type.clone(), webhook.clone());
+        Ok(webhook)
+    }
+
+    pub async fn delete_webhook(&self, event_type: &str) -> Result<bool, String> {
+        let deleted = self.db.delete_webhook(event_type).await?;
+        if deleted {
+            let mut webhooks = self.webhooks.write().await;
+            webhooks.remove(event_type);
+        }
+        Ok(deleted)
+    }
+
+    pub async fn load_workflows(&self) -> usize {
+        let db_workflows = self.db.get_all_workflows().await;
+        let mut workflows = self.workflows.write().await;
+
+        workflows.clear();
+        for workflow in db_workflows {
+            workflows.insert(workflow.name.clone(), workflow);
+        }
+        workflows.len()
+    }
+
+    pub async fn get_workflows(&self) -> Vec<WorkflowRecord> {
+        let workflows = self.workflows.read().await;
+        let mut all: Vec<WorkflowRecord> = workflows.values().cloned().collect();
+        all.sort_by(|a, b| a.name.cmp(&b.name));
+        all
+    }
+
+    pub async fn get_workflow(&self, name: &str) -> Option<WorkflowRecord> {
+        let workflows = self.workflows.read().await;
+        workflows.get(name).cloned()
+    }
+
+    pub async fn create_workflow(
+        &self,
+        workflow: WorkflowRecord,
+    ) -> Result<WorkflowRecord, String> {
+        self.db.insert_workflow(&workflow).await?;
+        let mut workflows = self.workflows.write().await;
+        workflows.insert(workflow.name.clone(), workflow.clone());
+        Ok(workflow)
+    }
+
+    /// Replace the stored workflow with the same name; runs in progress keep their steps
+    pub async fn update_workflow(
+        &self,
+        workflow: WorkflowRecord,
+    ) -> Result<WorkflowRecord, String> {
+        if !self.db.update_workflow(&workflow).await? {
+            return Err(format!("Workflow '{}' not found", workflow.name));
+        }
+        let mut workflows = self.workflows.write().await;
+        workflows.insert(workflow.name.clone(), workflow.clone());
+        Ok(workflow)
+    }
+
+    pub async fn delete_workflow(&self, name: &str) -> Result<bool, String> {
+        let deleted = self.db.delete_workflow(name).await?;
+        if deleted {
+            let mut workflows = self.workflows.write().await;
+            workflows.remove(name);
+        }
+        Ok(deleted)
+    }
+
+    pub async fn create_workflow_run(&self, run: &WorkflowRun) -> Result<(), String> {
+        self.db.insert_workflow_run(run).await
+    }
+
+    pub async fn update_workflow_run(&self, run: &WorkflowRun) {
+        let _ = self.db.update_workflow_run(run).await;
+    }
+
+    pub async fn get_workflow_run(&self, run_id: Uuid) -> Option<WorkflowRun> {
+        self.db.get_workflow_run(run_id).await
+    }
+
+    pub async fn get_workflow_runs(&self, workflow: Option<&str>) -> Vec<WorkflowRun> {
+        self.db.get_workflow_runs(workflow).await
+    }
+
+    /// The window suppressing events of this type at `now`. Without a handler only windows
+    /// covering every handler of the event type count. Windows that drop events take
+    /// precedence over windows that defer them.
+    pub async fn blocking_window(
+        &self,
+        event_type: &str,
+        handler: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Option<WindowRecord> {
+        let windows = self.windows.read().await;
+        windows
+            .values()
+            .filter(|w| w.event_type.as_deref().is_none_or(|e| e == event_type))
+            .filter(|w| w.handler_name.is_none() || w.handler_name.as_deref() == handler)
+            .filter(|w| window::is_active(w, now))
+            .min_by_key(|w| w.action != WindowAction::Drop)
+            .cloned()
+    }
+
+    /// Remember an idempotency key for a new event; returns the original event if the key was
+    /// already used
+    pub async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        event: &Event,
+    ) -> Result<Option<Event>, String> {
+        self.db.claim_idempotency_key(key, event).await
+    }
+
+    pub async fn release_idempotency_key(&self, key: &str) -> Result<(), String> {
+        self.db.release_idempotency_key(key).await
+    }
+
+    pub async fn get_job_ids_for_event(&self, event_id: Uuid) -> Vec<Uuid> {
+        self.db.get_job_ids_for_event(event_id).await
+    }
+
     pub async fn get_config(&self, key: &str) -> Option<String> {
         self.db.get_config(key).await
     }
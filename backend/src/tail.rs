@@ -0,0 +1,305 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::db::TailRecord;
+
+/// Longer lines are cut into pieces of this size
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Check that a tail's settings make sense
+pub fn validate(tail: &TailRecord) -> Result<(), String> {
+    if !Path::new(&tail.path).is_absolute() {
+        return Err(format!("Path '{}' must be absolute", tail.path));
+    }
+    if tail.patterns.is_empty() {
+        return Err("At least one pattern is required".to_string());
+    }
+    LineMatcher::new(&tail.patterns).map(|_| ())
+}
+
+/// Matches lines against a tail's patterns, first match wins
+pub struct LineMatcher(Vec<Regex>);
+
+impl LineMatcher {
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        patterns
+            .iter()
+            .map(|p| Regex::new(p).map_err(|e| format!("Invalid pattern '{}': {}", p, e)))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Event context for a matching line, e.g.
+    /// `{"path":"/var/log/auth.log","line":"...","pattern":0,"groups":{"user":"root"}}`
+    pub fn context(&self, path: &str, line: &str) -> Option<String> {
+        self.0.iter().enumerate().find_map(|(index, regex)| {
+            let captures = regex.captures(line)?;
+            let groups: Map<String, Value> = regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| {
+                    let value = captures.name(name)?;
+                    Some((name.to_string(), Value::from(value.as_str())))
+                })
+                .collect();
+            let context = serde_json::json!({
+                "path": path,
+                "line": line,
+                "pattern": index,
+                "groups": groups,
+            });
+            Some(context.to_string())
+        })
+    }
+}
+
+/// Identifies the file behind a path so a rotated log is noticed
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Reads the lines appended to a file, reopening it when it is rotated and starting over when
+/// it is truncated
+pub struct LogFollower {
+    path: PathBuf,
+    file: Option<File>,
+    id: Option<(u64, u64)>,
+    offset: u64,
+    /// The last line until its newline arrives
+    partial: Vec<u8>,
+    /// Whether the lines present when the file is first opened are read
+    from_start: bool,
+}
+
+impl LogFollower {
+    pub fn new(path: &str, from_start: bool) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            file: None,
+            id: None,
+            offset: 0,
+            partial: Vec::new(),
+            from_start,
+        }
+    }
+
+    /// The complete lines written since the last call
+    pub async fn poll(&mut self) -> Result<Vec<String>, String> {
+        let mut lines = Vec::new();
+        let metadata = match tokio::fs::metadata(&self.path).await {
+            Ok(metadata) => metadata,
+            // Missing, or moved away and not recreated yet; finish what was written to the old file
+            Err(_) => {
+                if self.file.is_none() {
+                    self.from_start = true;
+                }
+                self.read_new(&mut lines).await?;
+                return Ok(lines);
+            }
+        };
+
+        match &self.file {
+            None => {
+                let start = if self.from_start { 0 } else { metadata.len() };
+                self.open(&metadata, start).await?;
+            }
+            Some(_) if file_id(&metadata) != self.id => {
+                self.read_new(&mut lines).await?;
+                self.flush_partial(&mut lines);
+                self.open(&metadata, 0).await?;
+            }
+            Some(_) if metadata.len() < self.offset => {
+                self.partial.clear();
+                self.seek(0).await?;
+            }
+            Some(_) => {}
+        }
+
+        self.read_new(&mut lines).await?;
+        Ok(lines)
+    }
+
+    async fn open(&mut self, metadata: &std::fs::Metadata, offset: u64) -> Result<(), String> {
+        let file = File::open(&self.path)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        self.file = Some(file);
+        self.id = file_id(metadata);
+        self.partial.clear();
+        self.seek(offset).await
+    }
+
+    async fn seek(&mut self, offset: u64) -> Result<(), String> {
+        if let Some(file) = &mut self.file {
+            file.seek(SeekFrom::Start(offset))
+                .await
+                .map_err(|e| format!("Failed to seek {}: {}", self.path.display(), e))?;
+        }
+        self.offset = offset;
+        Ok(())
+    }
+
+    async fn read_new(&mut self, lines: &mut Vec<String>) -> Result<(), String> {
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        let mut buf = Vec::new();
+        let read = file
+            .read_to_end(&mut buf)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        self.offset += read as u64;
+
+        self.partial.extend_from_slice(&buf);
+        while let Some(end) = self.partial.iter().position(|b| *b == b'\n') {
+            let rest = self.partial.split_off(end + 1);
+            let line = std::mem::replace(&mut self.partial, rest);
+            lines.push(decode_line(&line[..end]));
+        }
+        while self.partial.len() >= MAX_LINE_BYTES {
+            let rest = self.partial.split_off(MAX_LINE_BYTES);
+            let line = std::mem::replace(&mut self.partial, rest);
+            lines.push(decode_line(&line));
+        }
+        Ok(())
+    }
+
+    /// A rotated file's last line may lack its newline
+    fn flush_partial(&mut self, lines: &mut Vec<String>) {
+        if !self.partial.is_empty() {
+            lines.push(decode_line(&self.partial));
+            self.partial.clear();
+        }
+    }
+}
+
+fn decode_line(bytes: &[u8]) -> String {
+    let line = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn matcher(patterns: &[&str]) -> LineMatcher {
+        LineMatcher::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    async fn append(path: &Path, text: &str) {
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .unwrap();
+        file.write_all(text.as_bytes()).await.unwrap();
+    }
+
+    fn log_path() -> PathBuf {
+        std::env::temp_dir().join(format!("shev-tail-{}.log", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn the_first_matching_pattern_names_the_groups() {
+        let matcher = matcher(&[r"Failed password for (?P<user>\w+)", r"error"]);
+        let context = matcher
+            .context(
+                "/var/log/auth.log",
+                "Failed password for root from 10.0.0.1",
+            )
+            .unwrap();
+        let context: Value = serde_json::from_str(&context).unwrap();
+        assert_eq!(context["pattern"], 0);
+        assert_eq!(context["groups"], serde_json::json!({"user": "root"}));
+        assert_eq!(context["path"], "/var/log/auth.log");
+
+        let context = matcher.context("/var/log/auth.log", "disk error").unwrap();
+        let context: Value = serde_json::from_str(&context).unwrap();
+        assert_eq!(context["pattern"], 1);
+        assert_eq!(context["groups"], serde_json::json!({}));
+
+        assert!(matcher.context("/var/log/auth.log", "all good").is_none());
+    }
+
+    #[test]
+    fn tails_are_validated() {
+        let mut tail = TailRecord::new(
+            "auth".to_string(),
+            "/var/log/auth.log".to_string(),
+            vec!["error".to_string()],
+        );
+        assert!(validate(&tail).is_ok());
+        tail.patterns = vec!["(unclosed".to_string()];
+        assert!(validate(&tail).is_err());
+        tail.patterns.clear();
+        assert!(validate(&tail).is_err());
+        tail.patterns = vec!["error".to_string()];
+        tail.path = "auth.log".to_string();
+        assert!(validate(&tail).is_err());
+    }
+
+    #[tokio::test]
+    async fn only_complete_new_lines_are_read() {
+        let path = log_path();
+        append(&path, "old line\n").await;
+        let mut follower = LogFollower::new(path.to_str().unwrap(), false);
+        assert!(follower.poll().await.unwrap().is_empty());
+
+        append(&path, "first\r\nsec").await;
+        assert_eq!(follower.poll().await.unwrap(), vec!["first"]);
+        append(&path, "ond\n").await;
+        assert_eq!(follower.poll().await.unwrap(), vec!["second"]);
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncated_files_are_read_from_the_start() {
+        let path = log_path();
+        append(&path, "one\ntwo\n").await;
+        let mut follower = LogFollower::new(path.to_str().unwrap(), true);
+        assert_eq!(follower.poll().await.unwrap(), vec!["one", "two"]);
+
+        tokio::fs::write(&path, "three\n").await.unwrap();
+        assert_eq!(follower.poll().await.unwrap(), vec!["three"]);
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rotated_files_are_finished_before_the_new_one_is_read() {
+        let path = log_path();
+        let rotated = path.with_extension("log.1");
+        append(&path, "").await;
+        let mut follower = LogFollower::new(path.to_str().unwrap(), false);
+        assert!(follower.poll().await.unwrap().is_empty());
+
+        append(&path, "before\nlast without newline").await;
+        tokio::fs::rename(&path, &rotated).await.unwrap();
+        append(&path, "after\n").await;
+        assert_eq!(
+            follower.poll().await.unwrap(),
+            vec!["before", "last without newline", "after"]
+        );
+
+        tokio::fs::remove_file(&path).await.unwrap();
+        tokio::fs::remove_file(&rotated).await.unwrap();
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::db::Event;
+use shev_core::ShellType;
+
+/// Replace the `{{ context.<field> }}`, `{{ context }}`, `{{ event_type }}` and `{{ event_id }}`
+/// placeholders in a handler command with values from the event
+///
+/// A bare placeholder becomes a single shell-quoted argument; one inside a `'...'` or `"..."`
+/// string is escaped so its value stays inside that string. Nested fields and array items are
+/// reached with dots (`{{ context.repo.branches.0 }}`) and missing fields render as empty.
+/// Anything else between braces, such as a Go template passed to `docker inspect`, is left as
+/// it is.
+pub fn render(command: &str, event: &Event, shell: &ShellType) -> Result<String, String> {
+    if !command.contains("{{") {
+        return Ok(command.to_string());
+    }
+    let context: Option<Value> = serde_json::from_str(&event.context).ok();
+
+    let mut rendered = String::with_capacity(command.len());
+    for piece in split(command, shell)? {
+        match piece {
+            Piece::Text(text) => rendered.push_str(text),
+            Piece::Placeholder(expression, quoting) => {
+                let value = resolve(expression, event, context.as_ref());
+                rendered.push_str(&escape(&value, quoting, shell));
+            }
+        }
+    }
+    Ok(rendered)
+}
+
+/// Check that every placeholder in a command sits where its value can be escaped
+pub fn validate(command: &str, shell: &ShellType) -> Result<(), String> {
+    split(command, shell).map(|_| ())
+}
+
+/// What surrounds a placeholder, which decides how its value is escaped
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Quoting {
+    Bare,
+    Single,
+    Double,
+}
+
+/// A run of command text, or one of our placeholders
+enum Piece<'a> {
+    Text(&'a str),
+    Placeholder(&'a str, Quoting),
+}
+
+/// Where the scanner is in the command
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Bare,
+    Single,
+    Double,
+    /// Inside a `` `...` `` command substitution
+    Backtick,
+    /// Inside a bash `$'...'` string
+    AnsiC,
+    /// After the start of a comment, up to the end of the line
+    Comment,
+    /// Inside a PowerShell `<# ... #>` comment
+    BlockComment,
+}
+
+/// Split a command into text and placeholders, following the shell's quoting far enough to
+/// know what surrounds each placeholder
+fn split<'a>(command: &'a str, shell: &ShellType) -> Result<Vec<Piece<'a>>, String> {
+    let pwsh = *shell == ShellType::Pwsh;
+    let escape_char = if pwsh { '`' } else { '\\' };
+    let is_single = |c: char| {
+        c == '\'' || pwsh && matches!(c, '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}')
+    };
+    let is_double = |c: char| c == '"' || pwsh && matches!(c, '\u{201c}' | '\u{201d}' | '\u{201e}');
+
+    let mut pieces = Vec::new();
+    let mut state = State::Bare;
+    // The state to go back to when each open `$(...)` or `` `...` `` ends, and how many
+    // parentheses are open inside it
+    let mut outer: Vec<(State, u32)> = Vec::new();
+    // Here-documents take their quoting from the delimiter, so placeholders after one are
+    // refused rather than guessed at
+    let mut here_doc = false;
+    let mut text_start = 0;
+    let mut previous = None;
+    let mut chars = command.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '{'
+            && command[i..].starts_with("{{")
+            && let Some(len) = command[i + 2..].find("}}")
+            && is_placeholder(command[i + 2..i + 2 + len].trim())
+        {
+            let expression = command[i + 2..i + 2 + len].trim();
+            let quoting = match state {
+                _ if here_doc => {
+                    return Err(format!(
+                        "Placeholder '{{{{ {} }}}}' cannot be used after a here-document; \
+                         use the EVENT_* environment variables instead",
+                        expression
+                    ));
+                }
+                State::Bare | State::Comment | State::BlockComment => Quoting::Bare,
+                State::Single => Quoting::Single,
+                State::Double => Quoting::Double,
+                State::Backtick => {
+                    return Err(format!(
+                        "Placeholder '{{{{ {} }}}}' cannot be used inside backticks; \
+                         use $(...) instead",
+                        expression
+                    ));
+                }
+                State::AnsiC => {
+                    return Err(format!(
+                        "Placeholder '{{{{ {} }}}}' cannot be used inside a $'...' string",
+                        expression
+                    ));
+                }
+            };
+            let end = i + 2 + len + 2;
+            pieces.push(Piece::Text(&command[text_start..i]));
+            pieces.push(Piece::Placeholder(expression, quoting));
+            while chars.next_if(|(j, _)| *j < end).is_some() {}
+            text_start = end;
+            previous = Some('}');
+            continue;
+        }
+
+        let next = chars.peek().map(|(_, n)| *n);
+        match state {
+            State::Comment => {
+                if c == '\n' {
+                    state = State::Bare;
+                }
+            }
+            State::BlockComment => {
+                if c == '#' && next == Some('>') {
+                    chars.next();
+                    state = State::Bare;
+                }
+            }
+            State::Single => {
+                // PowerShell escapes a quote inside a single-quoted string by doubling it
+                if is_single(c) {
+                    if pwsh && next.is_some_and(is_single) {
+                        chars.next();
+                    } else {
+                        state = State::Bare;
+                    }
+                }
+            }
+            State::AnsiC => match c {
+                '\\' => {
+                    chars.next();
+                }
+                '\'' => state = State::Bare,
+                _ => {}
+            },
+            State::Backtick => match c {
+                '\\' => {
+                    chars.next();
+                }
+                '`' => state = outer.pop().map_or(State::Bare, |(state, _)| state),
+                _ => {}
+            },
+            State::Double => {
+                if c == escape_char {
+                    chars.next();
+                } else if is_double(c) {
+                    if pwsh && next.is_some_and(is_double) {
+                        chars.next();
+                    } else {
+                        state = State::Bare;
+                    }
+                } else if c == '$' && next == Some('(') {
+                    chars.next();
+                    outer.push((State::Double, 0));
+                    state = State::Bare;
+                } else if c == '`' && !pwsh {
+                    outer.push((State::Double, 0));
+                    state = State::Backtick;
+                }
+            }
+            State::Bare => {
+                let word_start = previous
+                    .is_none_or(|p: char| p.is_whitespace() || matches!(p, ';' | '&' | '|' | '('));
+                if c == escape_char {
+                    chars.next();
+                } else if is_single(c) {
+                    state = State::Single;
+                } else if is_double(c) {
+                    state = State::Double;
+                } else if c == '#' && word_start {
+                    state = State::Comment;
+                } else if pwsh && c == '<' && next == Some('#') {
+                    chars.next();
+                    state = State::BlockComment;
+                } else if !pwsh && c == '$' && next == Some('\'') {
+                    chars.next();
+                    state = State::AnsiC;
+                } else if !pwsh && c == '`' {
+                    outer.push((State::Bare, 0));
+                    state = State::Backtick;
+                } else if c == '$' && next == Some('(') {
+                    chars.next();
+                    outer.push((State::Bare, 0));
+                } else if c == '(' {
+                    if let Some((_, depth)) = outer.last_mut() {
+                        *depth += 1;
+                    }
+                } else if c == ')' {
+                    match outer.last_mut() {
+                        Some((_, 0)) => state = outer.pop().map_or(State::Bare, |(s, _)| s),
+                        Some((_, depth)) => *depth -= 1,
+                        None => {}
+                    }
+                } else if !pwsh && c == '<' && next == Some('<') {
+                    chars.next();
+                    // `<<<` is a here-string, which is quoted like any other word
+                    if chars.next_if(|(_, n)| *n == '<').is_none() {
+                        here_doc = true;
+                    }
+                } else if pwsh && c == '@' && next.is_some_and(|n| is_single(n) || is_double(n)) {
+                    here_doc = true;
+                }
+            }
+        }
+        previous = Some(c);
+    }
+    pieces.push(Piece::Text(&command[text_start..]));
+    Ok(pieces)
+}
+
+/// Escape a value for what surrounds its placeholder
+fn escape(value: &str, quoting: Quoting, shell: &ShellType) -> String {
+    match quoting {
+        Quoting::Bare => shell.quote(value),
+        // Already inside quotes: everything `quote` would add between its own quotes
+        Quoting::Single => {
+            let quoted = shell.quote(value);
+            quoted[1..quoted.len() - 1].to_string()
+        }
+        Quoting::Double => {
+            let mut escaped = String::with_capacity(value.len());
+            for c in value.chars() {
+                let special = match shell {
+                    ShellType::Pwsh => {
+                        matches!(c, '`' | '$' | '"' | '\u{201c}' | '\u{201d}' | '\u{201e}')
+                    }
+                    ShellType::Bash | ShellType::Sh => matches!(c, '\\' | '$' | '`' | '"'),
+                };
+                if special {
+                    escaped.push(if *shell == ShellType::Pwsh { '`' } else { '\\' });
+                }
+                escaped.push(c);
+            }
+            escaped
+        }
+    }
+}
+
+/// Whether the text between braces is one of our placeholders
+fn is_placeholder(expression: &str) -> bool {
+    match expression {
+        "context" | "event_type" | "event_id" => true,
+        _ => expression.strip_prefix("context.").is_some_and(|path| {
+            path.split('.').all(|s| {
+                !s.is_empty()
+                    && s.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            })
+        }),
+    }
+}
+
+/// The value of one of our placeholders
+fn resolve(expression: &str, event: &Event, context: Option<&Value>) -> String {
+    match expression {
+        "context" => return event.context.clone(),
+        "event_type" => return event.event_type.clone(),
+        "event_id" => return event.id.to_string(),
+        _ => {}
+    }
+
+    let path = expression.strip_prefix("context.").unwrap_or_default();
+    let mut value = context;
+    for segment in path.split('.') {
+        value = value.and_then(|v| match v {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => v.get(segment),
+        });
+    }
+    value.map(value_to_string).unwrap_or_default()
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// `EVENT_<KEY>` environment variables for the top-level fields of a JSON object context,
+/// e.g. `{"branch":"main"}` gives `EVENT_BRANCH=main`
+pub fn context_env(context: &str) -> HashMap<String, String> {
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(context) else {
+        return HashMap::new();
+    };
+    fields
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| {
+            let name: String = key
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            (format!("EVENT_{}", name), value_to_string(value))
+        })
+        // EVENT_CONTEXT always holds the whole context
+        .filter(|(name, _)| name != "EVENT_CONTEXT")
+        .collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    /// Values that would break out of any quoting they were pasted into unescaped
+    const NASTY: &[&str] = &[
+        "plain",
+        "two words",
+        "a'b",
+        "a\"b",
+        "\"; touch /tmp/shev-template-test; \"",
+        "'; touch /tmp/shev-template-test; '",
+        "$(echo injected)",
+        "`echo injected`",
+        "$HOME ${HOME} \\ \\\\ \\$",
+        "*",
+        "line\nbreak",
+        "!x #comment",
+        "",
+    ];
+
+    fn event(value: &str) -> Event {
+        Event::new(
+            "test".to_string(),
+            serde_json::json!({ "value": value }).to_string(),
+        )
+    }
+
+    /// Render a command and run it, returning its standard output
+    fn run(command: &str, value: &str, shell: ShellType) -> String {
+        let rendered = render(command, &event(value), &shell).unwrap();
+        let (program, args) = shell.command_args(&rendered);
+        let output = Command::new(program).args(args).output().unwrap();
+        assert!(output.status.success(), "{} failed", rendered);
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn bare_placeholders_are_one_literal_argument() {
+        for shell in [ShellType::Sh, ShellType::Bash] {
+            for value in NASTY {
+                let output = run("printf '[%s]' {{ context.value }}", value, shell.clone());
+                assert_eq!(output, format!("[{}]", value));
+            }
+        }
+    }
+
+    #[test]
+    fn single_quoted_placeholders_stay_in_their_string() {
+        for shell in [ShellType::Sh, ShellType::Bash] {
+            for value in NASTY {
+                let output = run(
+                    "printf '[%s]' 'x {{ context.value }} y'",
+                    value,
+                    shell.clone(),
+                );
+                assert_eq!(output, format!("[x {} y]", value));
+            }
+        }
+    }
+
+    #[test]
+    fn double_quoted_placeholders_stay_in_their_string() {
+        for shell in [ShellType::Sh, ShellType::Bash] {
+            for value in NASTY {
+                let output = run(
+                    "printf '[%s]' \"x {{ context.value }} y\"",
+                    value,
+                    shell.clone(),
+                );
+                assert_eq!(output, format!("[x {} y]", value));
+            }
+        }
+    }
+
+    #[test]
+    fn placeholders_in_substitutions_follow_the_inner_quoting() {
+        for value in NASTY {
+            let output = run(
+                "printf '[%s]' \"x $(printf '%s' {{ context.value }}) y\"",
+                value,
+                ShellType::Bash,
+            );
+            // Command substitution drops trailing newlines
+            assert_eq!(output, format!("[x {} y]", value.trim_end_matches('\n')));
+            let output = run(
+                "printf '[%s]' \"$(printf '%s' \"{{ context.value }}\")\"",
+                value,
+                ShellType::Bash,
+            );
+            assert_eq!(output, format!("[{}]", value.trim_end_matches('\n')));
+        }
+    }
+
+    #[test]
+    fn quotes_in_comments_and_escapes_are_not_strings() {
+        let command = "echo it\\'s # don't\nprintf '[%s]' {{ context.value }}";
+        for value in NASTY {
+            let output = run(command, value, ShellType::Sh);
+            assert_eq!(output, format!("it's\n[{}]", value));
+        }
+    }
+
+    #[test]
+    fn pwsh_escapes_for_its_own_quotes() {
+        let render =
+            |command: &str, value: &str| render(command, &event(value), &ShellType::Pwsh).unwrap();
+        assert_eq!(render("echo {{ context.value }}", "it's"), "echo 'it''s'");
+        assert_eq!(
+            render("echo 'a {{ context.value }}'", "it\u{2019}s"),
+            "echo 'a it\u{2019}\u{2019}s'"
+        );
+        assert_eq!(
+            render("echo \"a {{ context.value }}\"", "$(x) `n \"q\""),
+            "echo \"a `$(x) ``n `\"q`\"\""
+        );
+        // A doubled quote does not end the string; the backtick is not an escape in '...'
+        assert_eq!(
+            render("echo 'it''s `' {{ context.value }}", "a b"),
+            "echo 'it''s `' 'a b'"
+        );
+    }
+
+    #[test]
+    fn other_braces_are_left_alone() {
+        let command = "docker inspect -f '{{.State.Status}}' {{ context.value }} {{ nope }}";
+        assert_eq!(
+            render(command, &event("web"), &ShellType::Sh).unwrap(),
+            "docker inspect -f '{{.State.Status}}' 'web' {{ nope }}"
+        );
+    }
+
+    #[test]
+    fn placeholders_that_cannot_be_escaped_are_rejected() {
+        for command in [
+            "echo `echo {{ context.value }}`",
+            "echo \"`echo {{ context.value }}`\"",
+            "echo $'{{ context.value }}'",
+            "cat <<EOF\n{{ context.value }}\nEOF",
+        ] {
+            assert!(validate(command, &ShellType::Bash).is_err(), "{}", command);
+        }
+        assert!(validate("cat <<< {{ context.value }}", &ShellType::Bash).is_ok());
+        assert!(validate("@'\n{{ context.value }}\n'@", &ShellType::Pwsh).is_err());
+        assert!(validate("echo `echo {{ nope }}`", &ShellType::Bash).is_ok());
+    }
+}
@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::EventKind;
+use notify::event::{ModifyKind, RenameMode};
+
+use crate::db::WatcherRecord;
+use shev_core::FileChange;
+
+/// Upper bound on the debounce delay
+const MAX_DEBOUNCE_MS: u32 = 3_600_000;
+
+fn build_globs(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| format!("Invalid globs: {}", e))
+}
+
+/// Check that a watcher's settings make sense
+pub fn validate(watcher: &WatcherRecord) -> Result<(), String> {
+    if watcher.paths.is_empty() {
+        return Err("At least one path is required".to_string());
+    }
+    if let Some(path) = watcher.paths.iter().find(|p| !Path::new(p).is_absolute()) {
+        return Err(format!("Path '{}' must be absolute", path));
+    }
+    if watcher.changes.is_empty() {
+        return Err("At least one kind of change is required".to_string());
+    }
+    if watcher.debounce_ms > MAX_DEBOUNCE_MS {
+        return Err(format!(
+            "The debounce must be at most {}ms",
+            MAX_DEBOUNCE_MS
+        ));
+    }
+    FileFilter::new(watcher).map(|_| ())
+}
+
+/// Decides which changed files a watcher reports, based on its include and exclude globs
+pub struct FileFilter {
+    roots: Vec<PathBuf>,
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl FileFilter {
+    pub fn new(watcher: &WatcherRecord) -> Result<Self, String> {
+        Ok(Self {
+            roots: watcher.paths.iter().map(PathBuf::from).collect(),
+            include: build_globs(&watcher.include)?,
+            exclude: build_globs(&watcher.exclude)?,
+        })
+    }
+
+    /// Globs are matched against the full path and the path relative to the watched directory,
+    /// so both `/data/in/*.csv` and `*.csv` work
+    pub fn matches(&self, path: &Path) -> bool {
+        let relative = self
+            .roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+            .filter(|p| !p.as_os_str().is_empty());
+        let matches =
+            |globs: &GlobSet| globs.is_match(path) || relative.is_some_and(|p| globs.is_match(p));
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+}
+
+/// The changes a file system notification stands for. Renames count as the old name being
+/// deleted and the new one created.
+pub fn classify(kind: &EventKind, paths: &[PathBuf]) -> Vec<(PathBuf, FileChange)> {
+    let all = |change: FileChange| paths.iter().map(|p| (p.clone(), change)).collect();
+    match kind {
+        EventKind::Create(_) => all(FileChange::Created),
+        EventKind::Remove(_) => all(FileChange::Deleted),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => all(FileChange::Deleted),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => all(FileChange::Created),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => vec![
+            (paths[0].clone(), FileChange::Deleted),
+            (paths[1].clone(), FileChange::Created),
+        ],
+        EventKind::Modify(ModifyKind::Name(_)) => paths
+            .iter()
+            .map(|p| match p.exists() {
+                true => (p.clone(), FileChange::Created),
+                false => (p.clone(), FileChange::Deleted),
+            })
+            .collect(),
+        EventKind::Modify(_) => all(FileChange::Modified),
+        EventKind::Access(_) | EventKind::Any | EventKind::Other => Vec::new(),
+    }
+}
+
+/// Combine a file's pending change with a newer one while debouncing. None means the two
+/// cancel out, e.g. a temporary file that was created and deleted again.
+pub fn merge(pending: FileChange, newer: FileChange) -> Option<FileChange> {
+    match (pending, newer) {
+        (FileChange::Created, FileChange::Deleted) => None,
+        (FileChange::Created, _) => Some(FileChange::Created),
+        (FileChange::Deleted, FileChange::Created) => Some(FileChange::Modified),
+        (_, newer) => Some(newer),
+    }
+}
+
+/// Event context describing a change, e.g. `{"change":"created","path":"/data/in/a.csv"}`
+pub fn context(path: &Path, change: FileChange) -> String {
+    serde_json::json!({
+        "change": change.as_str(),
+        "path": path.to_string_lossy(),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+
+    use super::*;
+
+    fn watcher(include: &[&str], exclude: &[&str]) -> WatcherRecord {
+        let mut watcher = WatcherRecord::new("incoming".to_string(), vec!["/data/in".to_string()]);
+        watcher.include = include.iter().map(|g| g.to_string()).collect();
+        watcher.exclude = exclude.iter().map(|g| g.to_string()).collect();
+        watcher
+    }
+
+    fn matches(watcher: &WatcherRecord, path: &str) -> bool {
+        FileFilter::new(watcher).unwrap().matches(Path::new(path))
+    }
+
+    #[test]
+    fn globs_match_full_and_relative_paths() {
+        let relative = watcher(&["*.csv"], &[]);
+        assert!(matches(&relative, "/data/in/a.csv"));
+        assert!(!matches(&relative, "/data/in/a.json"));
+
+        let absolute = watcher(&["/data/in/*.csv"], &[]);
+        assert!(matches(&absolute, "/data/in/a.csv"));
+
+        let everything = watcher(&[], &[]);
+        assert!(matches(&everything, "/data/in/a.json"));
+    }
+
+    #[test]
+    fn excludes_win_over_includes() {
+        let filter = watcher(&["*.csv"], &["tmp_*", "*.swp"]);
+        assert!(matches(&filter, "/data/in/a.csv"));
+        assert!(!matches(&filter, "/data/in/tmp_a.csv"));
+        assert!(!matches(&watcher(&[], &["*.swp"]), "/data/in/.a.csv.swp"));
+    }
+
+    #[test]
+    fn notifications_are_classified_as_changes() {
+        let (old, new) = (PathBuf::from("/data/in/a"), PathBuf::from("/data/in/b"));
+        let one = std::slice::from_ref(&old);
+        assert_eq!(
+            classify(&EventKind::Create(CreateKind::File), one),
+            vec![(old.clone(), FileChange::Created)]
+        );
+        assert_eq!(
+            classify(&EventKind::Modify(ModifyKind::Data(DataChange::Any)), one),
+            vec![(old.clone(), FileChange::Modified)]
+        );
+        assert_eq!(
+            classify(&EventKind::Remove(RemoveKind::File), one),
+            vec![(old.clone(), FileChange::Deleted)]
+        );
+        assert_eq!(
+            classify(
+                &EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &[old.clone(), new.clone()]
+            ),
+            vec![
+                (old.clone(), FileChange::Deleted),
+                (new, FileChange::Created)
+            ]
+        );
+        assert!(classify(&EventKind::Any, one).is_empty());
+    }
+
+    #[test]
+    fn pending_changes_are_merged() {
+        use FileChange::*;
+        assert_eq!(merge(Created, Modified), Some(Created));
+        assert_eq!(merge(Created, Deleted), None);
+        assert_eq!(merge(Deleted, Created), Some(Modified));
+        assert_eq!(merge(Modified, Deleted), Some(Deleted));
+    }
+
+    #[test]
+    fn context_names_the_change_and_path() {
+        let context: serde_json::Value =
+            serde_json::from_str(&context(Path::new("/data/in/a.csv"), FileChange::Created))
+                .unwrap();
+        assert_eq!(
+            context,
+            serde_json::json!({"change": "created", "path": "/data/in/a.csv"})
+        );
+    }
+
+    #[test]
+    fn watchers_are_validated() {
+        assert!(validate(&watcher(&["*.csv"], &[])).is_ok());
+        assert!(validate(&watcher(&["[*.csv"], &[])).is_err());
+
+        let mut relative = watcher(&[], &[]);
+        relative.paths = vec!["data/in".to_string()];
+        assert!(validate(&relative).is_err());
+
+        let mut nothing = watcher(&[], &[]);
+        nothing.changes.clear();
+        assert!(validate(&nothing).is_err());
+
+        let mut slow = watcher(&[], &[]);
+        slow.debounce_ms = MAX_DEBOUNCE_MS + 1;
+        assert!(validate(&slow).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use axum::http::HeaderName;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::db::WebhookRecord;
+
+/// One step of a JSONPath expression
+enum Segment {
+    Key(String),
+    Index(i64),
+}
+
+/// Parse the supported JSONPath subset: `$`, `.key`, `['key']` and `[index]` (negative indices
+/// count from the end)
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let invalid = |reason: &str| format!("Invalid JSONPath '{}': {}", path, reason);
+    let mut rest = path
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| invalid("must start with $"))?;
+    let mut segments = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let key = &after[..end];
+            if key.is_empty() || key == "*" {
+                return Err(invalid("wildcards and recursive descent are not supported"));
+            }
+            segments.push(Segment::Key(key.to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(|| invalid("missing ]"))?;
+            let inner = after[..end].trim();
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            match quoted {
+                Some(key) => segments.push(Segment::Key(key.to_string())),
+                None => {
+                    let index = inner
+                        .parse()
+                        .map_err(|_| invalid("expected an index or a quoted key in []"))?;
+                    segments.push(Segment::Index(index));
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            return Err(invalid("expected . or ["));
+        }
+    }
+
+    Ok(segments)
+}
+
+fn select<'a>(value: &'a Value, segments: &[Segment]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(value, |value, segment| match segment {
+            Segment::Key(key) => value.get(key),
+            Segment::Index(index) => {
+                let items = value.as_array()?;
+                let index = if *index < 0 {
+                    items.len().checked_sub(index.unsigned_abs() as usize)?
+                } else {
+                    *index as usize
+                };
+                items.get(index)
+            }
+        })
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Check that a webhook's settings make sense
+pub fn validate(webhook: &WebhookRecord) -> Result<(), String> {
+    if webhook.secret.as_deref().is_some_and(str::is_empty) {
+        return Err("The secret must not be empty".to_string());
+    }
+    HeaderName::from_bytes(webhook.signature_header.as_bytes())
+        .map_err(|_| format!("Invalid signature header '{}'", webhook.signature_header))?;
+    for (name, path) in &webhook.fields {
+        if !is_env_name(name) || name == "EVENT_CONTEXT" {
+            return Err(format!("Invalid environment variable name '{}'", name));
+        }
+        parse_path(path)?;
+    }
+    Ok(())
+}
+
+/// Check an HMAC-SHA256 signature of the body, given as hex with an optional `sha256=` prefix
+/// (GitHub style). The comparison takes constant time.
+pub fn verify_signature(secret: &str, signature: &str, body: &[u8]) -> bool {
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(expected) = hex::decode(signature) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Environment variables for the configured fields of a JSON body. Strings are used as is,
+/// other values as JSON; missing and null fields are left unset.
+pub fn extract_fields(fields: &HashMap<String, String>, body: &Value) -> HashMap<String, String> {
+    fields
+        .iter()
+        .filter_map(|(name, path)| {
+            let segments = parse_path(path).ok()?;
+            let value = match select(body, &segments)? {
+                Value::Null => return None,
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            Some((name.clone(), value))
+        })
+        .collect()
+}
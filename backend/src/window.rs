@@ -0,0 +1,356 @@
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+use tokio::time::sleep;
+use tracing::info;
+
+use crate::db::WindowRecord;
+use crate::recurrence::parse_timezone;
+use crate::store::JobStore;
+use shev_core::WindowAction;
+
+/// How often a deferred event checks whether it may go ahead, in case windows change
+const RECHECK_INTERVAL_SECS: i64 = 60;
+
+/// Parse a time of day such as `22:00` or `06:30:00`
+pub fn parse_time_of_day(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|_| format!("Invalid time of day '{}', use HH:MM", value))
+}
+
+/// Parse days of the week given as names (`mon`, `friday`), ranges (`mon-fri`), `weekdays` or
+/// `weekends`. Entries may also be comma-separated.
+pub fn parse_days(values: &[String]) -> Result<Vec<Weekday>, String> {
+    let mut days = Vec::new();
+    for value in values.iter().flat_map(|v| v.split(',')) {
+        let value = value.trim().to_lowercase();
+        match value.as_str() {
+            "" => {}
+            "weekdays" => days.extend([
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ]),
+            "weekends" => days.extend([Weekday::Sat, Weekday::Sun]),
+            _ => match value.split_once('-') {
+                Some((from, to)) => {
+                    let (mut day, last) = (parse_day(from)?, parse_day(to)?);
+                    days.push(day);
+                    while day != last {
+                        day = day.succ();
+                        days.push(day);
+                    }
+                }
+                None => days.push(parse_day(&value)?),
+            },
+        }
+    }
+    days.sort_by_key(|d| d.num_days_from_monday());
+    days.dedup();
+    Ok(days)
+}
+
+fn parse_day(value: &str) -> Result<Weekday, String> {
+    Weekday::from_str(value.trim()).map_err(|_| format!("Invalid day of the week '{}'", value))
+}
+
+/// Check that a window's fields can be evaluated
+pub fn validate(window: &WindowRecord) -> Result<(), String> {
+    if window.handler_name.is_some() && window.event_type.is_none() {
+        return Err("A handler can only be given together with an event type".to_string());
+    }
+    match (window.start_time, window.end_time) {
+        (Some(start), Some(end)) if start == end => {
+            return Err("The daily start and end time must differ".to_string());
+        }
+        (Some(_), None) | (None, Some(_)) => {
+            return Err("Give both a daily start and end time, or neither".to_string());
+        }
+        _ => {}
+    }
+    if window
+        .starts_at
+        .zip(window.ends_at)
+        .is_some_and(|(start, end)| end <= start)
+    {
+        return Err("The end must be after the start".to_string());
+    }
+    if let Some(ref timezone) = window.timezone {
+        parse_timezone(timezone)?;
+    }
+    Ok(())
+}
+
+fn timezone(window: &WindowRecord) -> Tz {
+    window
+        .timezone
+        .as_deref()
+        .and_then(|tz| parse_timezone(tz).ok())
+        .unwrap_or(Tz::UTC)
+}
+
+fn day_matches(window: &WindowRecord, date: NaiveDate) -> bool {
+    (window.days.is_empty() || window.days.contains(&date.weekday()))
+        && (window.dates.is_empty() || window.dates.contains(&date))
+}
+
+/// The local date on which the occurrence of the window covering `local` began, if any. A
+/// window spanning midnight belongs to the day it started on.
+fn occurrence_date(window: &WindowRecord, local: NaiveDateTime) -> Option<NaiveDate> {
+    let date = local.date();
+    let Some((start, end)) = window.start_time.zip(window.end_time) else {
+        return day_matches(window, date).then_some(date);
+    };
+    let time = local.time();
+    let began = if start < end {
+        (time >= start && time < end).then_some(date)
+    } else if time >= start {
+        Some(date)
+    } else if time < end {
+        date.pred_opt()
+    } else {
+        None
+    };
+    began.filter(|d| day_matches(window, *d))
+}
+
+/// Whether the window suppresses events at `now`
+pub fn is_active(window: &WindowRecord, now: DateTime<Utc>) -> bool {
+    if window.starts_at.is_some_and(|start| now < start)
+        || window.ends_at.is_some_and(|end| now >= end)
+    {
+        return false;
+    }
+    occurrence_date(window, now.with_timezone(&timezone(window)).naive_local()).is_some()
+}
+
+/// When the window that is active at `now` closes, or None if it stays open until it is
+/// changed or removed
+pub fn active_until(window: &WindowRecord, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let tz = timezone(window);
+    let local_end =
+        occurrence_date(window, now.with_timezone(&tz).naive_local()).and_then(|date| match window
+            .start_time
+            .zip(window.end_time)
+        {
+            Some((start, end)) => {
+                let end_date = if start < end { date } else { date.succ_opt()? };
+                Some(end_date.and_time(end))
+            }
+            None if window.days.is_empty() && window.dates.is_empty() => None,
+            None => {
+                // Whole days run on into the following days that match as well
+                let mut day = date.succ_opt()?;
+                for _ in 0..366 {
+                    if !day_matches(window, day) {
+                        break;
+                    }
+                    day = day.succ_opt()?;
+                }
+                Some(day.and_time(NaiveTime::MIN))
+            }
+        });
+    let end = local_end
+        .and_then(|t| tz.from_local_datetime(&t).earliest())
+        .map(|t| t.with_timezone(&Utc));
+    match (end, window.ends_at) {
+        (Some(end), Some(ends_at)) => Some(end.min(ends_at)),
+        (end, ends_at) => end.or(ends_at),
+    }
+}
+
+/// Hold an event back while a `defer` window covers it. Returns false if a `drop` window covers
+/// it instead and the event should be discarded.
+pub async fn wait_for_windows(
+    store: &JobStore,
+    event_type: &str,
+    handler: Option<&str>,
+    source: &str,
+) -> bool {
+    let target = match handler {
+        Some(name) => format!("{}/{}", event_type, name),
+        None => event_type.to_string(),
+    };
+    let mut deferred = false;
+    loop {
+        let now = Utc::now();
+        let Some(window) = store.blocking_window(event_type, handler, now).await else {
+            if deferred {
+                info!(
+                    "{} '{}': window closed, releasing deferred event",
+                    source, target
+                );
+            }
+            return true;
+        };
+
+        if window.action == WindowAction::Drop {
+            info!(
+                "{} '{}': suppressed by window '{}', dropping event",
+                source, target, window.name
+            );
+            return false;
+        }
+
+        let until = active_until(&window, now);
+        if !deferred {
+            match until {
+                Some(until) => info!(
+                    "{} '{}': deferred by window '{}' until {}",
+                    source, target, window.name, until
+                ),
+                None => info!(
+                    "{} '{}': deferred by window '{}' until it is removed",
+                    source, target, window.name
+                ),
+            }
+            deferred = true;
+        }
+
+        let recheck = now + ChronoDuration::seconds(RECHECK_INTERVAL_SECS);
+        let wake = until.map_or(recheck, |until| until.min(recheck));
+        sleep((wake - now).to_std().unwrap_or_default()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn nightly(start: &str, end: &str) -> WindowRecord {
+        let mut window = WindowRecord::new("quiet".to_string());
+        window.start_time = Some(parse_time_of_day(start).unwrap());
+        window.end_time = Some(parse_time_of_day(end).unwrap());
+        window
+    }
+
+    #[test]
+    fn days_are_read_as_names_ranges_and_groups() {
+        let days =
+            |values: &[&str]| parse_days(&values.iter().map(|v| v.to_string()).collect::<Vec<_>>());
+        assert_eq!(
+            days(&["fri-mon"]),
+            Ok(vec![Weekday::Mon, Weekday::Fri, Weekday::Sat, Weekday::Sun])
+        );
+        assert_eq!(
+            days(&["weekends,Monday", "sat"]),
+            Ok(vec![Weekday::Mon, Weekday::Sat, Weekday::Sun])
+        );
+        assert!(days(&["someday"]).is_err());
+        assert!(parse_time_of_day("06:30:15").is_ok());
+        assert!(parse_time_of_day("25:00").is_err());
+    }
+
+    #[test]
+    fn windows_past_midnight_belong_to_the_day_they_start() {
+        // 5 June 2026 is a Friday
+        let mut window = nightly("22:00", "06:00");
+        window.days = vec![Weekday::Fri];
+
+        assert!(!is_active(&window, at(2026, 6, 5, 21, 59)));
+        assert!(is_active(&window, at(2026, 6, 5, 22, 0)));
+        assert!(is_active(&window, at(2026, 6, 6, 5, 59)));
+        assert!(!is_active(&window, at(2026, 6, 6, 6, 0)));
+        // Friday morning belongs to Thursday night
+        assert!(!is_active(&window, at(2026, 6, 5, 3, 0)));
+
+        assert_eq!(
+            active_until(&window, at(2026, 6, 5, 23, 0)),
+            Some(at(2026, 6, 6, 6, 0))
+        );
+    }
+
+    #[test]
+    fn daily_times_are_local_to_the_time_zone() {
+        let mut window = nightly("09:00", "17:00");
+        window.timezone = Some("America/New_York".to_string());
+        // EDT is UTC-4
+        assert!(!is_active(&window, at(2026, 6, 1, 12, 59)));
+        assert!(is_active(&window, at(2026, 6, 1, 13, 0)));
+        assert_eq!(
+            active_until(&window, at(2026, 6, 1, 13, 0)),
+            Some(at(2026, 6, 1, 21, 0))
+        );
+    }
+
+    #[test]
+    fn whole_day_windows_run_until_the_matching_days_end() {
+        let mut holidays = WindowRecord::new("holidays".to_string());
+        holidays.dates = vec![
+            NaiveDate::from_ymd_opt(2026, 12, 25).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 12, 26).unwrap(),
+        ];
+        assert!(!is_active(&holidays, at(2026, 12, 24, 23, 59)));
+        assert!(is_active(&holidays, at(2026, 12, 25, 8, 0)));
+        assert_eq!(
+            active_until(&holidays, at(2026, 12, 25, 8, 0)),
+            Some(at(2026, 12, 27, 0, 0))
+        );
+    }
+
+    #[test]
+    fn windows_only_apply_between_their_start_and_end() {
+        let mut freeze = WindowRecord::new("freeze".to_string());
+        freeze.starts_at = Some(at(2026, 6, 1, 0, 0));
+        freeze.ends_at = Some(at(2026, 6, 3, 12, 0));
+
+        assert!(!is_active(&freeze, at(2026, 5, 31, 23, 59)));
+        assert!(is_active(&freeze, at(2026, 6, 2, 0, 0)));
+        assert!(!is_active(&freeze, at(2026, 6, 3, 12, 0)));
+        assert_eq!(
+            active_until(&freeze, at(2026, 6, 2, 0, 0)),
+            Some(at(2026, 6, 3, 12, 0))
+        );
+
+        // A window without any limits stays open until it is removed
+        let always = WindowRecord::new("always".to_string());
+        assert!(is_active(&always, at(2026, 6, 2, 0, 0)));
+        assert_eq!(active_until(&always, at(2026, 6, 2, 0, 0)), None);
+    }
+
+    #[test]
+    fn windows_are_validated() {
+        assert!(validate(&nightly("22:00", "06:00")).is_ok());
+        assert!(validate(&nightly("22:00", "22:00")).is_err());
+
+        let mut handler_only = WindowRecord::new("quiet".to_string());
+        handler_only.handler_name = Some("default".to_string());
+        assert!(validate(&handler_only).is_err());
+
+        let mut backwards = WindowRecord::new("freeze".to_string());
+        backwards.starts_at = Some(at(2026, 6, 2, 0, 0));
+        backwards.ends_at = Some(at(2026, 6, 1, 0, 0));
+        assert!(validate(&backwards).is_err());
+    }
+
+    #[tokio::test]
+    async fn drop_windows_discard_events_of_the_handlers_they_cover() {
+        let db = Database::open(":memory:").unwrap();
+        db.init_schema().await.unwrap();
+        let store = JobStore::new(db);
+
+        let mut window = WindowRecord::new("quiet".to_string());
+        window.event_type = Some("deploy".to_string());
+        window.handler_name = Some("notify".to_string());
+        window.action = WindowAction::Drop;
+        store.create_window(window).await.unwrap();
+
+        assert!(!wait_for_windows(&store, "deploy", Some("notify"), "Handler").await);
+        assert!(wait_for_windows(&store, "deploy", Some("build"), "Handler").await);
+        assert!(wait_for_windows(&store, "backup", Some("notify"), "Handler").await);
+        // Producers only hold back events that every handler would skip
+        assert!(wait_for_windows(&store, "deploy", None, "Timer").await);
+    }
+}
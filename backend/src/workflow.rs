@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use tokio::sync::oneshot::{self, error::RecvError};
+use tokio::task::{Id, JoinSet};
+use tracing::{info, warn};
+
+use crate::consumer::{self, Admission};
+use crate::db::{Event, JobStatus, WorkflowRecord, WorkflowRun};
+use crate::queue::EventSender;
+use crate::store::JobStore;
+use shev_core::{RunStatus, StepCondition, StepRun, StepStatus};
+
+/// Check that a workflow's steps form a DAG of well-formed steps
+pub fn validate(workflow: &WorkflowRecord) -> Result<(), String> {
+    if workflow.name.trim().is_empty() {
+        return Err("The workflow name must not be empty".to_string());
+    }
+    if workflow.steps.is_empty() {
+        return Err("At least one step is required".to_string());
+    }
+
+    let mut names = HashSet::new();
+    for step in &workflow.steps {
+        if step.name.trim().is_empty() {
+            return Err("Step names must not be empty".to_string());
+        }
+        if !names.insert(step.name.as_str()) {
+            return Err(format!("Duplicate step '{}'", step.name));
+        }
+        if step.event_type.trim().is_empty() || step.handler.trim().is_empty() {
+            return Err(format!(
+                "Step '{}' needs an event type and a handler",
+                step.name
+            ));
+        }
+    }
+    for step in &workflow.steps {
+        for dependency in &step.depends_on {
+            if dependency == &step.name {
+                return Err(format!("Step '{}' depends on itself", step.name));
+            }
+            if !names.contains(dependency.as_str()) {
+                return Err(format!(
+                    "Step '{}' depends on unknown step '{}'",
+                    step.name, dependency
+                ));
+            }
+        }
+    }
+
+    // Kahn's algorithm: whatever cannot be ordered is part of a cycle
+    let mut pending: HashMap<&str, usize> = workflow
+        .steps
+        .iter()
+        .map(|s| (s.name.as_str(), s.depends_on.len()))
+        .collect();
+    let mut ready: Vec<&str> = pending
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(name, _)| *name)
+        .collect();
+    while let Some(done) = ready.pop() {
+        pending.remove(done);
+        for step in &workflow.steps {
+            if step.depends_on.iter().any(|d| d == done)
+                && let Some(count) = pending.get_mut(step.name.as_str())
+            {
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(&step.name);
+                }
+            }
+        }
+    }
+    if !pending.is_empty() {
+        let mut cycle: Vec<&str> = pending.into_keys().collect();
+        cycle.sort();
+        return Err(format!(
+            "Steps depend on each other in a cycle: {}",
+            cycle.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Record a new run of the workflow and execute its steps in the background
+pub async fn start_run(
+    store: &JobStore,
+    sender: &EventSender,
+    workflow: WorkflowRecord,
+    context: String,
+) -> Result<WorkflowRun, String> {
+    let run = WorkflowRun::new(workflow.name, context, workflow.steps);
+    store.create_workflow_run(&run).await?;
+    info!("Workflow '{}' started (run: {:?})", run.workflow, run.id);
+
+    let store = store.clone();
+    let sender = sender.clone();
+    let started = run.clone();
+    tokio::spawn(async move {
+        drive(&store, &sender, started).await;
+    });
+
+    Ok(run)
+}
+
+/// Whether a step whose dependencies have all finished gets to run
+fn condition_met(run: &WorkflowRun, step: &StepRun) -> bool {
+    let mut dependencies = run
+        .steps
+        .iter()
+        .filter(|s| step.step.depends_on.contains(&s.step.name));
+    match step.step.condition {
+        StepCondition::Success => dependencies.all(|s| s.status == StepStatus::Completed),
+        StepCondition::Failure => dependencies.any(|s| s.status == StepStatus::Failed),
+        StepCondition::Always => true,
+    }
+}
+
+/// Index of a waiting step whose dependencies have all finished
+fn next_ready(run: &WorkflowRun) -> Option<usize> {
+    run.steps.iter().position(|step| {
+        step.status == StepStatus::Waiting
+            && run
+                .steps
+                .iter()
+                .filter(|s| step.step.depends_on.contains(&s.step.name))
+                .all(|s| s.status.is_finished())
+    })
+}
+
+/// What became of a step's event, from `consumer::offer`
+type StepAdmission = Result<Admission, RecvError>;
+
+/// Start every step as its dependencies finish until all steps are done
+async fn drive(store: &JobStore, sender: &EventSender, mut run: WorkflowRun) {
+    let mut jobs: JoinSet<StepAdmission> = JoinSet::new();
+    // Step index of each task, so a task that panicked still fails its step
+    let mut tasks: HashMap<Id, usize> = HashMap::new();
+
+    loop {
+        while let Some(index) = next_ready(&run) {
+            if !condition_met(&run, &run.steps[index]) {
+                let step = &mut run.steps[index];
+                info!(
+                    "Workflow '{}' skipping step '{}' ({} condition not met)",
+                    run.workflow,
+                    step.step.name,
+                    step.step.condition.as_str()
+                );
+                step.status = StepStatus::Skipped;
+                step.finished_at = Some(Utc::now());
+                continue;
+            }
+            if let Some(admission) = start_step(store, sender, &mut run, index).await {
+                tasks.insert(jobs.spawn(admission).id(), index);
+            }
+        }
+        store.update_workflow_run(&run).await;
+
+        let Some(joined) = jobs.join_next_with_id().await else {
+            break;
+        };
+        let (index, admission) = match joined {
+            Ok((id, admission)) => (tasks.remove(&id), Ok(admission)),
+            Err(e) => (tasks.remove(&e.id()), Err(e)),
+        };
+        let Some(index) = index else {
+            continue;
+        };
+        let step = &mut run.steps[index];
+        step.finished_at = Some(Utc::now());
+        match admission {
+            Ok(Ok(Admission::Ran(job, outcome))) => {
+                step.job_id = Some(job.id);
+                step.status = match outcome.map(|o| o.status) {
+                    Some(JobStatus::Completed) => StepStatus::Completed,
+                    Some(JobStatus::Failed | JobStatus::TimedOut) => StepStatus::Failed,
+                    _ => StepStatus::Cancelled,
+                };
+            }
+            Ok(Ok(Admission::Skipped(job))) => {
+                step.job_id = Some(job.id);
+                step.status = StepStatus::Skipped;
+                step.error = job.error;
+            }
+            Ok(Ok(Admission::Dropped)) => {
+                step.status = StepStatus::Skipped;
+                step.error = Some("Dropped by a blackout window".to_string());
+            }
+            Ok(Ok(Admission::Failed(error))) => {
+                step.status = StepStatus::Failed;
+                step.error = Some(error);
+            }
+            Ok(Err(_)) => {
+                step.status = StepStatus::Skipped;
+                step.error = Some("Suppressed by the handler's debounce or throttle".to_string());
+            }
+            Err(e) => {
+                step.status = StepStatus::Failed;
+                step.error = Some(format!("Step task failed: {}", e));
+            }
+        }
+        match &step.error {
+            Some(error) => warn!(
+                "Workflow '{}' step '{}' {}: {}",
+                run.workflow,
+                step.step.name,
+                step.status.as_str(),
+                error
+            ),
+            None => info!(
+                "Workflow '{}' step '{}' {} (job: {:?})",
+                run.workflow,
+                step.step.name,
+                step.status.as_str(),
+                step.job_id
+            ),
+        }
+    }
+
+    run.status = if run.steps.iter().any(|s| s.status == StepStatus::Failed) {
+        RunStatus::Failed
+    } else if run.steps.iter().any(|s| s.status == StepStatus::Cancelled) {
+        RunStatus::Cancelled
+    } else {
+        RunStatus::Completed
+    };
+    run.finished_at = Some(Utc::now());
+    store.update_workflow_run(&run).await;
+    info!(
+        "Workflow '{}' {} (run: {:?})",
+        run.workflow,
+        run.status.as_str(),
+        run.id
+    );
+}
+
+/// Offer the step's event to its handler the way the consumer offers queued events, so the
+/// handler's filter, debounce, throttle, blackout windows and concurrency limits apply. Fails
+/// the step instead if its handler is gone or paused.
+async fn start_step(
+    store: &JobStore,
+    sender: &EventSender,
+    run: &mut WorkflowRun,
+    index: usize,
+) -> Option<oneshot::Receiver<Admission>> {
+    let step = &mut run.steps[index];
+    step.started_at = Some(Utc::now());
+
+    let handler = match store
+        .get_handler(&step.step.event_type, &step.step.handler)
+        .await
+    {
+        Some(handler) if handler.enabled => handler,
+        found => {
+            let error = format!(
+                "Handler '{}/{}' {}",
+                step.step.event_type,
+                step.step.handler,
+                if found.is_some() {
+                    "is paused"
+                } else {
+                    "not found"
+                }
+            );
+            warn!(
+                "Workflow '{}' step '{}': {}",
+                run.workflow, step.step.name, error
+            );
+            step.status = StepStatus::Failed;
+            step.error = Some(error);
+            step.finished_at = Some(Utc::now());
+            return None;
+        }
+    };
+
+    let mut event = Event::new(step.step.event_type.clone(), run.context.clone());
+    event.env = HashMap::from([
+        ("WORKFLOW".to_string(), run.workflow.clone()),
+        ("WORKFLOW_RUN_ID".to_string(), run.id.to_string()),
+        ("WORKFLOW_STEP".to_string(), step.step.name.clone()),
+    ]);
+    info!(
+        "Workflow '{}' starting step '{}' (event: {:?})",
+        run.workflow, step.step.name, event.id
+    );
+    step.status = StepStatus::Running;
+    let event_id = event.id;
+    let admission = consumer::offer(store, sender, event, handler).await;
+    // The job exists by now unless debounce, throttle or a blackout window holds the event
+    step.job_id = store.get_job_ids_for_event(event_id).await.first().copied();
+    Some(admission)
+}
@@ -1,5 +1,6 @@
 use clap::Subcommand;
 use shev_core::api::{ConfigResponse, UpdateConfigRequest};
+use shev_core::parse_duration_secs;
 
 #[derive(Subcommand)]
 pub enum ConfigAction {
@@ -7,7 +8,7 @@ pub enum ConfigAction {
     Show,
     /// Set a configuration value
     Set {
-        /// Configuration key (port, queue_size)
+        /// Configuration key (port, queue_size, max_concurrent_jobs, idempotency_ttl_secs)
         key: String,
         /// Configuration value
         value: String,
@@ -34,6 +35,8 @@ pub async fn execute(url: &str, action: ConfigAction) -> Result<(), String> {
                 println!("Configuration:");
                 println!("  port: {}", config.port);
                 println!("  queue_size: {}", config.queue_size);
+                println!("  max_concurrent_jobs: {}", config.max_concurrent_jobs);
+                println!("  idempotency_ttl_secs: {}", config.idempotency_ttl_secs);
             } else {
                 let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
@@ -49,6 +52,8 @@ pub async fn execute(url: &str, action: ConfigAction) -> Result<(), String> {
                     UpdateConfigRequest {
                         port: Some(value.clone()),
                         queue_size: None,
+                        max_concurrent_jobs: None,
+                        idempotency_ttl_secs: None,
                     }
                 }
                 "queue_size" => {
@@ -58,11 +63,34 @@ pub async fn execute(url: &str, action: ConfigAction) -> Result<(), String> {
                     UpdateConfigRequest {
                         port: None,
                         queue_size: Some(value.clone()),
+                        max_concurrent_jobs: None,
+                        idempotency_ttl_secs: None,
+                    }
+                }
+                "max_concurrent_jobs" => {
+                    let _: usize = value
+                        .parse()
+                        .map_err(|_| format!("Invalid max_concurrent_jobs: {}", value))?;
+                    UpdateConfigRequest {
+                        port: None,
+                        queue_size: None,
+                        max_concurrent_jobs: Some(value.clone()),
+                        idempotency_ttl_secs: None,
+                    }
+                }
+                "idempotency_ttl_secs" => {
+                    // Also accepts durations such as 24h
+                    let secs = parse_duration_secs(&value)?;
+                    UpdateConfigRequest {
+                        port: None,
+                        queue_size: None,
+                        max_concurrent_jobs: None,
+                        idempotency_ttl_secs: Some(secs.to_string()),
                     }
                 }
                 _ => {
                     return Err(format!(
-                        "Unknown config key '{}'. Valid keys: port, queue_size",
+                        "Unknown config key '{}'. Valid keys: port, queue_size, max_concurrent_jobs, idempotency_ttl_secs",
                         key
                     ));
                 }
@@ -77,8 +105,10 @@ pub async fn execute(url: &str, action: ConfigAction) -> Result<(), String> {
 
             if resp.status().is_success() {
                 println!("Set {} = {}", key, value);
-                println!();
-                println!("Note: Restart the server for changes to take effect");
+                if key == "port" || key == "queue_size" {
+                    println!();
+                    println!("Note: Restart the server for changes to take effect");
+                }
             } else {
                 let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
@@ -10,6 +10,9 @@ pub enum EventAction {
         /// Context to pass to handler
         #[arg(long, short, default_value = "")]
         context: String,
+        /// Idempotency key: triggering again with the same key does not queue the event twice
+        #[arg(long, short)]
+        key: Option<String>,
     },
 }
 
@@ -18,11 +21,13 @@ pub async fn execute(url: &str, action: EventAction) -> Result<(), String> {
         EventAction::Trigger {
             event_type,
             context,
+            key,
         } => {
             let client = reqwest::Client::new();
             let request = TriggerEventRequest {
                 event_type: event_type.clone(),
                 context,
+                idempotency_key: key,
             };
 
             let resp = client
@@ -38,15 +43,20 @@ pub async fn execute(url: &str, action: EventAction) -> Result<(), String> {
                     .await
                     .map_err(|e| format!("Failed to parse response: {}", e))?;
 
-                if body.triggered {
-                    println!("Event '{}' triggered successfully", event_type);
-                    println!("  {}", body.message);
+                if body.duplicate {
+                    println!("Event '{}' was already triggered with this key", event_type);
                 } else {
-                    println!("Event '{}' was not triggered", event_type);
-                    println!("  {}", body.message);
+                    println!("Event '{}' triggered successfully", event_type);
+                }
+                println!("  {}", body.message);
+                println!("  Event ID: {}", body.event.id);
+                for job_id in &body.job_ids {
+                    println!("  Job ID: {}", job_id);
                 }
             } else {
-                return Err(format!("Server returned error: {}", resp.status()));
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
             }
         }
     }
@@ -1,7 +1,13 @@
 use std::collections::HashMap;
 
-use clap::Subcommand;
-use shev_core::api::{CreateHandlerRequest, HandlerResponse, UpdateHandlerRequest};
+use clap::{Args, Subcommand};
+use shev_core::api::{
+    CreateHandlerRequest, FilterTestRequest, FilterTestResult, HandlerResponse,
+    UpdateHandlerRequest,
+};
+use shev_core::{
+    Backoff, DEFAULT_HANDLER_NAME, RetryPolicy, ThrottleMode, ThrottlePolicy, parse_duration_secs,
+};
 
 #[derive(Subcommand)]
 pub enum HandlerAction {
@@ -9,6 +15,9 @@ pub enum HandlerAction {
     Add {
         /// Event type name
         event_type: String,
+        /// Handler name (several handlers can share one event type)
+        #[arg(long, short, default_value = DEFAULT_HANDLER_NAME)]
+        name: String,
         /// Shell to use (pwsh, bash, sh)
         #[arg(long, short)]
         shell: String,
@@ -21,11 +30,36 @@ pub enum HandlerAction {
         /// Set environment variable (can be used multiple times): KEY=VALUE
         #[arg(long, short)]
         env: Option<Vec<String>>,
+        /// Maximum number of jobs of this handler running at once (1 = one at a time)
+        #[arg(long)]
+        max_concurrency: Option<u32>,
+        #[command(flatten)]
+        retry: RetryArgs,
+        /// Event type to trigger when a job completes
+        #[arg(long)]
+        on_success: Option<String>,
+        /// Event type to trigger when a job fails (after its retries)
+        #[arg(long)]
+        on_failure: Option<String>,
+        /// Event type to trigger when a job times out (default: the --on-failure event)
+        #[arg(long)]
+        on_timeout: Option<String>,
+        /// Only run for events whose context matches, e.g. 'context.branch == "main"'
+        #[arg(long, short)]
+        filter: Option<String>,
+        /// Wait until no event arrived for this long (e.g. 30s), then run once with the last one
+        #[arg(long)]
+        debounce: Option<String>,
+        #[command(flatten)]
+        throttle: ThrottleArgs,
     },
     /// Update an existing handler (generates new UUID)
     Update {
         /// Event type name
         event_type: String,
+        /// Handler name
+        #[arg(long, short, default_value = DEFAULT_HANDLER_NAME)]
+        name: String,
         /// Shell to use (pwsh, bash, sh)
         #[arg(long, short)]
         shell: Option<String>,
@@ -41,21 +75,235 @@ pub enum HandlerAction {
         /// Clear all environment variables
         #[arg(long)]
         clear_env: bool,
+        /// Maximum number of jobs of this handler running at once (0 removes the limit)
+        #[arg(long)]
+        max_concurrency: Option<u32>,
+        #[command(flatten)]
+        retry: RetryArgs,
+        /// Remove the retry policy
+        #[arg(long, conflicts_with_all = ["max_attempts", "backoff", "retry_delay", "retry_on"])]
+        no_retry: bool,
+        /// Event type to trigger when a job completes ("" removes it)
+        #[arg(long)]
+        on_success: Option<String>,
+        /// Event type to trigger when a job fails ("" removes it)
+        #[arg(long)]
+        on_failure: Option<String>,
+        /// Event type to trigger when a job times out ("" removes it)
+        #[arg(long)]
+        on_timeout: Option<String>,
+        /// Only run for events whose context matches ("" removes the filter)
+        #[arg(long, short)]
+        filter: Option<String>,
+        /// Quiet period before running once with the last event (0 removes it)
+        #[arg(long)]
+        debounce: Option<String>,
+        #[command(flatten)]
+        throttle: ThrottleArgs,
+        /// Remove the throttle
+        #[arg(long, conflicts_with_all = ["throttle", "throttle_mode"])]
+        no_throttle: bool,
+    },
+    /// Pause a handler (events create no jobs for it)
+    Pause {
+        /// Event type name
+        event_type: String,
+        /// Handler name
+        #[arg(long, short, default_value = DEFAULT_HANDLER_NAME)]
+        name: String,
+    },
+    /// Resume a paused handler
+    Resume {
+        /// Event type name
+        event_type: String,
+        /// Handler name
+        #[arg(long, short, default_value = DEFAULT_HANDLER_NAME)]
+        name: String,
     },
     /// Remove a handler
     Remove {
         /// Event type name
         event_type: String,
+        /// Handler name
+        #[arg(long, short, default_value = DEFAULT_HANDLER_NAME)]
+        name: String,
     },
     /// List all handlers
-    List,
+    List {
+        /// Only list handlers for this event type
+        event_type: Option<String>,
+    },
     /// Show details of a handler
     Show {
         /// Event type name
         event_type: String,
+        /// Handler name
+        #[arg(long, short, default_value = DEFAULT_HANDLER_NAME)]
+        name: String,
+    },
+    /// Try a filter, or the filters of an event type's handlers, against a sample context
+    FilterTest {
+        /// Event type name
+        event_type: String,
+        /// Filter expression to try (default: the handlers' filters)
+        #[arg(long, short)]
+        filter: Option<String>,
+        /// Sample event context
+        #[arg(long, short, default_value = "")]
+        context: String,
     },
 }
 
+#[derive(Args)]
+pub struct RetryArgs {
+    /// Retry failed jobs: total attempts including the first run
+    #[arg(long)]
+    max_attempts: Option<u32>,
+    /// Backoff between attempts (fixed, exponential)
+    #[arg(long)]
+    backoff: Option<String>,
+    /// Delay before the first retry in seconds
+    #[arg(long)]
+    retry_delay: Option<u32>,
+    /// Only retry on these exit codes (comma-separated, default: any failure)
+    #[arg(long, value_delimiter = ',')]
+    retry_on: Option<Vec<i32>>,
+}
+
+impl RetryArgs {
+    fn is_empty(&self) -> bool {
+        self.max_attempts.is_none()
+            && self.backoff.is_none()
+            && self.retry_delay.is_none()
+            && self.retry_on.is_none()
+    }
+
+    /// Build a retry policy from the flags given, on top of an existing one
+    fn into_policy(self, existing: Option<RetryPolicy>) -> Result<Option<RetryPolicy>, String> {
+        if self.is_empty() {
+            return Ok(existing);
+        }
+
+        let mut policy = existing.unwrap_or(RetryPolicy {
+            max_attempts: 1,
+            backoff: Backoff::Fixed,
+            delay_secs: 0,
+            retry_on: Vec::new(),
+        });
+        if let Some(max_attempts) = self.max_attempts {
+            policy.max_attempts = max_attempts;
+        }
+        if let Some(backoff) = self.backoff {
+            policy.backoff = Backoff::from_str(&backoff).ok_or_else(|| {
+                format!("Invalid backoff '{}', use fixed or exponential", backoff)
+            })?;
+        }
+        if let Some(delay) = self.retry_delay {
+            policy.delay_secs = delay;
+        }
+        if let Some(retry_on) = self.retry_on {
+            policy.retry_on = retry_on;
+        }
+        Ok(Some(policy))
+    }
+}
+
+#[derive(Args)]
+pub struct ThrottleArgs {
+    /// Start at most N jobs per window, e.g. 5/1m
+    #[arg(long)]
+    throttle: Option<String>,
+    /// What happens to events beyond the throttle (drop, coalesce = run the last one later)
+    #[arg(long)]
+    throttle_mode: Option<String>,
+}
+
+impl ThrottleArgs {
+    fn is_empty(&self) -> bool {
+        self.throttle.is_none() && self.throttle_mode.is_none()
+    }
+
+    /// Build a throttle policy from the flags given, on top of an existing one
+    fn into_policy(
+        self,
+        existing: Option<ThrottlePolicy>,
+    ) -> Result<Option<ThrottlePolicy>, String> {
+        if self.is_empty() {
+            return Ok(existing);
+        }
+
+        let mut policy = match (self.throttle, existing) {
+            (Some(throttle), existing) => {
+                let (max_runs, window) = throttle.split_once('/').ok_or_else(|| {
+                    format!(
+                        "Invalid throttle '{}', use RUNS/WINDOW (e.g. 5/1m)",
+                        throttle
+                    )
+                })?;
+                ThrottlePolicy {
+                    max_runs: max_runs
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid number of runs '{}'", max_runs))?,
+                    window_secs: parse_duration_secs(window)?,
+                    mode: existing.map(|p| p.mode).unwrap_or_default(),
+                }
+            }
+            (None, Some(existing)) => existing,
+            (None, None) => return Err("--throttle-mode needs --throttle".to_string()),
+        };
+        if let Some(mode) = self.throttle_mode {
+            policy.mode = ThrottleMode::from_str(&mode)
+                .ok_or_else(|| format!("Invalid throttle mode '{}', use drop or coalesce", mode))?;
+        }
+        Ok(Some(policy))
+    }
+}
+
+fn format_throttle(policy: &ThrottlePolicy) -> String {
+    format!(
+        "at most {} jobs per {}s, {} the rest",
+        policy.max_runs,
+        policy.window_secs,
+        policy.mode.as_str()
+    )
+}
+
+fn format_retry(policy: &RetryPolicy) -> String {
+    let mut text = format!(
+        "up to {} attempts, {} backoff from {}s",
+        policy.max_attempts,
+        policy.backoff.as_str(),
+        policy.delay_secs
+    );
+    if !policy.retry_on.is_empty() {
+        let codes: Vec<String> = policy.retry_on.iter().map(|c| c.to_string()).collect();
+        text = format!("{}, on exit codes {}", text, codes.join(","));
+    }
+    text
+}
+
+async fn fetch_handler(
+    client: &reqwest::Client,
+    url: &str,
+    event_type: &str,
+    name: &str,
+) -> Result<Option<HandlerResponse>, String> {
+    let resp = client
+        .get(format!("{}/handlers/{}/{}", url, event_type, name))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+    if !resp.status().is_success() {
+        return Ok(None);
+    }
+    resp.json()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
 fn parse_env_vars(env: Option<Vec<String>>) -> Result<HashMap<String, String>, String> {
     let mut map = HashMap::new();
     if let Some(env_vars) = env {
@@ -70,14 +318,47 @@ fn parse_env_vars(env: Option<Vec<String>>) -> Result<HashMap<String, String>, S
     Ok(map)
 }
 
+/// An empty follow-up event type or filter removes it
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() { None } else { Some(value) }
+}
+
 fn print_handler(handler: &HandlerResponse) {
     println!("  ID: {}", handler.id);
     println!("  Event type: {}", handler.event_type);
+    println!("  Name: {}", handler.name);
     println!("  Shell: {}", handler.shell);
     println!("  Command: {}", handler.command);
     if let Some(t) = handler.timeout {
         println!("  Timeout: {}s", t);
     }
+    if !handler.enabled {
+        println!("  Paused: yes");
+    }
+    if let Some(m) = handler.max_concurrency {
+        println!("  Max concurrency: {}", m);
+    }
+    if let Some(ref retry) = handler.retry {
+        println!("  Retry: {}", format_retry(retry));
+    }
+    if let Some(ref event_type) = handler.on_success {
+        println!("  On success: {}", event_type);
+    }
+    if let Some(ref event_type) = handler.on_failure {
+        println!("  On failure: {}", event_type);
+    }
+    if let Some(ref event_type) = handler.on_timeout {
+        println!("  On timeout: {}", event_type);
+    }
+    if let Some(ref filter) = handler.filter {
+        println!("  Filter: {}", filter);
+    }
+    if let Some(secs) = handler.debounce_secs {
+        println!("  Debounce: {}s", secs);
+    }
+    if let Some(ref throttle) = handler.throttle {
+        println!("  Throttle: {}", format_throttle(throttle));
+    }
     if !handler.env.is_empty() {
         println!("  Environment:");
         for (k, v) in &handler.env {
@@ -86,24 +367,80 @@ fn print_handler(handler: &HandlerResponse) {
     }
 }
 
+async fn set_enabled(
+    client: &reqwest::Client,
+    url: &str,
+    event_type: &str,
+    name: &str,
+    enabled: bool,
+) -> Result<(), String> {
+    let action = if enabled { "resume" } else { "pause" };
+    let resp = client
+        .post(format!(
+            "{}/handlers/{}/{}/{}",
+            url, event_type, name, action
+        ))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+    if resp.status().is_success() {
+        let handler: HandlerResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        println!(
+            "Handler '{}/{}' {}",
+            handler.event_type,
+            handler.name,
+            if enabled { "resumed" } else { "paused" }
+        );
+    } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        println!("Handler '{}/{}' not found", event_type, name);
+    } else {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Server returned error {}: {}", status, body));
+    }
+    Ok(())
+}
+
 pub async fn execute(url: &str, action: HandlerAction) -> Result<(), String> {
     let client = reqwest::Client::new();
 
     match action {
         HandlerAction::Add {
             event_type,
+            name,
             shell,
             command,
             timeout,
             env,
+            max_concurrency,
+            retry,
+            on_success,
+            on_failure,
+            on_timeout,
+            filter,
+            debounce,
+            throttle,
         } => {
             let env_map = parse_env_vars(env)?;
             let request = CreateHandlerRequest {
                 event_type,
+                name,
                 shell,
                 command,
                 timeout,
                 env: env_map,
+                retry: retry.into_policy(None)?,
+                max_concurrency,
+                on_success,
+                on_failure,
+                on_timeout,
+                filter,
+                debounce_secs: debounce.as_deref().map(parse_duration_secs).transpose()?,
+                throttle: throttle.into_policy(None)?,
             };
 
             let resp = client
@@ -128,55 +465,79 @@ pub async fn execute(url: &str, action: HandlerAction) -> Result<(), String> {
         }
         HandlerAction::Update {
             event_type,
+            name,
             shell,
             command,
             timeout,
             env,
             clear_env,
+            max_concurrency,
+            retry,
+            no_retry,
+            on_success,
+            on_failure,
+            on_timeout,
+            filter,
+            debounce,
+            throttle,
+            no_throttle,
         } => {
+            let existing = if env.is_some() || !retry.is_empty() || !throttle.is_empty() {
+                fetch_handler(&client, url, &event_type, &name).await?
+            } else {
+                None
+            };
+
             let env_map = if clear_env {
                 Some(HashMap::new())
             } else if env.is_some() {
-                // Fetch existing handler to merge env vars
-                let existing = client
-                    .get(format!("{}/handlers", url))
-                    .send()
-                    .await
-                    .map_err(|e| format!("Failed to connect to server: {}", e))?;
-
-                if existing.status().is_success() {
-                    let handlers: Vec<HandlerResponse> = existing
-                        .json()
-                        .await
-                        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-                    let current_env = handlers
-                        .iter()
-                        .find(|h| h.event_type == event_type)
-                        .map(|h| h.env.clone())
-                        .unwrap_or_default();
-
-                    let mut new_env = current_env;
-                    for (k, v) in parse_env_vars(env)? {
-                        new_env.insert(k, v);
-                    }
-                    Some(new_env)
-                } else {
-                    Some(parse_env_vars(env)?)
+                // Merge with the existing handler's env vars
+                let mut new_env = existing.as_ref().map(|h| h.env.clone()).unwrap_or_default();
+                for (k, v) in parse_env_vars(env)? {
+                    new_env.insert(k, v);
                 }
+                Some(new_env)
             } else {
                 None
             };
 
+            let retry = if no_retry {
+                Some(None)
+            } else if retry.is_empty() {
+                None
+            } else {
+                Some(retry.into_policy(existing.as_ref().and_then(|h| h.retry.clone()))?)
+            };
+
+            let throttle = if no_throttle {
+                Some(None)
+            } else if throttle.is_empty() {
+                None
+            } else {
+                Some(throttle.into_policy(existing.and_then(|h| h.throttle))?)
+            };
+
             let request = UpdateHandlerRequest {
                 shell,
                 command,
                 timeout: timeout.map(Some),
                 env: env_map,
+                retry,
+                max_concurrency: max_concurrency.map(|m| if m == 0 { None } else { Some(m) }),
+                on_success: on_success.map(non_empty),
+                on_failure: on_failure.map(non_empty),
+                on_timeout: on_timeout.map(non_empty),
+                filter: filter.map(non_empty),
+                debounce_secs: debounce
+                    .as_deref()
+                    .map(parse_duration_secs)
+                    .transpose()?
+                    .map(|secs| if secs == 0 { None } else { Some(secs) }),
+                throttle,
             };
 
             let resp = client
-                .put(format!("{}/handlers/{}", url, event_type))
+                .put(format!("{}/handlers/{}/{}", url, event_type, name))
                 .json(&request)
                 .send()
                 .await
@@ -195,26 +556,37 @@ pub async fn execute(url: &str, action: HandlerAction) -> Result<(), String> {
                 return Err(format!("Server returned error {}: {}", status, body));
             }
         }
-        HandlerAction::Remove { event_type } => {
+        HandlerAction::Pause { event_type, name } => {
+            set_enabled(&client, url, &event_type, &name, false).await?;
+        }
+        HandlerAction::Resume { event_type, name } => {
+            set_enabled(&client, url, &event_type, &name, true).await?;
+        }
+        HandlerAction::Remove { event_type, name } => {
             let resp = client
-                .delete(format!("{}/handlers/{}", url, event_type))
+                .delete(format!("{}/handlers/{}/{}", url, event_type, name))
                 .send()
                 .await
                 .map_err(|e| format!("Failed to connect to server: {}", e))?;
 
             if resp.status().is_success() {
-                println!("Handler '{}' removed", event_type);
+                println!("Handler '{}/{}' removed", event_type, name);
             } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
-                println!("Handler '{}' not found", event_type);
+                println!("Handler '{}/{}' not found", event_type, name);
             } else {
                 let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
                 return Err(format!("Server returned error {}: {}", status, body));
             }
         }
-        HandlerAction::List => {
+        HandlerAction::List { event_type } => {
+            let mut request_url = format!("{}/handlers", url);
+            if let Some(ref event_type) = event_type {
+                request_url = format!("{}?event_type={}", request_url, event_type);
+            }
+
             let resp = client
-                .get(format!("{}/handlers", url))
+                .get(&request_url)
                 .send()
                 .await
                 .map_err(|e| format!("Failed to connect to server: {}", e))?;
@@ -229,18 +601,23 @@ pub async fn execute(url: &str, action: HandlerAction) -> Result<(), String> {
                     println!("No handlers configured");
                 } else {
                     println!(
-                        "{:<20} {:<8} {:<10} {}",
-                        "EVENT_TYPE", "SHELL", "TIMEOUT", "ID"
+                        "{:<20} {:<16} {:<8} {:<10} {:<8} ID",
+                        "EVENT_TYPE", "NAME", "SHELL", "TIMEOUT", "STATUS"
                     );
-                    println!("{}", "-".repeat(70));
+                    println!("{}", "-".repeat(100));
                     for h in handlers {
                         let timeout = h
                             .timeout
                             .map(|t| format!("{}s", t))
                             .unwrap_or_else(|| "-".to_string());
                         println!(
-                            "{:<20} {:<8} {:<10} {}",
-                            h.event_type, h.shell, timeout, h.id
+                            "{:<20} {:<16} {:<8} {:<10} {:<8} {}",
+                            h.event_type,
+                            h.name,
+                            h.shell,
+                            timeout,
+                            if h.enabled { "active" } else { "paused" },
+                            h.id
                         );
                     }
                 }
@@ -250,25 +627,61 @@ pub async fn execute(url: &str, action: HandlerAction) -> Result<(), String> {
                 return Err(format!("Server returned error {}: {}", status, body));
             }
         }
-        HandlerAction::Show { event_type } => {
+        HandlerAction::Show { event_type, name } => {
             let resp = client
-                .get(format!("{}/handlers", url))
+                .get(format!("{}/handlers/{}/{}", url, event_type, name))
                 .send()
                 .await
                 .map_err(|e| format!("Failed to connect to server: {}", e))?;
 
             if resp.status().is_success() {
-                let handlers: Vec<HandlerResponse> = resp
+                let h: HandlerResponse = resp
                     .json()
                     .await
                     .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Handler: {}/{}", h.event_type, h.name);
+                print_handler(&h);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Handler '{}/{}' not found", event_type, name);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        HandlerAction::FilterTest {
+            event_type,
+            filter,
+            context,
+        } => {
+            let request = FilterTestRequest { filter, context };
 
-                if let Some(h) = handlers.iter().find(|h| h.event_type == event_type) {
-                    println!("Handler: {}", h.event_type);
-                    print_handler(h);
-                } else {
-                    println!("Handler '{}' not found", event_type);
+            let resp = client
+                .post(format!("{}/handlers/{}/filter-test", url, event_type))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let results: Vec<FilterTestResult> = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                for r in results {
+                    let verdict = if r.matches { "match" } else { "no match" };
+                    match (r.handler, r.filter) {
+                        (Some(handler), Some(filter)) => {
+                            println!("{:<20} {:<10} {}", handler, verdict, filter)
+                        }
+                        (Some(handler), None) => {
+                            println!("{:<20} {:<10} (no filter)", handler, verdict)
+                        }
+                        (None, _) => println!("{}", verdict),
+                    }
                 }
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("No handlers for event type '{}'", event_type);
             } else {
                 let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
@@ -0,0 +1,654 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::process::Command;
+
+use chrono::{Datelike, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use clap::Subcommand;
+use serde::Serialize;
+use shev_core::api::{CreateHandlerRequest, CreateScheduleRequest, CreateTimerRequest};
+use shev_core::{DEFAULT_HANDLER_NAME, OverlapPolicy, ShellType};
+
+use super::{parse_overlap, slugify};
+
+#[derive(Subcommand)]
+pub enum ImportAction {
+    /// Create handlers plus timers or schedules from a crontab
+    Crontab {
+        /// Crontab file, `-` for stdin (default: the output of `crontab -l`)
+        file: Option<String>,
+        /// Prefix for the event types, which are derived from each command
+        #[arg(long, default_value = "cron-")]
+        prefix: String,
+        /// IANA time zone the crontab's times are in, unless it sets CRON_TZ (default: UTC)
+        #[arg(long)]
+        tz: Option<String>,
+        /// The file is a system crontab (e.g. /etc/crontab) with a user column
+        #[arg(long)]
+        system: bool,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short, default_value = "allow")]
+        overlap: String,
+        /// Print what would be created without creating anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// What makes an imported command run
+enum Trigger {
+    Timer(CreateTimerRequest),
+    Schedule(CreateScheduleRequest),
+    /// `@reboot` lines, which shev has no equivalent for
+    Manual,
+}
+
+/// A crontab line translated into a handler and its trigger
+struct Entry {
+    line: usize,
+    handler: CreateHandlerRequest,
+    trigger: Trigger,
+    notes: Vec<String>,
+}
+
+/// A crontab line that could not be translated or created
+struct Skipped {
+    line: usize,
+    text: String,
+    reason: String,
+}
+
+#[derive(Default)]
+struct Plan {
+    entries: Vec<Entry>,
+    skipped: Vec<Skipped>,
+}
+
+/// Environment and settings accumulated while reading the crontab, applied to the lines that
+/// follow them like cron does
+struct CrontabState {
+    shell: String,
+    timezone: Option<String>,
+    env: HashMap<String, String>,
+}
+
+fn read_crontab(file: Option<&str>) -> Result<String, String> {
+    match file {
+        Some("-") => {
+            let mut content = String::new();
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            Ok(content)
+        }
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))
+        }
+        None => {
+            let output = Command::new("crontab")
+                .arg("-l")
+                .output()
+                .map_err(|e| format!("Failed to run crontab -l: {}", e))?;
+            if !output.status.success() {
+                return Err(format!(
+                    "crontab -l failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+    }
+}
+
+/// Split an environment line like `PATH=/usr/bin` into name and value
+fn parse_env_line(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.split_once('=')?;
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return None;
+    }
+    let value = value.trim();
+    let value = ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+        .unwrap_or(value);
+    Some((name.to_string(), value.to_string()))
+}
+
+/// Split off the first `count` whitespace-separated fields, returning them and the rest
+fn split_fields(line: &str, count: usize) -> Option<(Vec<&str>, &str)> {
+    let mut fields = Vec::with_capacity(count);
+    let mut rest = line.trim_start();
+    for _ in 0..count {
+        let end = rest.find(char::is_whitespace)?;
+        fields.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    Some((fields, rest))
+}
+
+/// Undo cron's `\%` escaping. An unescaped `%` starts the command's stdin, which shev cannot
+/// provide.
+fn unescape_command(command: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'%') => {
+                result.push('%');
+                chars.next();
+            }
+            '%' => {
+                return Err(
+                    "a '%' in the command passes stdin to it, which shev cannot do; \
+                    escape it as '\\%' if it is meant literally"
+                        .to_string(),
+                );
+            }
+            _ => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+/// Name the event type after the program the command runs, e.g. `cron-backup-sh` for
+/// `/usr/local/bin/backup.sh --full`
+fn event_type_for(command: &str, prefix: &str, taken: &mut HashSet<String>) -> String {
+    let program = command
+        .split_whitespace()
+        .find(|word| !word.contains('='))
+        .map(|word| word.rsplit('/').next().unwrap_or(word))
+        .unwrap_or_default();
+    let slug = match slugify(program) {
+        slug if slug.is_empty() => "job".to_string(),
+        slug => slug,
+    };
+    let base = format!("{}{}", prefix, slug);
+    let mut event_type = base.clone();
+    let mut n = 2;
+    while !taken.insert(event_type.clone()) {
+        event_type = format!("{}-{}", base, n);
+        n += 1;
+    }
+    event_type
+}
+
+/// The interval of time fields that fire at fixed steps, so they can become an aligned timer,
+/// e.g. 900 seconds for `*/15 * * * *`
+fn fixed_interval(fields: &[&str], timezone: Option<&str>) -> Option<u32> {
+    let step = |field: &str, max: u32| -> Option<u32> {
+        match field {
+            "*" => Some(1),
+            _ => field
+                .strip_prefix("*/")?
+                .parse::<u32>()
+                .ok()
+                .filter(|n| *n > 0 && max.is_multiple_of(*n)),
+        }
+    };
+    if fields[2..] != ["*", "*", "*"] {
+        return None;
+    }
+    let utc = timezone.is_none_or(|tz| tz == "UTC");
+    match (fields[0], fields[1]) {
+        // Alignment counts from midnight UTC, so hour steps only line up in UTC
+        ("0", hour) if utc => step(hour, 24).map(|n| n * 3600),
+        // and minute steps only where the offset from UTC is a whole number of steps
+        (minute, "*") => step(minute, 60)
+            .filter(|n| utc || timezone.is_some_and(|tz| offset_is_multiple_of(tz, *n)))
+            .map(|n| n * 60),
+        _ => None,
+    }
+}
+
+/// Whether the zone's offset from UTC is a multiple of `minutes` all year round
+fn offset_is_multiple_of(timezone: &str, minutes: u32) -> bool {
+    let Ok(tz) = timezone.parse::<Tz>() else {
+        return false;
+    };
+    let year = Utc::now().year();
+    // The first of every month catches both the standard and the daylight saving offset
+    (1..=12).all(|month| {
+        let Some(date) = NaiveDate::from_ymd_opt(year, month, 1) else {
+            return false;
+        };
+        let offset = tz.offset_from_utc_date(&date).fix().local_minus_utc();
+        (offset / 60).rem_euclid(minutes as i32) == 0
+    })
+}
+
+fn parse_crontab(
+    content: &str,
+    prefix: &str,
+    timezone: Option<String>,
+    system: bool,
+    overlap: OverlapPolicy,
+) -> Plan {
+    let mut plan = Plan::default();
+    let mut state = CrontabState {
+        shell: "sh".to_string(),
+        timezone,
+        env: HashMap::new(),
+    };
+    let mut taken = HashSet::new();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let skip = |reason: String| Skipped {
+            line,
+            text: text.to_string(),
+            reason,
+        };
+
+        if let Some((name, value)) = parse_env_line(text) {
+            match name.as_str() {
+                "SHELL" => {
+                    let shell = value.rsplit('/').next().unwrap_or_default();
+                    match ShellType::from_str(shell) {
+                        Some(shell) => state.shell = shell.as_str().to_string(),
+                        None => plan.skipped.push(skip(format!(
+                            "shell '{}' is not supported (use sh, bash or pwsh); using {}",
+                            value, state.shell
+                        ))),
+                    }
+                }
+                "CRON_TZ" | "TZ" => state.timezone = Some(value),
+                "MAILTO" | "MAILFROM" => plan.skipped.push(skip(
+                    "shev does not mail job output; check it with `shev job show`".to_string(),
+                )),
+                _ => {
+                    state.env.insert(name, value);
+                }
+            }
+            continue;
+        }
+
+        let time_fields = if text.starts_with('@') { 1 } else { 5 };
+        let field_count = time_fields + usize::from(system);
+        let Some((fields, command)) = split_fields(text, field_count) else {
+            plan.skipped
+                .push(skip("not a valid crontab entry".to_string()));
+            continue;
+        };
+        if command.is_empty() {
+            plan.skipped
+                .push(skip("the entry has no command".to_string()));
+            continue;
+        }
+        let command = match unescape_command(command) {
+            Ok(command) => command,
+            Err(reason) => {
+                plan.skipped.push(skip(reason));
+                continue;
+            }
+        };
+
+        let mut notes = Vec::new();
+        if system {
+            notes.push(format!(
+                "ran as user '{}'; shev runs it as the backend's user",
+                fields[time_fields]
+            ));
+        }
+        let event_type = event_type_for(&command, prefix, &mut taken);
+
+        let trigger = match fields[0] {
+            "@reboot" => {
+                notes.push(format!(
+                    "shev has no startup trigger; run `shev event trigger {}` at boot instead",
+                    event_type
+                ));
+                Trigger::Manual
+            }
+            "@yearly" | "@annually" | "@monthly" | "@weekly" | "@daily" | "@midnight"
+            | "@hourly" => {
+                let cron = match fields[0] {
+                    "@midnight" => "@daily",
+                    alias => alias,
+                };
+                Trigger::Schedule(schedule_request(&event_type, cron, &state, overlap))
+            }
+            alias if alias.starts_with('@') => {
+                taken.remove(&event_type);
+                plan.skipped
+                    .push(skip(format!("unknown schedule '{}'", alias)));
+                continue;
+            }
+            _ => match fixed_interval(&fields[..5], state.timezone.as_deref()) {
+                Some(interval_secs) => Trigger::Timer(CreateTimerRequest {
+                    event_type: event_type.clone(),
+                    interval_secs,
+                    context: String::new(),
+                    overlap,
+                    align: true,
+                    jitter_secs: 0,
+                    start_at: None,
+                    end_at: None,
+                    max_fires: None,
+                }),
+                None => Trigger::Schedule(schedule_request(
+                    &event_type,
+                    &fields[..5].join(" "),
+                    &state,
+                    overlap,
+                )),
+            },
+        };
+
+        plan.entries.push(Entry {
+            line,
+            handler: CreateHandlerRequest {
+                event_type,
+                name: DEFAULT_HANDLER_NAME.to_string(),
+                shell: state.shell.clone(),
+                command,
+                timeout: None,
+                env: state.env.clone(),
+                retry: None,
+                max_concurrency: None,
+                on_success: None,
+                on_failure: None,
+                on_timeout: None,
+                filter: None,
+                debounce_secs: None,
+                throttle: None,
+            },
+            trigger,
+            notes,
+        });
+    }
+    plan
+}
+
+fn schedule_request(
+    event_type: &str,
+    cron: &str,
+    state: &CrontabState,
+    overlap: OverlapPolicy,
+) -> CreateScheduleRequest {
+    CreateScheduleRequest {
+        event_type: event_type.to_string(),
+        scheduled_time: None,
+        context: String::new(),
+        periodic: false,
+        overlap,
+        cron: Some(cron.to_string()),
+        timezone: state.timezone.clone(),
+        misfire: Default::default(),
+        rrule: None,
+        exdates: Vec::new(),
+    }
+}
+
+fn describe_trigger(trigger: &Trigger) -> String {
+    match trigger {
+        Trigger::Timer(timer) => format!("timer every {}s (aligned)", timer.interval_secs),
+        Trigger::Schedule(schedule) => format!(
+            "schedule '{}' ({})",
+            schedule.cron.as_deref().unwrap_or_default(),
+            schedule.timezone.as_deref().unwrap_or("UTC")
+        ),
+        Trigger::Manual => "no trigger".to_string(),
+    }
+}
+
+async fn post<T: Serialize>(client: &reqwest::Client, url: &str, body: &T) -> Result<(), String> {
+    let resp = client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        Err(format!("Server returned error {}: {}", status, body))
+    }
+}
+
+async fn create_entry(client: &reqwest::Client, url: &str, entry: &Entry) -> Result<(), String> {
+    post(client, &format!("{}/handlers", url), &entry.handler).await?;
+    let result = match &entry.trigger {
+        Trigger::Timer(timer) => post(client, &format!("{}/timers", url), timer).await,
+        Trigger::Schedule(schedule) => post(client, &format!("{}/schedules", url), schedule).await,
+        Trigger::Manual => Ok(()),
+    };
+    if let Err(e) = result {
+        // Do not leave a handler behind that nothing triggers
+        let _ = client
+            .delete(format!(
+                "{}/handlers/{}/{}",
+                url, entry.handler.event_type, entry.handler.name
+            ))
+            .send()
+            .await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+pub async fn execute(url: &str, action: ImportAction) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    match action {
+        ImportAction::Crontab {
+            file,
+            prefix,
+            tz,
+            system,
+            overlap,
+            dry_run,
+        } => {
+            let content = read_crontab(file.as_deref())?;
+            let mut plan = parse_crontab(&content, &prefix, tz, system, parse_overlap(&overlap)?);
+
+            let mut imported = 0;
+            for entry in &plan.entries {
+                let summary = format!(
+                    "line {}: '{}' runs `{}` via {}, {}",
+                    entry.line,
+                    entry.handler.event_type,
+                    entry.handler.command,
+                    entry.handler.shell,
+                    describe_trigger(&entry.trigger)
+                );
+                if dry_run {
+                    println!("Would import {}", summary);
+                } else if let Err(e) = create_entry(&client, url, entry).await {
+                    plan.skipped.push(Skipped {
+                        line: entry.line,
+                        text: entry.handler.command.clone(),
+                        reason: e,
+                    });
+                    continue;
+                } else {
+                    println!("Imported {}", summary);
+                }
+                imported += 1;
+                for note in &entry.notes {
+                    println!("  note: {}", note);
+                }
+            }
+
+            println!(
+                "{} {} of {} entries",
+                if dry_run { "Would import" } else { "Imported" },
+                imported,
+                plan.entries.len()
+            );
+            if !plan.skipped.is_empty() {
+                plan.skipped.sort_by_key(|s| s.line);
+                println!("Not imported:");
+                for skipped in plan.skipped {
+                    println!("  line {}: {}", skipped.line, skipped.text);
+                    println!("    {}", skipped.reason);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Plan {
+        parse_crontab(content, "cron-", None, false, OverlapPolicy::Allow)
+    }
+
+    fn timer_interval(entry: &Entry) -> Option<u32> {
+        match &entry.trigger {
+            Trigger::Timer(timer) => Some(timer.interval_secs),
+            _ => None,
+        }
+    }
+
+    fn schedule_cron(entry: &Entry) -> Option<(&str, Option<&str>)> {
+        match &entry.trigger {
+            Trigger::Schedule(schedule) => Some((
+                schedule.cron.as_deref().unwrap_or_default(),
+                schedule.timezone.as_deref(),
+            )),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn environment_lines_apply_to_the_entries_after_them() {
+        let plan = parse(
+            "0 1 * * * early.sh\n\
+             SHELL=/bin/bash\n\
+             PATH=\"/usr/local/bin:/usr/bin\"\n\
+             CRON_TZ=Europe/Berlin\n\
+             MAILTO=ops@example.com\n\
+             0 2 * * * late.sh\n",
+        );
+        let [early, late] = &plan.entries[..] else {
+            panic!("expected two entries");
+        };
+        assert_eq!(early.handler.shell, "sh");
+        assert!(early.handler.env.is_empty());
+        assert_eq!(schedule_cron(early), Some(("0 1 * * *", None)));
+
+        assert_eq!(late.handler.shell, "bash");
+        assert_eq!(late.handler.env["PATH"], "/usr/local/bin:/usr/bin");
+        assert_eq!(
+            schedule_cron(late),
+            Some(("0 2 * * *", Some("Europe/Berlin")))
+        );
+
+        // MAILTO is reported, not exported
+        assert!(!late.handler.env.contains_key("MAILTO"));
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.skipped[0].line, 5);
+    }
+
+    #[test]
+    fn unsupported_shells_are_reported() {
+        let plan = parse("SHELL=/bin/zsh\n* * * * * job.sh\n");
+        assert_eq!(plan.entries[0].handler.shell, "sh");
+        assert_eq!(plan.skipped[0].line, 1);
+    }
+
+    #[test]
+    fn special_schedules() {
+        let plan = parse(
+            "@reboot /opt/start.sh\n@daily backup.sh\n@midnight rotate.sh\n@fortnightly x.sh\n",
+        );
+        assert_eq!(plan.entries.len(), 3);
+        assert!(matches!(plan.entries[0].trigger, Trigger::Manual));
+        assert_eq!(plan.entries[0].handler.event_type, "cron-start-sh");
+        assert!(plan.entries[0].notes[0].contains("shev event trigger cron-start-sh"));
+        assert_eq!(schedule_cron(&plan.entries[1]), Some(("@daily", None)));
+        assert_eq!(schedule_cron(&plan.entries[2]), Some(("@daily", None)));
+        assert_eq!(plan.skipped[0].line, 4);
+    }
+
+    #[test]
+    fn percent_signs_must_be_escaped() {
+        let plan = parse("0 0 * * * date +\\%Y-\\%m-\\%d\n0 0 * * * mail -s hi root%body\n");
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].handler.command, "date +%Y-%m-%d");
+        assert_eq!(plan.skipped[0].line, 2);
+        assert!(plan.skipped[0].reason.contains("stdin"));
+    }
+
+    #[test]
+    fn system_crontabs_have_a_user_column() {
+        let plan = parse_crontab(
+            "17 * * * * root cd / && run-parts /etc/cron.hourly\n@daily www-data cleanup.sh\n",
+            "cron-",
+            None,
+            true,
+            OverlapPolicy::Skip,
+        );
+        let [hourly, daily] = &plan.entries[..] else {
+            panic!("expected two entries");
+        };
+        assert_eq!(hourly.handler.command, "cd / && run-parts /etc/cron.hourly");
+        assert!(hourly.notes[0].contains("'root'"));
+        assert_eq!(daily.handler.command, "cleanup.sh");
+        assert!(daily.notes[0].contains("'www-data'"));
+        match &hourly.trigger {
+            Trigger::Schedule(schedule) => assert_eq!(schedule.overlap, OverlapPolicy::Skip),
+            _ => panic!("expected a schedule"),
+        }
+    }
+
+    #[test]
+    fn event_types_are_unique() {
+        let plan = parse("0 1 * * * backup.sh\n0 2 * * * /usr/bin/backup.sh --full\n");
+        assert_eq!(plan.entries[0].handler.event_type, "cron-backup-sh");
+        assert_eq!(plan.entries[1].handler.event_type, "cron-backup-sh-2");
+    }
+
+    #[test]
+    fn fixed_steps_become_timers_where_they_line_up() {
+        let plan = parse(
+            "* * * * * a\n*/15 * * * * b\n0 */6 * * * c\n*/7 * * * * d\n5 * * * * e\n*/15 9 * * * f\n",
+        );
+        let triggers: Vec<_> = plan.entries.iter().map(timer_interval).collect();
+        assert_eq!(
+            triggers,
+            [Some(60), Some(900), Some(21600), None, None, None]
+        );
+
+        // Hour steps count from midnight UTC, so they stay schedules elsewhere
+        let plan = parse("CRON_TZ=Europe/Berlin\n0 */6 * * * c\n*/15 * * * * b\n");
+        assert_eq!(timer_interval(&plan.entries[0]), None);
+        assert_eq!(timer_interval(&plan.entries[1]), Some(900));
+    }
+
+    #[test]
+    fn minute_steps_follow_offsets_that_are_not_whole_hours() {
+        // India is UTC+5:30: a 20 minute timer aligned to UTC would fire at :10, :30 and :50
+        let plan = parse("CRON_TZ=Asia/Kolkata\n*/20 * * * * a\n*/15 * * * * b\n*/30 * * * * c\n");
+        let triggers: Vec<_> = plan.entries.iter().map(timer_interval).collect();
+        assert_eq!(triggers, [None, Some(900), Some(1800)]);
+        assert_eq!(
+            schedule_cron(&plan.entries[0]),
+            Some(("*/20 * * * *", Some("Asia/Kolkata")))
+        );
+
+        // Nepal is UTC+5:45
+        let plan = parse("CRON_TZ=Asia/Kathmandu\n*/15 * * * * a\n*/30 * * * * b\n");
+        let triggers: Vec<_> = plan.entries.iter().map(timer_interval).collect();
+        assert_eq!(triggers, [Some(900), None]);
+
+        // An unknown zone is left to the server to check
+        let plan = parse("CRON_TZ=Mars/Olympus\n*/15 * * * * a\n");
+        assert_eq!(timer_interval(&plan.entries[0]), None);
+    }
+}
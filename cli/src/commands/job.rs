@@ -5,7 +5,7 @@ use shev_core::api::JobResponse;
 pub enum JobAction {
     /// List jobs
     List {
-        /// Filter by status (pending, running, completed, failed, cancelled)
+        /// Filter by status (pending, running, completed, failed, cancelled, timed_out, skipped)
         #[arg(long, short)]
         status: Option<String>,
         /// Maximum number of jobs to show
@@ -19,6 +19,9 @@ pub enum JobAction {
         /// Maximum output lines to show (0 for no limit)
         #[arg(long, short = 'n', default_value = "20")]
         max_lines: usize,
+        /// Stream output as it is produced until the job finishes
+        #[arg(long, short)]
+        follow: bool,
     },
     /// Cancel a pending or running job
     Cancel {
@@ -53,6 +56,59 @@ fn print_lines(text: &str, max_lines: usize) {
     }
 }
 
+/// Print job output from the server's event stream until the job finishes
+async fn follow_output(client: &reqwest::Client, url: &str, job_id: &str) -> Result<(), String> {
+    let mut resp = client
+        .get(format!("{}/jobs/{}/stream", url, job_id))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Server returned error {}: {}", status, body));
+    }
+
+    let mut buf = String::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read stream: {}", e))?
+    {
+        buf.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(pos) = buf.find("\n\n") {
+            let frame: String = buf.drain(..pos + 2).collect();
+            let (event, data) = parse_sse_frame(&frame);
+            match event.as_str() {
+                "stdout" => println!("{}", data),
+                "stderr" => eprintln!("{}", data),
+                "lagged" => eprintln!("[{} lines skipped, the terminal fell behind]", data),
+                "end" => {
+                    println!("Job finished: {}", data);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Err("Stream closed before the job finished".to_string())
+}
+
+fn parse_sse_frame(frame: &str) -> (String, String) {
+    let mut event = String::new();
+    let mut data: Vec<&str> = Vec::new();
+    for line in frame.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event = value.trim_start().to_string();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    (event, data.join("\n"))
+}
+
 pub async fn execute(url: &str, action: JobAction) -> Result<(), String> {
     let client = reqwest::Client::new();
 
@@ -79,16 +135,17 @@ pub async fn execute(url: &str, action: JobAction) -> Result<(), String> {
                     println!("No jobs found");
                 } else {
                     println!(
-                        "{:<36} {:<15} {:<12} {}",
-                        "JOB_ID", "EVENT_TYPE", "STATUS", "TIMESTAMP"
+                        "{:<36} {:<15} {:<12} {:<12} TIMESTAMP",
+                        "JOB_ID", "EVENT_TYPE", "HANDLER", "STATUS"
                     );
-                    println!("{}", "-".repeat(90));
+                    println!("{}", "-".repeat(103));
                     for j in jobs.iter().take(limit) {
                         let timestamp = j.event.timestamp.format("%Y-%m-%d %H:%M").to_string();
                         println!(
-                            "{:<36} {:<15} {:<12} {}",
+                            "{:<36} {:<15} {:<12} {:<12} {}",
                             j.id,
                             truncate(&j.event.event_type, 15),
+                            truncate(&j.handler_name, 12),
                             j.status,
                             timestamp
                         );
@@ -100,7 +157,11 @@ pub async fn execute(url: &str, action: JobAction) -> Result<(), String> {
                 return Err(format!("Server returned error {}: {}", status, body));
             }
         }
-        JobAction::Show { job_id, max_lines } => {
+        JobAction::Show {
+            job_id,
+            max_lines,
+            follow,
+        } => {
             let resp = client
                 .get(format!("{}/jobs/{}", url, job_id))
                 .send()
@@ -117,25 +178,53 @@ pub async fn execute(url: &str, action: JobAction) -> Result<(), String> {
                 println!("  Status: {}", j.status);
                 println!("  Event type: {}", j.event.event_type);
                 println!("  Event ID: {}", j.event.id);
-                println!("  Handler ID: {}", j.handler_id);
+                println!("  Handler: {} ({})", j.handler_name, j.handler_id);
+                if let Some(ref parent) = j.parent_job_id {
+                    println!("  Attempt: {} (retry of {})", j.attempt, parent);
+                }
+                if j.suppressed > 0 {
+                    println!(
+                        "  Suppressed events: {} (debounced or throttled)",
+                        j.suppressed
+                    );
+                }
                 println!("  Timestamp: {}", j.event.timestamp.to_rfc3339());
                 if !j.event.context.is_empty() {
                     println!("  Context: {}", j.event.context);
                 }
+                if !j.event.env.is_empty() {
+                    println!("  Event environment:");
+                    for (k, v) in &j.event.env {
+                        println!("    {}={}", k, v);
+                    }
+                }
                 if let Some(ref started) = j.started_at {
                     println!("  Started: {}", started.to_rfc3339());
                 }
                 if let Some(ref finished) = j.finished_at {
                     println!("  Finished: {}", finished.to_rfc3339());
                 }
-                if let Some(ref output) = j.output {
-                    println!("  Output:");
-                    print_lines(output, max_lines);
+                if follow {
+                    return follow_output(&client, url, &job_id).await;
+                }
+                if let Some(duration_ms) = j.duration_ms {
+                    println!("  Duration: {} ms", duration_ms);
+                }
+                if let Some(exit_code) = j.exit_code {
+                    println!("  Exit code: {}", exit_code);
                 }
                 if let Some(ref error) = j.error {
                     println!("  Error:");
                     print_lines(error, max_lines);
                 }
+                if let Some(stdout) = j.stdout.as_ref().filter(|s| !s.is_empty()) {
+                    println!("  Stdout:");
+                    print_lines(stdout, max_lines);
+                }
+                if let Some(stderr) = j.stderr.as_ref().filter(|s| !s.is_empty()) {
+                    println!("  Stderr:");
+                    print_lines(stderr, max_lines);
+                }
             } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
                 println!("Job '{}' not found", job_id);
             } else {
@@ -1,6 +1,57 @@
 pub mod config;
 pub mod event;
 pub mod handler;
+pub mod import;
 pub mod job;
 pub mod schedule;
+pub mod tail;
 pub mod timer;
+pub mod watcher;
+pub mod webhook;
+pub mod window;
+pub mod workflow;
+
+use chrono::{DateTime, Utc};
+use shev_core::{MisfirePolicy, OverlapPolicy};
+
+pub fn parse_time(time_str: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(time_str)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| {
+            format!(
+                "Invalid time format '{}': {}. Use RFC3339 format like 2025-01-15T14:30:00Z",
+                time_str, e
+            )
+        })
+}
+
+pub fn parse_overlap(value: &str) -> Result<OverlapPolicy, String> {
+    OverlapPolicy::from_str(value).ok_or_else(|| {
+        format!(
+            "Invalid overlap policy '{}', use allow, skip, queue or replace",
+            value
+        )
+    })
+}
+
+pub fn parse_misfire(value: &str) -> Result<MisfirePolicy, String> {
+    MisfirePolicy::from_str(value).ok_or_else(|| {
+        format!(
+            "Invalid misfire policy '{}', use fire_once, fire_all or skip",
+            value
+        )
+    })
+}
+
+/// Turn a name into an event type, e.g. "Team Standup" into "team-standup"
+pub fn slugify(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
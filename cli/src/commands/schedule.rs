@@ -1,6 +1,18 @@
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use clap::Subcommand;
-use shev_core::api::{CreateScheduleRequest, ScheduleResponse, UpdateScheduleRequest};
+use ical::parser::ical::component::IcalEvent;
+use ical::property::Property;
+use shev_core::api::{
+    CreateScheduleRequest, SchedulePreviewResponse, ScheduleResponse, UpdateScheduleRequest,
+};
+
+use super::{parse_misfire, parse_overlap, parse_time, slugify};
 
 #[derive(Subcommand)]
 pub enum ScheduleAction {
@@ -8,15 +20,35 @@ pub enum ScheduleAction {
     Add {
         /// Event type name
         event_type: String,
-        /// Scheduled time in RFC3339/ISO8601 format (e.g., 2025-01-15T14:30:00Z)
-        #[arg(long, short)]
-        time: String,
+        /// Scheduled time in RFC3339/ISO8601 format (e.g., 2025-01-15T14:30:00Z); with --cron or
+        /// --rrule, the time to start from (defaults to now)
+        #[arg(long, short, required_unless_present_any = ["cron", "rrule"])]
+        time: Option<String>,
         /// Context to pass to handler
         #[arg(long, short, default_value = "")]
         context: String,
         /// Run periodically (daily at the same time)
-        #[arg(long, short)]
+        #[arg(long, short, conflicts_with_all = ["cron", "rrule"])]
         periodic: bool,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short, default_value = "allow")]
+        overlap: String,
+        /// Cron expression, e.g. "0 9 * * MON-FRI" or @hourly
+        #[arg(long, conflicts_with = "rrule")]
+        cron: Option<String>,
+        /// RFC 5545 recurrence rule, e.g. "FREQ=MONTHLY;BYDAY=-1FR" (times of day default to
+        /// those of --time)
+        #[arg(long)]
+        rrule: Option<String>,
+        /// Leave out the occurrence at this time (RFC3339, repeatable)
+        #[arg(long)]
+        exdate: Vec<String>,
+        /// IANA time zone for the cron expression or rrule, e.g. Europe/Berlin (default: UTC)
+        #[arg(long)]
+        tz: Option<String>,
+        /// What to do about runs missed while the backend was down (fire_once, fire_all, skip)
+        #[arg(long, short, default_value = "fire_once")]
+        misfire: String,
     },
     /// Update an existing schedule (generates new UUID)
     Update {
@@ -31,6 +63,68 @@ pub enum ScheduleAction {
         /// Run periodically (daily at the same time)
         #[arg(long, short)]
         periodic: Option<bool>,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short)]
+        overlap: Option<String>,
+        /// Cron expression, e.g. "0 9 * * MON-FRI" or @hourly
+        #[arg(long)]
+        cron: Option<String>,
+        /// IANA time zone for the cron expression or rrule, e.g. Europe/Berlin
+        #[arg(long)]
+        tz: Option<String>,
+        /// Remove the cron expression and time zone (fire at the scheduled time again)
+        #[arg(long, conflicts_with_all = ["cron", "tz"])]
+        no_cron: bool,
+        /// RFC 5545 recurrence rule, e.g. "FREQ=WEEKLY;BYDAY=MO,WE"
+        #[arg(long)]
+        rrule: Option<String>,
+        /// Remove the recurrence rule
+        #[arg(long, conflicts_with = "rrule")]
+        no_rrule: bool,
+        /// Leave out the occurrence at this time (RFC3339, repeatable; replaces the current list)
+        #[arg(long)]
+        exdate: Vec<String>,
+        /// Remove all excluded dates
+        #[arg(long, conflicts_with = "exdate")]
+        no_exdates: bool,
+        /// What to do about runs missed while the backend was down (fire_once, fire_all, skip)
+        #[arg(long, short)]
+        misfire: Option<String>,
+    },
+    /// Import the events of an iCalendar (.ics) file as schedules
+    Import {
+        /// Path to the .ics file
+        file: String,
+        /// Prefix for the event types, which are derived from each event's summary
+        #[arg(long)]
+        prefix: Option<String>,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short, default_value = "allow")]
+        overlap: String,
+        /// What to do about runs missed while the backend was down (fire_once, fire_all, skip)
+        #[arg(long, short, default_value = "fire_once")]
+        misfire: String,
+        /// Show the schedules that would be created without creating them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List the next times a schedule will fire
+    Preview {
+        /// Event type name
+        event_type: String,
+        /// Number of fire times to list
+        #[arg(long, short = 'n', default_value_t = 10)]
+        count: usize,
+    },
+    /// Pause a schedule (runs that fall due while paused are skipped)
+    Pause {
+        /// Event type name
+        event_type: String,
+    },
+    /// Resume a paused schedule
+    Resume {
+        /// Event type name
+        event_type: String,
     },
     /// Remove a schedule
     Remove {
@@ -46,34 +140,271 @@ pub enum ScheduleAction {
     },
 }
 
-fn parse_time(time_str: &str) -> Result<DateTime<Utc>, String> {
-    DateTime::parse_from_rfc3339(time_str)
-        .map(|t| t.with_timezone(&Utc))
-        .map_err(|e| {
-            format!(
-                "Invalid time format '{}': {}. Use RFC3339 format like 2025-01-15T14:30:00Z",
-                time_str, e
-            )
-        })
-}
-
 fn print_schedule(schedule: &ScheduleResponse) {
     println!("  ID: {}", schedule.id);
     println!("  Event type: {}", schedule.event_type);
-    println!("  Scheduled time: {}", schedule.scheduled_time);
-    println!(
-        "  Periodic: {}",
-        if schedule.periodic {
-            "yes (daily)"
-        } else {
-            "no (one-shot)"
-        }
-    );
+    if let Some(ref cron) = schedule.cron {
+        println!("  Cron: {}", cron);
+        println!(
+            "  Time zone: {}",
+            schedule.timezone.as_deref().unwrap_or("UTC")
+        );
+        println!("  Starts: {}", schedule.scheduled_time);
+    } else if let Some(ref rrule) = schedule.rrule {
+        println!("  RRULE: {}", rrule);
+        println!(
+            "  Time zone: {}",
+            schedule.timezone.as_deref().unwrap_or("UTC")
+        );
+        println!("  Starts: {}", schedule.scheduled_time);
+    } else {
+        println!("  Scheduled time: {}", schedule.scheduled_time);
+        println!(
+            "  Periodic: {}",
+            if schedule.periodic {
+                "yes (daily)"
+            } else {
+                "no (one-shot)"
+            }
+        );
+    }
+    for exdate in &schedule.exdates {
+        println!("  Except: {}", exdate);
+    }
+    println!("  Overlap: {}", schedule.overlap.as_str());
+    println!("  Misfire: {}", schedule.misfire.as_str());
+    println!("  State: {}", schedule.state.as_str());
+    if !schedule.enabled {
+        println!("  Paused: yes");
+    }
+    if let Some(last) = schedule.last_fired_at {
+        println!("  Last fired: {}", last);
+    }
+    if let Some(next) = schedule.next_fire_at {
+        println!("  Next fire: {}", next);
+    }
     if !schedule.context.is_empty() {
         println!("  Context: {}", schedule.context);
     }
 }
 
+async fn set_enabled(
+    client: &reqwest::Client,
+    url: &str,
+    event_type: &str,
+    enabled: bool,
+) -> Result<(), String> {
+    let action = if enabled { "resume" } else { "pause" };
+    let resp = client
+        .post(format!("{}/schedules/{}/{}", url, event_type, action))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+    if resp.status().is_success() {
+        let schedule: ScheduleResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        println!(
+            "Schedule '{}' {}",
+            schedule.event_type,
+            if enabled { "resumed" } else { "paused" }
+        );
+    } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        println!("Schedule '{}' not found", event_type);
+    } else {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Server returned error {}: {}", status, body));
+    }
+    Ok(())
+}
+
+/// Undo the escaping of an iCalendar text value
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+fn property<'a>(event: &'a IcalEvent, name: &str) -> Option<&'a Property> {
+    event.properties.iter().find(|p| p.name == name)
+}
+
+fn param<'a>(property: &'a Property, name: &str) -> Option<&'a str> {
+    property
+        .params
+        .as_ref()?
+        .iter()
+        .find(|(key, _)| key == name)
+        .and_then(|(_, values)| values.first())
+        .map(|value| value.as_str())
+}
+
+/// Convert a DTSTART or EXDATE value to UTC. Values without a time zone are taken as UTC.
+fn ical_time(value: &str, tz: Option<&Tz>) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("Invalid iCalendar date-time '{}'", value);
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map(|t| t.and_utc())
+            .map_err(|_| invalid());
+    }
+    let local = if value.len() == 8 {
+        NaiveDate::parse_from_str(value, "%Y%m%d").map(|d| d.and_time(NaiveTime::MIN))
+    } else {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+    }
+    .map_err(|_| invalid())?;
+    match tz {
+        Some(tz) => tz
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .ok_or_else(invalid),
+        None => Ok(local.and_utc()),
+    }
+}
+
+fn ical_timezone(property: &Property) -> Result<Option<Tz>, String> {
+    param(property, "TZID")
+        .map(|name| {
+            Tz::from_str(name.trim_matches('"'))
+                .map_err(|_| format!("Unknown time zone '{}' in {}", name, property.name))
+        })
+        .transpose()
+}
+
+/// Build a schedule from a VEVENT: its summary names the event type, its description becomes
+/// the context and its start time, RRULE and EXDATEs define when it fires
+fn event_to_request(
+    event: &IcalEvent,
+    prefix: Option<&str>,
+) -> Result<CreateScheduleRequest, String> {
+    let summary = property(event, "SUMMARY")
+        .and_then(|p| p.value.as_deref())
+        .map(unescape_text)
+        .unwrap_or_default();
+    let slug = slugify(&summary);
+    if slug.is_empty() {
+        return Err(format!(
+            "Event '{}' has no summary to name its event type",
+            property(event, "UID")
+                .and_then(|p| p.value.as_deref())
+                .unwrap_or("?")
+        ));
+    }
+    let event_type = match prefix {
+        Some(prefix) => format!("{}{}", prefix, slug),
+        None => slug,
+    };
+
+    let dtstart =
+        property(event, "DTSTART").ok_or_else(|| format!("Event '{}' has no DTSTART", summary))?;
+    let tz = ical_timezone(dtstart)?;
+    let scheduled_time = ical_time(dtstart.value.as_deref().unwrap_or_default(), tz.as_ref())?;
+    let rrule = property(event, "RRULE").and_then(|p| p.value.clone());
+
+    let mut exdates = Vec::new();
+    for exdate in event.properties.iter().filter(|p| p.name == "EXDATE") {
+        let exdate_tz = ical_timezone(exdate)?.or(tz);
+        for value in exdate.value.as_deref().unwrap_or_default().split(',') {
+            exdates.push(ical_time(value.trim(), exdate_tz.as_ref())?);
+        }
+    }
+
+    Ok(CreateScheduleRequest {
+        event_type,
+        scheduled_time: Some(scheduled_time),
+        context: property(event, "DESCRIPTION")
+            .and_then(|p| p.value.as_deref())
+            .map(unescape_text)
+            .unwrap_or_default(),
+        periodic: false,
+        overlap: Default::default(),
+        cron: None,
+        // The time zone only matters for evaluating the rule in local time
+        timezone: tz
+            .filter(|_| rrule.is_some())
+            .map(|tz| tz.name().to_string()),
+        misfire: Default::default(),
+        exdates: if rrule.is_some() { exdates } else { Vec::new() },
+        rrule,
+    })
+}
+
+/// Create the schedule, or replace the one with the same event type
+async fn import_schedule(
+    client: &reqwest::Client,
+    url: &str,
+    request: CreateScheduleRequest,
+) -> Result<(), String> {
+    let exists = client
+        .get(format!("{}/schedules/{}", url, request.event_type))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to server: {}", e))?
+        .status()
+        .is_success();
+
+    let resp = if exists {
+        let update = UpdateScheduleRequest {
+            scheduled_time: request.scheduled_time,
+            context: Some(request.context.clone()),
+            periodic: Some(false),
+            overlap: Some(request.overlap),
+            cron: Some(None),
+            timezone: Some(request.timezone.clone()),
+            misfire: Some(request.misfire),
+            rrule: Some(request.rrule.clone()),
+            exdates: Some(request.exdates.clone()),
+        };
+        client
+            .put(format!("{}/schedules/{}", url, request.event_type))
+            .json(&update)
+            .send()
+            .await
+    } else {
+        client
+            .post(format!("{}/schedules", url))
+            .json(&request)
+            .send()
+            .await
+    }
+    .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+    if resp.status().is_success() {
+        let schedule: ScheduleResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        println!(
+            "Schedule '{}' {}",
+            schedule.event_type,
+            if exists { "updated" } else { "imported" }
+        );
+    } else {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to import '{}': server returned error {}: {}",
+            request.event_type, status, body
+        ));
+    }
+    Ok(())
+}
+
 pub async fn execute(url: &str, action: ScheduleAction) -> Result<(), String> {
     let client = reqwest::Client::new();
 
@@ -83,13 +414,28 @@ pub async fn execute(url: &str, action: ScheduleAction) -> Result<(), String> {
             time,
             context,
             periodic,
+            overlap,
+            cron,
+            rrule,
+            exdate,
+            tz,
+            misfire,
         } => {
-            let scheduled_time = parse_time(&time)?;
+            let scheduled_time = time.map(|t| parse_time(&t)).transpose()?;
             let request = CreateScheduleRequest {
                 event_type: event_type.clone(),
                 scheduled_time,
                 context,
                 periodic,
+                overlap: parse_overlap(&overlap)?,
+                cron,
+                timezone: tz,
+                misfire: parse_misfire(&misfire)?,
+                rrule,
+                exdates: exdate
+                    .iter()
+                    .map(|t| parse_time(t))
+                    .collect::<Result<_, _>>()?,
             };
 
             let resp = client
@@ -117,12 +463,47 @@ pub async fn execute(url: &str, action: ScheduleAction) -> Result<(), String> {
             time,
             context,
             periodic,
+            overlap,
+            cron,
+            tz,
+            no_cron,
+            rrule,
+            no_rrule,
+            exdate,
+            no_exdates,
+            misfire,
         } => {
             let scheduled_time = time.map(|t| parse_time(&t)).transpose()?;
+            let (cron, timezone) = if no_cron {
+                (Some(None), Some(None))
+            } else {
+                (cron.map(Some), tz.map(Some))
+            };
             let request = UpdateScheduleRequest {
                 scheduled_time,
                 context,
                 periodic,
+                overlap: overlap.as_deref().map(parse_overlap).transpose()?,
+                cron,
+                timezone,
+                misfire: misfire.as_deref().map(parse_misfire).transpose()?,
+                rrule: if no_rrule {
+                    Some(None)
+                } else {
+                    rrule.map(Some)
+                },
+                exdates: if no_exdates {
+                    Some(Vec::new())
+                } else if exdate.is_empty() {
+                    None
+                } else {
+                    Some(
+                        exdate
+                            .iter()
+                            .map(|t| parse_time(t))
+                            .collect::<Result<_, _>>()?,
+                    )
+                },
             };
 
             let resp = client
@@ -145,6 +526,81 @@ pub async fn execute(url: &str, action: ScheduleAction) -> Result<(), String> {
                 return Err(format!("Server returned error {}: {}", status, body));
             }
         }
+        ScheduleAction::Import {
+            file,
+            prefix,
+            overlap,
+            misfire,
+            dry_run,
+        } => {
+            let overlap = parse_overlap(&overlap)?;
+            let misfire = parse_misfire(&misfire)?;
+            let reader =
+                File::open(&file).map_err(|e| format!("Failed to open {}: {}", file, e))?;
+
+            let mut requests = Vec::new();
+            for calendar in ical::IcalParser::new(BufReader::new(reader)) {
+                let calendar = calendar.map_err(|e| format!("Failed to parse {}: {}", file, e))?;
+                for event in calendar.events {
+                    let mut request = event_to_request(&event, prefix.as_deref())?;
+                    request.overlap = overlap;
+                    request.misfire = misfire;
+                    let uid = property(&event, "UID")
+                        .and_then(|p| p.value.clone())
+                        .unwrap_or_else(|| "?".to_string());
+                    requests.push((request, uid));
+                }
+            }
+
+            if requests.is_empty() {
+                println!("No events found in {}", file);
+                return Ok(());
+            }
+
+            // Events with the same summary would overwrite each other's schedule
+            let mut uids: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+            for (request, uid) in &requests {
+                uids.entry(&request.event_type).or_default().push(uid);
+            }
+            let collisions: Vec<String> = uids
+                .iter()
+                .filter(|(_, uids)| uids.len() > 1)
+                .map(|(event_type, uids)| {
+                    format!("  '{}' from events {}", event_type, uids.join(", "))
+                })
+                .collect();
+            if !collisions.is_empty() {
+                return Err(format!(
+                    "Several events in {} map to the same event type; give them distinct summaries:\n{}",
+                    file,
+                    collisions.join("\n")
+                ));
+            }
+            let requests: Vec<_> = requests.into_iter().map(|(request, _)| request).collect();
+
+            for request in requests {
+                if dry_run {
+                    println!(
+                        "Would import '{}': {}{}",
+                        request.event_type,
+                        request.scheduled_time.unwrap_or_default(),
+                        request
+                            .rrule
+                            .as_deref()
+                            .map(|r| format!(" {}", r))
+                            .unwrap_or_default()
+                    );
+                    continue;
+                }
+                import_schedule(&client, url, request).await?;
+            }
+        }
+        ScheduleAction::Pause { event_type } => {
+            set_enabled(&client, url, &event_type, false).await?;
+        }
+        ScheduleAction::Resume { event_type } => {
+            set_enabled(&client, url, &event_type, true).await?;
+        }
         ScheduleAction::Remove { event_type } => {
             let resp = client
                 .delete(format!("{}/schedules/{}", url, event_type))
@@ -179,10 +635,10 @@ pub async fn execute(url: &str, action: ScheduleAction) -> Result<(), String> {
                     println!("No schedules configured");
                 } else {
                     println!(
-                        "{:<20} {:<26} {:<10} {:<15} {}",
-                        "EVENT_TYPE", "SCHEDULED_TIME", "PERIODIC", "CONTEXT", "ID"
+                        "{:<20} {:<22} {:<10} {:<10} {:<22} {:<15} ID",
+                        "EVENT_TYPE", "SCHEDULED_TIME", "PERIODIC", "STATE", "NEXT_FIRE", "CONTEXT"
                     );
-                    println!("{}", "-".repeat(110));
+                    println!("{}", "-".repeat(140));
                     for s in schedules {
                         let context = if s.context.is_empty() {
                             "-".to_string()
@@ -191,12 +647,30 @@ pub async fn execute(url: &str, action: ScheduleAction) -> Result<(), String> {
                         } else {
                             s.context.clone()
                         };
-                        let periodic = if s.periodic { "daily" } else { "one-shot" };
+                        let periodic = if s.cron.is_some() {
+                            "cron"
+                        } else if s.rrule.is_some() {
+                            "rrule"
+                        } else if s.periodic {
+                            "daily"
+                        } else {
+                            "one-shot"
+                        };
+                        let next_fire = s
+                            .next_fire_at
+                            .map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                            .unwrap_or_else(|| "-".to_string());
                         println!(
-                            "{:<20} {:<26} {:<10} {:<15} {}",
+                            "{:<20} {:<22} {:<10} {:<10} {:<22} {:<15} {}",
                             s.event_type,
                             s.scheduled_time.format("%Y-%m-%dT%H:%M:%SZ"),
                             periodic,
+                            if s.enabled {
+                                s.state.as_str()
+                            } else {
+                                "paused"
+                            },
+                            next_fire,
                             context,
                             s.id
                         );
@@ -208,6 +682,38 @@ pub async fn execute(url: &str, action: ScheduleAction) -> Result<(), String> {
                 return Err(format!("Server returned error {}: {}", status, body));
             }
         }
+        ScheduleAction::Preview { event_type, count } => {
+            let resp = client
+                .get(format!(
+                    "{}/schedules/{}/preview?count={}",
+                    url, event_type, count
+                ))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let preview: SchedulePreviewResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+                if preview.fire_times.is_empty() {
+                    println!("Schedule '{}' will not fire again", event_type);
+                } else {
+                    println!("Next fire times for '{}':", event_type);
+                    for time in preview.fire_times {
+                        println!("  {}", time.format("%Y-%m-%dT%H:%M:%SZ (%a)"));
+                    }
+                }
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Schedule '{}' not found", event_type);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
         ScheduleAction::Show { event_type } => {
             let resp = client
                 .get(format!("{}/schedules/{}", url, event_type))
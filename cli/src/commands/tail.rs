@@ -0,0 +1,230 @@
+use clap::Subcommand;
+use shev_core::api::{CreateTailRequest, TailResponse, UpdateTailRequest};
+
+use super::parse_overlap;
+
+#[derive(Subcommand)]
+pub enum TailAction {
+    /// Follow a log file and produce an event for every matching line
+    Add {
+        /// Event type name
+        event_type: String,
+        /// Log file to follow
+        #[arg(long, short)]
+        path: String,
+        /// Regular expression to match lines against (can be used multiple times); named groups
+        /// such as (?P<user>\w+) end up in the event context
+        #[arg(long = "pattern", short = 'e', required = true)]
+        patterns: Vec<String>,
+        /// Also read the lines already in the file
+        #[arg(long)]
+        from_start: bool,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short, default_value = "allow")]
+        overlap: String,
+    },
+    /// Update an existing tail (generates new UUID)
+    Update {
+        /// Event type name
+        event_type: String,
+        /// Log file to follow
+        #[arg(long, short)]
+        path: Option<String>,
+        /// Regular expression to match lines against (replaces the current patterns)
+        #[arg(long = "pattern", short = 'e')]
+        patterns: Vec<String>,
+        /// Also read the lines already in the file
+        #[arg(long)]
+        from_start: Option<bool>,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short)]
+        overlap: Option<String>,
+    },
+    /// Remove a tail
+    Remove {
+        /// Event type name
+        event_type: String,
+    },
+    /// List all tails
+    List,
+    /// Show details of a tail
+    Show {
+        /// Event type name
+        event_type: String,
+    },
+}
+
+/// The backend needs an absolute path; a relative one is taken from the current directory
+fn absolute_path(path: String) -> Result<String, String> {
+    std::path::absolute(&path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .map_err(|e| format!("Invalid path '{}': {}", path, e))
+}
+
+fn print_tail(tail: &TailResponse) {
+    println!("  ID: {}", tail.id);
+    println!("  Event type: {}", tail.event_type);
+    println!("  Path: {}", tail.path);
+    for pattern in &tail.patterns {
+        println!("  Pattern: {}", pattern);
+    }
+    if tail.from_start {
+        println!("  From start: yes");
+    }
+    println!("  Overlap: {}", tail.overlap.as_str());
+}
+
+pub async fn execute(url: &str, action: TailAction) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    match action {
+        TailAction::Add {
+            event_type,
+            path,
+            patterns,
+            from_start,
+            overlap,
+        } => {
+            let request = CreateTailRequest {
+                event_type,
+                path: absolute_path(path)?,
+                patterns,
+                from_start,
+                overlap: parse_overlap(&overlap)?,
+            };
+
+            let resp = client
+                .post(format!("{}/tails", url))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let tail: TailResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Tail added:");
+                print_tail(&tail);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        TailAction::Update {
+            event_type,
+            path,
+            patterns,
+            from_start,
+            overlap,
+        } => {
+            let request = UpdateTailRequest {
+                path: path.map(absolute_path).transpose()?,
+                patterns: if patterns.is_empty() {
+                    None
+                } else {
+                    Some(patterns)
+                },
+                from_start,
+                overlap: overlap.as_deref().map(parse_overlap).transpose()?,
+            };
+
+            let resp = client
+                .put(format!("{}/tails/{}", url, event_type))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let tail: TailResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Tail updated (new UUID generated):");
+                print_tail(&tail);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        TailAction::Remove { event_type } => {
+            let resp = client
+                .delete(format!("{}/tails/{}", url, event_type))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                println!("Tail '{}' removed", event_type);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Tail '{}' not found", event_type);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        TailAction::List => {
+            let resp = client
+                .get(format!("{}/tails", url))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let tails: Vec<TailResponse> = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+                if tails.is_empty() {
+                    println!("No tails configured");
+                } else {
+                    println!("{:<20} {:<40} {:<10} ID", "EVENT_TYPE", "PATH", "PATTERNS");
+                    println!("{}", "-".repeat(110));
+                    for t in tails {
+                        println!(
+                            "{:<20} {:<40} {:<10} {}",
+                            t.event_type,
+                            t.path,
+                            t.patterns.len(),
+                            t.id
+                        );
+                    }
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        TailAction::Show { event_type } => {
+            let resp = client
+                .get(format!("{}/tails/{}", url, event_type))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let tail: TailResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Tail: {}", tail.event_type);
+                print_tail(&tail);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Tail '{}' not found", event_type);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
+use chrono::Utc;
 use clap::Subcommand;
 use shev_core::api::{CreateTimerRequest, TimerResponse, UpdateTimerRequest};
+use shev_core::parse_duration_secs;
+
+use super::{parse_overlap, parse_time};
 
 #[derive(Subcommand)]
 pub enum TimerAction {
@@ -7,23 +11,78 @@ pub enum TimerAction {
     Add {
         /// Event type name
         event_type: String,
-        /// Interval in seconds
+        /// Interval in seconds or as a duration (e.g. 90, 15m, 1h30m)
         #[arg(long, short)]
-        interval: u32,
+        interval: String,
         /// Context to pass to handler
         #[arg(long, short, default_value = "")]
         context: String,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short, default_value = "allow")]
+        overlap: String,
+        /// Fire on multiples of the interval (e.g. :00, :15, :30 and :45 for 15m)
+        #[arg(long, short)]
+        align: bool,
+        /// Delay each event by a random amount up to this duration (e.g. 30s)
+        #[arg(long, short)]
+        jitter: Option<String>,
+        /// Do not fire before this time (RFC3339)
+        #[arg(long)]
+        start: Option<String>,
+        /// Stop firing after this time (RFC3339)
+        #[arg(long)]
+        end: Option<String>,
+        /// Stop after this many events
+        #[arg(long)]
+        max_fires: Option<u32>,
     },
     /// Update an existing timer (generates new UUID)
     Update {
         /// Event type name
         event_type: String,
-        /// Interval in seconds
+        /// Interval in seconds or as a duration (e.g. 90, 15m, 1h30m)
         #[arg(long, short)]
-        interval: Option<u32>,
+        interval: Option<String>,
         /// Context to pass to handler
         #[arg(long, short)]
         context: Option<String>,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short)]
+        overlap: Option<String>,
+        /// Fire on multiples of the interval
+        #[arg(long, short)]
+        align: Option<bool>,
+        /// Delay each event by a random amount up to this duration (0 to disable)
+        #[arg(long, short)]
+        jitter: Option<String>,
+        /// Do not fire before this time (RFC3339)
+        #[arg(long)]
+        start: Option<String>,
+        /// Remove the start time
+        #[arg(long, conflicts_with = "start")]
+        no_start: bool,
+        /// Stop firing after this time (RFC3339)
+        #[arg(long)]
+        end: Option<String>,
+        /// Remove the end time
+        #[arg(long, conflicts_with = "end")]
+        no_end: bool,
+        /// Stop after this many events
+        #[arg(long)]
+        max_fires: Option<u32>,
+        /// Remove the event limit
+        #[arg(long, conflicts_with = "max_fires")]
+        no_max_fires: bool,
+    },
+    /// Pause a timer (it keeps its configuration but produces no events)
+    Pause {
+        /// Event type name
+        event_type: String,
+    },
+    /// Resume a paused timer
+    Resume {
+        /// Event type name
+        event_type: String,
     },
     /// Remove a timer
     Remove {
@@ -39,15 +98,88 @@ pub enum TimerAction {
     },
 }
 
+/// Whether the timer is running, paused or done for good
+fn timer_status(timer: &TimerResponse) -> &'static str {
+    if timer.max_fires.is_some_and(|max| timer.fire_count >= max)
+        || timer.end_at.is_some_and(|end| end < Utc::now())
+    {
+        "finished"
+    } else if !timer.enabled {
+        "paused"
+    } else {
+        "active"
+    }
+}
+
 fn print_timer(timer: &TimerResponse) {
     println!("  ID: {}", timer.id);
     println!("  Event type: {}", timer.event_type);
-    println!("  Interval: {}s", timer.interval_secs);
+    println!(
+        "  Interval: {}s{}",
+        timer.interval_secs,
+        if timer.align { " (aligned)" } else { "" }
+    );
+    if timer.jitter_secs > 0 {
+        println!("  Jitter: up to {}s", timer.jitter_secs);
+    }
+    println!("  Overlap: {}", timer.overlap.as_str());
+    if !timer.enabled {
+        println!("  Paused: yes");
+    }
+    if let Some(start) = timer.start_at {
+        println!("  Starts: {}", start);
+    }
+    if let Some(end) = timer.end_at {
+        println!("  Ends: {}", end);
+    }
+    match timer.max_fires {
+        Some(max) => println!("  Fired: {} of {}", timer.fire_count, max),
+        None => println!("  Fired: {}", timer.fire_count),
+    }
+    if let Some(last) = timer.last_fired_at {
+        println!("  Last fired: {}", last);
+    }
+    if let Some(next) = timer.next_fire_at {
+        println!("  Next fire: {}", next);
+    }
     if !timer.context.is_empty() {
         println!("  Context: {}", timer.context);
     }
 }
 
+async fn set_enabled(
+    client: &reqwest::Client,
+    url: &str,
+    event_type: &str,
+    enabled: bool,
+) -> Result<(), String> {
+    let action = if enabled { "resume" } else { "pause" };
+    let resp = client
+        .post(format!("{}/timers/{}/{}", url, event_type, action))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+    if resp.status().is_success() {
+        let timer: TimerResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        println!(
+            "Timer '{}' {}",
+            timer.event_type,
+            if enabled { "resumed" } else { "paused" }
+        );
+    } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        println!("Timer '{}' not found", event_type);
+    } else {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Server returned error {}: {}", status, body));
+    }
+    Ok(())
+}
+
 pub async fn execute(url: &str, action: TimerAction) -> Result<(), String> {
     let client = reqwest::Client::new();
 
@@ -56,11 +188,27 @@ pub async fn execute(url: &str, action: TimerAction) -> Result<(), String> {
             event_type,
             interval,
             context,
+            overlap,
+            align,
+            jitter,
+            start,
+            end,
+            max_fires,
         } => {
             let request = CreateTimerRequest {
                 event_type: event_type.clone(),
-                interval_secs: interval,
+                interval_secs: parse_duration_secs(&interval)?,
                 context,
+                overlap: parse_overlap(&overlap)?,
+                align,
+                jitter_secs: jitter
+                    .as_deref()
+                    .map(parse_duration_secs)
+                    .transpose()?
+                    .unwrap_or(0),
+                start_at: start.as_deref().map(parse_time).transpose()?,
+                end_at: end.as_deref().map(parse_time).transpose()?,
+                max_fires,
             };
 
             let resp = client
@@ -87,10 +235,35 @@ pub async fn execute(url: &str, action: TimerAction) -> Result<(), String> {
             event_type,
             interval,
             context,
+            overlap,
+            align,
+            jitter,
+            start,
+            no_start,
+            end,
+            no_end,
+            max_fires,
+            no_max_fires,
         } => {
+            let start_at = start.as_deref().map(parse_time).transpose()?;
+            let end_at = end.as_deref().map(parse_time).transpose()?;
             let request = UpdateTimerRequest {
-                interval_secs: interval,
+                interval_secs: interval.as_deref().map(parse_duration_secs).transpose()?,
                 context,
+                overlap: overlap.as_deref().map(parse_overlap).transpose()?,
+                align,
+                jitter_secs: jitter.as_deref().map(parse_duration_secs).transpose()?,
+                start_at: if no_start {
+                    Some(None)
+                } else {
+                    start_at.map(Some)
+                },
+                end_at: if no_end { Some(None) } else { end_at.map(Some) },
+                max_fires: if no_max_fires {
+                    Some(None)
+                } else {
+                    max_fires.map(Some)
+                },
             };
 
             let resp = client
@@ -113,6 +286,12 @@ pub async fn execute(url: &str, action: TimerAction) -> Result<(), String> {
                 return Err(format!("Server returned error {}: {}", status, body));
             }
         }
+        TimerAction::Pause { event_type } => {
+            set_enabled(&client, url, &event_type, false).await?;
+        }
+        TimerAction::Resume { event_type } => {
+            set_enabled(&client, url, &event_type, true).await?;
+        }
         TimerAction::Remove { event_type } => {
             let resp = client
                 .delete(format!("{}/timers/{}", url, event_type))
@@ -147,10 +326,10 @@ pub async fn execute(url: &str, action: TimerAction) -> Result<(), String> {
                     println!("No timers configured");
                 } else {
                     println!(
-                        "{:<20} {:<12} {:<20} {}",
-                        "EVENT_TYPE", "INTERVAL", "CONTEXT", "ID"
+                        "{:<20} {:<12} {:<8} {:<22} {:<20} ID",
+                        "EVENT_TYPE", "INTERVAL", "STATUS", "NEXT_FIRE", "CONTEXT"
                     );
-                    println!("{}", "-".repeat(80));
+                    println!("{}", "-".repeat(110));
                     for t in timers {
                         let context = if t.context.is_empty() {
                             "-".to_string()
@@ -159,10 +338,16 @@ pub async fn execute(url: &str, action: TimerAction) -> Result<(), String> {
                         } else {
                             t.context.clone()
                         };
+                        let next_fire = t
+                            .next_fire_at
+                            .map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                            .unwrap_or_else(|| "-".to_string());
                         println!(
-                            "{:<20} {:<12} {:<20} {}",
+                            "{:<20} {:<12} {:<8} {:<22} {:<20} {}",
                             t.event_type,
                             format!("{}s", t.interval_secs),
+                            timer_status(&t),
+                            next_fire,
                             context,
                             t.id
                         );
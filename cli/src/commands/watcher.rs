@@ -0,0 +1,319 @@
+use clap::Subcommand;
+use shev_core::FileChange;
+use shev_core::api::{CreateWatcherRequest, UpdateWatcherRequest, WatcherResponse};
+
+use super::parse_overlap;
+
+#[derive(Subcommand)]
+pub enum WatcherAction {
+    /// Add a new file system watcher
+    Add {
+        /// Event type name
+        event_type: String,
+        /// File or directory to watch (can be used multiple times)
+        #[arg(long, short, required = true)]
+        path: Vec<String>,
+        /// Only report files matching this glob, e.g. "*.csv" (can be used multiple times)
+        #[arg(long, short)]
+        include: Vec<String>,
+        /// Ignore files matching this glob, e.g. "*.tmp" (can be used multiple times)
+        #[arg(long, short = 'x')]
+        exclude: Vec<String>,
+        /// Also watch subdirectories
+        #[arg(long, short)]
+        recursive: bool,
+        /// Kinds of change to report (created, modified, deleted; default: all)
+        #[arg(long, value_delimiter = ',')]
+        on: Vec<String>,
+        /// Milliseconds a file must be left alone before its change is reported
+        #[arg(long, short, default_value_t = 500)]
+        debounce_ms: u32,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short, default_value = "allow")]
+        overlap: String,
+    },
+    /// Update an existing watcher (generates new UUID)
+    Update {
+        /// Event type name
+        event_type: String,
+        /// File or directory to watch (replaces the current paths)
+        #[arg(long, short)]
+        path: Vec<String>,
+        /// Only report files matching this glob (replaces the current globs)
+        #[arg(long, short)]
+        include: Vec<String>,
+        /// Report all files again
+        #[arg(long, conflicts_with = "include")]
+        no_include: bool,
+        /// Ignore files matching this glob (replaces the current globs)
+        #[arg(long, short = 'x')]
+        exclude: Vec<String>,
+        /// Stop ignoring files
+        #[arg(long, conflicts_with = "exclude")]
+        no_exclude: bool,
+        /// Also watch subdirectories
+        #[arg(long, short)]
+        recursive: Option<bool>,
+        /// Kinds of change to report (created, modified, deleted)
+        #[arg(long, value_delimiter = ',')]
+        on: Vec<String>,
+        /// Milliseconds a file must be left alone before its change is reported
+        #[arg(long, short)]
+        debounce_ms: Option<u32>,
+        /// What to do if the previous job is still active (allow, skip, queue, replace)
+        #[arg(long, short)]
+        overlap: Option<String>,
+    },
+    /// Remove a watcher
+    Remove {
+        /// Event type name
+        event_type: String,
+    },
+    /// List all watchers
+    List,
+    /// Show details of a watcher
+    Show {
+        /// Event type name
+        event_type: String,
+    },
+}
+
+/// The backend needs absolute paths; relative ones are taken from the current directory
+fn absolute_paths(paths: Vec<String>) -> Result<Vec<String>, String> {
+    paths
+        .into_iter()
+        .map(|path| {
+            std::path::absolute(&path)
+                .map(|p| p.to_string_lossy().into_owned())
+                .map_err(|e| format!("Invalid path '{}': {}", path, e))
+        })
+        .collect()
+}
+
+fn parse_changes(values: &[String]) -> Result<Vec<FileChange>, String> {
+    values
+        .iter()
+        .map(|v| {
+            FileChange::from_str(v.trim())
+                .ok_or_else(|| format!("Invalid change '{}', use created, modified or deleted", v))
+        })
+        .collect()
+}
+
+fn print_watcher(watcher: &WatcherResponse) {
+    println!("  ID: {}", watcher.id);
+    println!("  Event type: {}", watcher.event_type);
+    for path in &watcher.paths {
+        println!("  Path: {}", path);
+    }
+    println!(
+        "  Recursive: {}",
+        if watcher.recursive { "yes" } else { "no" }
+    );
+    if !watcher.include.is_empty() {
+        println!("  Include: {}", watcher.include.join(", "));
+    }
+    if !watcher.exclude.is_empty() {
+        println!("  Exclude: {}", watcher.exclude.join(", "));
+    }
+    let changes: Vec<&str> = watcher.changes.iter().map(|c| c.as_str()).collect();
+    println!("  Changes: {}", changes.join(", "));
+    println!("  Debounce: {}ms", watcher.debounce_ms);
+    println!("  Overlap: {}", watcher.overlap.as_str());
+}
+
+pub async fn execute(url: &str, action: WatcherAction) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    match action {
+        WatcherAction::Add {
+            event_type,
+            path,
+            include,
+            exclude,
+            recursive,
+            on,
+            debounce_ms,
+            overlap,
+        } => {
+            let request = CreateWatcherRequest {
+                event_type,
+                paths: absolute_paths(path)?,
+                include,
+                exclude,
+                recursive,
+                changes: if on.is_empty() {
+                    FileChange::ALL.to_vec()
+                } else {
+                    parse_changes(&on)?
+                },
+                debounce_ms,
+                overlap: parse_overlap(&overlap)?,
+            };
+
+            let resp = client
+                .post(format!("{}/watchers", url))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let watcher: WatcherResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Watcher added:");
+                print_watcher(&watcher);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WatcherAction::Update {
+            event_type,
+            path,
+            include,
+            no_include,
+            exclude,
+            no_exclude,
+            recursive,
+            on,
+            debounce_ms,
+            overlap,
+        } => {
+            let request = UpdateWatcherRequest {
+                paths: if path.is_empty() {
+                    None
+                } else {
+                    Some(absolute_paths(path)?)
+                },
+                include: if no_include || !include.is_empty() {
+                    Some(include)
+                } else {
+                    None
+                },
+                exclude: if no_exclude || !exclude.is_empty() {
+                    Some(exclude)
+                } else {
+                    None
+                },
+                recursive,
+                changes: if on.is_empty() {
+                    None
+                } else {
+                    Some(parse_changes(&on)?)
+                },
+                debounce_ms,
+                overlap: overlap.as_deref().map(parse_overlap).transpose()?,
+            };
+
+            let resp = client
+                .put(format!("{}/watchers/{}", url, event_type))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let watcher: WatcherResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Watcher updated (new UUID generated):");
+                print_watcher(&watcher);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WatcherAction::Remove { event_type } => {
+            let resp = client
+                .delete(format!("{}/watchers/{}", url, event_type))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                println!("Watcher '{}' removed", event_type);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Watcher '{}' not found", event_type);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WatcherAction::List => {
+            let resp = client
+                .get(format!("{}/watchers", url))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let watchers: Vec<WatcherResponse> = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+                if watchers.is_empty() {
+                    println!("No watchers configured");
+                } else {
+                    println!(
+                        "{:<20} {:<32} {:<10} {:<26} ID",
+                        "EVENT_TYPE", "PATHS", "RECURSIVE", "CHANGES"
+                    );
+                    println!("{}", "-".repeat(130));
+                    for w in watchers {
+                        let paths = w.paths.join(",");
+                        let paths = if paths.len() > 30 {
+                            format!("{}...", &paths[..27])
+                        } else {
+                            paths
+                        };
+                        let changes: Vec<&str> = w.changes.iter().map(|c| c.as_str()).collect();
+                        println!(
+                            "{:<20} {:<32} {:<10} {:<26} {}",
+                            w.event_type,
+                            paths,
+                            if w.recursive { "yes" } else { "no" },
+                            changes.join(","),
+                            w.id
+                        );
+                    }
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WatcherAction::Show { event_type } => {
+            let resp = client
+                .get(format!("{}/watchers/{}", url, event_type))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let watcher: WatcherResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Watcher: {}", watcher.event_type);
+                print_watcher(&watcher);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Watcher '{}' not found", event_type);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+    }
+
+    Ok(())
+}
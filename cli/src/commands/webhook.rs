@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use clap::Subcommand;
+use shev_core::DEFAULT_SIGNATURE_HEADER;
+use shev_core::api::{CreateWebhookRequest, UpdateWebhookRequest, WebhookResponse};
+
+#[derive(Subcommand)]
+pub enum WebhookAction {
+    /// Add a webhook, received on POST /hooks/<event_type>
+    Add {
+        /// Event type name
+        event_type: String,
+        /// Secret for the HMAC-SHA256 signature of the payload
+        #[arg(long, short, conflicts_with = "generate_secret")]
+        secret: Option<String>,
+        /// Generate a random secret and print it
+        #[arg(long, short)]
+        generate_secret: bool,
+        /// Header carrying the signature (GitHub uses X-Hub-Signature-256)
+        #[arg(long, default_value = DEFAULT_SIGNATURE_HEADER)]
+        header: String,
+        /// Set an environment variable from the JSON payload (can be used multiple times):
+        /// NAME=JSONPATH, e.g. GIT_REF=$.ref
+        #[arg(long, short)]
+        field: Vec<String>,
+    },
+    /// Update an existing webhook (generates new UUID)
+    Update {
+        /// Event type name
+        event_type: String,
+        /// Secret for the HMAC-SHA256 signature of the payload
+        #[arg(long, short, conflicts_with_all = ["generate_secret", "no_secret"])]
+        secret: Option<String>,
+        /// Generate a new random secret and print it
+        #[arg(long, short, conflicts_with = "no_secret")]
+        generate_secret: bool,
+        /// Accept unsigned requests (from allowed write IPs only)
+        #[arg(long)]
+        no_secret: bool,
+        /// Header carrying the signature
+        #[arg(long)]
+        header: Option<String>,
+        /// Set an environment variable from the JSON payload (replaces the current fields)
+        #[arg(long, short)]
+        field: Vec<String>,
+        /// Remove all fields
+        #[arg(long, conflicts_with = "field")]
+        no_fields: bool,
+    },
+    /// Remove a webhook
+    Remove {
+        /// Event type name
+        event_type: String,
+    },
+    /// List all webhooks
+    List,
+    /// Show details of a webhook
+    Show {
+        /// Event type name
+        event_type: String,
+    },
+}
+
+fn generate_secret() -> String {
+    rand::random::<[u8; 32]>()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn parse_fields(fields: Vec<String>) -> Result<HashMap<String, String>, String> {
+    fields
+        .into_iter()
+        .map(|field| match field.split_once('=') {
+            Some((name, path)) => Ok((name.to_string(), path.to_string())),
+            None => Err(format!(
+                "Invalid field format '{}', use NAME=JSONPATH",
+                field
+            )),
+        })
+        .collect()
+}
+
+fn print_webhook(url: &str, webhook: &WebhookResponse) {
+    println!("  ID: {}", webhook.id);
+    println!("  Event type: {}", webhook.event_type);
+    println!("  URL: {}/hooks/{}", url, webhook.event_type);
+    if webhook.signed {
+        println!("  Signature header: {}", webhook.signature_header);
+    } else {
+        println!("  Signature: none (allowed write IPs only)");
+    }
+    if !webhook.fields.is_empty() {
+        println!("  Fields:");
+        let mut fields: Vec<_> = webhook.fields.iter().collect();
+        fields.sort();
+        for (name, path) in fields {
+            println!("    {}={}", name, path);
+        }
+    }
+}
+
+pub async fn execute(url: &str, action: WebhookAction) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    match action {
+        WebhookAction::Add {
+            event_type,
+            secret,
+            generate_secret: generate,
+            header,
+            field,
+        } => {
+            let generated = generate.then(generate_secret);
+            let request = CreateWebhookRequest {
+                event_type,
+                secret: generated.clone().or(secret),
+                signature_header: header,
+                fields: parse_fields(field)?,
+            };
+
+            let resp = client
+                .post(format!("{}/webhooks", url))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let webhook: WebhookResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Webhook added:");
+                print_webhook(url, &webhook);
+                if let Some(secret) = generated {
+                    println!("  Secret: {}", secret);
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WebhookAction::Update {
+            event_type,
+            secret,
+            generate_secret: generate,
+            no_secret,
+            header,
+            field,
+            no_fields,
+        } => {
+            let generated = generate.then(generate_secret);
+            let request = UpdateWebhookRequest {
+                secret: if no_secret {
+                    Some(None)
+                } else {
+                    generated.clone().or(secret).map(Some)
+                },
+                signature_header: header,
+                fields: if no_fields || !field.is_empty() {
+                    Some(parse_fields(field)?)
+                } else {
+                    None
+                },
+            };
+
+            let resp = client
+                .put(format!("{}/webhooks/{}", url, event_type))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let webhook: WebhookResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Webhook updated (new UUID generated):");
+                print_webhook(url, &webhook);
+                if let Some(secret) = generated {
+                    println!("  Secret: {}", secret);
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WebhookAction::Remove { event_type } => {
+            let resp = client
+                .delete(format!("{}/webhooks/{}", url, event_type))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                println!("Webhook '{}' removed", event_type);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Webhook '{}' not found", event_type);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WebhookAction::List => {
+            let resp = client
+                .get(format!("{}/webhooks", url))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let webhooks: Vec<WebhookResponse> = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+                if webhooks.is_empty() {
+                    println!("No webhooks configured");
+                } else {
+                    println!(
+                        "{:<20} {:<8} {:<24} {:<8} ID",
+                        "EVENT_TYPE", "SIGNED", "SIGNATURE_HEADER", "FIELDS"
+                    );
+                    println!("{}", "-".repeat(100));
+                    for w in webhooks {
+                        println!(
+                            "{:<20} {:<8} {:<24} {:<8} {}",
+                            w.event_type,
+                            if w.signed { "yes" } else { "no" },
+                            if w.signed { &w.signature_header } else { "-" },
+                            w.fields.len(),
+                            w.id
+                        );
+                    }
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WebhookAction::Show { event_type } => {
+            let resp = client
+                .get(format!("{}/webhooks/{}", url, event_type))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let webhook: WebhookResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Webhook: {}", webhook.event_type);
+                print_webhook(url, &webhook);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Webhook '{}' not found", event_type);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,441 @@
+use chrono::NaiveDate;
+use clap::Subcommand;
+use shev_core::WindowAction as SuppressAction;
+use shev_core::api::{CreateWindowRequest, UpdateWindowRequest, WindowResponse};
+
+use super::parse_time;
+
+#[derive(Subcommand)]
+pub enum WindowAction {
+    /// Add a blackout window
+    Add {
+        /// Window name
+        name: String,
+        /// Event type to suppress (default: all event types)
+        #[arg(long, short)]
+        event_type: Option<String>,
+        /// Handler of the event type to suppress (default: all handlers)
+        #[arg(long, short = 'n', requires = "event_type")]
+        handler: Option<String>,
+        /// What to do with suppressed events (drop, defer)
+        #[arg(long, short, default_value = "drop")]
+        action: String,
+        /// Daily start time (HH:MM)
+        #[arg(long, short, requires = "to")]
+        from: Option<String>,
+        /// Daily end time (HH:MM); may be before --from for windows spanning midnight
+        #[arg(long, short, requires = "from")]
+        to: Option<String>,
+        /// Days of the week, e.g. mon-fri, sat,sun, weekdays or weekends (default: every day)
+        #[arg(long)]
+        days: Option<String>,
+        /// Date the window applies to (YYYY-MM-DD, repeatable)
+        #[arg(long = "date")]
+        dates: Vec<String>,
+        /// File with one date per line (YYYY-MM-DD, text after the date and # comments ignored)
+        #[arg(long)]
+        dates_file: Option<String>,
+        /// IANA time zone for times, days and dates, e.g. Europe/Berlin (default: UTC)
+        #[arg(long)]
+        tz: Option<String>,
+        /// The window applies from this time on (RFC3339)
+        #[arg(long)]
+        start: Option<String>,
+        /// The window no longer applies from this time on (RFC3339)
+        #[arg(long)]
+        end: Option<String>,
+        /// Description, e.g. the reason for a maintenance freeze
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+    /// Update a blackout window
+    Update {
+        /// Window name
+        name: String,
+        /// Event type to suppress
+        #[arg(long, short)]
+        event_type: Option<String>,
+        /// Apply to all event types
+        #[arg(long, conflicts_with_all = ["event_type", "handler"])]
+        all_events: bool,
+        /// Handler of the event type to suppress
+        #[arg(long, short = 'n')]
+        handler: Option<String>,
+        /// Apply to all handlers of the event type
+        #[arg(long, conflicts_with = "handler")]
+        all_handlers: bool,
+        /// What to do with suppressed events (drop, defer)
+        #[arg(long, short)]
+        action: Option<String>,
+        /// Daily start time (HH:MM)
+        #[arg(long, short)]
+        from: Option<String>,
+        /// Daily end time (HH:MM)
+        #[arg(long, short)]
+        to: Option<String>,
+        /// Remove the daily times (the window covers whole days)
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        all_day: bool,
+        /// Days of the week, e.g. mon-fri
+        #[arg(long)]
+        days: Option<String>,
+        /// Apply on every day of the week
+        #[arg(long, conflicts_with = "days")]
+        every_day: bool,
+        /// Date the window applies to (YYYY-MM-DD, repeatable; replaces the current dates)
+        #[arg(long = "date")]
+        dates: Vec<String>,
+        /// File with one date per line (replaces the current dates)
+        #[arg(long)]
+        dates_file: Option<String>,
+        /// Remove the date list
+        #[arg(long, conflicts_with_all = ["dates", "dates_file"])]
+        no_dates: bool,
+        /// IANA time zone for times, days and dates
+        #[arg(long)]
+        tz: Option<String>,
+        /// Use UTC
+        #[arg(long, conflicts_with = "tz")]
+        no_tz: bool,
+        /// The window applies from this time on (RFC3339)
+        #[arg(long)]
+        start: Option<String>,
+        /// Remove the start time
+        #[arg(long, conflicts_with = "start")]
+        no_start: bool,
+        /// The window no longer applies from this time on (RFC3339)
+        #[arg(long)]
+        end: Option<String>,
+        /// Remove the end time
+        #[arg(long, conflicts_with = "end")]
+        no_end: bool,
+        /// Description
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// Remove a blackout window
+    Remove {
+        /// Window name
+        name: String,
+    },
+    /// List all blackout windows
+    List,
+    /// Show details of a blackout window
+    Show {
+        /// Window name
+        name: String,
+    },
+}
+
+fn parse_action(value: &str) -> Result<SuppressAction, String> {
+    SuppressAction::from_str(value)
+        .ok_or_else(|| format!("Invalid window action '{}', use drop or defer", value))
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}. Use YYYY-MM-DD", value, e))
+}
+
+/// Collect dates from the command line and from a holiday-calendar style file
+fn collect_dates(dates: &[String], dates_file: Option<&str>) -> Result<Vec<NaiveDate>, String> {
+    let mut result = dates
+        .iter()
+        .map(|d| parse_date(d))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(path) = dates_file {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            if let Some(date) = line.split_whitespace().next() {
+                result.push(parse_date(date)?);
+            }
+        }
+    }
+    result.sort();
+    result.dedup();
+    Ok(result)
+}
+
+fn split_days(days: &str) -> Vec<String> {
+    days.split(',').map(|d| d.trim().to_string()).collect()
+}
+
+fn applies_to(window: &WindowResponse) -> String {
+    match (&window.event_type, &window.handler_name) {
+        (Some(event_type), Some(handler)) => format!("{}/{}", event_type, handler),
+        (Some(event_type), None) => event_type.clone(),
+        _ => "all".to_string(),
+    }
+}
+
+/// Short description of when the window applies
+fn describe_when(window: &WindowResponse) -> String {
+    let mut parts = Vec::new();
+    if let (Some(from), Some(to)) = (&window.start_time, &window.end_time) {
+        parts.push(format!("{}-{}", from, to));
+    }
+    if !window.days.is_empty() {
+        parts.push(window.days.join(","));
+    }
+    if !window.dates.is_empty() {
+        parts.push(format!("{} date(s)", window.dates.len()));
+    }
+    if parts.is_empty() {
+        parts.push("always".to_string());
+    }
+    parts.join(" ")
+}
+
+fn print_window(window: &WindowResponse) {
+    println!("  ID: {}", window.id);
+    println!("  Name: {}", window.name);
+    if !window.description.is_empty() {
+        println!("  Description: {}", window.description);
+    }
+    println!("  Applies to: {}", applies_to(window));
+    println!("  Action: {}", window.action.as_str());
+    if let (Some(from), Some(to)) = (&window.start_time, &window.end_time) {
+        println!("  Daily: {} to {}", from, to);
+    }
+    if !window.days.is_empty() {
+        println!("  Days: {}", window.days.join(", "));
+    }
+    if !window.dates.is_empty() {
+        let dates: Vec<String> = window.dates.iter().map(|d| d.to_string()).collect();
+        println!("  Dates: {}", dates.join(", "));
+    }
+    println!(
+        "  Time zone: {}",
+        window.timezone.as_deref().unwrap_or("UTC")
+    );
+    if let Some(start) = window.starts_at {
+        println!("  Starts: {}", start);
+    }
+    if let Some(end) = window.ends_at {
+        println!("  Ends: {}", end);
+    }
+    println!("  Active now: {}", if window.active { "yes" } else { "no" });
+}
+
+pub async fn execute(url: &str, action: WindowAction) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    match action {
+        WindowAction::Add {
+            name,
+            event_type,
+            handler,
+            action,
+            from,
+            to,
+            days,
+            dates,
+            dates_file,
+            tz,
+            start,
+            end,
+            description,
+        } => {
+            let request = CreateWindowRequest {
+                name,
+                description,
+                event_type,
+                handler_name: handler,
+                action: parse_action(&action)?,
+                start_time: from,
+                end_time: to,
+                days: days.as_deref().map(split_days).unwrap_or_default(),
+                dates: collect_dates(&dates, dates_file.as_deref())?,
+                timezone: tz,
+                starts_at: start.as_deref().map(parse_time).transpose()?,
+                ends_at: end.as_deref().map(parse_time).transpose()?,
+            };
+
+            let resp = client
+                .post(format!("{}/windows", url))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let window: WindowResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Window added:");
+                print_window(&window);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WindowAction::Update {
+            name,
+            event_type,
+            all_events,
+            handler,
+            all_handlers,
+            action,
+            from,
+            to,
+            all_day,
+            days,
+            every_day,
+            dates,
+            dates_file,
+            no_dates,
+            tz,
+            no_tz,
+            start,
+            no_start,
+            end,
+            no_end,
+            description,
+        } => {
+            let dates = if no_dates {
+                Some(Vec::new())
+            } else if !dates.is_empty() || dates_file.is_some() {
+                Some(collect_dates(&dates, dates_file.as_deref())?)
+            } else {
+                None
+            };
+            let start_at = start.as_deref().map(parse_time).transpose()?;
+            let end_at = end.as_deref().map(parse_time).transpose()?;
+            let request = UpdateWindowRequest {
+                description,
+                event_type: if all_events {
+                    Some(None)
+                } else {
+                    event_type.map(Some)
+                },
+                handler_name: if all_handlers || all_events {
+                    Some(None)
+                } else {
+                    handler.map(Some)
+                },
+                action: action.as_deref().map(parse_action).transpose()?,
+                start_time: if all_day { Some(None) } else { from.map(Some) },
+                end_time: if all_day { Some(None) } else { to.map(Some) },
+                days: if every_day {
+                    Some(Vec::new())
+                } else {
+                    days.as_deref().map(split_days)
+                },
+                dates,
+                timezone: if no_tz { Some(None) } else { tz.map(Some) },
+                starts_at: if no_start {
+                    Some(None)
+                } else {
+                    start_at.map(Some)
+                },
+                ends_at: if no_end { Some(None) } else { end_at.map(Some) },
+            };
+
+            let resp = client
+                .put(format!("{}/windows/{}", url, name))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let window: WindowResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Window updated:");
+                print_window(&window);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Window '{}' not found", name);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WindowAction::Remove { name } => {
+            let resp = client
+                .delete(format!("{}/windows/{}", url, name))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                println!("Window '{}' removed", name);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Window '{}' not found", name);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WindowAction::List => {
+            let resp = client
+                .get(format!("{}/windows", url))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let windows: Vec<WindowResponse> = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+                if windows.is_empty() {
+                    println!("No windows configured");
+                } else {
+                    println!(
+                        "{:<20} {:<24} {:<8} {:<7} {:<30} TIME_ZONE",
+                        "NAME", "APPLIES_TO", "ACTION", "ACTIVE", "WHEN"
+                    );
+                    println!("{}", "-".repeat(110));
+                    for w in windows {
+                        println!(
+                            "{:<20} {:<24} {:<8} {:<7} {:<30} {}",
+                            w.name,
+                            applies_to(&w),
+                            w.action.as_str(),
+                            if w.active { "yes" } else { "no" },
+                            describe_when(&w),
+                            w.timezone.as_deref().unwrap_or("UTC")
+                        );
+                    }
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WindowAction::Show { name } => {
+            let resp = client
+                .get(format!("{}/windows/{}", url, name))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let window: WindowResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Window: {}", window.name);
+                print_window(&window);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Window '{}' not found", name);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+    }
+
+    Ok(())
+}
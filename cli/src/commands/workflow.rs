@@ -0,0 +1,453 @@
+use clap::Subcommand;
+use shev_core::api::{
+    CreateWorkflowRequest, RunWorkflowRequest, UpdateWorkflowRequest, WorkflowResponse,
+};
+use shev_core::{DEFAULT_HANDLER_NAME, StepCondition, WorkflowRun, WorkflowStep};
+use uuid::Uuid;
+
+#[derive(Subcommand)]
+pub enum WorkflowAction {
+    /// Add a workflow: a DAG of steps, each running a job of a handler
+    Add {
+        /// Workflow name
+        name: String,
+        /// Description
+        #[arg(long, short)]
+        description: Option<String>,
+        /// Step as NAME=EVENT_TYPE[/HANDLER] (can be used multiple times)
+        #[arg(long = "step", short, required = true)]
+        steps: Vec<String>,
+        /// Steps a step waits for as STEP=DEP[,DEP...] (can be used multiple times)
+        #[arg(long, short)]
+        needs: Vec<String>,
+        /// When a step runs as STEP=CONDITION: success (all dependencies completed, the
+        /// default), failure (any dependency failed) or always
+        #[arg(long, short)]
+        when: Vec<String>,
+    },
+    /// Update an existing workflow (generates new UUID)
+    Update {
+        /// Workflow name
+        name: String,
+        /// Description
+        #[arg(long, short)]
+        description: Option<String>,
+        /// Step as NAME=EVENT_TYPE[/HANDLER] (replaces the current steps)
+        #[arg(long = "step", short)]
+        steps: Vec<String>,
+        /// Steps a step waits for as STEP=DEP[,DEP...] (STEP= removes them)
+        #[arg(long, short)]
+        needs: Vec<String>,
+        /// When a step runs as STEP=CONDITION (success, failure or always)
+        #[arg(long, short)]
+        when: Vec<String>,
+    },
+    /// Remove a workflow
+    Remove {
+        /// Workflow name
+        name: String,
+    },
+    /// List all workflows
+    List,
+    /// Show a workflow, or the status of each step of a run
+    Show {
+        /// Workflow name or run ID
+        target: String,
+    },
+    /// Start a run of a workflow
+    Run {
+        /// Workflow name
+        name: String,
+        /// Context to pass to the handlers
+        #[arg(long, short, default_value = "")]
+        context: String,
+    },
+    /// List workflow runs, latest first
+    Runs {
+        /// Only list runs of this workflow
+        name: Option<String>,
+        /// Maximum number of runs to show
+        #[arg(long, short, default_value = "20")]
+        limit: usize,
+    },
+}
+
+fn parse_step(spec: &str) -> Result<WorkflowStep, String> {
+    let (name, target) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid step '{}', use NAME=EVENT_TYPE[/HANDLER]", spec))?;
+    let (event_type, handler) = target
+        .split_once('/')
+        .unwrap_or((target, DEFAULT_HANDLER_NAME));
+    Ok(WorkflowStep {
+        name: name.to_string(),
+        event_type: event_type.to_string(),
+        handler: handler.to_string(),
+        depends_on: Vec::new(),
+        condition: StepCondition::default(),
+    })
+}
+
+fn find_step<'a>(
+    steps: &'a mut [WorkflowStep],
+    spec: &'a str,
+) -> Result<(&'a mut WorkflowStep, &'a str), String> {
+    let (name, value) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid value '{}', use STEP=...", spec))?;
+    let step = steps
+        .iter_mut()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Unknown step '{}'", name))?;
+    Ok((step, value))
+}
+
+/// Apply --needs and --when to the steps
+fn link_steps(steps: &mut [WorkflowStep], needs: &[String], when: &[String]) -> Result<(), String> {
+    for spec in needs {
+        let (step, deps) = find_step(steps, spec)?;
+        step.depends_on = deps
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(String::from)
+            .collect();
+    }
+    for spec in when {
+        let (step, condition) = find_step(steps, spec)?;
+        step.condition = StepCondition::from_str(condition).ok_or_else(|| {
+            format!(
+                "Invalid condition '{}', use success, failure or always",
+                condition
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn print_workflow(workflow: &WorkflowResponse) {
+    println!("  ID: {}", workflow.id);
+    println!("  Name: {}", workflow.name);
+    if !workflow.description.is_empty() {
+        println!("  Description: {}", workflow.description);
+    }
+    println!("  Steps:");
+    for step in &workflow.steps {
+        let mut line = format!("    {}: {}/{}", step.name, step.event_type, step.handler);
+        if !step.depends_on.is_empty() {
+            line.push_str(&format!(" after {}", step.depends_on.join(", ")));
+        }
+        if step.condition != StepCondition::Success {
+            line.push_str(&format!(" ({})", step.condition.as_str()));
+        }
+        println!("{}", line);
+    }
+}
+
+fn print_run(run: &WorkflowRun) {
+    println!("Run: {}", run.id);
+    println!("  Workflow: {}", run.workflow);
+    println!("  Status: {}", run.status.as_str());
+    if !run.context.is_empty() {
+        println!("  Context: {}", run.context);
+    }
+    println!("  Started: {}", run.started_at.to_rfc3339());
+    if let Some(finished) = run.finished_at {
+        println!("  Finished: {}", finished.to_rfc3339());
+    }
+    println!();
+    println!(
+        "{:<16} {:<10} {:<36} {:<10} NEEDS",
+        "STEP", "STATUS", "JOB_ID", "DURATION"
+    );
+    println!("{}", "-".repeat(100));
+    for s in &run.steps {
+        let job_id = s
+            .job_id
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let duration = match (s.started_at, s.finished_at) {
+            (Some(started), Some(finished)) => {
+                format!("{}s", (finished - started).num_seconds())
+            }
+            _ => "-".to_string(),
+        };
+        let mut needs = s.step.depends_on.join(",");
+        if s.step.condition != StepCondition::Success {
+            needs = format!("{} ({})", needs, s.step.condition.as_str());
+        }
+        println!(
+            "{:<16} {:<10} {:<36} {:<10} {}",
+            s.step.name,
+            s.status.as_str(),
+            job_id,
+            duration,
+            needs.trim()
+        );
+    }
+    for s in run.steps.iter().filter(|s| s.error.is_some()) {
+        println!(
+            "  {}: {}",
+            s.step.name,
+            s.error.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+pub async fn execute(url: &str, action: WorkflowAction) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    match action {
+        WorkflowAction::Add {
+            name,
+            description,
+            steps,
+            needs,
+            when,
+        } => {
+            let mut steps = steps
+                .iter()
+                .map(|s| parse_step(s))
+                .collect::<Result<Vec<_>, _>>()?;
+            link_steps(&mut steps, &needs, &when)?;
+            let request = CreateWorkflowRequest {
+                name,
+                description: description.unwrap_or_default(),
+                steps,
+            };
+
+            let resp = client
+                .post(format!("{}/workflows", url))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let workflow: WorkflowResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Workflow added:");
+                print_workflow(&workflow);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WorkflowAction::Update {
+            name,
+            description,
+            steps,
+            needs,
+            when,
+        } => {
+            let steps = if !steps.is_empty() {
+                let mut steps = steps
+                    .iter()
+                    .map(|s| parse_step(s))
+                    .collect::<Result<Vec<_>, _>>()?;
+                link_steps(&mut steps, &needs, &when)?;
+                Some(steps)
+            } else if !needs.is_empty() || !when.is_empty() {
+                // Re-link the current steps
+                let resp = client
+                    .get(format!("{}/workflows/{}", url, name))
+                    .send()
+                    .await
+                    .map_err(|e| format!("Failed to connect to server: {}", e))?;
+                if !resp.status().is_success() {
+                    return Err(format!("Workflow '{}' not found", name));
+                }
+                let existing: WorkflowResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                let mut steps = existing.steps;
+                link_steps(&mut steps, &needs, &when)?;
+                Some(steps)
+            } else {
+                None
+            };
+            let request = UpdateWorkflowRequest { description, steps };
+
+            let resp = client
+                .put(format!("{}/workflows/{}", url, name))
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let workflow: WorkflowResponse = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Workflow updated (new UUID generated):");
+                print_workflow(&workflow);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WorkflowAction::Remove { name } => {
+            let resp = client
+                .delete(format!("{}/workflows/{}", url, name))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                println!("Workflow '{}' removed", name);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Workflow '{}' not found", name);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WorkflowAction::List => {
+            let resp = client
+                .get(format!("{}/workflows", url))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let workflows: Vec<WorkflowResponse> = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+                if workflows.is_empty() {
+                    println!("No workflows configured");
+                } else {
+                    println!("{:<20} {:<6} {:<36} DESCRIPTION", "NAME", "STEPS", "ID");
+                    println!("{}", "-".repeat(100));
+                    for w in workflows {
+                        println!(
+                            "{:<20} {:<6} {:<36} {}",
+                            w.name,
+                            w.steps.len(),
+                            w.id,
+                            w.description
+                        );
+                    }
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WorkflowAction::Show { target } => {
+            let is_run = Uuid::parse_str(&target).is_ok();
+            let request_url = if is_run {
+                format!("{}/workflow-runs/{}", url, target)
+            } else {
+                format!("{}/workflows/{}", url, target)
+            };
+            let resp = client
+                .get(request_url)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                if is_run {
+                    let run: WorkflowRun = resp
+                        .json()
+                        .await
+                        .map_err(|e| format!("Failed to parse response: {}", e))?;
+                    print_run(&run);
+                } else {
+                    let workflow: WorkflowResponse = resp
+                        .json()
+                        .await
+                        .map_err(|e| format!("Failed to parse response: {}", e))?;
+                    println!("Workflow: {}", workflow.name);
+                    print_workflow(&workflow);
+                }
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                let kind = if is_run { "Run" } else { "Workflow" };
+                println!("{} '{}' not found", kind, target);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WorkflowAction::Run { name, context } => {
+            let resp = client
+                .post(format!("{}/workflows/{}/run", url, name))
+                .json(&RunWorkflowRequest { context })
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let run: WorkflowRun = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+                println!("Workflow '{}' started", name);
+                println!("  Run ID: {}", run.id);
+                println!("  Follow with: shev workflow show {}", run.id);
+            } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                println!("Workflow '{}' not found", name);
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+        WorkflowAction::Runs { name, limit } => {
+            let mut request_url = format!("{}/workflow-runs", url);
+            if let Some(ref name) = name {
+                request_url = format!("{}?workflow={}", request_url, name);
+            }
+            let resp = client
+                .get(&request_url)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to connect to server: {}", e))?;
+
+            if resp.status().is_success() {
+                let runs: Vec<WorkflowRun> = resp
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+                if runs.is_empty() {
+                    println!("No workflow runs found");
+                } else {
+                    println!(
+                        "{:<36} {:<20} {:<10} {:<8} STARTED",
+                        "RUN_ID", "WORKFLOW", "STATUS", "STEPS"
+                    );
+                    println!("{}", "-".repeat(100));
+                    for r in runs.iter().take(limit) {
+                        let done = r.steps.iter().filter(|s| s.status.is_finished()).count();
+                        println!(
+                            "{:<36} {:<20} {:<10} {:<8} {}",
+                            r.id,
+                            r.workflow,
+                            r.status.as_str(),
+                            format!("{}/{}", done, r.steps.len()),
+                            r.started_at.format("%Y-%m-%d %H:%M")
+                        );
+                    }
+                }
+            } else {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Server returned error {}: {}", status, body));
+            }
+        }
+    }
+
+    Ok(())
+}
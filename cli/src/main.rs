@@ -2,7 +2,9 @@ mod commands;
 
 use clap::{Parser, Subcommand};
 
-use commands::{config, event, handler, job, schedule, timer};
+use commands::{
+    config, event, handler, import, job, schedule, tail, timer, watcher, webhook, window, workflow,
+};
 
 const DEFAULT_URL: &str = "http://127.0.0.1:3000";
 
@@ -39,6 +41,31 @@ enum Commands {
         #[command(subcommand)]
         action: schedule::ScheduleAction,
     },
+    /// Manage file system watchers
+    Watcher {
+        #[command(subcommand)]
+        action: watcher::WatcherAction,
+    },
+    /// Manage log file tails
+    Tail {
+        #[command(subcommand)]
+        action: tail::TailAction,
+    },
+    /// Manage inbound webhooks
+    Webhook {
+        #[command(subcommand)]
+        action: webhook::WebhookAction,
+    },
+    /// Manage blackout windows
+    Window {
+        #[command(subcommand)]
+        action: window::WindowAction,
+    },
+    /// Manage workflows and their runs
+    Workflow {
+        #[command(subcommand)]
+        action: workflow::WorkflowAction,
+    },
     /// Query jobs
     Job {
         #[command(subcommand)]
@@ -49,12 +76,17 @@ enum Commands {
         #[command(subcommand)]
         action: event::EventAction,
     },
+    /// Import jobs from other schedulers
+    Import {
+        #[command(subcommand)]
+        action: import::ImportAction,
+    },
     /// Manage configuration
     Config {
         #[command(subcommand)]
         action: config::ConfigAction,
     },
-    /// Reload handlers/timers/schedules in running server
+    /// Reload handlers/timers/schedules/watchers/tails/webhooks/windows/workflows in running server
     Reload,
 }
 
@@ -67,8 +99,14 @@ async fn main() {
         Commands::Handler { action } => handler::execute(&url, action).await,
         Commands::Timer { action } => timer::execute(&url, action).await,
         Commands::Schedule { action } => schedule::execute(&url, action).await,
+        Commands::Watcher { action } => watcher::execute(&url, action).await,
+        Commands::Tail { action } => tail::execute(&url, action).await,
+        Commands::Webhook { action } => webhook::execute(&url, action).await,
+        Commands::Window { action } => window::execute(&url, action).await,
+        Commands::Workflow { action } => workflow::execute(&url, action).await,
         Commands::Job { action } => job::execute(&url, action).await,
         Commands::Event { action } => event::execute(&url, action).await,
+        Commands::Import { action } => import::execute(&url, action).await,
         Commands::Config { action } => config::execute(&url, action).await,
         Commands::Reload => reload(&url).await,
     };
@@ -96,6 +134,11 @@ async fn reload(url: &str) -> Result<(), String> {
         println!("  Handlers loaded: {}", body["handlers_loaded"]);
         println!("  Timers loaded: {}", body["timers_loaded"]);
         println!("  Schedules loaded: {}", body["schedules_loaded"]);
+        println!("  Watchers loaded: {}", body["watchers_loaded"]);
+        println!("  Tails loaded: {}", body["tails_loaded"]);
+        println!("  Webhooks loaded: {}", body["webhooks_loaded"]);
+        println!("  Windows loaded: {}", body["windows_loaded"]);
+        println!("  Workflows loaded: {}", body["workflows_loaded"]);
         Ok(())
     } else {
         Err(format!("Server returned error: {}", resp.status()))
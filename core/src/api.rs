@@ -1,9 +1,14 @@
 use std::collections::HashMap;
 
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use utoipa::ToSchema;
 
+use crate::models::{
+    DEFAULT_HANDLER_NAME, FileChange, MisfirePolicy, OverlapPolicy, RetryPolicy, ScheduleState,
+    ThrottlePolicy, WindowAction, WorkflowStep, parse_duration_secs,
+};
+
 // ============================================================================
 // Handler types
 // ============================================================================
@@ -12,20 +17,71 @@ use utoipa::ToSchema;
 pub struct HandlerResponse {
     pub id: String,
     pub event_type: String,
+    pub name: String,
     pub shell: String,
     pub command: String,
     pub timeout: Option<u32>,
     pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub on_success: Option<String>,
+    #[serde(default)]
+    pub on_failure: Option<String>,
+    #[serde(default)]
+    pub on_timeout: Option<String>,
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub debounce_secs: Option<u32>,
+    #[serde(default)]
+    pub throttle: Option<ThrottlePolicy>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateHandlerRequest {
     pub event_type: String,
+    #[serde(default = "default_handler_name")]
+    pub name: String,
     pub shell: String,
     pub command: String,
     pub timeout: Option<u32>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    /// Event type to trigger when a job completes
+    #[serde(default)]
+    pub on_success: Option<String>,
+    /// Event type to trigger when a job fails (after its retries)
+    #[serde(default)]
+    pub on_failure: Option<String>,
+    /// Event type to trigger when a job times out (defaults to `on_failure`)
+    #[serde(default)]
+    pub on_timeout: Option<String>,
+    /// Only create jobs for events whose context matches, e.g. `context.branch == "main"`
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Run once with the last event after no event arrived for this many seconds
+    #[serde(default)]
+    pub debounce_secs: Option<u32>,
+    /// Limit how many jobs start within a window
+    #[serde(default)]
+    pub throttle: Option<ThrottlePolicy>,
+}
+
+fn default_handler_name() -> String {
+    DEFAULT_HANDLER_NAME.to_string()
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -34,6 +90,80 @@ pub struct UpdateHandlerRequest {
     pub command: Option<String>,
     pub timeout: Option<Option<u32>>,
     pub env: Option<HashMap<String, String>>,
+    /// Omit to keep the current policy, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub retry: Option<Option<RetryPolicy>>,
+    /// Omit to keep the current limit, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub max_concurrency: Option<Option<u32>>,
+    /// Omit to keep the current event type, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub on_success: Option<Option<String>>,
+    /// Omit to keep the current event type, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub on_failure: Option<Option<String>>,
+    /// Omit to keep the current event type, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub on_timeout: Option<Option<String>>,
+    /// Omit to keep the current filter, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub filter: Option<Option<String>>,
+    /// Omit to keep the current quiet period, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub debounce_secs: Option<Option<u32>>,
+    /// Omit to keep the current policy, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub throttle: Option<Option<ThrottlePolicy>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FilterTestRequest {
+    /// Expression to try; omit it to try the filters of the event type's handlers
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Sample event context, a string or JSON
+    #[serde(default, deserialize_with = "deserialize_context")]
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FilterTestResult {
+    /// Name of the handler whose filter was tried, if any
+    pub handler: Option<String>,
+    pub filter: Option<String>,
+    pub matches: bool,
 }
 
 // ============================================================================
@@ -46,20 +176,141 @@ pub struct TimerResponse {
     pub event_type: String,
     pub interval_secs: u32,
     pub context: String,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    #[serde(default)]
+    pub last_fired_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub next_fire_at: Option<DateTime<Utc>>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub align: bool,
+    #[serde(default)]
+    pub jitter_secs: u32,
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub max_fires: Option<u32>,
+    /// Number of events the timer has produced
+    #[serde(default)]
+    pub fire_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateTimerRequest {
     pub event_type: String,
+    /// Seconds, or a duration such as `1h30m`
+    #[serde(deserialize_with = "deserialize_duration_secs")]
     pub interval_secs: u32,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_context")]
     pub context: String,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    /// Fire on multiples of the interval (e.g. :00, :15, :30 and :45 for 15 minutes)
+    #[serde(default)]
+    pub align: bool,
+    /// Delay each event by a random amount up to this many seconds (or a duration such as `30s`)
+    #[serde(default, deserialize_with = "deserialize_duration_secs")]
+    pub jitter_secs: u32,
+    /// Do not fire before this time
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+    /// Stop firing after this time
+    #[serde(default)]
+    pub end_at: Option<DateTime<Utc>>,
+    /// Stop after this many events
+    #[serde(default)]
+    pub max_fires: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateTimerRequest {
+    /// Seconds, or a duration such as `1h30m`
+    #[serde(default, deserialize_with = "deserialize_optional_duration_secs")]
     pub interval_secs: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_optional_context")]
     pub context: Option<String>,
+    #[serde(default)]
+    pub overlap: Option<OverlapPolicy>,
+    #[serde(default)]
+    pub align: Option<bool>,
+    /// Seconds, or a duration such as `30s`
+    #[serde(default, deserialize_with = "deserialize_optional_duration_secs")]
+    pub jitter_secs: Option<u32>,
+    /// Omit to keep the current start, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub start_at: Option<Option<DateTime<Utc>>>,
+    /// Omit to keep the current end, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub end_at: Option<Option<DateTime<Utc>>>,
+    /// Omit to keep the current limit, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub max_fires: Option<Option<u32>>,
+}
+
+/// A duration given either as a number of seconds or as a string like `1h30m`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationSecs {
+    Secs(u32),
+    Text(String),
+}
+
+impl DurationSecs {
+    fn into_secs<E: serde::de::Error>(self) -> Result<u32, E> {
+        match self {
+            DurationSecs::Secs(secs) => Ok(secs),
+            DurationSecs::Text(text) => parse_duration_secs(&text).map_err(E::custom),
+        }
+    }
+}
+
+fn deserialize_duration_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    DurationSecs::deserialize(deserializer)?.into_secs()
+}
+
+fn deserialize_optional_duration_secs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    Option::<DurationSecs>::deserialize(deserializer)?
+        .map(DurationSecs::into_secs)
+        .transpose()
+}
+
+/// Accept an event context as a string or as JSON (e.g. an object), which is kept as JSON text
+pub fn deserialize_context<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    })
+}
+
+fn deserialize_optional_context<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::String(s)) => Some(s),
+            Some(other) => Some(other.to_string()),
+            None => None,
+        },
+    )
 }
 
 // ============================================================================
@@ -73,23 +324,390 @@ pub struct ScheduleResponse {
     pub scheduled_time: DateTime<Utc>,
     pub context: String,
     pub periodic: bool,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    #[serde(default)]
+    pub cron: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub misfire: MisfirePolicy,
+    #[serde(default)]
+    pub state: ScheduleState,
+    #[serde(default)]
+    pub last_fired_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub next_fire_at: Option<DateTime<Utc>>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rrule: Option<String>,
+    #[serde(default)]
+    pub exdates: Vec<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SchedulePreviewResponse {
+    pub event_type: String,
+    /// Upcoming fire times, earliest first
+    pub fire_times: Vec<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateScheduleRequest {
     pub event_type: String,
-    pub scheduled_time: DateTime<Utc>,
+    /// Time to fire at; for cron and rrule schedules the time to start from (defaults to now)
     #[serde(default)]
+    pub scheduled_time: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "deserialize_context")]
     pub context: String,
     #[serde(default)]
     pub periodic: bool,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    /// Cron expression (5 fields or an alias like @hourly)
+    #[serde(default)]
+    pub cron: Option<String>,
+    /// IANA time zone for the cron expression or rrule, e.g. Europe/Berlin (defaults to UTC)
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub misfire: MisfirePolicy,
+    /// RFC 5545 recurrence rule, e.g. `FREQ=MONTHLY;BYDAY=-1FR;BYHOUR=17`
+    #[serde(default)]
+    pub rrule: Option<String>,
+    /// Occurrences to leave out
+    #[serde(default)]
+    pub exdates: Vec<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateScheduleRequest {
     pub scheduled_time: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "deserialize_optional_context")]
     pub context: Option<String>,
     pub periodic: Option<bool>,
+    #[serde(default)]
+    pub overlap: Option<OverlapPolicy>,
+    /// Omit to keep the current expression, `null` to turn it back into a fixed-time schedule
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub cron: Option<Option<String>>,
+    /// Omit to keep the current time zone, `null` to use UTC
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub timezone: Option<Option<String>>,
+    #[serde(default)]
+    pub misfire: Option<MisfirePolicy>,
+    /// Omit to keep the current rule, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub rrule: Option<Option<String>>,
+    #[serde(default)]
+    pub exdates: Option<Vec<DateTime<Utc>>>,
+}
+
+// ============================================================================
+// Window types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WindowResponse {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub event_type: Option<String>,
+    pub handler_name: Option<String>,
+    pub action: WindowAction,
+    /// Daily start time (HH:MM)
+    pub start_time: Option<String>,
+    /// Daily end time (HH:MM)
+    pub end_time: Option<String>,
+    pub days: Vec<String>,
+    pub dates: Vec<NaiveDate>,
+    pub timezone: Option<String>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Whether the window is suppressing events right now
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateWindowRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Event type to suppress (all event types if omitted)
+    #[serde(default)]
+    pub event_type: Option<String>,
+    /// Handler of the event type to suppress (all handlers if omitted)
+    #[serde(default)]
+    pub handler_name: Option<String>,
+    #[serde(default)]
+    pub action: WindowAction,
+    /// Daily start time (HH:MM); may be after `end_time` for windows spanning midnight
+    #[serde(default)]
+    pub start_time: Option<String>,
+    /// Daily end time (HH:MM)
+    #[serde(default)]
+    pub end_time: Option<String>,
+    /// Days of the week, e.g. `mon`, `mon-fri`, `weekdays` or `weekends` (every day if empty)
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// Dates the window applies to, e.g. public holidays (every date if empty)
+    #[serde(default)]
+    pub dates: Vec<NaiveDate>,
+    /// IANA time zone for times, days and dates (defaults to UTC)
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateWindowRequest {
+    pub description: Option<String>,
+    /// Omit to keep the current event type, `null` to apply to all event types
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub event_type: Option<Option<String>>,
+    /// Omit to keep the current handler, `null` to apply to all handlers
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub handler_name: Option<Option<String>>,
+    #[serde(default)]
+    pub action: Option<WindowAction>,
+    /// Omit to keep the current time, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub start_time: Option<Option<String>>,
+    /// Omit to keep the current time, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub end_time: Option<Option<String>>,
+    pub days: Option<Vec<String>>,
+    pub dates: Option<Vec<NaiveDate>>,
+    /// Omit to keep the current time zone, `null` to use UTC
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub timezone: Option<Option<String>>,
+    /// Omit to keep the current start, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub starts_at: Option<Option<DateTime<Utc>>>,
+    /// Omit to keep the current end, `null` to remove it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub ends_at: Option<Option<DateTime<Utc>>>,
+}
+
+// ============================================================================
+// Watcher types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WatcherResponse {
+    pub id: String,
+    pub event_type: String,
+    pub paths: Vec<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub recursive: bool,
+    pub changes: Vec<FileChange>,
+    pub debounce_ms: u32,
+    pub overlap: OverlapPolicy,
+}
+
+fn default_changes() -> Vec<FileChange> {
+    FileChange::ALL.to_vec()
+}
+
+fn default_debounce_ms() -> u32 {
+    500
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateWatcherRequest {
+    pub event_type: String,
+    /// Absolute paths of files or directories to watch
+    pub paths: Vec<String>,
+    /// Globs a changed file must match, e.g. `*.csv` (all files if empty)
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Globs of files to ignore, e.g. `*.tmp`
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Also watch subdirectories
+    #[serde(default)]
+    pub recursive: bool,
+    /// Kinds of change that produce events (all by default)
+    #[serde(default = "default_changes")]
+    pub changes: Vec<FileChange>,
+    /// Milliseconds a file must be left alone before its change is reported
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u32,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateWatcherRequest {
+    pub paths: Option<Vec<String>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub recursive: Option<bool>,
+    pub changes: Option<Vec<FileChange>>,
+    pub debounce_ms: Option<u32>,
+    #[serde(default)]
+    pub overlap: Option<OverlapPolicy>,
+}
+
+// ============================================================================
+// Tail types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TailResponse {
+    pub id: String,
+    pub event_type: String,
+    pub path: String,
+    pub patterns: Vec<String>,
+    pub from_start: bool,
+    pub overlap: OverlapPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateTailRequest {
+    pub event_type: String,
+    /// Absolute path of the log file to follow
+    pub path: String,
+    /// Regular expressions; named capture groups end up in the event context
+    pub patterns: Vec<String>,
+    /// Also read the lines already in the file when the tail starts
+    #[serde(default)]
+    pub from_start: bool,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateTailRequest {
+    pub path: Option<String>,
+    pub patterns: Option<Vec<String>>,
+    pub from_start: Option<bool>,
+    #[serde(default)]
+    pub overlap: Option<OverlapPolicy>,
+}
+
+// ============================================================================
+// Webhook types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub event_type: String,
+    /// Whether requests must be signed; the secret itself is never returned
+    pub signed: bool,
+    pub signature_header: String,
+    pub fields: HashMap<String, String>,
+}
+
+fn default_signature_header() -> String {
+    crate::db::DEFAULT_SIGNATURE_HEADER.to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    pub event_type: String,
+    /// Key for the HMAC-SHA256 signature of the body (unsigned hooks only accept allowed write IPs)
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Header carrying the hex signature, with or without a `sha256=` prefix
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+    /// Environment variables to set from the JSON body, e.g. `{"GIT_REF": "$.ref"}`
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateWebhookRequest {
+    /// Omit to keep the current secret, `null` to accept unsigned requests
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub secret: Option<Option<String>>,
+    pub signature_header: Option<String>,
+    pub fields: Option<HashMap<String, String>>,
+}
+
+// ============================================================================
+// Workflow types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowResponse {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateWorkflowRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Steps of the DAG; each may depend on steps listed anywhere in the workflow
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateWorkflowRequest {
+    pub description: Option<String>,
+    pub steps: Option<Vec<WorkflowStep>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RunWorkflowRequest {
+    /// Context of the events of all step jobs
+    #[serde(default, deserialize_with = "deserialize_context")]
+    pub context: String,
 }
 
 // ============================================================================
@@ -101,8 +719,20 @@ pub struct JobResponse {
     pub id: String,
     pub event: EventResponse,
     pub handler_id: String,
+    #[serde(default)]
+    pub handler_name: String,
+    #[serde(default)]
+    pub attempt: u32,
+    #[serde(default)]
+    pub parent_job_id: Option<String>,
+    /// Events debounced, coalesced or dropped in favour of this job
+    #[serde(default)]
+    pub suppressed: u32,
     pub status: String,
-    pub output: Option<String>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<i64>,
     pub error: Option<String>,
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
@@ -114,6 +744,8 @@ pub struct EventResponse {
     pub event_type: String,
     pub context: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
 }
 
 // ============================================================================
@@ -124,12 +756,20 @@ pub struct EventResponse {
 pub struct ConfigResponse {
     pub port: String,
     pub queue_size: String,
+    #[serde(default)]
+    pub max_concurrent_jobs: String,
+    #[serde(default)]
+    pub idempotency_ttl_secs: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateConfigRequest {
     pub port: Option<String>,
     pub queue_size: Option<String>,
+    #[serde(default)]
+    pub max_concurrent_jobs: Option<String>,
+    #[serde(default)]
+    pub idempotency_ttl_secs: Option<String>,
 }
 
 // ============================================================================
@@ -152,14 +792,25 @@ pub struct StatusResponse {
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TriggerEventRequest {
     pub event_type: String,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_context")]
     pub context: String,
+    /// Triggering again with the same key returns the original event instead of a new one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TriggerEventResponse {
-    pub triggered: bool,
+    #[serde(flatten)]
+    pub event: EventResponse,
     pub message: String,
+    /// Whether the idempotency key was used before, so no new event was queued
+    #[serde(default)]
+    pub duplicate: bool,
+    /// Jobs of the original event created so far, for a duplicate; empty until the consumer
+    /// has created them
+    #[serde(default)]
+    pub job_ids: Vec<String>,
 }
 
 // ============================================================================
@@ -172,6 +823,16 @@ pub struct ReloadResponse {
     pub handlers_loaded: usize,
     pub timers_loaded: usize,
     pub schedules_loaded: usize,
+    #[serde(default)]
+    pub windows_loaded: usize,
+    #[serde(default)]
+    pub watchers_loaded: usize,
+    #[serde(default)]
+    pub webhooks_loaded: usize,
+    #[serde(default)]
+    pub tails_loaded: usize,
+    #[serde(default)]
+    pub workflows_loaded: usize,
 }
 
 // ============================================================================
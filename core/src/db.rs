@@ -1,22 +1,36 @@
 use std::collections::HashMap;
 use std::path::Path;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc, Weekday};
 use rusqlite::{Connection, params};
 use uuid::Uuid;
 
-use crate::models::{Event, EventHandler, Job, JobStatus, ShellType};
+use crate::models::{
+    Event, EventHandler, FileChange, Job, JobStatus, MisfirePolicy, OverlapPolicy, RetryPolicy,
+    RunStatus, ScheduleState, ShellType, StepStatus, WindowAction, WorkflowRun, WorkflowStep,
+};
 
 pub const SCHEMA: &str = r#"
 CREATE TABLE IF NOT EXISTS handlers (
     id TEXT PRIMARY KEY,
-    event_type TEXT UNIQUE NOT NULL,
+    event_type TEXT NOT NULL,
+    name TEXT NOT NULL DEFAULT 'default',
     shell TEXT NOT NULL,
     command TEXT NOT NULL,
     timeout INTEGER,
     env TEXT,
     created_at TEXT NOT NULL,
-    updated_at TEXT NOT NULL
+    updated_at TEXT NOT NULL,
+    retry TEXT,
+    max_concurrency INTEGER,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    on_success TEXT,
+    on_failure TEXT,
+    on_timeout TEXT,
+    filter TEXT,
+    debounce_secs INTEGER,
+    throttle TEXT,
+    UNIQUE (event_type, name)
 );
 
 CREATE TABLE IF NOT EXISTS timers (
@@ -25,7 +39,17 @@ CREATE TABLE IF NOT EXISTS timers (
     context TEXT DEFAULT '',
     interval_secs INTEGER NOT NULL,
     created_at TEXT NOT NULL,
-    updated_at TEXT NOT NULL
+    updated_at TEXT NOT NULL,
+    overlap TEXT NOT NULL DEFAULT 'allow',
+    last_fired_at TEXT,
+    next_fire_at TEXT,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    align INTEGER NOT NULL DEFAULT 0,
+    jitter_secs INTEGER NOT NULL DEFAULT 0,
+    start_at TEXT,
+    end_at TEXT,
+    max_fires INTEGER,
+    fire_count INTEGER NOT NULL DEFAULT 0
 );
 
 CREATE TABLE IF NOT EXISTS jobs (
@@ -36,10 +60,18 @@ CREATE TABLE IF NOT EXISTS jobs (
     event_timestamp TEXT NOT NULL,
     handler_id TEXT NOT NULL,
     status TEXT NOT NULL,
-    output TEXT,
+    stdout TEXT,
     error TEXT,
     started_at TEXT,
-    finished_at TEXT
+    finished_at TEXT,
+    handler_name TEXT NOT NULL DEFAULT 'default',
+    stderr TEXT,
+    exit_code INTEGER,
+    duration_ms INTEGER,
+    attempt INTEGER NOT NULL DEFAULT 1,
+    parent_job_id TEXT,
+    event_env TEXT NOT NULL DEFAULT '{}',
+    suppressed INTEGER NOT NULL DEFAULT 0
 );
 
 CREATE TABLE IF NOT EXISTS schedules (
@@ -49,9 +81,97 @@ CREATE TABLE IF NOT EXISTS schedules (
     scheduled_time TEXT NOT NULL,
     periodic INTEGER DEFAULT 0,
     created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    overlap TEXT NOT NULL DEFAULT 'allow',
+    cron TEXT,
+    timezone TEXT,
+    last_fired_at TEXT,
+    next_fire_at TEXT,
+    misfire TEXT NOT NULL DEFAULT 'fire_once',
+    state TEXT NOT NULL DEFAULT 'active',
+    enabled INTEGER NOT NULL DEFAULT 1,
+    rrule TEXT,
+    exdates TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS windows (
+    id TEXT PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    event_type TEXT,
+    handler_name TEXT,
+    action TEXT NOT NULL DEFAULT 'drop',
+    start_time TEXT,
+    end_time TEXT,
+    days TEXT NOT NULL DEFAULT '',
+    dates TEXT NOT NULL DEFAULT '',
+    timezone TEXT,
+    starts_at TEXT,
+    ends_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS watchers (
+    id TEXT PRIMARY KEY,
+    event_type TEXT UNIQUE NOT NULL,
+    paths TEXT NOT NULL,
+    include TEXT NOT NULL DEFAULT '[]',
+    exclude TEXT NOT NULL DEFAULT '[]',
+    recursive INTEGER NOT NULL DEFAULT 0,
+    changes TEXT NOT NULL DEFAULT 'created,modified,deleted',
+    debounce_ms INTEGER NOT NULL DEFAULT 500,
+    overlap TEXT NOT NULL DEFAULT 'allow',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tails (
+    id TEXT PRIMARY KEY,
+    event_type TEXT UNIQUE NOT NULL,
+    path TEXT NOT NULL,
+    patterns TEXT NOT NULL DEFAULT '[]',
+    from_start INTEGER NOT NULL DEFAULT 0,
+    overlap TEXT NOT NULL DEFAULT 'allow',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    id TEXT PRIMARY KEY,
+    event_type TEXT UNIQUE NOT NULL,
+    secret TEXT,
+    signature_header TEXT NOT NULL DEFAULT 'X-Hub-Signature-256',
+    fields TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS workflows (
+    id TEXT PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    steps TEXT NOT NULL DEFAULT '[]',
+    created_at TEXT NOT NULL,
     updated_at TEXT NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS workflow_runs (
+    id TEXT PRIMARY KEY,
+    workflow TEXT NOT NULL,
+    context TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL,
+    steps TEXT NOT NULL DEFAULT '[]',
+    started_at TEXT NOT NULL,
+    finished_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT PRIMARY KEY,
+    event TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS config (
     key TEXT PRIMARY KEY,
     value TEXT NOT NULL
@@ -61,12 +181,71 @@ INSERT OR IGNORE INTO config (key, value) VALUES ('port', '3000');
 INSERT OR IGNORE INTO config (key, value) VALUES ('queue_size', '100');
 "#;
 
+/// Handlers used to be unique per event type; rebuild the table so several named handlers can share one
+const MIGRATE_HANDLER_NAMES: &str = r#"
+BEGIN;
+ALTER TABLE handlers RENAME TO handlers_old;
+CREATE TABLE handlers (
+    id TEXT PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    name TEXT NOT NULL DEFAULT 'default',
+    shell TEXT NOT NULL,
+    command TEXT NOT NULL,
+    timeout INTEGER,
+    env TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (event_type, name)
+);
+INSERT INTO handlers (id, event_type, name, shell, command, timeout, env, created_at, updated_at)
+    SELECT id, event_type, 'default', shell, command, timeout, env, created_at, updated_at FROM handlers_old;
+DROP TABLE handlers_old;
+COMMIT;
+"#;
+
+const HANDLER_COLUMNS: &str = "id, event_type, name, shell, command, timeout, env, retry, max_concurrency, enabled, on_success, on_failure, on_timeout, filter, debounce_secs, throttle";
+
+const JOB_COLUMNS: &str = "id, event_id, event_type, event_context, event_timestamp, handler_id, status, stdout, error, started_at, finished_at, handler_name, stderr, exit_code, duration_ms, attempt, parent_job_id, event_env, suppressed";
+
+const TIMER_COLUMNS: &str = "id, event_type, context, interval_secs, overlap, last_fired_at, next_fire_at, enabled, align, jitter_secs, start_at, end_at, max_fires, fire_count";
+
+const WINDOW_COLUMNS: &str = "id, name, description, event_type, handler_name, action, start_time, end_time, days, dates, timezone, starts_at, ends_at";
+
+const TAIL_COLUMNS: &str = "id, event_type, path, patterns, from_start, overlap";
+
+const WEBHOOK_COLUMNS: &str = "id, event_type, secret, signature_header, fields";
+
+const WORKFLOW_COLUMNS: &str = "id, name, description, steps";
+
+const WORKFLOW_RUN_COLUMNS: &str = "id, workflow, context, status, steps, started_at, finished_at";
+
+const WATCHER_COLUMNS: &str =
+    "id, event_type, paths, include, exclude, recursive, changes, debounce_ms, overlap";
+
+const SCHEDULE_COLUMNS: &str = "id, event_type, context, scheduled_time, periodic, overlap, cron, timezone, last_fired_at, next_fire_at, misfire, state, enabled, rrule, exdates";
+
 #[derive(Debug, Clone)]
 pub struct TimerRecord {
     pub id: Uuid,
     pub event_type: String,
     pub context: String,
     pub interval_secs: u32,
+    pub overlap: OverlapPolicy,
+    /// When the timer last produced an event
+    pub last_fired_at: Option<DateTime<Utc>>,
+    /// When the timer will produce its next event
+    pub next_fire_at: Option<DateTime<Utc>>,
+    /// Paused timers keep their place but produce no events
+    pub enabled: bool,
+    /// Fire on multiples of the interval since the Unix epoch rather than relative to creation
+    pub align: bool,
+    /// Upper bound of the random delay added to each event
+    pub jitter_secs: u32,
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    /// Stop once the timer has produced this many events
+    pub max_fires: Option<u32>,
+    pub fire_count: u32,
 }
 
 impl TimerRecord {
@@ -76,6 +255,16 @@ impl TimerRecord {
             event_type,
             context,
             interval_secs,
+            overlap: OverlapPolicy::default(),
+            last_fired_at: None,
+            next_fire_at: None,
+            enabled: true,
+            align: false,
+            jitter_secs: 0,
+            start_at: None,
+            end_at: None,
+            max_fires: None,
+            fire_count: 0,
         }
     }
 }
@@ -87,6 +276,23 @@ pub struct ScheduleRecord {
     pub context: String,
     pub scheduled_time: DateTime<Utc>,
     pub periodic: bool,
+    pub overlap: OverlapPolicy,
+    /// Cron expression; when set the schedule repeats from `scheduled_time` on
+    pub cron: Option<String>,
+    /// IANA time zone the cron expression is evaluated in (UTC if unset)
+    pub timezone: Option<String>,
+    /// When the schedule last produced an event
+    pub last_fired_at: Option<DateTime<Utc>>,
+    /// When the schedule will produce its next event
+    pub next_fire_at: Option<DateTime<Utc>>,
+    pub misfire: MisfirePolicy,
+    pub state: ScheduleState,
+    /// Paused schedules skip the runs that fall due meanwhile
+    pub enabled: bool,
+    /// RFC 5545 recurrence rule; when set the schedule repeats from `scheduled_time` on
+    pub rrule: Option<String>,
+    /// Occurrences that are left out
+    pub exdates: Vec<DateTime<Utc>>,
 }
 
 impl ScheduleRecord {
@@ -102,6 +308,176 @@ impl ScheduleRecord {
             context,
             scheduled_time,
             periodic,
+            overlap: OverlapPolicy::default(),
+            cron: None,
+            timezone: None,
+            last_fired_at: None,
+            next_fire_at: None,
+            misfire: MisfirePolicy::default(),
+            state: ScheduleState::default(),
+            enabled: true,
+            rrule: None,
+            exdates: Vec::new(),
+        }
+    }
+}
+
+/// A period during which events are suppressed, e.g. nightly quiet hours or a maintenance freeze
+#[derive(Debug, Clone)]
+pub struct WindowRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    /// Event type the window applies to (all event types if unset)
+    pub event_type: Option<String>,
+    /// Handler of the event type the window applies to (all handlers if unset)
+    pub handler_name: Option<String>,
+    pub action: WindowAction,
+    /// Daily local start time; the window runs past midnight if it is after `end_time`
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    /// Days of the week the window applies to (every day if empty)
+    pub days: Vec<Weekday>,
+    /// Dates the window applies to, e.g. public holidays (every date if empty)
+    pub dates: Vec<NaiveDate>,
+    /// IANA time zone for the daily times, days and dates (UTC if unset)
+    pub timezone: Option<String>,
+    /// The window applies from this time on
+    pub starts_at: Option<DateTime<Utc>>,
+    /// The window no longer applies from this time on
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+impl WindowRecord {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            description: String::new(),
+            event_type: None,
+            handler_name: None,
+            action: WindowAction::default(),
+            start_time: None,
+            end_time: None,
+            days: Vec::new(),
+            dates: Vec::new(),
+            timezone: None,
+            starts_at: None,
+            ends_at: None,
+        }
+    }
+}
+
+/// Watches paths and produces an event for every file that is created, modified or deleted
+#[derive(Debug, Clone)]
+pub struct WatcherRecord {
+    pub id: Uuid,
+    pub event_type: String,
+    pub paths: Vec<String>,
+    /// Only report files matching one of these globs (all files if empty)
+    pub include: Vec<String>,
+    /// Never report files matching one of these globs
+    pub exclude: Vec<String>,
+    /// Also watch subdirectories
+    pub recursive: bool,
+    /// Kinds of change that produce events
+    pub changes: Vec<FileChange>,
+    /// How long a file must be left alone before its change is reported
+    pub debounce_ms: u32,
+    pub overlap: OverlapPolicy,
+}
+
+impl WatcherRecord {
+    pub fn new(event_type: String, paths: Vec<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event_type,
+            paths,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            recursive: false,
+            changes: FileChange::ALL.to_vec(),
+            debounce_ms: 500,
+            overlap: OverlapPolicy::default(),
+        }
+    }
+}
+
+/// Follows a log file and produces an event for every line matching one of its patterns
+#[derive(Debug, Clone)]
+pub struct TailRecord {
+    pub id: Uuid,
+    pub event_type: String,
+    pub path: String,
+    /// Regular expressions; a line produces one event for the first pattern it matches
+    pub patterns: Vec<String>,
+    /// Read the lines already in the file when the tail starts instead of only new ones
+    pub from_start: bool,
+    pub overlap: OverlapPolicy,
+}
+
+impl TailRecord {
+    pub fn new(event_type: String, path: String, patterns: Vec<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event_type,
+            path,
+            patterns,
+            from_start: false,
+            overlap: OverlapPolicy::default(),
+        }
+    }
+}
+
+/// Header GitHub puts the HMAC-SHA256 signature of a webhook payload in
+pub const DEFAULT_SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+
+/// Idempotency keys are remembered for a day unless configured otherwise
+pub const DEFAULT_IDEMPOTENCY_TTL_SECS: u32 = 86400;
+
+/// Accepts requests on `/hooks/{event_type}` and turns their body into an event
+#[derive(Debug, Clone)]
+pub struct WebhookRecord {
+    pub id: Uuid,
+    pub event_type: String,
+    /// Key for the HMAC-SHA256 signature of the body; unsigned hooks only accept requests
+    /// from allowed write IPs
+    pub secret: Option<String>,
+    /// Header carrying the hex signature, with or without a `sha256=` prefix
+    pub signature_header: String,
+    /// Environment variables set from the JSON body, mapped to JSONPath expressions
+    pub fields: HashMap<String, String>,
+}
+
+impl WebhookRecord {
+    pub fn new(event_type: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event_type,
+            secret: None,
+            signature_header: DEFAULT_SIGNATURE_HEADER.to_string(),
+            fields: HashMap::new(),
+        }
+    }
+}
+
+/// A named DAG of steps, each running a job of a handler
+#[derive(Debug, Clone)]
+pub struct WorkflowRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    /// Steps in the order they were given; `depends_on` links them into a DAG
+    pub steps: Vec<WorkflowStep>,
+}
+
+impl WorkflowRecord {
+    pub fn new(name: String, steps: Vec<WorkflowStep>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            description: String::new(),
+            steps,
         }
     }
 }
@@ -120,9 +496,105 @@ impl Database {
         self.conn
             .execute_batch(SCHEMA)
             .map_err(|e| format!("Failed to init schema: {}", e))?;
+        self.migrate()
+    }
+
+    /// Bring tables created by older versions up to the current schema
+    fn migrate(&self) -> Result<(), String> {
+        if !self.has_column("handlers", "name")? {
+            self.conn
+                .execute_batch(MIGRATE_HANDLER_NAMES)
+                .map_err(|e| format!("Failed to migrate handlers: {}", e))?;
+        }
+        self.add_column("jobs", "handler_name", "TEXT NOT NULL DEFAULT 'default'")?;
+        if self.has_column("jobs", "output")? {
+            self.conn
+                .execute_batch("ALTER TABLE jobs RENAME COLUMN output TO stdout")
+                .map_err(|e| format!("Failed to migrate jobs: {}", e))?;
+        }
+        self.add_column("jobs", "stderr", "TEXT")?;
+        self.add_column("jobs", "exit_code", "INTEGER")?;
+        self.add_column("jobs", "duration_ms", "INTEGER")?;
+        self.add_column("handlers", "retry", "TEXT")?;
+        self.add_column("handlers", "max_concurrency", "INTEGER")?;
+        self.add_column("jobs", "attempt", "INTEGER NOT NULL DEFAULT 1")?;
+        self.add_column("jobs", "parent_job_id", "TEXT")?;
+        self.add_column("jobs", "event_env", "TEXT NOT NULL DEFAULT '{}'")?;
+        for column in [
+            "on_success",
+            "on_failure",
+            "on_timeout",
+            "filter",
+            "throttle",
+        ] {
+            self.add_column("handlers", column, "TEXT")?;
+        }
+        self.add_column("handlers", "debounce_secs", "INTEGER")?;
+        self.add_column("jobs", "suppressed", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column("timers", "overlap", "TEXT NOT NULL DEFAULT 'allow'")?;
+        self.add_column("schedules", "overlap", "TEXT NOT NULL DEFAULT 'allow'")?;
+        self.add_column("schedules", "cron", "TEXT")?;
+        self.add_column("schedules", "timezone", "TEXT")?;
+        for table in ["timers", "schedules"] {
+            self.add_column(table, "last_fired_at", "TEXT")?;
+            self.add_column(table, "next_fire_at", "TEXT")?;
+        }
+        self.add_column("schedules", "misfire", "TEXT NOT NULL DEFAULT 'fire_once'")?;
+        for table in ["handlers", "timers", "schedules"] {
+            self.add_column(table, "enabled", "INTEGER NOT NULL DEFAULT 1")?;
+        }
+        self.add_column("schedules", "rrule", "TEXT")?;
+        self.add_column("schedules", "exdates", "TEXT NOT NULL DEFAULT ''")?;
+        self.add_column("timers", "align", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column("timers", "jitter_secs", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column("timers", "start_at", "TEXT")?;
+        self.add_column("timers", "end_at", "TEXT")?;
+        self.add_column("timers", "max_fires", "INTEGER")?;
+        self.add_column("timers", "fire_count", "INTEGER NOT NULL DEFAULT 0")?;
+        if !self.has_column("schedules", "state")? {
+            self.add_column("schedules", "state", "TEXT NOT NULL DEFAULT 'active'")?;
+            // One-shot schedules that already fired are done. Older versions did not record
+            // firing and skipped one-shots whose time had passed, so those are expired.
+            self.conn
+                .execute(
+                    "UPDATE schedules SET state = CASE
+                         WHEN last_fired_at >= scheduled_time THEN 'completed'
+                         ELSE 'expired'
+                     END
+                     WHERE periodic = 0 AND cron IS NULL
+                       AND (last_fired_at >= scheduled_time OR scheduled_time < ?1)",
+                    params![Utc::now().to_rfc3339()],
+                )
+                .map_err(|e| format!("Failed to migrate schedule state: {}", e))?;
+        }
         Ok(())
     }
 
+    fn has_column(&self, table: &str, column: &str) -> Result<bool, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        Ok(names.filter_map(|r| r.ok()).any(|name| name == column))
+    }
+
+    fn add_column(&self, table: &str, column: &str, definition: &str) -> Result<(), String> {
+        if self.has_column(table, column)? {
+            return Ok(());
+        }
+        self.conn
+            .execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .map_err(|e| format!("Failed to add column {}.{}: {}", table, column, e))
+    }
+
     // Config operations
     pub fn get_config(&self, key: &str) -> Option<String> {
         self.conn
@@ -156,120 +628,158 @@ impl Database {
             .unwrap_or(100)
     }
 
+    /// Maximum number of jobs running at once (0 = unlimited)
+    pub fn get_max_concurrent_jobs(&self) -> usize {
+        self.get_config("max_concurrent_jobs")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// How long an idempotency key is remembered, in seconds
+    pub fn get_idempotency_ttl_secs(&self) -> u32 {
+        self.get_config("idempotency_ttl_secs")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECS)
+    }
+
     // Handler operations
-    pub fn insert_handler(
-        &self,
-        event_type: &str,
-        shell: &ShellType,
-        command: &str,
-        timeout: Option<u32>,
-        env: &HashMap<String, String>,
-    ) -> Result<EventHandler, String> {
-        let id = Uuid::new_v4();
+    pub fn insert_handler(&self, handler: &EventHandler) -> Result<(), String> {
         let now = Utc::now().to_rfc3339();
-        let env_json = serde_json::to_string(env).unwrap_or_default();
+        let env_json = serde_json::to_string(&handler.env).unwrap_or_default();
+        let retry_json = handler
+            .retry
+            .as_ref()
+            .and_then(|r| serde_json::to_string(r).ok());
+        let throttle_json = handler
+            .throttle
+            .as_ref()
+            .and_then(|t| serde_json::to_string(t).ok());
 
         self.conn
             .execute(
-                r#"INSERT INTO handlers (id, event_type, shell, command, timeout, env, created_at, updated_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+                r#"INSERT INTO handlers (id, event_type, name, shell, command, timeout, env, retry, max_concurrency, enabled,
+                   on_success, on_failure, on_timeout, filter, debounce_secs, throttle, created_at, updated_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)"#,
                 params![
-                    id.to_string(),
-                    event_type,
-                    shell.as_str(),
-                    command,
-                    timeout,
+                    handler.id.to_string(),
+                    handler.event_type,
+                    handler.name,
+                    handler.shell.as_str(),
+                    handler.command,
+                    handler.timeout,
                     env_json,
+                    retry_json,
+                    handler.max_concurrency,
+                    handler.enabled as i32,
+                    handler.on_success,
+                    handler.on_failure,
+                    handler.on_timeout,
+                    handler.filter,
+                    handler.debounce_secs,
+                    throttle_json,
                     now,
                     now
                 ],
             )
             .map_err(|e| format!("Failed to insert handler: {}", e))?;
-
-        Ok(EventHandler {
-            id,
-            event_type: event_type.to_string(),
-            shell: shell.clone(),
-            command: command.to_string(),
-            timeout,
-            env: env.clone(),
-        })
+        Ok(())
     }
 
-    pub fn update_handler(
-        &self,
-        event_type: &str,
-        shell: Option<&ShellType>,
-        command: Option<&str>,
-        timeout: Option<Option<u32>>,
-        env: Option<&HashMap<String, String>>,
-    ) -> Result<EventHandler, String> {
-        let existing = self
-            .get_handler(event_type)?
-            .ok_or_else(|| format!("Handler '{}' not found", event_type))?;
-
-        let new_id = Uuid::new_v4();
+    /// Replace the handler with the same event type and name (including its id)
+    pub fn update_handler(&self, handler: &EventHandler) -> Result<bool, String> {
         let now = Utc::now().to_rfc3339();
-        let new_shell = shell.unwrap_or(&existing.shell);
-        let new_command = command.unwrap_or(&existing.command);
-        let new_timeout = timeout.unwrap_or(existing.timeout);
-        let new_env = env.unwrap_or(&existing.env);
-        let env_json = serde_json::to_string(new_env).unwrap_or_else(|_| "{}".to_string());
+        let env_json = serde_json::to_string(&handler.env).unwrap_or_else(|_| "{}".to_string());
+        let retry_json = handler
+            .retry
+            .as_ref()
+            .and_then(|r| serde_json::to_string(r).ok());
+        let throttle_json = handler
+            .throttle
+            .as_ref()
+            .and_then(|t| serde_json::to_string(t).ok());
 
-        self.conn
+        let rows = self
+            .conn
             .execute(
-                r#"UPDATE handlers SET id = ?1, shell = ?2, command = ?3, timeout = ?4, env = ?5, updated_at = ?6
-               WHERE event_type = ?7"#,
+                r#"UPDATE handlers SET id = ?1, shell = ?2, command = ?3, timeout = ?4, env = ?5, retry = ?6,
+                   max_concurrency = ?7, enabled = ?8, on_success = ?9, on_failure = ?10, on_timeout = ?11,
+                   filter = ?12, debounce_secs = ?13, throttle = ?14, updated_at = ?15
+               WHERE event_type = ?16 AND name = ?17"#,
                 params![
-                    new_id.to_string(),
-                    new_shell.as_str(),
-                    new_command,
-                    new_timeout,
+                    handler.id.to_string(),
+                    handler.shell.as_str(),
+                    handler.command,
+                    handler.timeout,
                     env_json,
+                    retry_json,
+                    handler.max_concurrency,
+                    handler.enabled as i32,
+                    handler.on_success,
+                    handler.on_failure,
+                    handler.on_timeout,
+                    handler.filter,
+                    handler.debounce_secs,
+                    throttle_json,
                     now,
-                    event_type
+                    handler.event_type,
+                    handler.name
                 ],
             )
             .map_err(|e| format!("Failed to update handler: {}", e))?;
+        Ok(rows > 0)
+    }
 
-        Ok(EventHandler {
-            id: new_id,
-            event_type: event_type.to_string(),
-            shell: new_shell.clone(),
-            command: new_command.to_string(),
-            timeout: new_timeout,
-            env: new_env.clone(),
-        })
+    pub fn set_handler_enabled(
+        &self,
+        event_type: &str,
+        name: &str,
+        enabled: bool,
+    ) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE handlers SET enabled = ?1, updated_at = ?2 WHERE event_type = ?3 AND name = ?4",
+                params![enabled as i32, Utc::now().to_rfc3339(), event_type, name],
+            )
+            .map_err(|e| format!("Failed to update handler: {}", e))?;
+        Ok(rows > 0)
     }
 
-    pub fn delete_handler(&self, event_type: &str) -> Result<bool, String> {
+    pub fn delete_handler(&self, event_type: &str, name: &str) -> Result<bool, String> {
         let rows = self
             .conn
             .execute(
-                "DELETE FROM handlers WHERE event_type = ?1",
-                params![event_type],
+                "DELETE FROM handlers WHERE event_type = ?1 AND name = ?2",
+                params![event_type, name],
             )
             .map_err(|e| format!("Failed to delete handler: {}", e))?;
         Ok(rows > 0)
     }
 
-    pub fn get_handler(&self, event_type: &str) -> Result<Option<EventHandler>, String> {
+    pub fn get_handler(
+        &self,
+        event_type: &str,
+        name: &str,
+    ) -> Result<Option<EventHandler>, String> {
         self.conn
             .query_row(
-                "SELECT id, event_type, shell, command, timeout, env FROM handlers WHERE event_type = ?1",
-                params![event_type],
-                |row| Self::row_to_handler(row),
+                &format!(
+                    "SELECT {} FROM handlers WHERE event_type = ?1 AND name = ?2",
+                    HANDLER_COLUMNS
+                ),
+                params![event_type, name],
+                Self::row_to_handler,
             )
             .optional()
             .map_err(|e| format!("Failed to get handler: {}", e))
     }
 
-    /// Get the current handler UUID for an event type (for checking if a job's handler is still current)
-    pub fn get_handler_id(&self, event_type: &str) -> Result<Option<Uuid>, String> {
+    /// Get the current handler UUID (for checking if a job's handler is still current)
+    pub fn get_handler_id(&self, event_type: &str, name: &str) -> Result<Option<Uuid>, String> {
         self.conn
             .query_row(
-                "SELECT id FROM handlers WHERE event_type = ?1",
-                params![event_type],
+                "SELECT id FROM handlers WHERE event_type = ?1 AND name = ?2",
+                params![event_type, name],
                 |row| {
                     let id: String = row.get(0)?;
                     Ok(Uuid::parse_str(&id).ok())
@@ -283,11 +793,14 @@ impl Database {
     pub fn get_all_handlers(&self) -> Result<Vec<EventHandler>, String> {
         let mut stmt = self
             .conn
-            .prepare("SELECT id, event_type, shell, command, timeout, env FROM handlers ORDER BY event_type")
+            .prepare(&format!(
+                "SELECT {} FROM handlers ORDER BY event_type, name",
+                HANDLER_COLUMNS
+            ))
             .map_err(|e| format!("Failed to prepare query: {}", e))?;
 
         let iter = stmt
-            .query_map([], |row| Self::row_to_handler(row))
+            .query_map([], Self::row_to_handler)
             .map_err(|e| format!("Failed to query handlers: {}", e))?;
 
         Ok(iter.filter_map(|r| r.ok()).collect())
@@ -296,85 +809,137 @@ impl Database {
     fn row_to_handler(row: &rusqlite::Row) -> rusqlite::Result<EventHandler> {
         let id: String = row.get(0)?;
         let event_type: String = row.get(1)?;
-        let shell_str: String = row.get(2)?;
-        let command: String = row.get(3)?;
-        let timeout: Option<u32> = row.get(4)?;
-        let env_json: String = row.get(5)?;
+        let name: String = row.get(2)?;
+        let shell_str: String = row.get(3)?;
+        let command: String = row.get(4)?;
+        let timeout: Option<u32> = row.get(5)?;
+        let env_json: String = row.get(6)?;
+        let retry_json: Option<String> = row.get(7)?;
+        let max_concurrency: Option<u32> = row.get(8)?;
+        let enabled: i32 = row.get(9)?;
 
         let shell = ShellType::from_str(&shell_str).unwrap_or(ShellType::Sh);
         let env: HashMap<String, String> = serde_json::from_str(&env_json).unwrap_or_default();
+        let retry: Option<RetryPolicy> = retry_json.and_then(|j| serde_json::from_str(&j).ok());
+        let throttle_json: Option<String> = row.get(15)?;
 
         Ok(EventHandler {
             id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
             event_type,
+            name,
             shell,
             command,
             timeout,
             env,
+            retry,
+            max_concurrency,
+            enabled: enabled != 0,
+            on_success: row.get(10)?,
+            on_failure: row.get(11)?,
+            on_timeout: row.get(12)?,
+            filter: row.get(13)?,
+            debounce_secs: row.get(14)?,
+            throttle: throttle_json.and_then(|j| serde_json::from_str(&j).ok()),
         })
     }
 
     // Timer operations
-    pub fn insert_timer(
-        &self,
-        event_type: &str,
-        interval_secs: u32,
-        context: &str,
-    ) -> Result<TimerRecord, String> {
-        let id = Uuid::new_v4();
+    pub fn insert_timer(&self, timer: &TimerRecord) -> Result<(), String> {
         let now = Utc::now().to_rfc3339();
 
         self.conn
             .execute(
-                r#"INSERT INTO timers (id, event_type, context, interval_secs, created_at, updated_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
-                params![id.to_string(), event_type, context, interval_secs, now, now],
+                r#"INSERT INTO timers (id, event_type, context, interval_secs, overlap, last_fired_at, next_fire_at, enabled,
+                   align, jitter_secs, start_at, end_at, max_fires, fire_count, created_at, updated_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"#,
+                params![
+                    timer.id.to_string(),
+                    timer.event_type,
+                    timer.context,
+                    timer.interval_secs,
+                    timer.overlap.as_str(),
+                    timer.last_fired_at.map(|t| t.to_rfc3339()),
+                    timer.next_fire_at.map(|t| t.to_rfc3339()),
+                    timer.enabled as i32,
+                    timer.align as i32,
+                    timer.jitter_secs,
+                    timer.start_at.map(|t| t.to_rfc3339()),
+                    timer.end_at.map(|t| t.to_rfc3339()),
+                    timer.max_fires,
+                    timer.fire_count,
+                    now,
+                    now
+                ],
             )
             .map_err(|e| format!("Failed to insert timer: {}", e))?;
-
-        Ok(TimerRecord {
-            id,
-            event_type: event_type.to_string(),
-            context: context.to_string(),
-            interval_secs,
-        })
+        Ok(())
     }
 
-    pub fn update_timer(
-        &self,
-        event_type: &str,
-        interval_secs: Option<u32>,
-        context: Option<&str>,
-    ) -> Result<TimerRecord, String> {
-        let existing = self
-            .get_timer(event_type)?
-            .ok_or_else(|| format!("Timer '{}' not found", event_type))?;
-
-        let new_id = Uuid::new_v4();
+    /// Replace the timer with the same event type (including its id)
+    pub fn update_timer(&self, timer: &TimerRecord) -> Result<bool, String> {
         let now = Utc::now().to_rfc3339();
-        let new_interval = interval_secs.unwrap_or(existing.interval_secs);
-        let new_context = context.unwrap_or(&existing.context);
 
-        self.conn
+        let rows = self
+            .conn
             .execute(
-                r#"UPDATE timers SET id = ?1, context = ?2, interval_secs = ?3, updated_at = ?4
-               WHERE event_type = ?5"#,
+                r#"UPDATE timers SET id = ?1, context = ?2, interval_secs = ?3, overlap = ?4,
+                   last_fired_at = ?5, next_fire_at = ?6, enabled = ?7, align = ?8, jitter_secs = ?9,
+                   start_at = ?10, end_at = ?11, max_fires = ?12, fire_count = ?13, updated_at = ?14
+               WHERE event_type = ?15"#,
                 params![
-                    new_id.to_string(),
-                    new_context,
-                    new_interval,
+                    timer.id.to_string(),
+                    timer.context,
+                    timer.interval_secs,
+                    timer.overlap.as_str(),
+                    timer.last_fired_at.map(|t| t.to_rfc3339()),
+                    timer.next_fire_at.map(|t| t.to_rfc3339()),
+                    timer.enabled as i32,
+                    timer.align as i32,
+                    timer.jitter_secs,
+                    timer.start_at.map(|t| t.to_rfc3339()),
+                    timer.end_at.map(|t| t.to_rfc3339()),
+                    timer.max_fires,
+                    timer.fire_count,
                     now,
-                    event_type
+                    timer.event_type
                 ],
             )
             .map_err(|e| format!("Failed to update timer: {}", e))?;
+        Ok(rows > 0)
+    }
 
-        Ok(TimerRecord {
-            id: new_id,
-            event_type: event_type.to_string(),
-            context: new_context.to_string(),
-            interval_secs: new_interval,
-        })
+    /// Record when a timer last fired and will fire next and how often it has fired. Only the
+    /// timer with this id is touched, so an outdated timer cannot overwrite its replacement.
+    pub fn set_timer_fire_times(
+        &self,
+        id: &Uuid,
+        last_fired_at: Option<DateTime<Utc>>,
+        next_fire_at: Option<DateTime<Utc>>,
+        fire_count: u32,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE timers SET last_fired_at = ?1, next_fire_at = ?2, fire_count = ?3 WHERE id = ?4",
+                params![
+                    last_fired_at.map(|t| t.to_rfc3339()),
+                    next_fire_at.map(|t| t.to_rfc3339()),
+                    fire_count,
+                    id.to_string()
+                ],
+            )
+            .map_err(|e| format!("Failed to update timer fire times: {}", e))?;
+        Ok(())
+    }
+
+    pub fn set_timer_enabled(&self, event_type: &str, enabled: bool) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE timers SET enabled = ?1, updated_at = ?2 WHERE event_type = ?3",
+                params![enabled as i32, Utc::now().to_rfc3339(), event_type],
+            )
+            .map_err(|e| format!("Failed to update timer: {}", e))?;
+        Ok(rows > 0)
     }
 
     pub fn delete_timer(&self, event_type: &str) -> Result<bool, String> {
@@ -407,21 +972,9 @@ impl Database {
     pub fn get_timer(&self, event_type: &str) -> Result<Option<TimerRecord>, String> {
         self.conn
             .query_row(
-                "SELECT id, event_type, context, interval_secs FROM timers WHERE event_type = ?1",
+                &format!("SELECT {} FROM timers WHERE event_type = ?1", TIMER_COLUMNS),
                 params![event_type],
-                |row| {
-                    let id: String = row.get(0)?;
-                    let event_type: String = row.get(1)?;
-                    let context: String = row.get(2)?;
-                    let interval_secs: u32 = row.get(3)?;
-
-                    Ok(TimerRecord {
-                        id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
-                        event_type,
-                        context,
-                        interval_secs,
-                    })
-                },
+                Self::row_to_timer,
             )
             .optional()
             .map_err(|e| format!("Failed to get timer: {}", e))
@@ -430,104 +983,168 @@ impl Database {
     pub fn get_all_timers(&self) -> Result<Vec<TimerRecord>, String> {
         let mut stmt = self
             .conn
-            .prepare(
-                "SELECT id, event_type, context, interval_secs FROM timers ORDER BY event_type",
-            )
+            .prepare(&format!(
+                "SELECT {} FROM timers ORDER BY event_type",
+                TIMER_COLUMNS
+            ))
             .map_err(|e| format!("Failed to prepare query: {}", e))?;
 
         let iter = stmt
-            .query_map([], |row| {
-                let id: String = row.get(0)?;
-                let event_type: String = row.get(1)?;
-                let context: String = row.get(2)?;
-                let interval_secs: u32 = row.get(3)?;
-
-                Ok(TimerRecord {
-                    id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
-                    event_type,
-                    context,
-                    interval_secs,
-                })
-            })
+            .query_map([], Self::row_to_timer)
             .map_err(|e| format!("Failed to query timers: {}", e))?;
 
         Ok(iter.filter_map(|r| r.ok()).collect())
     }
 
+    fn row_to_timer(row: &rusqlite::Row) -> rusqlite::Result<TimerRecord> {
+        let id: String = row.get(0)?;
+        let event_type: String = row.get(1)?;
+        let context: String = row.get(2)?;
+        let interval_secs: u32 = row.get(3)?;
+        let overlap: String = row.get(4)?;
+        let last_fired_at: Option<String> = row.get(5)?;
+        let next_fire_at: Option<String> = row.get(6)?;
+        let enabled: i32 = row.get(7)?;
+        let align: i32 = row.get(8)?;
+        let jitter_secs: u32 = row.get(9)?;
+        let start_at: Option<String> = row.get(10)?;
+        let end_at: Option<String> = row.get(11)?;
+        let max_fires: Option<u32> = row.get(12)?;
+        let fire_count: u32 = row.get(13)?;
+
+        Ok(TimerRecord {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            event_type,
+            context,
+            interval_secs,
+            overlap: OverlapPolicy::from_str(&overlap).unwrap_or_default(),
+            last_fired_at: parse_optional_time(last_fired_at),
+            next_fire_at: parse_optional_time(next_fire_at),
+            enabled: enabled != 0,
+            align: align != 0,
+            jitter_secs,
+            start_at: parse_optional_time(start_at),
+            end_at: parse_optional_time(end_at),
+            max_fires,
+            fire_count,
+        })
+    }
+
     // Schedule operations
-    pub fn insert_schedule(
-        &self,
-        event_type: &str,
-        scheduled_time: DateTime<Utc>,
-        context: &str,
-        periodic: bool,
-    ) -> Result<ScheduleRecord, String> {
-        let id = Uuid::new_v4();
+    pub fn insert_schedule(&self, schedule: &ScheduleRecord) -> Result<(), String> {
         let now = Utc::now().to_rfc3339();
 
         self.conn
             .execute(
-                r#"INSERT INTO schedules (id, event_type, context, scheduled_time, periodic, created_at, updated_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
-                params![id.to_string(), event_type, context, scheduled_time.to_rfc3339(), periodic as i32, now, now],
+                r#"INSERT INTO schedules (id, event_type, context, scheduled_time, periodic, overlap, cron, timezone,
+                   last_fired_at, next_fire_at, misfire, state, enabled, rrule, exdates, created_at, updated_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)"#,
+                params![
+                    schedule.id.to_string(),
+                    schedule.event_type,
+                    schedule.context,
+                    schedule.scheduled_time.to_rfc3339(),
+                    schedule.periodic as i32,
+                    schedule.overlap.as_str(),
+                    schedule.cron,
+                    schedule.timezone,
+                    schedule.last_fired_at.map(|t| t.to_rfc3339()),
+                    schedule.next_fire_at.map(|t| t.to_rfc3339()),
+                    schedule.misfire.as_str(),
+                    schedule.state.as_str(),
+                    schedule.enabled as i32,
+                    schedule.rrule,
+                    format_times(&schedule.exdates),
+                    now,
+                    now
+                ],
             )
             .map_err(|e| format!("Failed to insert schedule: {}", e))?;
-
-        Ok(ScheduleRecord {
-            id,
-            event_type: event_type.to_string(),
-            context: context.to_string(),
-            scheduled_time,
-            periodic,
-        })
+        Ok(())
     }
 
-    pub fn update_schedule(
-        &self,
-        event_type: &str,
-        scheduled_time: Option<DateTime<Utc>>,
-        context: Option<&str>,
-        periodic: Option<bool>,
-    ) -> Result<ScheduleRecord, String> {
-        let existing = self
-            .get_schedule(event_type)?
-            .ok_or_else(|| format!("Schedule '{}' not found", event_type))?;
-
-        let new_id = Uuid::new_v4();
+    /// Replace the schedule with the same event type (including its id)
+    pub fn update_schedule(&self, schedule: &ScheduleRecord) -> Result<bool, String> {
         let now = Utc::now().to_rfc3339();
-        let new_time = scheduled_time.unwrap_or(existing.scheduled_time);
-        let new_context = context.unwrap_or(&existing.context);
-        let new_periodic = periodic.unwrap_or(existing.periodic);
 
-        self.conn
+        let rows = self
+            .conn
             .execute(
-                r#"UPDATE schedules SET id = ?1, context = ?2, scheduled_time = ?3, periodic = ?4, updated_at = ?5
-               WHERE event_type = ?6"#,
+                r#"UPDATE schedules SET id = ?1, context = ?2, scheduled_time = ?3, periodic = ?4, overlap = ?5,
+                   cron = ?6, timezone = ?7, last_fired_at = ?8, next_fire_at = ?9, misfire = ?10,
+                   state = ?11, enabled = ?12, rrule = ?13, exdates = ?14, updated_at = ?15
+               WHERE event_type = ?16"#,
                 params![
-                    new_id.to_string(),
-                    new_context,
-                    new_time.to_rfc3339(),
-                    new_periodic as i32,
+                    schedule.id.to_string(),
+                    schedule.context,
+                    schedule.scheduled_time.to_rfc3339(),
+                    schedule.periodic as i32,
+                    schedule.overlap.as_str(),
+                    schedule.cron,
+                    schedule.timezone,
+                    schedule.last_fired_at.map(|t| t.to_rfc3339()),
+                    schedule.next_fire_at.map(|t| t.to_rfc3339()),
+                    schedule.misfire.as_str(),
+                    schedule.state.as_str(),
+                    schedule.enabled as i32,
+                    schedule.rrule,
+                    format_times(&schedule.exdates),
                     now,
-                    event_type
+                    schedule.event_type
                 ],
             )
             .map_err(|e| format!("Failed to update schedule: {}", e))?;
-
-        Ok(ScheduleRecord {
-            id: new_id,
-            event_type: event_type.to_string(),
-            context: new_context.to_string(),
-            scheduled_time: new_time,
-            periodic: new_periodic,
-        })
+        Ok(rows > 0)
     }
 
-    pub fn delete_schedule(&self, event_type: &str) -> Result<bool, String> {
-        let rows = self
-            .conn
+    /// Record when a schedule last fired and will fire next. Only the schedule with this id is
+    /// touched, so an outdated schedule cannot overwrite its replacement.
+    pub fn set_schedule_fire_times(
+        &self,
+        id: &Uuid,
+        last_fired_at: Option<DateTime<Utc>>,
+        next_fire_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String> {
+        self.conn
             .execute(
-                "DELETE FROM schedules WHERE event_type = ?1",
+                "UPDATE schedules SET last_fired_at = ?1, next_fire_at = ?2 WHERE id = ?3",
+                params![
+                    last_fired_at.map(|t| t.to_rfc3339()),
+                    next_fire_at.map(|t| t.to_rfc3339()),
+                    id.to_string()
+                ],
+            )
+            .map_err(|e| format!("Failed to update schedule fire times: {}", e))?;
+        Ok(())
+    }
+
+    /// Set the state of the schedule with this id
+    pub fn set_schedule_state(&self, id: &Uuid, state: ScheduleState) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE schedules SET state = ?1 WHERE id = ?2",
+                params![state.as_str(), id.to_string()],
+            )
+            .map_err(|e| format!("Failed to update schedule state: {}", e))?;
+        Ok(())
+    }
+
+    pub fn set_schedule_enabled(&self, event_type: &str, enabled: bool) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE schedules SET enabled = ?1, updated_at = ?2 WHERE event_type = ?3",
+                params![enabled as i32, Utc::now().to_rfc3339(), event_type],
+            )
+            .map_err(|e| format!("Failed to update schedule: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete_schedule(&self, event_type: &str) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "DELETE FROM schedules WHERE event_type = ?1",
                 params![event_type],
             )
             .map_err(|e| format!("Failed to delete schedule: {}", e))?;
@@ -552,67 +1169,763 @@ impl Database {
     pub fn get_schedule(&self, event_type: &str) -> Result<Option<ScheduleRecord>, String> {
         self.conn
             .query_row(
-                "SELECT id, event_type, context, scheduled_time, periodic FROM schedules WHERE event_type = ?1",
+                &format!(
+                    "SELECT {} FROM schedules WHERE event_type = ?1",
+                    SCHEDULE_COLUMNS
+                ),
+                params![event_type],
+                Self::row_to_schedule,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to get schedule: {}", e))
+    }
+
+    pub fn get_all_schedules(&self) -> Result<Vec<ScheduleRecord>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM schedules ORDER BY scheduled_time",
+                SCHEDULE_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map([], Self::row_to_schedule)
+            .map_err(|e| format!("Failed to query schedules: {}", e))?;
+
+        Ok(iter.filter_map(|r| r.ok()).collect())
+    }
+
+    fn row_to_schedule(row: &rusqlite::Row) -> rusqlite::Result<ScheduleRecord> {
+        let id: String = row.get(0)?;
+        let event_type: String = row.get(1)?;
+        let context: String = row.get(2)?;
+        let scheduled_time: String = row.get(3)?;
+        let periodic: i32 = row.get(4)?;
+        let overlap: String = row.get(5)?;
+        let cron: Option<String> = row.get(6)?;
+        let timezone: Option<String> = row.get(7)?;
+        let last_fired_at: Option<String> = row.get(8)?;
+        let next_fire_at: Option<String> = row.get(9)?;
+        let misfire: String = row.get(10)?;
+        let state: String = row.get(11)?;
+        let enabled: i32 = row.get(12)?;
+        let rrule: Option<String> = row.get(13)?;
+        let exdates: String = row.get(14)?;
+
+        Ok(ScheduleRecord {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            event_type,
+            context,
+            scheduled_time: DateTime::parse_from_rfc3339(&scheduled_time)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            periodic: periodic != 0,
+            overlap: OverlapPolicy::from_str(&overlap).unwrap_or_default(),
+            cron,
+            timezone,
+            last_fired_at: parse_optional_time(last_fired_at),
+            next_fire_at: parse_optional_time(next_fire_at),
+            misfire: MisfirePolicy::from_str(&misfire).unwrap_or_default(),
+            state: ScheduleState::from_str(&state).unwrap_or_default(),
+            enabled: enabled != 0,
+            rrule,
+            exdates: exdates
+                .split(',')
+                .filter_map(|t| parse_optional_time(Some(t.to_string())))
+                .collect(),
+        })
+    }
+
+    // Window operations
+    pub fn insert_window(&self, window: &WindowRecord) -> Result<(), String> {
+        let now = Utc::now().to_rfc3339();
+
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO windows ({}, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                    WINDOW_COLUMNS
+                ),
+                params![
+                    window.id.to_string(),
+                    window.name,
+                    window.description,
+                    window.event_type,
+                    window.handler_name,
+                    window.action.as_str(),
+                    window.start_time.map(format_time_of_day),
+                    window.end_time.map(format_time_of_day),
+                    format_days(&window.days),
+                    format_dates(&window.dates),
+                    window.timezone,
+                    window.starts_at.map(|t| t.to_rfc3339()),
+                    window.ends_at.map(|t| t.to_rfc3339()),
+                    now,
+                    now
+                ],
+            )
+            .map_err(|e| format!("Failed to insert window: {}", e))?;
+        Ok(())
+    }
+
+    /// Replace the window with the same name
+    pub fn update_window(&self, window: &WindowRecord) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                r#"UPDATE windows SET description = ?1, event_type = ?2, handler_name = ?3, action = ?4,
+                   start_time = ?5, end_time = ?6, days = ?7, dates = ?8, timezone = ?9, starts_at = ?10,
+                   ends_at = ?11, updated_at = ?12
+               WHERE name = ?13"#,
+                params![
+                    window.description,
+                    window.event_type,
+                    window.handler_name,
+                    window.action.as_str(),
+                    window.start_time.map(format_time_of_day),
+                    window.end_time.map(format_time_of_day),
+                    format_days(&window.days),
+                    format_dates(&window.dates),
+                    window.timezone,
+                    window.starts_at.map(|t| t.to_rfc3339()),
+                    window.ends_at.map(|t| t.to_rfc3339()),
+                    Utc::now().to_rfc3339(),
+                    window.name
+                ],
+            )
+            .map_err(|e| format!("Failed to update window: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete_window(&self, name: &str) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute("DELETE FROM windows WHERE name = ?1", params![name])
+            .map_err(|e| format!("Failed to delete window: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn get_all_windows(&self) -> Result<Vec<WindowRecord>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM windows ORDER BY name",
+                WINDOW_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map([], Self::row_to_window)
+            .map_err(|e| format!("Failed to query windows: {}", e))?;
+
+        Ok(iter.filter_map(|r| r.ok()).collect())
+    }
+
+    fn row_to_window(row: &rusqlite::Row) -> rusqlite::Result<WindowRecord> {
+        let id: String = row.get(0)?;
+        let action: String = row.get(5)?;
+        let start_time: Option<String> = row.get(6)?;
+        let end_time: Option<String> = row.get(7)?;
+        let days: String = row.get(8)?;
+        let dates: String = row.get(9)?;
+        let starts_at: Option<String> = row.get(11)?;
+        let ends_at: Option<String> = row.get(12)?;
+
+        Ok(WindowRecord {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            name: row.get(1)?,
+            description: row.get(2)?,
+            event_type: row.get(3)?,
+            handler_name: row.get(4)?,
+            action: WindowAction::from_str(&action).unwrap_or_default(),
+            start_time: start_time.and_then(|t| NaiveTime::parse_from_str(&t, "%H:%M:%S").ok()),
+            end_time: end_time.and_then(|t| NaiveTime::parse_from_str(&t, "%H:%M:%S").ok()),
+            days: days.split(',').filter_map(|d| d.parse().ok()).collect(),
+            dates: dates
+                .split(',')
+                .filter_map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .collect(),
+            timezone: row.get(10)?,
+            starts_at: parse_optional_time(starts_at),
+            ends_at: parse_optional_time(ends_at),
+        })
+    }
+
+    // Watcher operations
+    pub fn insert_watcher(&self, watcher: &WatcherRecord) -> Result<(), String> {
+        let now = Utc::now().to_rfc3339();
+
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO watchers ({}, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    WATCHER_COLUMNS
+                ),
+                params![
+                    watcher.id.to_string(),
+                    watcher.event_type,
+                    format_list(&watcher.paths),
+                    format_list(&watcher.include),
+                    format_list(&watcher.exclude),
+                    watcher.recursive as i32,
+                    format_changes(&watcher.changes),
+                    watcher.debounce_ms,
+                    watcher.overlap.as_str(),
+                    now,
+                    now
+                ],
+            )
+            .map_err(|e| format!("Failed to insert watcher: {}", e))?;
+        Ok(())
+    }
+
+    /// Replace the watcher with the same event type (including its id)
+    pub fn update_watcher(&self, watcher: &WatcherRecord) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                r#"UPDATE watchers SET id = ?1, paths = ?2, include = ?3, exclude = ?4, recursive = ?5,
+                   changes = ?6, debounce_ms = ?7, overlap = ?8, updated_at = ?9
+               WHERE event_type = ?10"#,
+                params![
+                    watcher.id.to_string(),
+                    format_list(&watcher.paths),
+                    format_list(&watcher.include),
+                    format_list(&watcher.exclude),
+                    watcher.recursive as i32,
+                    format_changes(&watcher.changes),
+                    watcher.debounce_ms,
+                    watcher.overlap.as_str(),
+                    Utc::now().to_rfc3339(),
+                    watcher.event_type
+                ],
+            )
+            .map_err(|e| format!("Failed to update watcher: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete_watcher(&self, event_type: &str) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "DELETE FROM watchers WHERE event_type = ?1",
+                params![event_type],
+            )
+            .map_err(|e| format!("Failed to delete watcher: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    /// Get the current watcher UUID for an event type (for checking if a watcher is still current)
+    pub fn get_watcher_id(&self, event_type: &str) -> Result<Option<Uuid>, String> {
+        self.conn
+            .query_row(
+                "SELECT id FROM watchers WHERE event_type = ?1",
                 params![event_type],
                 |row| {
                     let id: String = row.get(0)?;
-                    let event_type: String = row.get(1)?;
-                    let context: String = row.get(2)?;
-                    let scheduled_time: String = row.get(3)?;
-                    let periodic: i32 = row.get(4)?;
-
-                    Ok(ScheduleRecord {
-                        id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
-                        event_type,
-                        context,
-                        scheduled_time: DateTime::parse_from_rfc3339(&scheduled_time)
-                            .map(|t| t.with_timezone(&Utc))
-                            .unwrap_or_else(|_| Utc::now()),
-                        periodic: periodic != 0,
-                    })
+                    Ok(Uuid::parse_str(&id).ok())
                 },
             )
             .optional()
-            .map_err(|e| format!("Failed to get schedule: {}", e))
+            .map_err(|e| format!("Failed to get watcher id: {}", e))
+            .map(|opt| opt.flatten())
     }
 
-    pub fn get_all_schedules(&self) -> Result<Vec<ScheduleRecord>, String> {
+    pub fn get_all_watchers(&self) -> Result<Vec<WatcherRecord>, String> {
         let mut stmt = self
             .conn
-            .prepare(
-                "SELECT id, event_type, context, scheduled_time, periodic FROM schedules ORDER BY scheduled_time",
+            .prepare(&format!(
+                "SELECT {} FROM watchers ORDER BY event_type",
+                WATCHER_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map([], Self::row_to_watcher)
+            .map_err(|e| format!("Failed to query watchers: {}", e))?;
+
+        Ok(iter.filter_map(|r| r.ok()).collect())
+    }
+
+    fn row_to_watcher(row: &rusqlite::Row) -> rusqlite::Result<WatcherRecord> {
+        let id: String = row.get(0)?;
+        let paths: String = row.get(2)?;
+        let include: String = row.get(3)?;
+        let exclude: String = row.get(4)?;
+        let recursive: i32 = row.get(5)?;
+        let changes: String = row.get(6)?;
+        let overlap: String = row.get(8)?;
+
+        Ok(WatcherRecord {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            event_type: row.get(1)?,
+            paths: serde_json::from_str(&paths).unwrap_or_default(),
+            include: serde_json::from_str(&include).unwrap_or_default(),
+            exclude: serde_json::from_str(&exclude).unwrap_or_default(),
+            recursive: recursive != 0,
+            changes: changes
+                .split(',')
+                .filter_map(FileChange::from_str)
+                .collect(),
+            debounce_ms: row.get(7)?,
+            overlap: OverlapPolicy::from_str(&overlap).unwrap_or_default(),
+        })
+    }
+
+    // Tail operations
+    pub fn insert_tail(&self, tail: &TailRecord) -> Result<(), String> {
+        let now = Utc::now().to_rfc3339();
+
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO tails ({}, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    TAIL_COLUMNS
+                ),
+                params![
+                    tail.id.to_string(),
+                    tail.event_type,
+                    tail.path,
+                    format_list(&tail.patterns),
+                    tail.from_start as i32,
+                    tail.overlap.as_str(),
+                    now,
+                    now
+                ],
+            )
+            .map_err(|e| format!("Failed to insert tail: {}", e))?;
+        Ok(())
+    }
+
+    /// Replace the tail with the same event type (including its id)
+    pub fn update_tail(&self, tail: &TailRecord) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                r#"UPDATE tails SET id = ?1, path = ?2, patterns = ?3, from_start = ?4, overlap = ?5, updated_at = ?6
+               WHERE event_type = ?7"#,
+                params![
+                    tail.id.to_string(),
+                    tail.path,
+                    format_list(&tail.patterns),
+                    tail.from_start as i32,
+                    tail.overlap.as_str(),
+                    Utc::now().to_rfc3339(),
+                    tail.event_type
+                ],
+            )
+            .map_err(|e| format!("Failed to update tail: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete_tail(&self, event_type: &str) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "DELETE FROM tails WHERE event_type = ?1",
+                params![event_type],
+            )
+            .map_err(|e| format!("Failed to delete tail: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    /// Get the current tail UUID for an event type (for checking if a tail is still current)
+    pub fn get_tail_id(&self, event_type: &str) -> Result<Option<Uuid>, String> {
+        self.conn
+            .query_row(
+                "SELECT id FROM tails WHERE event_type = ?1",
+                params![event_type],
+                |row| {
+                    let id: String = row.get(0)?;
+                    Ok(Uuid::parse_str(&id).ok())
+                },
             )
+            .optional()
+            .map_err(|e| format!("Failed to get tail id: {}", e))
+            .map(|opt| opt.flatten())
+    }
+
+    pub fn get_all_tails(&self) -> Result<Vec<TailRecord>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM tails ORDER BY event_type",
+                TAIL_COLUMNS
+            ))
             .map_err(|e| format!("Failed to prepare query: {}", e))?;
 
         let iter = stmt
-            .query_map([], |row| {
-                let id: String = row.get(0)?;
-                let event_type: String = row.get(1)?;
-                let context: String = row.get(2)?;
-                let scheduled_time: String = row.get(3)?;
-                let periodic: i32 = row.get(4)?;
-
-                Ok(ScheduleRecord {
-                    id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
-                    event_type,
-                    context,
-                    scheduled_time: DateTime::parse_from_rfc3339(&scheduled_time)
-                        .map(|t| t.with_timezone(&Utc))
-                        .unwrap_or_else(|_| Utc::now()),
-                    periodic: periodic != 0,
-                })
-            })
-            .map_err(|e| format!("Failed to query schedules: {}", e))?;
+            .query_map([], Self::row_to_tail)
+            .map_err(|e| format!("Failed to query tails: {}", e))?;
 
         Ok(iter.filter_map(|r| r.ok()).collect())
     }
 
+    fn row_to_tail(row: &rusqlite::Row) -> rusqlite::Result<TailRecord> {
+        let id: String = row.get(0)?;
+        let patterns: String = row.get(3)?;
+        let from_start: i32 = row.get(4)?;
+        let overlap: String = row.get(5)?;
+
+        Ok(TailRecord {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            event_type: row.get(1)?,
+            path: row.get(2)?,
+            patterns: serde_json::from_str(&patterns).unwrap_or_default(),
+            from_start: from_start != 0,
+            overlap: OverlapPolicy::from_str(&overlap).unwrap_or_default(),
+        })
+    }
+
+    // Webhook operations
+    pub fn insert_webhook(&self, webhook: &WebhookRecord) -> Result<(), String> {
+        let now = Utc::now().to_rfc3339();
+
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO webhooks ({}, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    WEBHOOK_COLUMNS
+                ),
+                params![
+                    webhook.id.to_string(),
+                    webhook.event_type,
+                    webhook.secret,
+                    webhook.signature_header,
+                    serde_json::to_string(&webhook.fields).unwrap_or_else(|_| "{}".to_string()),
+                    now,
+                    now
+                ],
+            )
+            .map_err(|e| format!("Failed to insert webhook: {}", e))?;
+        Ok(())
+    }
+
+    /// Replace the webhook with the same event type (including its id)
+    pub fn update_webhook(&self, webhook: &WebhookRecord) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                r#"UPDATE webhooks SET id = ?1, secret = ?2, signature_header = ?3, fields = ?4, updated_at = ?5
+               WHERE event_type = ?6"#,
+                params![
+                    webhook.id.to_string(),
+                    webhook.secret,
+                    webhook.signature_header,
+                    serde_json::to_string(&webhook.fields).unwrap_or_else(|_| "{}".to_string()),
+                    Utc::now().to_rfc3339(),
+                    webhook.event_type
+                ],
+            )
+            .map_err(|e| format!("Failed to update webhook: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete_webhook(&self, event_type: &str) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "DELETE FROM webhooks WHERE event_type = ?1",
+                params![event_type],
+            )
+            .map_err(|e| format!("Failed to delete webhook: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn get_all_webhooks(&self) -> Result<Vec<WebhookRecord>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM webhooks ORDER BY event_type",
+                WEBHOOK_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map([], Self::row_to_webhook)
+            .map_err(|e| format!("Failed to query webhooks: {}", e))?;
+
+        Ok(iter.filter_map(|r| r.ok()).collect())
+    }
+
+    fn row_to_webhook(row: &rusqlite::Row) -> rusqlite::Result<WebhookRecord> {
+        let id: String = row.get(0)?;
+        let fields: String = row.get(4)?;
+
+        Ok(WebhookRecord {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            event_type: row.get(1)?,
+            secret: row.get(2)?,
+            signature_header: row.get(3)?,
+            fields: serde_json::from_str(&fields).unwrap_or_default(),
+        })
+    }
+
+    // Workflow operations
+    pub fn insert_workflow(&self, workflow: &WorkflowRecord) -> Result<(), String> {
+        let now = Utc::now().to_rfc3339();
+
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO workflows ({}, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    WORKFLOW_COLUMNS
+                ),
+                params![
+                    workflow.id.to_string(),
+                    workflow.name,
+                    workflow.description,
+                    serde_json::to_string(&workflow.steps).unwrap_or_else(|_| "[]".to_string()),
+                    now,
+                    now
+                ],
+            )
+            .map_err(|e| format!("Failed to insert workflow: {}", e))?;
+        Ok(())
+    }
+
+    /// Replace the workflow with the same name (including its id)
+    pub fn update_workflow(&self, workflow: &WorkflowRecord) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE workflows SET id = ?1, description = ?2, steps = ?3, updated_at = ?4 WHERE name = ?5",
+                params![
+                    workflow.id.to_string(),
+                    workflow.description,
+                    serde_json::to_string(&workflow.steps).unwrap_or_else(|_| "[]".to_string()),
+                    Utc::now().to_rfc3339(),
+                    workflow.name
+                ],
+            )
+            .map_err(|e| format!("Failed to update workflow: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete_workflow(&self, name: &str) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute("DELETE FROM workflows WHERE name = ?1", params![name])
+            .map_err(|e| format!("Failed to delete workflow: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    pub fn get_all_workflows(&self) -> Result<Vec<WorkflowRecord>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM workflows ORDER BY name",
+                WORKFLOW_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map([], Self::row_to_workflow)
+            .map_err(|e| format!("Failed to query workflows: {}", e))?;
+
+        Ok(iter.filter_map(|r| r.ok()).collect())
+    }
+
+    fn row_to_workflow(row: &rusqlite::Row) -> rusqlite::Result<WorkflowRecord> {
+        let id: String = row.get(0)?;
+        let steps: String = row.get(3)?;
+
+        Ok(WorkflowRecord {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            name: row.get(1)?,
+            description: row.get(2)?,
+            steps: serde_json::from_str(&steps).unwrap_or_default(),
+        })
+    }
+
+    // Workflow run operations
+    pub fn insert_workflow_run(&self, run: &WorkflowRun) -> Result<(), String> {
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO workflow_runs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    WORKFLOW_RUN_COLUMNS
+                ),
+                params![
+                    run.id.to_string(),
+                    run.workflow,
+                    run.context,
+                    run.status.as_str(),
+                    serde_json::to_string(&run.steps).unwrap_or_else(|_| "[]".to_string()),
+                    run.started_at.to_rfc3339(),
+                    run.finished_at.map(|t| t.to_rfc3339())
+                ],
+            )
+            .map_err(|e| format!("Failed to insert workflow run: {}", e))?;
+        Ok(())
+    }
+
+    pub fn update_workflow_run(&self, run: &WorkflowRun) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE workflow_runs SET status = ?1, steps = ?2, finished_at = ?3 WHERE id = ?4",
+                params![
+                    run.status.as_str(),
+                    serde_json::to_string(&run.steps).unwrap_or_else(|_| "[]".to_string()),
+                    run.finished_at.map(|t| t.to_rfc3339()),
+                    run.id.to_string()
+                ],
+            )
+            .map_err(|e| format!("Failed to update workflow run: {}", e))?;
+        Ok(())
+    }
+
+    pub fn get_workflow_run(&self, run_id: Uuid) -> Result<Option<WorkflowRun>, String> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM workflow_runs WHERE id = ?1",
+                    WORKFLOW_RUN_COLUMNS
+                ),
+                params![run_id.to_string()],
+                Self::row_to_workflow_run,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to get workflow run: {}", e))
+    }
+
+    /// Latest runs first, of one workflow or of all of them
+    pub fn get_workflow_runs(
+        &self,
+        workflow: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<WorkflowRun>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM workflow_runs WHERE ?1 IS NULL OR workflow = ?1 ORDER BY started_at DESC LIMIT ?2",
+                WORKFLOW_RUN_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map(params![workflow, limit as i64], Self::row_to_workflow_run)
+            .map_err(|e| format!("Failed to query workflow runs: {}", e))?;
+
+        Ok(iter.filter_map(|r| r.ok()).collect())
+    }
+
+    /// Fail runs that were in progress when the backend stopped, along with their running
+    /// steps; steps that never started are cancelled. Their jobs are cancelled by
+    /// `cancel_stale_jobs`.
+    pub fn fail_stale_workflow_runs(&self) -> Result<usize, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM workflow_runs WHERE status = 'running'",
+                WORKFLOW_RUN_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let runs: Vec<WorkflowRun> = stmt
+            .query_map([], Self::row_to_workflow_run)
+            .map_err(|e| format!("Failed to query workflow runs: {}", e))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let now = Utc::now();
+        for mut run in runs.iter().cloned() {
+            for step in &mut run.steps {
+                match step.status {
+                    StepStatus::Running => {
+                        step.status = StepStatus::Failed;
+                        step.error = Some("Backend restarted".to_string());
+                        step.finished_at = Some(now);
+                    }
+                    StepStatus::Waiting => step.status = StepStatus::Cancelled,
+                    _ => {}
+                }
+            }
+            run.status = RunStatus::Failed;
+            run.finished_at = Some(now);
+            self.update_workflow_run(&run)?;
+        }
+        Ok(runs.len())
+    }
+
+    // Idempotency key operations
+
+    /// Remember the key for the event, unless it is already remembered. Returns the event the
+    /// key was first used for if so. Expired keys are forgotten first.
+    pub fn claim_idempotency_key(&self, key: &str, event: &Event) -> Result<Option<Event>, String> {
+        let now = Utc::now();
+        let ttl = chrono::Duration::seconds(self.get_idempotency_ttl_secs().into());
+        self.conn
+            .execute(
+                "DELETE FROM idempotency_keys WHERE created_at < ?1",
+                params![(now - ttl).to_rfc3339()],
+            )
+            .map_err(|e| format!("Failed to expire idempotency keys: {}", e))?;
+
+        let event_json = serde_json::to_string(event)
+            .map_err(|e| format!("Failed to serialize event: {}", e))?;
+        let inserted = self
+            .conn
+            .execute(
+                "INSERT OR IGNORE INTO idempotency_keys (key, event, created_at) VALUES (?1, ?2, ?3)",
+                params![key, event_json, now.to_rfc3339()],
+            )
+            .map_err(|e| format!("Failed to store idempotency key: {}", e))?;
+        if inserted > 0 {
+            return Ok(None);
+        }
+
+        let original: String = self
+            .conn
+            .query_row(
+                "SELECT event FROM idempotency_keys WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to get idempotency key: {}", e))?;
+        serde_json::from_str(&original)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse stored event: {}", e))
+    }
+
+    /// Forget a key whose event could not be queued, so a retry can use it
+    pub fn release_idempotency_key(&self, key: &str) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM idempotency_keys WHERE key = ?1", params![key])
+            .map_err(|e| format!("Failed to delete idempotency key: {}", e))?;
+        Ok(())
+    }
+
+    fn row_to_workflow_run(row: &rusqlite::Row) -> rusqlite::Result<WorkflowRun> {
+        let id: String = row.get(0)?;
+        let status: String = row.get(3)?;
+        let steps: String = row.get(4)?;
+        let started_at: String = row.get(5)?;
+        let finished_at: Option<String> = row.get(6)?;
+
+        Ok(WorkflowRun {
+            id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+            workflow: row.get(1)?,
+            context: row.get(2)?,
+            status: RunStatus::from_str(&status).unwrap_or(RunStatus::Cancelled),
+            steps: serde_json::from_str(&steps).unwrap_or_default(),
+            started_at: DateTime::parse_from_rfc3339(&started_at)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            finished_at: parse_optional_time(finished_at),
+        })
+    }
+
     // Job operations
     pub fn insert_job(&self, job: &Job) -> Result<(), String> {
         self.conn
             .execute(
-                r#"INSERT INTO jobs (id, event_id, event_type, event_context, event_timestamp, handler_id, status, output, error, started_at, finished_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
+                &format!(
+                    "INSERT INTO jobs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+                    JOB_COLUMNS
+                ),
                 params![
                     job.id.to_string(),
                     job.event.id.to_string(),
@@ -621,10 +1934,18 @@ impl Database {
                     job.event.timestamp.to_rfc3339(),
                     job.handler_id.to_string(),
                     job.status.as_str(),
-                    job.output,
+                    job.stdout,
                     job.error,
                     job.started_at.map(|t| t.to_rfc3339()),
-                    job.finished_at.map(|t| t.to_rfc3339())
+                    job.finished_at.map(|t| t.to_rfc3339()),
+                    job.handler_name,
+                    job.stderr,
+                    job.exit_code,
+                    job.duration_ms,
+                    job.attempt,
+                    job.parent_job_id.map(|id| id.to_string()),
+                    serde_json::to_string(&job.event.env).unwrap_or_else(|_| "{}".to_string()),
+                    job.suppressed
                 ],
             )
             .map_err(|e| format!("Failed to insert job: {}", e))?;
@@ -634,11 +1955,15 @@ impl Database {
     pub fn update_job(&self, job: &Job) -> Result<(), String> {
         self.conn
             .execute(
-                r#"UPDATE jobs SET status = ?1, output = ?2, error = ?3, started_at = ?4, finished_at = ?5
-               WHERE id = ?6"#,
+                r#"UPDATE jobs SET status = ?1, stdout = ?2, stderr = ?3, exit_code = ?4, duration_ms = ?5,
+                   error = ?6, started_at = ?7, finished_at = ?8
+               WHERE id = ?9"#,
                 params![
                     job.status.as_str(),
-                    job.output,
+                    job.stdout,
+                    job.stderr,
+                    job.exit_code,
+                    job.duration_ms,
                     job.error,
                     job.started_at.map(|t| t.to_rfc3339()),
                     job.finished_at.map(|t| t.to_rfc3339()),
@@ -649,18 +1974,91 @@ impl Database {
         Ok(())
     }
 
+    /// Update a job only while it is still pending or running, so a cancelled job keeps its final state
+    pub fn update_active_job(&self, job: &Job) -> Result<bool, String> {
+        let rows = self
+            .conn
+            .execute(
+                r#"UPDATE jobs SET status = ?1, stdout = ?2, stderr = ?3, exit_code = ?4, duration_ms = ?5,
+                   error = ?6, started_at = ?7, finished_at = ?8
+               WHERE id = ?9 AND (status = 'pending' OR status = 'running')"#,
+                params![
+                    job.status.as_str(),
+                    job.stdout,
+                    job.stderr,
+                    job.exit_code,
+                    job.duration_ms,
+                    job.error,
+                    job.started_at.map(|t| t.to_rfc3339()),
+                    job.finished_at.map(|t| t.to_rfc3339()),
+                    job.id.to_string()
+                ],
+            )
+            .map_err(|e| format!("Failed to update job: {}", e))?;
+        Ok(rows > 0)
+    }
+
+    /// Record process output without touching the job's status
+    pub fn update_job_output(&self, job: &Job) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE jobs SET stdout = ?1, stderr = ?2, exit_code = ?3, duration_ms = ?4 WHERE id = ?5",
+                params![
+                    job.stdout,
+                    job.stderr,
+                    job.exit_code,
+                    job.duration_ms,
+                    job.id.to_string()
+                ],
+            )
+            .map_err(|e| format!("Failed to update job output: {}", e))?;
+        Ok(())
+    }
+
+    /// Append newly produced output to what is stored for a running job
+    pub fn append_job_output(
+        &self,
+        job_id: Uuid,
+        stdout: &str,
+        stderr: &str,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE jobs SET stdout = COALESCE(stdout, '') || ?1, stderr = COALESCE(stderr, '') || ?2 WHERE id = ?3",
+                params![stdout, stderr, job_id.to_string()],
+            )
+            .map_err(|e| format!("Failed to append job output: {}", e))?;
+        Ok(())
+    }
+
     pub fn get_job(&self, job_id: Uuid) -> Result<Option<Job>, String> {
         self.conn
             .query_row(
-                r#"SELECT id, event_id, event_type, event_context, event_timestamp, handler_id, status, output, error, started_at, finished_at
-               FROM jobs WHERE id = ?1"#,
+                &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
                 params![job_id.to_string()],
-                |row| Self::row_to_job(row),
+                Self::row_to_job,
             )
             .optional()
             .map_err(|e| format!("Failed to get job: {}", e))
     }
 
+    /// Ids of the jobs created for an event, oldest first
+    pub fn get_job_ids_for_event(&self, event_id: Uuid) -> Result<Vec<Uuid>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM jobs WHERE event_id = ?1 ORDER BY rowid")
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map(params![event_id.to_string()], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to query jobs: {}", e))?;
+
+        Ok(iter
+            .filter_map(|r| r.ok())
+            .filter_map(|id| Uuid::parse_str(&id).ok())
+            .collect())
+    }
+
     pub fn get_all_jobs(
         &self,
         status: Option<&JobStatus>,
@@ -668,14 +2066,14 @@ impl Database {
     ) -> Result<Vec<Job>, String> {
         let query = match status {
             Some(s) => format!(
-                "SELECT id, event_id, event_type, event_context, event_timestamp, handler_id, status, output, error, started_at, finished_at
-                 FROM jobs WHERE status = '{}' ORDER BY event_timestamp DESC LIMIT {}",
-                s.as_str(), limit
+                "SELECT {} FROM jobs WHERE status = '{}' ORDER BY event_timestamp DESC LIMIT {}",
+                JOB_COLUMNS,
+                s.as_str(),
+                limit
             ),
             None => format!(
-                "SELECT id, event_id, event_type, event_context, event_timestamp, handler_id, status, output, error, started_at, finished_at
-                 FROM jobs ORDER BY event_timestamp DESC LIMIT {}",
-                limit
+                "SELECT {} FROM jobs ORDER BY event_timestamp DESC LIMIT {}",
+                JOB_COLUMNS, limit
             ),
         };
 
@@ -685,7 +2083,7 @@ impl Database {
             .map_err(|e| format!("Failed to prepare query: {}", e))?;
 
         let iter = stmt
-            .query_map([], |row| Self::row_to_job(row))
+            .query_map([], Self::row_to_job)
             .map_err(|e| format!("Failed to query jobs: {}", e))?;
 
         Ok(iter.filter_map(|r| r.ok()).collect())
@@ -704,6 +2102,24 @@ impl Database {
             .unwrap_or(false)
     }
 
+    pub fn get_active_job_ids(&self, event_type: &str) -> Result<Vec<Uuid>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id FROM jobs WHERE event_type = ?1 AND (status = 'pending' OR status = 'running')",
+            )
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let iter = stmt
+            .query_map(params![event_type], |row| {
+                let id: String = row.get(0)?;
+                Ok(Uuid::parse_str(&id).ok())
+            })
+            .map_err(|e| format!("Failed to query jobs: {}", e))?;
+
+        Ok(iter.filter_map(|r| r.ok().flatten()).collect())
+    }
+
     /// Mark all pending/running jobs as cancelled (used on startup to clean up stale jobs from unexpected shutdown)
     pub fn cancel_stale_jobs(&self) -> Result<usize, String> {
         let now = Utc::now().to_rfc3339();
@@ -737,10 +2153,18 @@ impl Database {
         let event_timestamp: String = row.get(4)?;
         let handler_id: String = row.get(5)?;
         let status_str: String = row.get(6)?;
-        let output: Option<String> = row.get(7)?;
+        let stdout: Option<String> = row.get(7)?;
         let error: Option<String> = row.get(8)?;
         let started_at: Option<String> = row.get(9)?;
         let finished_at: Option<String> = row.get(10)?;
+        let handler_name: String = row.get(11)?;
+        let stderr: Option<String> = row.get(12)?;
+        let exit_code: Option<i32> = row.get(13)?;
+        let duration_ms: Option<i64> = row.get(14)?;
+        let attempt: u32 = row.get(15)?;
+        let parent_job_id: Option<String> = row.get(16)?;
+        let event_env: String = row.get(17)?;
+        let suppressed: u32 = row.get(18)?;
 
         let status = JobStatus::from_str(&status_str).unwrap_or(JobStatus::Cancelled);
 
@@ -753,10 +2177,18 @@ impl Database {
                 timestamp: DateTime::parse_from_rfc3339(&event_timestamp)
                     .map(|t| t.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
+                env: serde_json::from_str(&event_env).unwrap_or_default(),
             },
             handler_id: Uuid::parse_str(&handler_id).unwrap_or_else(|_| Uuid::new_v4()),
+            handler_name,
+            attempt,
+            parent_job_id: parent_job_id.and_then(|id| Uuid::parse_str(&id).ok()),
+            suppressed,
             status,
-            output,
+            stdout,
+            stderr,
+            exit_code,
+            duration_ms,
             error,
             started_at: started_at.and_then(|s| {
                 DateTime::parse_from_rfc3339(&s)
@@ -772,6 +2204,54 @@ impl Database {
     }
 }
 
+fn format_time_of_day(time: NaiveTime) -> String {
+    time.format("%H:%M:%S").to_string()
+}
+
+fn format_days(days: &[Weekday]) -> String {
+    days.iter()
+        .map(|d| d.to_string().to_lowercase())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn format_dates(dates: &[NaiveDate]) -> String {
+    dates
+        .iter()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn format_times(times: &[DateTime<Utc>]) -> String {
+    times
+        .iter()
+        .map(|t| t.to_rfc3339())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Paths and globs may contain commas, so lists of them are stored as JSON
+fn format_list(values: &[String]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn format_changes(changes: &[FileChange]) -> String {
+    changes
+        .iter()
+        .map(|c| c.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_optional_time(value: Option<String>) -> Option<DateTime<Utc>> {
+    value.and_then(|s| {
+        DateTime::parse_from_rfc3339(&s)
+            .map(|t| t.with_timezone(&Utc))
+            .ok()
+    })
+}
+
 trait OptionalExt<T> {
     fn optional(self) -> Result<Option<T>, rusqlite::Error>;
 }
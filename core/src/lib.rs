@@ -3,5 +3,13 @@ mod db;
 mod models;
 
 pub use api::*;
-pub use db::{Database, ScheduleRecord, TimerRecord};
-pub use models::{Event, EventHandler, Job, JobStatus, ShellType};
+pub use db::{
+    DEFAULT_IDEMPOTENCY_TTL_SECS, DEFAULT_SIGNATURE_HEADER, Database, ScheduleRecord, TailRecord,
+    TimerRecord, WatcherRecord, WebhookRecord, WindowRecord, WorkflowRecord,
+};
+pub use models::{
+    Backoff, DEFAULT_HANDLER_NAME, Event, EventHandler, FileChange, Job, JobStatus, MisfirePolicy,
+    OverlapPolicy, RetryPolicy, RunStatus, ScheduleState, ShellType, StepCondition, StepRun,
+    StepStatus, ThrottleMode, ThrottlePolicy, WindowAction, WorkflowRun, WorkflowStep,
+    parse_duration_secs,
+};
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -22,6 +23,25 @@ impl ShellType {
         }
     }
 
+    /// Quote a value so the shell passes it on as a single literal argument
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            // PowerShell also treats the typographic single quotes as quotes
+            ShellType::Pwsh => {
+                let mut quoted = String::from("'");
+                for c in value.chars() {
+                    if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+                        quoted.push(c);
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('\'');
+                quoted
+            }
+            ShellType::Bash | ShellType::Sh => format!("'{}'", value.replace('\'', "'\\''")),
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ShellType::Pwsh => "pwsh",
@@ -30,6 +50,7 @@ impl ShellType {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "pwsh" | "powershell" => Some(ShellType::Pwsh),
@@ -46,6 +67,10 @@ pub struct Event {
     pub event_type: String,
     pub context: String,
     pub timestamp: DateTime<Utc>,
+    /// Extra environment variables for the jobs of this event, e.g. fields extracted from a
+    /// webhook payload
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
 }
 
 impl Event {
@@ -55,14 +80,296 @@ impl Event {
             event_type,
             context,
             timestamp: Utc::now(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+/// What a timer or schedule does when it fires while its previous event is still being handled:
+/// queued, debounced, deferred by a blackout window or running as a job
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlapPolicy {
+    /// Always emit the event
+    #[default]
+    Allow,
+    /// Drop the event
+    Skip,
+    /// Hold the event until the previous events are handled; further firings meanwhile are
+    /// dropped
+    Queue,
+    /// Cancel the active jobs, then emit the event
+    Replace,
+}
+
+impl OverlapPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverlapPolicy::Allow => "allow",
+            OverlapPolicy::Skip => "skip",
+            OverlapPolicy::Queue => "queue",
+            OverlapPolicy::Replace => "replace",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "allow" => Some(OverlapPolicy::Allow),
+            "skip" => Some(OverlapPolicy::Skip),
+            "queue" => Some(OverlapPolicy::Queue),
+            "replace" => Some(OverlapPolicy::Replace),
+            _ => None,
+        }
+    }
+}
+
+/// What a schedule does about runs it missed, e.g. while the backend was down
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    /// Emit a single event for all missed runs
+    #[default]
+    FireOnce,
+    /// Emit one event per missed run
+    FireAll,
+    /// Drop missed runs
+    Skip,
+}
+
+impl MisfirePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MisfirePolicy::FireOnce => "fire_once",
+            MisfirePolicy::FireAll => "fire_all",
+            MisfirePolicy::Skip => "skip",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "fire_once" => Some(MisfirePolicy::FireOnce),
+            "fire_all" => Some(MisfirePolicy::FireAll),
+            "skip" => Some(MisfirePolicy::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to events that fall inside a blackout window
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowAction {
+    /// Discard the event
+    #[default]
+    Drop,
+    /// Hold the event back until the window closes
+    Defer,
+}
+
+impl WindowAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WindowAction::Drop => "drop",
+            WindowAction::Defer => "defer",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "drop" => Some(WindowAction::Drop),
+            "defer" => Some(WindowAction::Defer),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of file system change reported by a watcher
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChange {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl FileChange {
+    pub const ALL: [FileChange; 3] = [
+        FileChange::Created,
+        FileChange::Modified,
+        FileChange::Deleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileChange::Created => "created",
+            FileChange::Modified => "modified",
+            FileChange::Deleted => "deleted",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "created" => Some(FileChange::Created),
+            "modified" => Some(FileChange::Modified),
+            "deleted" => Some(FileChange::Deleted),
+            _ => None,
         }
     }
 }
 
+/// Lifecycle of a schedule. Only one-shot schedules leave the active state.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleState {
+    /// Waiting for its next run
+    #[default]
+    Active,
+    /// Fired and has no runs left
+    Completed,
+    /// Its time passed without it firing
+    Expired,
+}
+
+impl ScheduleState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduleState::Active => "active",
+            ScheduleState::Completed => "completed",
+            ScheduleState::Expired => "expired",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "active" => Some(ScheduleState::Active),
+            "completed" => Some(ScheduleState::Completed),
+            "expired" => Some(ScheduleState::Expired),
+            _ => None,
+        }
+    }
+}
+
+/// Upper bound for a single retry delay, however many attempts came before
+pub const MAX_RETRY_DELAY_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Backoff {
+    #[default]
+    Fixed,
+    Exponential,
+}
+
+impl Backoff {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backoff::Fixed => "fixed",
+            Backoff::Exponential => "exponential",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "fixed" => Some(Backoff::Fixed),
+            "exponential" | "exp" => Some(Backoff::Exponential),
+            _ => None,
+        }
+    }
+}
+
+/// How a handler's failed jobs are re-executed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub backoff: Backoff,
+    /// Delay before the first retry; doubled for each further attempt with exponential backoff
+    #[serde(default)]
+    pub delay_secs: u32,
+    /// Only retry on these exit codes (empty retries any failure, including timeouts)
+    #[serde(default)]
+    pub retry_on: Vec<i32>,
+}
+
+impl RetryPolicy {
+    /// Whether the job that just failed on `attempt` should run again
+    pub fn should_retry(&self, attempt: u32, exit_code: Option<i32>) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        if self.retry_on.is_empty() {
+            return true;
+        }
+        exit_code.is_some_and(|code| self.retry_on.contains(&code))
+    }
+
+    /// Delay before the attempt following `attempt`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = u64::from(self.delay_secs);
+        let secs = match self.backoff {
+            Backoff::Fixed => base,
+            Backoff::Exponential => {
+                base.saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+            }
+        };
+        Duration::from_secs(secs.min(MAX_RETRY_DELAY_SECS))
+    }
+}
+
+/// What happens to a handler's events beyond its throttle limit
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ThrottleMode {
+    /// Drop them
+    #[default]
+    Drop,
+    /// Run once with the last of them when the window has room again
+    Coalesce,
+}
+
+impl ThrottleMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThrottleMode::Drop => "drop",
+            ThrottleMode::Coalesce => "coalesce",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "drop" => Some(ThrottleMode::Drop),
+            "coalesce" => Some(ThrottleMode::Coalesce),
+            _ => None,
+        }
+    }
+}
+
+/// How often a handler's jobs may start
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ThrottlePolicy {
+    /// Jobs allowed to start within any window
+    pub max_runs: u32,
+    pub window_secs: u32,
+    #[serde(default)]
+    pub mode: ThrottleMode,
+}
+
+/// Name given to a handler when none is specified
+pub const DEFAULT_HANDLER_NAME: &str = "default";
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct EventHandler {
     pub id: Uuid,
     pub event_type: String,
+    pub name: String,
     pub shell: ShellType,
     #[serde(skip_serializing)]
     pub command: String,
@@ -70,11 +377,42 @@ pub struct EventHandler {
     pub timeout: Option<u32>,
     #[serde(default, skip_serializing)]
     pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+    /// Maximum number of this handler's jobs running at once (1 serializes them)
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    /// Paused handlers get no jobs
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Event type to trigger when a job of this handler completes
+    #[serde(default)]
+    pub on_success: Option<String>,
+    /// Event type to trigger when a job fails for good (after its retries)
+    #[serde(default)]
+    pub on_failure: Option<String>,
+    /// Event type to trigger when a job times out for good; falls back to `on_failure`
+    #[serde(default)]
+    pub on_timeout: Option<String>,
+    /// Expression the event context must match for a job to be created, e.g.
+    /// `context.branch == "main" && context.files > 0`
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Wait until no event arrived for this long, then run once with the last event
+    #[serde(default)]
+    pub debounce_secs: Option<u32>,
+    #[serde(default)]
+    pub throttle: Option<ThrottlePolicy>,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 impl EventHandler {
     pub fn new(
         event_type: String,
+        name: String,
         shell: ShellType,
         command: String,
         timeout: Option<u32>,
@@ -83,10 +421,20 @@ impl EventHandler {
         Self {
             id: Uuid::new_v4(),
             event_type,
+            name,
             shell,
             command,
             timeout,
             env,
+            retry: None,
+            max_concurrency: None,
+            enabled: true,
+            on_success: None,
+            on_failure: None,
+            on_timeout: None,
+            filter: None,
+            debounce_secs: None,
+            throttle: None,
         }
     }
 }
@@ -99,6 +447,10 @@ pub enum JobStatus {
     Completed,
     Failed,
     Cancelled,
+    #[serde(rename = "timed_out")]
+    TimedOut,
+    /// The handler's filter did not match the event; the job never ran
+    Skipped,
 }
 
 impl JobStatus {
@@ -109,9 +461,12 @@ impl JobStatus {
             JobStatus::Completed => "completed",
             JobStatus::Failed => "failed",
             JobStatus::Cancelled => "cancelled",
+            JobStatus::TimedOut => "timed_out",
+            JobStatus::Skipped => "skipped",
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "pending" => Some(JobStatus::Pending),
@@ -119,6 +474,8 @@ impl JobStatus {
             "completed" => Some(JobStatus::Completed),
             "failed" => Some(JobStatus::Failed),
             "cancelled" => Some(JobStatus::Cancelled),
+            "timed_out" => Some(JobStatus::TimedOut),
+            "skipped" => Some(JobStatus::Skipped),
             _ => None,
         }
     }
@@ -129,24 +486,350 @@ pub struct Job {
     pub id: Uuid,
     pub event: Event,
     pub handler_id: Uuid,
+    pub handler_name: String,
+    /// 1 for the first run, incremented for each retry
+    pub attempt: u32,
+    /// The previous attempt this job retries
+    pub parent_job_id: Option<Uuid>,
+    /// Events of the handler debounced, coalesced or dropped in favour of this job
+    #[serde(default)]
+    pub suppressed: u32,
     pub status: JobStatus,
-    pub output: Option<String>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<i64>,
     pub error: Option<String>,
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
 }
 
 impl Job {
-    pub fn new(event: Event, handler_id: Uuid) -> Self {
+    pub fn new(event: Event, handler: &EventHandler) -> Self {
         Self {
             id: Uuid::new_v4(),
             event,
-            handler_id,
+            handler_id: handler.id,
+            handler_name: handler.name.clone(),
+            attempt: 1,
+            parent_job_id: None,
+            suppressed: 0,
+            status: JobStatus::Pending,
+            stdout: None,
+            stderr: None,
+            exit_code: None,
+            duration_ms: None,
+            error: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    /// A finished job recording that the handler did not run for the event
+    pub fn skipped(event: Event, handler: &EventHandler, reason: String) -> Self {
+        let now = Utc::now();
+        Self {
+            status: JobStatus::Skipped,
+            error: Some(reason),
+            started_at: Some(now),
+            finished_at: Some(now),
+            ..Self::new(event, handler)
+        }
+    }
+
+    /// A new pending job running the same event and handler again
+    pub fn retry(&self) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event: self.event.clone(),
+            handler_id: self.handler_id,
+            handler_name: self.handler_name.clone(),
+            attempt: self.attempt + 1,
+            parent_job_id: Some(self.id),
+            suppressed: 0,
             status: JobStatus::Pending,
-            output: None,
+            stdout: None,
+            stderr: None,
+            exit_code: None,
+            duration_ms: None,
             error: None,
             started_at: None,
             finished_at: None,
         }
     }
 }
+
+/// When a workflow step runs, judged by how the steps it depends on ended
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StepCondition {
+    /// Every dependency completed
+    #[default]
+    Success,
+    /// At least one dependency failed
+    Failure,
+    /// Every dependency finished, however it ended
+    Always,
+}
+
+impl StepCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepCondition::Success => "success",
+            StepCondition::Failure => "failure",
+            StepCondition::Always => "always",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "success" => Some(StepCondition::Success),
+            "failure" => Some(StepCondition::Failure),
+            "always" => Some(StepCondition::Always),
+            _ => None,
+        }
+    }
+}
+
+fn default_handler_name() -> String {
+    DEFAULT_HANDLER_NAME.to_string()
+}
+
+/// One node of a workflow: a job of a handler, run once the steps it depends on are done
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct WorkflowStep {
+    pub name: String,
+    /// Event type of the handler to run
+    pub event_type: String,
+    #[serde(default = "default_handler_name")]
+    pub handler: String,
+    /// Steps that must finish first (the step starts right away if empty)
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub condition: StepCondition,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    /// Its dependencies are not done yet
+    Waiting,
+    Running,
+    Completed,
+    /// Its job failed or timed out, or it could not get one
+    Failed,
+    Cancelled,
+    /// Its condition was not met
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Waiting => "waiting",
+            StepStatus::Running => "running",
+            StepStatus::Completed => "completed",
+            StepStatus::Failed => "failed",
+            StepStatus::Cancelled => "cancelled",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, StepStatus::Waiting | StepStatus::Running)
+    }
+}
+
+/// A step of a workflow run, with the definition it ran under
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepRun {
+    #[serde(flatten)]
+    pub step: WorkflowStep,
+    pub status: StepStatus,
+    /// The step's job; its last attempt once it was retried
+    pub job_id: Option<Uuid>,
+    /// Why the step failed without a job, e.g. a missing handler
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Running,
+    /// No step failed or was cancelled
+    Completed,
+    /// At least one step failed, or the backend stopped during the run
+    Failed,
+    /// A step was cancelled
+    Cancelled,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Completed => "completed",
+            RunStatus::Failed => "failed",
+            RunStatus::Cancelled => "cancelled",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "running" => Some(RunStatus::Running),
+            "completed" => Some(RunStatus::Completed),
+            "failed" => Some(RunStatus::Failed),
+            "cancelled" => Some(RunStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// One execution of a workflow
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowRun {
+    pub id: Uuid,
+    pub workflow: String,
+    /// Context of the events of all step jobs
+    pub context: String,
+    pub status: RunStatus,
+    pub steps: Vec<StepRun>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl WorkflowRun {
+    pub fn new(workflow: String, context: String, steps: Vec<WorkflowStep>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            workflow,
+            context,
+            status: RunStatus::Running,
+            steps: steps
+                .into_iter()
+                .map(|step| StepRun {
+                    step,
+                    status: StepStatus::Waiting,
+                    job_id: None,
+                    error: None,
+                    started_at: None,
+                    finished_at: None,
+                })
+                .collect(),
+            started_at: Utc::now(),
+            finished_at: None,
+        }
+    }
+}
+
+/// Parse a duration given in seconds (`90`) or as a human-readable duration (`1h30m`, `15min`)
+pub fn parse_duration_secs(value: &str) -> Result<u32, String> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u32>() {
+        return Ok(secs);
+    }
+    let duration = humantime::parse_duration(value)
+        .map_err(|e| format!("Invalid duration '{}': {}", value, e))?;
+    if duration.subsec_nanos() != 0 {
+        return Err(format!("Duration '{}' must be whole seconds", value));
+    }
+    u32::try_from(duration.as_secs()).map_err(|_| format!("Duration '{}' is too long", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(
+        max_attempts: u32,
+        backoff: Backoff,
+        delay_secs: u32,
+        retry_on: &[i32],
+    ) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            backoff,
+            delay_secs,
+            retry_on: retry_on.to_vec(),
+        }
+    }
+
+    #[test]
+    fn retries_stop_after_max_attempts() {
+        let retry = policy(3, Backoff::Fixed, 0, &[]);
+        assert!(retry.should_retry(1, Some(1)));
+        assert!(retry.should_retry(2, Some(1)));
+        assert!(!retry.should_retry(3, Some(1)));
+        assert!(!policy(1, Backoff::Fixed, 0, &[]).should_retry(1, Some(1)));
+    }
+
+    #[test]
+    fn retry_on_limits_the_exit_codes() {
+        let retry = policy(5, Backoff::Fixed, 0, &[75, 111]);
+        assert!(retry.should_retry(1, Some(75)));
+        assert!(retry.should_retry(1, Some(111)));
+        assert!(!retry.should_retry(1, Some(1)));
+        // A timed out job has no exit code, so only an empty list retries it
+        assert!(!retry.should_retry(1, None));
+        assert!(policy(5, Backoff::Fixed, 0, &[]).should_retry(1, None));
+    }
+
+    #[test]
+    fn fixed_backoff_waits_the_same_each_time() {
+        let retry = policy(5, Backoff::Fixed, 30, &[]);
+        for attempt in 1..5 {
+            assert_eq!(retry.delay(attempt), Duration::from_secs(30));
+        }
+    }
+
+    #[test]
+    fn exponential_backoff_doubles_up_to_the_cap() {
+        let retry = policy(20, Backoff::Exponential, 10, &[]);
+        let delays: Vec<u64> = (1..=5).map(|a| retry.delay(a).as_secs()).collect();
+        assert_eq!(delays, [10, 20, 40, 80, 160]);
+        assert_eq!(retry.delay(10).as_secs(), MAX_RETRY_DELAY_SECS);
+        assert_eq!(retry.delay(u32::MAX).as_secs(), MAX_RETRY_DELAY_SECS);
+        assert_eq!(
+            policy(20, Backoff::Exponential, 0, &[]).delay(5),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn retry_jobs_link_to_the_attempt_before() {
+        let handler = EventHandler::new(
+            "build".to_string(),
+            "default".to_string(),
+            ShellType::Sh,
+            "make".to_string(),
+            None,
+            HashMap::new(),
+        );
+        let mut first = Job::new(Event::new("build".to_string(), "ctx".to_string()), &handler);
+        first.status = JobStatus::Failed;
+        first.exit_code = Some(2);
+
+        let second = first.retry();
+        assert_eq!(second.attempt, first.attempt + 1);
+        assert_eq!(second.parent_job_id, Some(first.id));
+        assert_eq!(second.event.id, first.event.id);
+        assert_eq!(second.status, JobStatus::Pending);
+        assert_eq!(second.exit_code, None);
+        assert_ne!(second.id, first.id);
+    }
+
+    #[test]
+    fn durations_are_read_as_seconds_or_human_readable() {
+        assert_eq!(parse_duration_secs("90"), Ok(90));
+        assert_eq!(parse_duration_secs(" 1h30m "), Ok(5400));
+        assert_eq!(parse_duration_secs("15min"), Ok(900));
+        assert!(parse_duration_secs("1500ms").is_err());
+        assert!(parse_duration_secs("200years").is_err());
+        assert!(parse_duration_secs("soon").is_err());
+    }
+}